@@ -0,0 +1,6 @@
+#[path = "../ctl/main.rs"]
+mod ctl;
+
+fn main() {
+    ctl::run();
+}
@@ -1,5 +1,8 @@
 use serde::Deserialize;
 use std::path::Path;
+use streamrs::image::calendar::CalendarFace;
+use streamrs::image::clock::ClockFace;
+use streamrs::image::metric::MetricFace;
 
 #[derive(Debug, Deserialize)]
 pub(super) struct Config {
@@ -12,6 +15,58 @@ pub(super) struct KeyBinding {
     pub(super) icon_on: Option<String>,
     pub(super) icon_off: Option<String>,
     pub(super) status: Option<String>,
+    pub(super) title: Option<String>,
+    pub(super) title_position: Option<String>,
+    pub(super) title_color: Option<String>,
+    pub(super) title_size: Option<u32>,
+    pub(super) clock_background: Option<String>,
+    pub(super) clock_format: Option<String>,
+    #[serde(default)]
+    pub(super) clock_seconds: bool,
+    #[serde(default)]
+    pub(super) clock_date: bool,
+    pub(super) clock_time_zone: Option<String>,
+    pub(super) clock_label: Option<String>,
+    pub(super) clock_style: Option<String>,
+    #[serde(default)]
+    pub(super) calendar_weekday: bool,
+    #[serde(default)]
+    pub(super) calendar_week_number: bool,
+    pub(super) calendar_first_weekday: Option<String>,
+    pub(super) calendar_header_color: Option<String>,
+    pub(super) calendar_body_color: Option<String>,
+    pub(super) calendar_layout: Option<String>,
+    pub(super) metric_style: Option<String>,
+    pub(super) metric_interval_seconds: Option<u64>,
+}
+
+impl KeyBinding {
+    pub(super) fn clock_face(&self) -> Result<ClockFace, String> {
+        ClockFace::from_fields(
+            self.clock_background.as_deref(),
+            self.clock_format.as_deref(),
+            self.clock_seconds,
+            self.clock_date,
+            self.clock_time_zone.as_deref(),
+            self.clock_label.as_deref(),
+            self.clock_style.as_deref(),
+        )
+    }
+
+    pub(super) fn calendar_face(&self) -> Result<CalendarFace, String> {
+        CalendarFace::from_fields(
+            self.calendar_weekday,
+            self.calendar_week_number,
+            self.calendar_first_weekday.as_deref(),
+            self.calendar_header_color.as_deref(),
+            self.calendar_body_color.as_deref(),
+            self.calendar_layout.as_deref(),
+        )
+    }
+
+    pub(super) fn metric_face(&self) -> Result<MetricFace, String> {
+        MetricFace::from_fields(self.metric_style.as_deref(), self.metric_interval_seconds)
+    }
 }
 
 pub(super) fn load_config(path: &Path) -> Result<Config, String> {
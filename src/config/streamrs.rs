@@ -1,14 +1,90 @@
 use super::{
-    Config, DEFAULT_STATUS_CHECK_INTERVAL_SECONDS, KeyBinding, MAX_KEYS_PER_PAGE,
-    MAX_STATUS_CHECK_INTERVAL_SECONDS, MIN_KEYS_PER_PAGE, MIN_STATUS_CHECK_INTERVAL_SECONDS,
+    Config, DEFAULT_BRIGHTNESS_LEVELS, DEFAULT_BRIGHTNESS_STEP,
+    DEFAULT_STATUS_CHECK_INTERVAL_SECONDS, KeyBinding, MAX_DOUBLE_PRESS_MS, MAX_KEYS_PER_PAGE,
+    MAX_LONG_PRESS_MS, MAX_MACRO_DELAY_MS, MAX_STATUS_CHECK_INTERVAL_SECONDS, MIN_DOUBLE_PRESS_MS,
+    MIN_KEYS_PER_PAGE, MIN_LONG_PRESS_MS, MIN_STATUS_CHECK_INTERVAL_SECONDS,
 };
 use std::path::Path;
 use std::time::Duration;
+use streamrs::config::current_profile::normalize_profile_name;
+use streamrs::config::streamrs_schema::StreamrsMacroStep;
+use streamrs::idle::{DEFAULT_IDLE_DIM_BRIGHTNESS, IdleThresholds};
+use streamrs::image::calendar::CalendarFace;
+use streamrs::image::clock::ClockFace;
+use streamrs::image::metric::MetricFace;
+use streamrs::image::overlay::KeyTitle;
+use streamrs::image::timer::TimerKind;
+use streamrs::key_events::KeyEventThresholds;
+use streamrs::metrics::Metric;
+use streamrs::streamdeck::{DeviceModel, supported_product_ids};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) enum ConfiguredAction {
     Launch(String),
     KeyboardShortcut(String),
+    Text(String),
+    Macro(KeyMacro),
+    Builtin(BuiltinAction),
+}
+
+// Actions the daemon runs itself instead of handing to the desktop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum BuiltinAction {
+    Brightness(BrightnessChange),
+    Page(PageTarget),
+    // Switches the daemon's profile, like `streamrsctl profile set`.
+    Profile(String),
+    // Sent to the MPRIS player the now-playing keys follow.
+    Media(MediaCommand),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MediaCommand {
+    PlayPause,
+    Next,
+    Previous,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum BrightnessChange {
+    Up(usize),
+    Down(usize),
+    Cycle(Vec<usize>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PageTarget {
+    Next,
+    Previous,
+    First,
+    Last,
+    // The page shown before the last page change at this folder level.
+    Back,
+    Page(usize),
+}
+
+// How a status command's result picks what its key shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum StatusMode {
+    // The exit status picks `icon_on` or `icon_off`.
+    Exit,
+    // Stdout names a state, gives a title or is a JSON object.
+    Output,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum MacroStep {
+    Launch(String),
+    KeyboardShortcut(String),
+    Text(String),
+    Delay(Duration),
+    Page(PageTarget),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct KeyMacro {
+    pub(crate) steps: Vec<MacroStep>,
+    pub(crate) stop_on_error: bool,
 }
 
 pub(crate) fn read_config_file(path: &Path) -> Result<String, String> {
@@ -28,9 +104,230 @@ fn validate_config(path: &Path, config: &Config) -> Result<(), String> {
             MAX_KEYS_PER_PAGE
         ));
     }
+    if !(MIN_LONG_PRESS_MS..=MAX_LONG_PRESS_MS).contains(&config.long_press_ms) {
+        return Err(format!(
+            "Config '{}' has invalid long_press_ms {}; expected {}..={}",
+            path.display(),
+            config.long_press_ms,
+            MIN_LONG_PRESS_MS,
+            MAX_LONG_PRESS_MS
+        ));
+    }
+    if !(MIN_DOUBLE_PRESS_MS..=MAX_DOUBLE_PRESS_MS).contains(&config.double_press_ms) {
+        return Err(format!(
+            "Config '{}' has invalid double_press_ms {}; expected {}..={}",
+            path.display(),
+            config.double_press_ms,
+            MIN_DOUBLE_PRESS_MS,
+            MAX_DOUBLE_PRESS_MS
+        ));
+    }
+    for (name, minutes) in [
+        ("idle_dim_minutes", config.idle_dim_minutes),
+        ("idle_off_minutes", config.idle_off_minutes),
+    ] {
+        if minutes == Some(0) {
+            return Err(format!(
+                "Config '{}' has invalid {} 0; expected at least 1",
+                path.display(),
+                name
+            ));
+        }
+    }
+    if let Some(brightness) = config.idle_dim_brightness
+        && brightness > 100
+    {
+        return Err(format!(
+            "Config '{}' has invalid idle_dim_brightness {}; expected 0..=100",
+            path.display(),
+            brightness
+        ));
+    }
+    if DeviceModel::from_product_id(config.product_id).is_none() {
+        let supported = supported_product_ids()
+            .iter()
+            .map(|id| format!("0x{id:04x}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        return Err(format!(
+            "Config '{}' has unsupported product_id 0x{:04x}; expected one of {}",
+            path.display(),
+            config.product_id,
+            supported
+        ));
+    }
+    validate_keys(path, &config.keys)?;
+    for (serial, profile) in &config.devices {
+        if normalize_profile_name(profile).as_deref() != Some(profile.as_str()) {
+            return Err(format!(
+                "Config '{}' maps device '{}' to invalid profile name '{}'",
+                path.display(),
+                serial,
+                profile
+            ));
+        }
+    }
+    for (index, rule) in config.auto_profile.iter().enumerate() {
+        if rule.app_id.trim().is_empty() {
+            return Err(format!(
+                "Config '{}' auto_profile {} has an empty app_id",
+                path.display(),
+                index + 1
+            ));
+        }
+        if normalize_profile_name(&rule.profile).as_deref() != Some(rule.profile.as_str()) {
+            return Err(format!(
+                "Config '{}' auto_profile {} has invalid profile name '{}'",
+                path.display(),
+                index + 1,
+                rule.profile
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn validate_keys(path: &Path, keys: &[KeyBinding]) -> Result<(), String> {
+    for (index, key) in keys.iter().enumerate() {
+        key_builtin_action(key)
+            .and_then(|_| key_status_mode(key))
+            .and_then(|_| key_title(key))
+            .and_then(|_| key_live_faces(key))
+            .and_then(|_| key_timer(key))
+            .and_then(|_| Metric::from_icon(&key.icon).transpose())
+            .map_err(|err| format!("Config '{}' key {}: {}", path.display(), index + 1, err))?;
+        for (step_index, step) in key.steps.iter().enumerate() {
+            parse_macro_step(step).map_err(|err| {
+                format!(
+                    "Config '{}' key {} step {}: {}",
+                    path.display(),
+                    index + 1,
+                    step_index + 1,
+                    err
+                )
+            })?;
+        }
+        if let Some(folder_keys) = &key.keys {
+            validate_keys(path, folder_keys)?;
+        }
+    }
     Ok(())
 }
 
+pub(crate) fn parse_page_target(value: &str) -> Result<PageTarget, String> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "next" => Ok(PageTarget::Next),
+        "previous" | "prev" => Ok(PageTarget::Previous),
+        "first" => Ok(PageTarget::First),
+        "last" => Ok(PageTarget::Last),
+        "back" => Ok(PageTarget::Back),
+        other => match other.parse::<usize>() {
+            Ok(page) if page > 0 => Ok(PageTarget::Page(page - 1)),
+            _ => Err(format!(
+                "invalid page '{value}'; expected next, previous, first, last, back or a page number"
+            )),
+        },
+    }
+}
+
+pub(crate) fn key_builtin_action(key: &KeyBinding) -> Result<Option<BuiltinAction>, String> {
+    let Some(builtin) = trimmed_non_empty(key.builtin.as_deref()) else {
+        return Ok(None);
+    };
+    if let Some((kind, value)) = builtin.split_once(':') {
+        return match kind.trim().to_ascii_lowercase().as_str() {
+            "page" => Ok(Some(BuiltinAction::Page(parse_page_target(value)?))),
+            "profile" => match normalize_profile_name(value) {
+                Some(profile) => Ok(Some(BuiltinAction::Profile(profile))),
+                None => Err(format!(
+                    "invalid profile name '{}' in builtin '{builtin}'",
+                    value.trim()
+                )),
+            },
+            "media" => match value.trim().to_ascii_lowercase().as_str() {
+                "play_pause" => Ok(Some(BuiltinAction::Media(MediaCommand::PlayPause))),
+                "next" => Ok(Some(BuiltinAction::Media(MediaCommand::Next))),
+                "previous" => Ok(Some(BuiltinAction::Media(MediaCommand::Previous))),
+                _ => Err(format!(
+                    "unknown media command in builtin '{builtin}'; expected play_pause, next or previous"
+                )),
+            },
+            _ => Err(unknown_builtin(&builtin)),
+        };
+    }
+    let step = key.brightness_step.unwrap_or(DEFAULT_BRIGHTNESS_STEP);
+    if !(1..=100).contains(&step) {
+        return Err(format!("invalid brightness_step {step}; expected 1..=100"));
+    }
+    if let Some(level) = key.brightness_levels.iter().find(|&&level| level > 100) {
+        return Err(format!(
+            "invalid brightness level {level}; expected 0..=100"
+        ));
+    }
+    let change = match builtin.to_ascii_lowercase().as_str() {
+        "brightness_up" => BrightnessChange::Up(step),
+        "brightness_down" => BrightnessChange::Down(step),
+        "brightness_cycle" => {
+            let levels = if key.brightness_levels.is_empty() {
+                DEFAULT_BRIGHTNESS_LEVELS.to_vec()
+            } else {
+                key.brightness_levels.clone()
+            };
+            BrightnessChange::Cycle(levels)
+        }
+        _ => return Err(unknown_builtin(&builtin)),
+    };
+    Ok(Some(BuiltinAction::Brightness(change)))
+}
+
+fn unknown_builtin(builtin: &str) -> String {
+    format!(
+        "unknown builtin '{builtin}'; expected brightness_up, brightness_down, brightness_cycle, page:<target>, profile:<name> or media:<command>"
+    )
+}
+
+pub(crate) fn parse_macro_step(step: &StreamrsMacroStep) -> Result<MacroStep, String> {
+    let mut parsed = Vec::new();
+    if let Some(action) = trimmed_non_empty(step.action.as_deref()) {
+        parsed.push(MacroStep::Launch(action));
+    }
+    if let Some(shortcut) = trimmed_non_empty(step.shortcut.as_deref()) {
+        parsed.push(MacroStep::KeyboardShortcut(shortcut));
+    }
+    if let Some(text) = step.text.as_ref().filter(|text| !text.is_empty()) {
+        parsed.push(MacroStep::Text(text.clone()));
+    }
+    if let Some(delay_ms) = step.delay_ms {
+        if delay_ms > MAX_MACRO_DELAY_MS {
+            return Err(format!(
+                "delay_ms {delay_ms} is longer than {MAX_MACRO_DELAY_MS}"
+            ));
+        }
+        parsed.push(MacroStep::Delay(Duration::from_millis(delay_ms)));
+    }
+    if let Some(page) = step.page.as_deref() {
+        parsed.push(MacroStep::Page(parse_page_target(page)?));
+    }
+
+    match parsed.len() {
+        1 => Ok(parsed.remove(0)),
+        0 => Err("expected one of action, shortcut, text, delay_ms or page".to_string()),
+        _ => Err("only one of action, shortcut, text, delay_ms or page may be set".to_string()),
+    }
+}
+
+pub(crate) fn key_macro(key: &KeyBinding) -> Option<KeyMacro> {
+    let steps = key
+        .steps
+        .iter()
+        .filter_map(|step| parse_macro_step(step).ok())
+        .collect::<Vec<_>>();
+    (!steps.is_empty()).then_some(KeyMacro {
+        steps,
+        stop_on_error: key.stop_on_error,
+    })
+}
+
 pub(crate) fn load_config(path: &Path, profile: &str) -> Result<Config, String> {
     let config = streamrs::config::streamrs_profile::load_config_for_profile(path, profile)?;
     validate_config(path, &config)?;
@@ -59,14 +356,78 @@ pub(crate) fn key_keyboard_shortcut(key: &KeyBinding) -> Option<String> {
     trimmed_non_empty(key.shortcut.as_deref())
 }
 
+pub(crate) fn key_text(key: &KeyBinding) -> Option<String> {
+    key.text.clone().filter(|text| !text.is_empty())
+}
+
 pub(crate) fn key_configured_action(key: &KeyBinding) -> Option<ConfiguredAction> {
+    if let Ok(Some(builtin)) = key_builtin_action(key) {
+        return Some(ConfiguredAction::Builtin(builtin));
+    }
+    if let Some(key_macro) = key_macro(key) {
+        return Some(ConfiguredAction::Macro(key_macro));
+    }
     if let Some(shortcut) = key_keyboard_shortcut(key) {
         return Some(ConfiguredAction::KeyboardShortcut(shortcut));
     }
+    if let Some(text) = key_text(key) {
+        return Some(ConfiguredAction::Text(text));
+    }
 
     key_launch_action(key).map(ConfiguredAction::Launch)
 }
 
+pub(crate) fn key_long_press_action(key: &KeyBinding) -> Option<String> {
+    trimmed_non_empty(key.long_press_action.as_deref())
+}
+
+pub(crate) fn key_double_press_action(key: &KeyBinding) -> Option<String> {
+    trimmed_non_empty(key.double_press_action.as_deref())
+}
+
+// Presses start, pause and reset timer keys, so they can't have other press
+// actions or a status command.
+pub(crate) fn key_timer(key: &KeyBinding) -> Result<Option<TimerKind>, String> {
+    let Some(kind) = TimerKind::from_icon(&key.icon).transpose()? else {
+        return Ok(None);
+    };
+    if key_configured_action(key).is_some()
+        || key_long_press_action(key).is_some()
+        || key_status_command(key).is_some()
+    {
+        return Err(format!(
+            "timer icon '{}' can't be combined with another action, long_press_action or status",
+            key.icon
+        ));
+    }
+    Ok(Some(kind))
+}
+
+pub(crate) fn key_timer_command(key: &KeyBinding) -> Option<String> {
+    trimmed_non_empty(key.timer_command.as_deref())
+}
+
+pub(crate) fn key_event_thresholds(config: &Config) -> KeyEventThresholds {
+    KeyEventThresholds {
+        long_press: Duration::from_millis(config.long_press_ms),
+        double_press: Duration::from_millis(config.double_press_ms),
+    }
+}
+
+pub(crate) fn idle_thresholds(config: &Config) -> IdleThresholds {
+    let minutes = |value: Option<u64>| value.map(|minutes| Duration::from_secs(minutes * 60));
+    IdleThresholds {
+        dim_after: minutes(config.idle_dim_minutes),
+        off_after: minutes(config.idle_off_minutes),
+    }
+}
+
+pub(crate) fn idle_dim_brightness(config: &Config) -> usize {
+    config
+        .idle_dim_brightness
+        .unwrap_or(DEFAULT_IDLE_DIM_BRIGHTNESS)
+}
+
 fn trimmed_non_empty(value: Option<&str>) -> Option<String> {
     value.and_then(|value| {
         let trimmed = value.trim();
@@ -82,6 +443,20 @@ pub(crate) fn key_status_command(key: &KeyBinding) -> Option<String> {
     trimmed_non_empty(key.status.as_deref())
 }
 
+pub(crate) fn key_status_mode(key: &KeyBinding) -> Result<StatusMode, String> {
+    match trimmed_non_empty(key.status_mode.as_deref())
+        .map(|mode| mode.to_ascii_lowercase())
+        .as_deref()
+    {
+        None | Some("exit") => Ok(StatusMode::Exit),
+        Some("output") => Ok(StatusMode::Output),
+        Some(_) => Err(format!(
+            "invalid status_mode '{}'; expected exit or output",
+            key.status_mode.as_deref().unwrap_or_default().trim()
+        )),
+    }
+}
+
 pub(crate) fn is_launcher_like_command(command: &str) -> bool {
     let mut parts = command.split_whitespace();
     matches!(
@@ -115,6 +490,45 @@ pub(crate) fn key_status_interval(key: &KeyBinding) -> Duration {
     Duration::from_secs(key_status_interval_seconds(key))
 }
 
-pub(crate) fn key_clock_background(key: &KeyBinding) -> Option<String> {
-    trimmed_non_empty(key.clock_background.as_deref())
+pub(crate) fn key_title(key: &KeyBinding) -> Result<Option<KeyTitle>, String> {
+    KeyTitle::from_fields(
+        key.title.as_deref(),
+        key.title_position.as_deref(),
+        key.title_color.as_deref(),
+        key.title_size,
+    )
+}
+
+// How a key draws the built-in clock, calendar and metric icons.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub(crate) struct LiveFaces {
+    pub(crate) clock: ClockFace,
+    pub(crate) calendar: CalendarFace,
+    pub(crate) metric: MetricFace,
+}
+
+pub(crate) fn key_live_faces(key: &KeyBinding) -> Result<LiveFaces, String> {
+    let clock = ClockFace::from_fields(
+        key.clock_background.as_deref(),
+        key.clock_format.as_deref(),
+        key.clock_seconds,
+        key.clock_date,
+        key.clock_time_zone.as_deref(),
+        key.clock_label.as_deref(),
+        key.clock_style.as_deref(),
+    )?;
+    let calendar = CalendarFace::from_fields(
+        key.calendar_weekday,
+        key.calendar_week_number,
+        key.calendar_first_weekday.as_deref(),
+        key.calendar_header_color.as_deref(),
+        key.calendar_body_color.as_deref(),
+        key.calendar_layout.as_deref(),
+    )?;
+    let metric = MetricFace::from_fields(key.metric_style.as_deref(), key.metric_interval_seconds)?;
+    Ok(LiveFaces {
+        clock,
+        calendar,
+        metric,
+    })
 }
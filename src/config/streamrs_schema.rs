@@ -1,11 +1,14 @@
+use crate::key_events::{DEFAULT_DOUBLE_PRESS_MS, DEFAULT_LONG_PRESS_MS};
+use crate::streamdeck::{DEFAULT_DEVICE_MODEL, DeviceModel};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 pub const DEFAULT_VENDOR_ID: u16 = 0x0fd9;
 pub const DEFAULT_PRODUCT_ID: u16 = 0x0080;
 pub const DEFAULT_USAGE: u16 = 0x0001;
 pub const DEFAULT_USAGE_PAGE: u16 = 0x000c;
 pub const DEFAULT_BRIGHTNESS: usize = 60;
-pub const DEFAULT_KEYS_PER_PAGE: usize = crate::paging::STREAMDECK_KEY_COUNT;
+pub const DEFAULT_KEYS_PER_PAGE: usize = DEFAULT_DEVICE_MODEL.key_count;
 
 pub fn default_vendor_id() -> u16 {
     DEFAULT_VENDOR_ID
@@ -31,6 +34,22 @@ pub fn default_keys_per_page() -> usize {
     DEFAULT_KEYS_PER_PAGE
 }
 
+pub fn default_long_press_ms() -> u64 {
+    DEFAULT_LONG_PRESS_MS
+}
+
+pub fn default_double_press_ms() -> u64 {
+    DEFAULT_DOUBLE_PRESS_MS
+}
+
+fn is_default_long_press(ms: &u64) -> bool {
+    *ms == DEFAULT_LONG_PRESS_MS
+}
+
+fn is_default_double_press(ms: &u64) -> bool {
+    *ms == DEFAULT_DOUBLE_PRESS_MS
+}
+
 pub fn default_icon_name() -> String {
     "blank.png".to_string()
 }
@@ -49,20 +68,123 @@ pub struct StreamrsConfig {
     pub brightness: usize,
     #[serde(default = "default_keys_per_page")]
     pub keys_per_page: usize,
+    // Leaves page navigation to keys bound to `page:` builtins instead of
+    // reserving the last slots of each page for previous/next keys.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub manual_page_navigation: bool,
+    #[serde(
+        default = "default_long_press_ms",
+        skip_serializing_if = "is_default_long_press"
+    )]
+    pub long_press_ms: u64,
+    #[serde(
+        default = "default_double_press_ms",
+        skip_serializing_if = "is_default_double_press"
+    )]
+    pub double_press_ms: u64,
+    // Minutes without key presses before the deck dims, and before it turns off.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_dim_minutes: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_dim_brightness: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_off_minutes: Option<u64>,
+    // Also draws every key black while the deck is off.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub idle_blank_keys: bool,
+    // Profiles to switch to while a matching app has focus; the first match wins.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub auto_profile: Vec<StreamrsAutoProfile>,
+    // Maps a deck serial number to the profile it runs; unmapped decks use this profile.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub devices: BTreeMap<String, String>,
     #[serde(default)]
     pub keys: Vec<StreamrsKeyBinding>,
 }
 
+// `app_id` is a Wayland app_id or an X11 WM_CLASS instance or class, compared
+// without regard to case.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamrsAutoProfile {
+    pub app_id: String,
+    pub profile: String,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct StreamrsKeyBinding {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub action: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub shortcut: Option<String>,
+    // Typed as-is, so surrounding whitespace and newlines are kept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stop_on_error: bool,
+    // A daemon action such as `brightness_up` or `page:first`; wins over every other action.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub builtin: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub brightness_step: Option<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub brightness_levels: Vec<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub long_press_action: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub double_press_action: Option<String>,
     #[serde(default = "default_icon_name")]
     pub icon: String,
+    // Run when a `timer://` countdown reaches zero.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timer_command: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub clock_background: Option<String>,
+    // "24h" (the default) or "12h".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clock_format: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub clock_seconds: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub clock_date: bool,
+    // An IANA name such as "Europe/Riga"; the clock shows local time without one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clock_time_zone: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clock_label: Option<String>,
+    // "digital" (the default) or "analog".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clock_style: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub calendar_weekday: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub calendar_week_number: bool,
+    // A day name such as "sunday"; weeks start on Monday without one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calendar_first_weekday: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calendar_header_color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calendar_body_color: Option<String>,
+    // "day" (the default) or "month".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calendar_layout: Option<String>,
+    // "gauge" or "sparkline"; CPU and load default to sparklines, the rest to gauges.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metric_style: Option<String>,
+    // Seconds between `metric://` readings, 2 by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metric_interval_seconds: Option<u64>,
+    // Text drawn over the icon; each line of it is a row.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    // "top", "middle" or "bottom" (the default).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title_position: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title_color: Option<String>,
+    // Font size in pixels of a 72 pixel key; the title shrinks to fit without it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title_size: Option<u32>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub icon_on: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -73,6 +195,34 @@ pub struct StreamrsKeyBinding {
     pub status_interval_seconds: Option<u64>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub status_interval_ms: Option<u64>,
+    // "exit" (the default) picks icon_on or icon_off; "output" reads the command's stdout.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status_mode: Option<String>,
+    // Output mode: icons for the named states a status command may print.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub status_icons: BTreeMap<String, String>,
+    // Run in order instead of `action` and `shortcut` when present.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<StreamrsMacroStep>,
+    // Present on folder keys; pressing the key opens these keys as their own pages.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keys: Option<Vec<StreamrsKeyBinding>>,
+}
+
+// One macro step; exactly one field is expected to be set.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamrsMacroStep {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shortcut: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay_ms: Option<u64>,
+    // "next", "previous", "first", "last", "back" or a 1-based page number.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<String>,
 }
 
 impl Default for StreamrsKeyBinding {
@@ -80,13 +230,43 @@ impl Default for StreamrsKeyBinding {
         Self {
             action: None,
             shortcut: None,
+            text: None,
+            stop_on_error: false,
+            builtin: None,
+            brightness_step: None,
+            brightness_levels: Vec::new(),
+            long_press_action: None,
+            double_press_action: None,
             icon: default_icon_name(),
+            timer_command: None,
             clock_background: None,
+            clock_format: None,
+            clock_seconds: false,
+            clock_date: false,
+            clock_time_zone: None,
+            clock_label: None,
+            clock_style: None,
+            calendar_weekday: false,
+            calendar_week_number: false,
+            calendar_first_weekday: None,
+            calendar_header_color: None,
+            calendar_body_color: None,
+            calendar_layout: None,
+            metric_style: None,
+            metric_interval_seconds: None,
+            title: None,
+            title_position: None,
+            title_color: None,
+            title_size: None,
             icon_on: None,
             icon_off: None,
             status: None,
             status_interval_seconds: None,
             status_interval_ms: None,
+            status_mode: None,
+            status_icons: BTreeMap::new(),
+            steps: Vec::new(),
+            keys: None,
         }
     }
 }
@@ -100,8 +280,39 @@ impl Default for StreamrsConfig {
             usage_page: default_usage_page(),
             brightness: default_brightness(),
             keys_per_page: default_keys_per_page(),
-            keys: vec![StreamrsKeyBinding::default(); crate::paging::STREAMDECK_KEY_COUNT],
+            manual_page_navigation: false,
+            long_press_ms: default_long_press_ms(),
+            double_press_ms: default_double_press_ms(),
+            idle_dim_minutes: None,
+            idle_dim_brightness: None,
+            idle_off_minutes: None,
+            idle_blank_keys: false,
+            auto_profile: Vec::new(),
+            devices: BTreeMap::new(),
+            keys: vec![StreamrsKeyBinding::default(); DEFAULT_DEVICE_MODEL.key_count],
+        }
+    }
+}
+
+impl StreamrsKeyBinding {
+    pub fn is_folder(&self) -> bool {
+        self.keys.is_some()
+    }
+}
+
+impl StreamrsConfig {
+    pub fn device_model(&self) -> &'static DeviceModel {
+        DeviceModel::from_product_id(self.product_id).unwrap_or(&DEFAULT_DEVICE_MODEL)
+    }
+
+    // Keys shown at a folder level; `path` holds the key index of each folder
+    // from the top level down, so an empty path is the top level.
+    pub fn folder_keys(&self, path: &[usize]) -> Option<&[StreamrsKeyBinding]> {
+        let mut keys = self.keys.as_slice();
+        for &index in path {
+            keys = keys.get(index)?.keys.as_deref()?;
         }
+        Some(keys)
     }
 }
 
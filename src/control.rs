@@ -0,0 +1,194 @@
+use crate::paths::control_socket_path;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::time::Duration;
+
+pub const CONTROL_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// One request per line; the daemon answers each with one `ControlResponse` line.
+// Keys are 1-based physical key positions, and commands without a `serial`
+// apply to every connected deck.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum ControlRequest {
+    Reload,
+    SwitchProfile {
+        profile: String,
+    },
+    Page {
+        // "next", "previous", "first", "last", "back" or a 1-based page number.
+        page: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        serial: Option<String>,
+    },
+    PressKey {
+        key: usize,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        serial: Option<String>,
+    },
+    SetBrightness {
+        brightness: usize,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        serial: Option<String>,
+    },
+    SetKeyImage {
+        key: usize,
+        icon: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        seconds: Option<u64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        serial: Option<String>,
+    },
+    State,
+    // Sent by compositor integrations; `app_id` is `None` while nothing has focus.
+    FocusChanged {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        app_id: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ControlResponse {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<DaemonState>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DaemonState {
+    pub profile: String,
+    pub decks: Vec<DeckState>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeckState {
+    pub serial: String,
+    pub model: String,
+    pub profile: String,
+    // 1-based, like `page` in requests.
+    pub page: usize,
+    pub pages: usize,
+    // 1-based key number of each opened folder from the top level down.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub folder: Vec<usize>,
+    pub brightness: usize,
+}
+
+impl ControlResponse {
+    pub fn success() -> Self {
+        Self {
+            ok: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn failure(error: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            error: Some(error.into()),
+            state: None,
+        }
+    }
+
+    pub fn with_state(state: DaemonState) -> Self {
+        Self {
+            ok: true,
+            error: None,
+            state: Some(state),
+        }
+    }
+
+    pub fn into_result(self) -> Result<Option<DaemonState>, String> {
+        if self.ok {
+            Ok(self.state)
+        } else {
+            Err(self
+                .error
+                .unwrap_or_else(|| "streamrs daemon rejected the request".to_string()))
+        }
+    }
+}
+
+pub fn control_socket_available() -> bool {
+    control_socket_path().is_some_and(|path| path.exists())
+}
+
+pub fn send_control_request(request: &ControlRequest) -> Result<ControlResponse, String> {
+    let path = control_socket_path()
+        .ok_or_else(|| "XDG_RUNTIME_DIR is not set; no control socket available".to_string())?;
+    send_control_request_to(&path, request)
+}
+
+pub fn send_control_request_to(
+    path: &Path,
+    request: &ControlRequest,
+) -> Result<ControlResponse, String> {
+    let mut stream = UnixStream::connect(path).map_err(|err| {
+        format!(
+            "Failed to connect to control socket '{}': {err}",
+            path.display()
+        )
+    })?;
+    stream
+        .set_read_timeout(Some(CONTROL_REQUEST_TIMEOUT))
+        .map_err(|err| format!("Failed to set control socket timeout: {err}"))?;
+
+    let mut line = serde_json::to_string(request)
+        .map_err(|err| format!("Failed to encode control request: {err}"))?;
+    line.push('\n');
+    stream
+        .write_all(line.as_bytes())
+        .map_err(|err| format!("Failed to send control request: {err}"))?;
+
+    let mut reply = String::new();
+    BufReader::new(stream)
+        .read_line(&mut reply)
+        .map_err(|err| format!("Failed to read control response: {err}"))?;
+    if reply.trim().is_empty() {
+        return Err("streamrs daemon closed the control connection without a response".to_string());
+    }
+    serde_json::from_str(&reply).map_err(|err| format!("Invalid control response: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_use_a_command_tag_and_omit_unset_fields() {
+        let request = ControlRequest::Page {
+            page: "next".to_string(),
+            serial: None,
+        };
+        assert_eq!(
+            serde_json::to_string(&request).expect("request should encode"),
+            r#"{"command":"page","page":"next"}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<ControlRequest>(
+                r#"{"command":"set_key_image","key":3,"icon":"red.png","serial":"CL1"}"#
+            )
+            .expect("request should decode"),
+            ControlRequest::SetKeyImage {
+                key: 3,
+                icon: "red.png".to_string(),
+                seconds: None,
+                serial: Some("CL1".to_string()),
+            }
+        );
+        assert!(serde_json::from_str::<ControlRequest>(r#"{"command":"explode"}"#).is_err());
+    }
+
+    #[test]
+    fn failed_responses_surface_their_error() {
+        assert_eq!(ControlResponse::success().into_result(), Ok(None));
+        assert_eq!(
+            ControlResponse::failure("nope").into_result(),
+            Err("nope".to_string())
+        );
+    }
+}
@@ -0,0 +1,359 @@
+use std::env;
+use streamrs::control::{ControlRequest, DaemonState, send_control_request};
+
+const EXIT_REJECTED: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+const EXIT_UNREACHABLE: i32 = 3;
+
+#[derive(Debug, PartialEq, Eq)]
+enum Output {
+    Quiet,
+    Profile,
+    Status,
+    StatusJson,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Command {
+    request: ControlRequest,
+    output: Output,
+}
+
+fn print_usage(program: &str) {
+    println!("Usage: {program} <command> [--serial <serial>]");
+    println!();
+    println!("Commands:");
+    println!("  status [--json]                       Show the profile and the page of each deck");
+    println!("  reload                                Reload the profile configs");
+    println!("  profile [set <name>]                  Show or switch the active profile");
+    println!("  page <target>                         Change the page");
+    println!("  press <key>                           Run the action of a key on the current page");
+    println!("  brightness <0-100>                    Set the brightness until the next reload");
+    println!(
+        "  focus [<app_id>]                      Report the focused app for auto_profile rules"
+    );
+    println!("  key set-icon <key> <icon> [--for <duration>]");
+    println!(
+        "                                        Show an icon on a key for a while (default 5s)"
+    );
+    println!();
+    println!("Page targets are next, previous, first, last, back or a page number.");
+    println!("Keys are numbered from 1. Durations accept s, m and h suffixes, e.g. 30s or 2m.");
+    println!("--serial sends page, press, brightness and key commands to one deck only.");
+    println!();
+    println!("Exit status: 0 on success, 1 when the daemon rejects the request,");
+    println!("2 on usage errors and 3 when the daemon is not reachable.");
+}
+
+fn parse_key(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(key) if key > 0 => Ok(key),
+        _ => Err(format!(
+            "Invalid key '{value}'; expected a key number from 1"
+        )),
+    }
+}
+
+fn parse_duration_seconds(value: &str) -> Result<u64, String> {
+    let (digits, scale) = match value.char_indices().find(|(_, ch)| !ch.is_ascii_digit()) {
+        None => (value, 1),
+        Some((index, _)) => match &value[index..] {
+            "s" => (&value[..index], 1),
+            "m" => (&value[..index], 60),
+            "h" => (&value[..index], 3600),
+            _ => {
+                return Err(format!(
+                    "Invalid duration '{value}'; expected e.g. 5s, 2m or 1h"
+                ));
+            }
+        },
+    };
+    digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|amount| amount.checked_mul(scale))
+        .filter(|seconds| *seconds > 0)
+        .ok_or_else(|| format!("Invalid duration '{value}'; expected e.g. 5s, 2m or 1h"))
+}
+
+fn take_option(args: &mut Vec<String>, name: &str) -> Result<Option<String>, String> {
+    let Some(index) = args.iter().position(|arg| arg == name) else {
+        return Ok(None);
+    };
+    if index + 1 >= args.len() {
+        return Err(format!("Missing value for {name}"));
+    }
+    let value = args.remove(index + 1);
+    args.remove(index);
+    Ok(Some(value))
+}
+
+fn take_flag(args: &mut Vec<String>, name: &str) -> bool {
+    let Some(index) = args.iter().position(|arg| arg == name) else {
+        return false;
+    };
+    args.remove(index);
+    true
+}
+
+fn parse_command(mut args: Vec<String>) -> Result<Command, String> {
+    let serial = take_option(&mut args, "--serial")?;
+    let json = take_flag(&mut args, "--json");
+    let duration = take_option(&mut args, "--for")?;
+    let words = args.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let command = match words.as_slice() {
+        ["status"] => Command {
+            request: ControlRequest::State,
+            output: if json {
+                Output::StatusJson
+            } else {
+                Output::Status
+            },
+        },
+        ["reload"] => Command {
+            request: ControlRequest::Reload,
+            output: Output::Quiet,
+        },
+        ["profile"] => Command {
+            request: ControlRequest::State,
+            output: Output::Profile,
+        },
+        ["profile", "set", profile] => Command {
+            request: ControlRequest::SwitchProfile {
+                profile: profile.to_string(),
+            },
+            output: Output::Quiet,
+        },
+        ["page", page] => Command {
+            request: ControlRequest::Page {
+                page: page.to_string(),
+                serial: serial.clone(),
+            },
+            output: Output::Quiet,
+        },
+        ["press", key] => Command {
+            request: ControlRequest::PressKey {
+                key: parse_key(key)?,
+                serial: serial.clone(),
+            },
+            output: Output::Quiet,
+        },
+        ["brightness", value] => Command {
+            request: ControlRequest::SetBrightness {
+                brightness: value
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|brightness| *brightness <= 100)
+                    .ok_or_else(|| {
+                        format!("Invalid brightness '{value}'; expected a number from 0 to 100")
+                    })?,
+                serial: serial.clone(),
+            },
+            output: Output::Quiet,
+        },
+        ["focus"] => Command {
+            request: ControlRequest::FocusChanged { app_id: None },
+            output: Output::Quiet,
+        },
+        ["focus", app_id] => Command {
+            request: ControlRequest::FocusChanged {
+                app_id: Some(app_id.to_string()),
+            },
+            output: Output::Quiet,
+        },
+        ["key", "set-icon", key, icon] => Command {
+            request: ControlRequest::SetKeyImage {
+                key: parse_key(key)?,
+                icon: icon.to_string(),
+                seconds: duration
+                    .as_deref()
+                    .map(parse_duration_seconds)
+                    .transpose()?,
+                serial: serial.clone(),
+            },
+            output: Output::Quiet,
+        },
+        [] => return Err("Missing command".to_string()),
+        _ => return Err(format!("Unknown command: {}", words.join(" "))),
+    };
+
+    let takes_serial = matches!(
+        command.request,
+        ControlRequest::Page { .. }
+            | ControlRequest::PressKey { .. }
+            | ControlRequest::SetBrightness { .. }
+            | ControlRequest::SetKeyImage { .. }
+    );
+    if serial.is_some() && !takes_serial {
+        return Err("--serial is only supported by page, press, brightness and key".to_string());
+    }
+    if json && command.output != Output::StatusJson {
+        return Err("--json is only supported by status".to_string());
+    }
+    if duration.is_some() && !matches!(command.request, ControlRequest::SetKeyImage { .. }) {
+        return Err("--for is only supported by key set-icon".to_string());
+    }
+    Ok(command)
+}
+
+fn format_status(state: &DaemonState) -> String {
+    let mut lines = vec![format!("Profile: {}", state.profile)];
+    if state.decks.is_empty() {
+        lines.push("No Stream Deck connected".to_string());
+    }
+    for deck in &state.decks {
+        let mut line = format!(
+            "{} ({}): profile {}, page {}/{}, brightness {}",
+            deck.serial, deck.model, deck.profile, deck.page, deck.pages, deck.brightness
+        );
+        if !deck.folder.is_empty() {
+            let folder = deck
+                .folder
+                .iter()
+                .map(usize::to_string)
+                .collect::<Vec<_>>()
+                .join(" > ");
+            line.push_str(&format!(", folder {folder}"));
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+pub(crate) fn run() {
+    let program = env::args()
+        .next()
+        .unwrap_or_else(|| "streamrsctl".to_string());
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if args.first().is_some_and(|arg| arg == "help")
+        || args
+            .iter()
+            .any(|arg| matches!(arg.as_str(), "--help" | "-h"))
+    {
+        print_usage(&program);
+        return;
+    }
+
+    let command = match parse_command(args) {
+        Ok(command) => command,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            eprintln!("Run '{program} --help' for usage.");
+            std::process::exit(EXIT_USAGE);
+        }
+    };
+
+    let response = match send_control_request(&command.request) {
+        Ok(response) => response,
+        Err(err) => {
+            eprintln!("Error: streamrs daemon is not reachable: {err}");
+            std::process::exit(EXIT_UNREACHABLE);
+        }
+    };
+    let state = match response.into_result() {
+        Ok(state) => state,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            std::process::exit(EXIT_REJECTED);
+        }
+    };
+
+    match (command.output, state) {
+        (Output::Profile, Some(state)) => println!("{}", state.profile),
+        (Output::Status, Some(state)) => println!("{}", format_status(&state)),
+        (Output::StatusJson, Some(state)) => match serde_json::to_string_pretty(&state) {
+            Ok(json) => println!("{json}"),
+            Err(err) => {
+                eprintln!("Error: failed to encode state: {err}");
+                std::process::exit(EXIT_REJECTED);
+            }
+        },
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use streamrs::control::DeckState;
+
+    fn parse(args: &[&str]) -> Result<Command, String> {
+        parse_command(args.iter().map(|arg| arg.to_string()).collect())
+    }
+
+    #[test]
+    fn subcommands_map_to_control_requests() {
+        assert_eq!(
+            parse(&["profile", "set", "work"]).map(|command| command.request),
+            Ok(ControlRequest::SwitchProfile {
+                profile: "work".to_string()
+            })
+        );
+        assert_eq!(
+            parse(&["page", "next", "--serial", "CL1"]).map(|command| command.request),
+            Ok(ControlRequest::Page {
+                page: "next".to_string(),
+                serial: Some("CL1".to_string()),
+            })
+        );
+        assert_eq!(
+            parse(&["key", "set-icon", "4", "foo.png", "--for", "2m"]).map(|c| c.request),
+            Ok(ControlRequest::SetKeyImage {
+                key: 4,
+                icon: "foo.png".to_string(),
+                seconds: Some(120),
+                serial: None,
+            })
+        );
+        assert_eq!(
+            parse(&["focus", "org.gimp.GIMP"]).map(|command| command.request),
+            Ok(ControlRequest::FocusChanged {
+                app_id: Some("org.gimp.GIMP".to_string()),
+            })
+        );
+        assert_eq!(
+            parse(&["status", "--json"]).map(|command| command.output),
+            Ok(Output::StatusJson)
+        );
+    }
+
+    #[test]
+    fn invalid_arguments_are_reported() {
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["press", "0"]).is_err());
+        assert!(parse(&["brightness", "101"]).is_err());
+        assert!(parse(&["reload", "--serial", "CL1"]).is_err());
+        assert!(parse(&["key", "set-icon", "1", "a.png", "--for", "soon"]).is_err());
+        assert!(parse(&["page"]).is_err());
+    }
+
+    #[test]
+    fn durations_accept_unit_suffixes() {
+        assert_eq!(parse_duration_seconds("5"), Ok(5));
+        assert_eq!(parse_duration_seconds("5s"), Ok(5));
+        assert_eq!(parse_duration_seconds("1h"), Ok(3600));
+        assert!(parse_duration_seconds("0s").is_err());
+        assert!(parse_duration_seconds("s").is_err());
+    }
+
+    #[test]
+    fn status_lists_each_deck() {
+        let state = DaemonState {
+            profile: "default".to_string(),
+            decks: vec![DeckState {
+                serial: "CL1".to_string(),
+                model: "Stream Deck MK.2".to_string(),
+                profile: "default".to_string(),
+                page: 2,
+                pages: 3,
+                folder: vec![4],
+                brightness: 60,
+            }],
+        };
+        assert_eq!(
+            format_status(&state),
+            "Profile: default\nCL1 (Stream Deck MK.2): profile default, page 2/3, brightness 60, folder 4"
+        );
+    }
+}
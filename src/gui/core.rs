@@ -10,6 +10,8 @@ mod i18n;
 mod icon_cache;
 #[path = "core/icon_catalog.rs"]
 mod icon_catalog;
+#[path = "core/macro_steps.rs"]
+mod macro_steps;
 #[path = "core/paging.rs"]
 mod paging;
 #[path = "core/prelude.rs"]
@@ -23,6 +25,7 @@ pub(crate) use editor::*;
 pub(crate) use i18n::*;
 pub(crate) use icon_cache::*;
 pub(crate) use icon_catalog::*;
+pub(crate) use macro_steps::*;
 pub(crate) use paging::*;
 pub(crate) use prelude::*;
 pub(crate) use style::*;
@@ -1,4 +1,7 @@
 use super::*;
+use streamrs::control::{
+    ControlRequest, ControlResponse, control_socket_available, send_control_request,
+};
 
 pub(crate) fn load_config(path: &Path) -> Result<Config, String> {
     let profile = profile_from_config_path(path);
@@ -11,7 +14,15 @@ pub(crate) fn save_config(path: &Path, config: &Config) -> Result<(), String> {
     streamrs_profile::save(path, config)
 }
 
+// Prefers the daemon's control socket; signals are only sent when no daemon answers on it.
 pub(crate) fn signal_daemon_reload() -> Result<(), String> {
+    if control_socket_available() {
+        match send_control_request(&ControlRequest::Reload).and_then(ControlResponse::into_result) {
+            Ok(_) => return Ok(()),
+            Err(err) => eprintln!("{err}"),
+        }
+    }
+
     let systemctl = std::process::Command::new("systemctl")
         .args([
             "--user",
@@ -197,6 +208,23 @@ mod tests {
         assert_eq!(config.keys[0].icon, "custom.png");
     }
 
+    #[test]
+    fn save_config_leaves_out_default_press_thresholds() {
+        let dir = test_temp_dir("press-thresholds");
+        let path = dir.join("test_profile.toml");
+        let mut config = Config::default();
+        save_config(&path, &config).expect("default config should save");
+        let saved = fs::read_to_string(&path).expect("saved config should be readable");
+        assert!(!saved.contains("long_press_ms"));
+        assert!(!saved.contains("double_press_ms"));
+
+        config.long_press_ms = 800;
+        save_config(&path, &config).expect("config should save");
+        let saved = fs::read_to_string(&path).expect("saved config should be readable");
+        assert!(saved.contains("long_press_ms = 800"));
+        assert!(!saved.contains("double_press_ms"));
+    }
+
     #[test]
     fn load_config_blank_profile_remains_empty() {
         let dir = test_temp_dir("blank-profile");
@@ -2,6 +2,7 @@ use super::*;
 
 pub(crate) const EMBEDDED_DECK_LABEL: &str = "embedded:scripts/streamdeck.svg";
 pub(crate) const EMBEDDED_DECK_SVG: &[u8] = include_bytes!("../../../scripts/streamdeck.svg");
+const TEMPLATE_KEY_COUNT: usize = 15;
 
 pub(crate) fn render_blank_base(
     label: &str,
@@ -116,8 +117,11 @@ pub(crate) fn detect_key_slots(base: &RgbaImage) -> Result<Vec<KeySlot>, String>
         }
     }
 
-    if slots.len() != KEY_COUNT {
-        return Err(format!("Expected 15 buttons, found {}", slots.len()));
+    if slots.len() != TEMPLATE_KEY_COUNT {
+        return Err(format!(
+            "Expected {TEMPLATE_KEY_COUNT} buttons, found {}",
+            slots.len()
+        ));
     }
 
     slots.sort_by(|left, right| {
@@ -165,22 +169,33 @@ pub(crate) fn scale_slots(template_slots: &[KeySlot], width: u32, height: u32) -
         .collect()
 }
 
-pub(crate) fn fallback_slots(width: u32, height: u32) -> Vec<KeySlot> {
+pub(crate) fn uses_deck_template(model: &DeviceModel) -> bool {
+    model.key_count == TEMPLATE_KEY_COUNT && (model.rows, model.columns) == (3, 5)
+}
+
+pub(crate) fn fallback_slots(model: &DeviceModel, width: u32, height: u32) -> Vec<KeySlot> {
+    let rows = model.rows.max(1) as i32;
+    let columns = model.columns.max(1) as i32;
     let margin_x = (width as f32 * 0.12) as i32;
     let margin_top = (height as f32 * 0.18) as i32;
     let gap_x = (width as f32 * 0.035) as i32;
     let gap_y = (height as f32 * 0.065) as i32;
 
-    let key_w = ((width as i32 - (margin_x * 2) - (gap_x * 4)) / 5).max(1);
-    let key_h = ((height as i32 - (margin_top * 2) - (gap_y * 2)) / 3).max(1);
-
-    let mut slots = Vec::with_capacity(KEY_COUNT);
-    for row in 0..3 {
-        for col in 0..5 {
-            let x0 = margin_x + col * (key_w + gap_x);
-            let y0 = margin_top + row * (key_h + gap_y);
-            let x1 = x0 + key_w;
-            let y1 = y0 + key_h;
+    let key_w = ((width as i32 - (margin_x * 2) - (gap_x * (columns - 1))) / columns).max(1);
+    let key_h = ((height as i32 - (margin_top * 2) - (gap_y * (rows - 1))) / rows).max(1);
+    let key_size = key_w.min(key_h);
+    let grid_w = key_size * columns + gap_x * (columns - 1);
+    let grid_h = key_size * rows + gap_y * (rows - 1);
+    let origin_x = (width as i32 - grid_w) / 2;
+    let origin_y = (height as i32 - grid_h) / 2;
+
+    let mut slots = Vec::with_capacity(model.key_count);
+    for row in 0..rows {
+        for col in 0..columns {
+            let x0 = origin_x + col * (key_size + gap_x);
+            let y0 = origin_y + row * (key_size + gap_y);
+            let x1 = x0 + key_size;
+            let y1 = y0 + key_size;
             slots.push(KeySlot {
                 x0: x0.max(0) as u32,
                 y0: y0.max(0) as u32,
@@ -195,7 +210,30 @@ pub(crate) fn fallback_slots(width: u32, height: u32) -> Vec<KeySlot> {
     slots
 }
 
-pub(crate) fn key_slots_for_deck(deck_svg_path: &Path) -> Vec<KeySlot> {
+pub(crate) fn deck_svg_for_model(model: &DeviceModel, width: u32, height: u32) -> String {
+    let radius = width.min(height) / 12;
+    let mut svg = format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}"><rect width="{width}" height="{height}" rx="{radius}" fill="#2e3038"/>"##
+    );
+    for slot in fallback_slots(model, width, height) {
+        let key_width = slot.x1 - slot.x0;
+        let key_height = slot.y1 - slot.y0;
+        svg.push_str(&format!(
+            r##"<rect x="{}" y="{}" width="{key_width}" height="{key_height}" rx="{}" fill="#000000"/>"##,
+            slot.x0,
+            slot.y0,
+            key_width.min(key_height) / 8
+        ));
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+pub(crate) fn key_slots_for_deck(deck_svg_path: &Path, model: &DeviceModel) -> Vec<KeySlot> {
+    if !uses_deck_template(model) {
+        return fallback_slots(model, PREVIEW_WIDTH, PREVIEW_HEIGHT);
+    }
+
     let rendered = match fs::read(deck_svg_path) {
         Ok(svg) => render_blank_base(
             &deck_svg_path.display().to_string(),
@@ -223,7 +261,7 @@ pub(crate) fn key_slots_for_deck(deck_svg_path: &Path) -> Vec<KeySlot> {
         Ok(image) => image,
         Err(err) => {
             eprintln!("{err}; using fallback button layout");
-            return fallback_slots(PREVIEW_WIDTH, PREVIEW_HEIGHT);
+            return fallback_slots(model, PREVIEW_WIDTH, PREVIEW_HEIGHT);
         }
     };
 
@@ -231,7 +269,7 @@ pub(crate) fn key_slots_for_deck(deck_svg_path: &Path) -> Vec<KeySlot> {
         Ok(slots) => slots,
         Err(err) => {
             eprintln!("{err}; using fallback button layout");
-            return fallback_slots(PREVIEW_WIDTH, PREVIEW_HEIGHT);
+            return fallback_slots(model, PREVIEW_WIDTH, PREVIEW_HEIGHT);
         }
     };
 
@@ -291,23 +329,29 @@ pub(crate) fn deck_background_temp_path() -> PathBuf {
 
 pub(crate) fn write_deck_background_png(
     deck_svg_path: &Path,
+    model: &DeviceModel,
     width: u32,
     height: u32,
 ) -> Option<PathBuf> {
-    let rendered = match fs::read(deck_svg_path) {
-        Ok(svg) => render_blank_base(
-            &deck_svg_path.display().to_string(),
-            &svg,
-            deck_svg_path.parent(),
-            width,
-            height,
-        ),
-        Err(err) => {
-            eprintln!(
-                "Failed to read deck SVG '{}': {err}; falling back to embedded template",
-                deck_svg_path.display()
-            );
-            render_blank_base(EMBEDDED_DECK_LABEL, EMBEDDED_DECK_SVG, None, width, height)
+    let rendered = if !uses_deck_template(model) {
+        let svg = deck_svg_for_model(model, width, height);
+        load_svg_image_data(model.name, svg.as_bytes(), None, width, height)
+    } else {
+        match fs::read(deck_svg_path) {
+            Ok(svg) => render_blank_base(
+                &deck_svg_path.display().to_string(),
+                &svg,
+                deck_svg_path.parent(),
+                width,
+                height,
+            ),
+            Err(err) => {
+                eprintln!(
+                    "Failed to read deck SVG '{}': {err}; falling back to embedded template",
+                    deck_svg_path.display()
+                );
+                render_blank_base(EMBEDDED_DECK_LABEL, EMBEDDED_DECK_SVG, None, width, height)
+            }
         }
     }
     .ok()?;
@@ -14,8 +14,8 @@ pub(crate) fn slot_has_plain_blank_key(
 ) -> bool {
     let mut state = state.borrow_mut();
     normalize_config(&mut state.config);
-    key_index_for_slot(&state.config, current_page, selected_slot)
-        .and_then(|index| state.config.keys.get(index))
+    key_index_for_slot(&state.config, &state.folder, current_page, selected_slot)
+        .and_then(|index| state.folder_keys().get(index))
         .is_some_and(is_plain_blank_key)
 }
 
@@ -26,29 +26,41 @@ pub(crate) fn refresh_key_grid(
     current_page: usize,
     clock_backgrounds: &[String],
 ) {
-    let (config, image_dirs, page, total_pages) = {
+    let (config, folder, image_dirs, page, total_pages) = {
         let mut state = state.borrow_mut();
         normalize_config(&mut state.config);
-        let total_pages = page_count(&state.config).max(1);
+        state.normalize_folder();
+        let total_pages = page_count(&state.config, &state.folder).max(1);
         let page = current_page.min(total_pages.saturating_sub(1));
         (
             state.config.clone(),
+            state.folder.clone(),
             state.image_dirs.clone(),
             page,
             total_pages,
         )
     };
+    let keys = config.folder_keys(&folder).unwrap_or_default();
 
     for (slot, picture) in key_pictures.iter().enumerate() {
-        if let Some(key_index) = key_index_for_slot(&config, page, slot) {
-            if let Some(key) = config.keys.get(key_index) {
+        if let Some(key_index) = key_index_for_slot(&config, &folder, page, slot) {
+            if let Some(key) = keys.get(key_index) {
                 set_picture_icon(picture, &image_dirs, key, clock_backgrounds);
                 if is_plain_blank_key(key) {
                     key_buttons[slot].add_css_class("key-blank-binding");
                 } else {
                     key_buttons[slot].remove_css_class("key-blank-binding");
                 }
-                let tip = if is_plain_blank_key(key) {
+                let tip = if key.is_folder() {
+                    trf(
+                        "Button {button} (page {page}, slot {slot}) - Folder",
+                        &[
+                            ("button", (key_index + 1).to_string()),
+                            ("page", (page + 1).to_string()),
+                            ("slot", (slot + 1).to_string()),
+                        ],
+                    )
+                } else if is_plain_blank_key(key) {
                     trf(
                         "Button {button} (page {page}, slot {slot}) - Blank button",
                         &[
@@ -76,7 +88,8 @@ pub(crate) fn refresh_key_grid(
             continue;
         }
 
-        if let Some(nav_slot) = navigation_slot_for_slot(&config, page, total_pages, slot) {
+        if let Some(nav_slot) = navigation_slot_for_slot(&config, &folder, page, total_pages, slot)
+        {
             key_buttons[slot].set_sensitive(true);
             key_buttons[slot].remove_css_class("key-has-binding");
             key_buttons[slot].remove_css_class("key-blank-binding");
@@ -87,6 +100,7 @@ pub(crate) fn refresh_key_grid(
             let tip = match nav_slot {
                 ReservedNavigationSlot::PreviousPage => tr("Previous page"),
                 ReservedNavigationSlot::NextPage => tr("Next page"),
+                ReservedNavigationSlot::FolderBack => tr("Back"),
             };
             key_buttons[slot].set_tooltip_text(Some(&tip));
             picture.set_tooltip_text(Some(&tip));
@@ -126,6 +140,9 @@ pub(crate) fn action_mode_from_index(selected: u32) -> ActionMode {
     match selected {
         1 => ActionMode::Launch,
         2 => ActionMode::KeyboardShortcut,
+        3 => ActionMode::Folder,
+        4 => ActionMode::Macro,
+        5 => ActionMode::Text,
         _ => ActionMode::None,
     }
 }
@@ -136,6 +153,27 @@ pub(crate) fn set_action_mode_visibility(widgets: &EditorWidgets, mode: ActionMo
 
     widgets.action_entry.set_visible(is_launch);
     widgets.shortcut_entry.set_visible(is_shortcut);
+    widgets.text_entry.set_visible(mode == ActionMode::Text);
+    widgets
+        .open_folder_button
+        .set_visible(mode == ActionMode::Folder);
+    widgets.steps_group.set_visible(mode == ActionMode::Macro);
+}
+
+pub(crate) fn title_position_from_index(selected: u32) -> TitlePosition {
+    match selected {
+        0 => TitlePosition::Top,
+        1 => TitlePosition::Middle,
+        _ => TitlePosition::Bottom,
+    }
+}
+
+fn title_position_index(position: Option<&str>) -> u32 {
+    match position.map(parse_title_position) {
+        Some(Ok(TitlePosition::Top)) => 0,
+        Some(Ok(TitlePosition::Middle)) => 1,
+        _ => 2,
+    }
 }
 
 pub(crate) fn set_editor_mode_visibility(widgets: &EditorWidgets, mode: EditorMode) {
@@ -154,11 +192,16 @@ pub(crate) fn set_editor_controls_sensitive(widgets: &EditorWidgets, enabled: bo
     widgets.action_type_dropdown.set_sensitive(enabled);
     widgets.action_entry.set_sensitive(enabled);
     widgets.shortcut_entry.set_sensitive(enabled);
+    widgets.text_entry.set_sensitive(enabled);
+    widgets.open_folder_button.set_sensitive(enabled);
+    widgets.steps_group.set_sensitive(enabled);
     widgets.icon_kind_dropdown.set_sensitive(enabled);
     widgets.icon_row.set_sensitive(enabled);
     widgets.icon_on_row.set_sensitive(enabled);
     widgets.icon_off_row.set_sensitive(enabled);
     widgets.clock_background_row.set_sensitive(enabled);
+    widgets.title_entry.set_sensitive(enabled);
+    widgets.title_position_dropdown.set_sensitive(enabled);
     widgets.status_group.set_sensitive(enabled);
     widgets.apply_button.set_sensitive(enabled);
     widgets.clear_button.set_sensitive(enabled);
@@ -228,12 +271,15 @@ pub(crate) fn populate_editor(
     let (key, image_dirs, key_index, nav_slot) = {
         let mut state = state.borrow_mut();
         normalize_config(&mut state.config);
-        let total_pages = page_count(&state.config).max(1);
+        state.normalize_folder();
+        let folder = state.folder.as_slice();
+        let total_pages = page_count(&state.config, folder).max(1);
         let page = current_page.min(total_pages.saturating_sub(1));
-        let key_index = key_index_for_slot(&state.config, page, selected_slot);
-        let nav_slot = navigation_slot_for_slot(&state.config, page, total_pages, selected_slot);
+        let key_index = key_index_for_slot(&state.config, folder, page, selected_slot);
+        let nav_slot =
+            navigation_slot_for_slot(&state.config, folder, page, total_pages, selected_slot);
         let key = key_index
-            .and_then(|index| state.config.keys.get(index).cloned())
+            .and_then(|index| state.folder_keys().get(index).cloned())
             .unwrap_or_default();
         (
             key,
@@ -246,12 +292,18 @@ pub(crate) fn populate_editor(
     set_editor_controls_sensitive(widgets, key_index.is_some());
 
     if key_index.is_some() {
-        let action_mode = if key
+        let action_mode = if key.is_folder() {
+            ActionMode::Folder
+        } else if !key.steps.is_empty() {
+            ActionMode::Macro
+        } else if key
             .shortcut
             .as_deref()
             .is_some_and(|shortcut| !shortcut.trim().is_empty())
         {
             ActionMode::KeyboardShortcut
+        } else if key.text.as_deref().is_some_and(|text| !text.is_empty()) {
+            ActionMode::Text
         } else if key
             .action
             .as_deref()
@@ -267,14 +319,28 @@ pub(crate) fn populate_editor(
                 ActionMode::None => 0,
                 ActionMode::Launch => 1,
                 ActionMode::KeyboardShortcut => 2,
+                ActionMode::Folder => 3,
+                ActionMode::Macro => 4,
+                ActionMode::Text => 5,
             });
         set_action_mode_visibility(widgets, action_mode);
+        widgets.steps_editor.set_steps(&key.steps);
+        widgets.stop_on_error_switch.set_active(key.stop_on_error);
         widgets
             .action_entry
             .set_text(key.action.as_deref().unwrap_or_default());
         widgets
             .shortcut_entry
             .set_text(key.shortcut.as_deref().unwrap_or_default());
+        widgets
+            .text_entry
+            .set_text(key.text.as_deref().unwrap_or_default());
+        widgets
+            .title_entry
+            .set_text(key.title.as_deref().unwrap_or_default());
+        widgets
+            .title_position_dropdown
+            .set_selected(title_position_index(key.title_position.as_deref()));
 
         let mode = if icon_is_clock(&key.icon) {
             EditorMode::Clock
@@ -362,8 +428,13 @@ pub(crate) fn populate_editor(
     } else if let Some(nav_slot) = nav_slot {
         widgets.action_type_dropdown.set_selected(0);
         set_action_mode_visibility(widgets, ActionMode::None);
+        widgets.steps_editor.set_steps(&[]);
+        widgets.stop_on_error_switch.set_active(false);
         widgets.action_entry.set_text("");
         widgets.shortcut_entry.set_text("");
+        widgets.text_entry.set_text("");
+        widgets.title_entry.set_text("");
+        widgets.title_position_dropdown.set_selected(2);
         widgets.status_entry.set_text("");
         widgets
             .interval_spin
@@ -391,8 +462,13 @@ pub(crate) fn populate_editor(
     } else {
         widgets.action_type_dropdown.set_selected(0);
         set_action_mode_visibility(widgets, ActionMode::None);
+        widgets.steps_editor.set_steps(&[]);
+        widgets.stop_on_error_switch.set_active(false);
         widgets.action_entry.set_text("");
         widgets.shortcut_entry.set_text("");
+        widgets.text_entry.set_text("");
+        widgets.title_entry.set_text("");
+        widgets.title_position_dropdown.set_selected(2);
         widgets.status_entry.set_text("");
         widgets
             .interval_spin
@@ -414,6 +490,18 @@ pub(crate) fn populate_editor(
     }
 }
 
+// The key the editor shows for `selected_slot`, if the slot holds one.
+pub(crate) fn selected_key_binding(
+    state: &AppState,
+    current_page: usize,
+    selected_slot: usize,
+) -> Option<KeyBinding> {
+    let folder = state.folder.as_slice();
+    let page = current_page.min(page_count(&state.config, folder).max(1) - 1);
+    key_index_for_slot(&state.config, folder, page, selected_slot)
+        .and_then(|index| state.folder_keys().get(index).cloned())
+}
+
 pub(crate) fn populate_editor_guarded(
     state: &Rc<RefCell<AppState>>,
     current_page: usize,
@@ -465,6 +553,12 @@ pub(crate) fn apply_editor_to_selected_key(
 ) -> bool {
     let launch_action = trimmed_or_none(widgets.action_entry.text().as_str());
     let shortcut = trimmed_or_none(widgets.shortcut_entry.text().as_str());
+    let text = Some(widgets.text_entry.text().to_string()).filter(|text| !text.is_empty());
+    let title = trimmed_or_none(widgets.title_entry.text().as_str());
+    let title_position =
+        title_position_from_index(widgets.title_position_dropdown.selected());
+    let steps = widgets.steps_editor.steps();
+    let stop_on_error = widgets.stop_on_error_switch.is_active();
     let action_mode = action_mode(widgets);
     let mode = editor_mode(widgets);
     let regular_icon = dropdown_selected_icon(&widgets.icon_dropdown, icon_names);
@@ -480,29 +574,68 @@ pub(crate) fn apply_editor_to_selected_key(
         let mut state = state.borrow_mut();
         normalize_config(&mut state.config);
 
-        let Some(key_index) = key_index_for_slot(&state.config, current_page, selected_slot) else {
+        let Some(key_index) =
+            key_index_for_slot(&state.config, &state.folder, current_page, selected_slot)
+        else {
             return false;
         };
 
-        let key = &mut state.config.keys[key_index];
+        let key = &mut state.folder_keys_mut()[key_index];
+        key.text = None;
+        // Color and size are kept from the config while the key has a title.
+        key.title_position = match title_position {
+            _ if title.is_none() => None,
+            TitlePosition::Top => Some("top".to_string()),
+            TitlePosition::Middle => Some("middle".to_string()),
+            TitlePosition::Bottom => None,
+        };
+        if title.is_none() {
+            key.title_color = None;
+            key.title_size = None;
+        }
+        key.title = title;
+        key.steps.clear();
+        key.stop_on_error = false;
         match action_mode {
             ActionMode::None => {
                 key.action = None;
                 key.shortcut = None;
+                key.keys = None;
             }
             ActionMode::Launch => {
                 key.action = launch_action;
                 key.shortcut = None;
+                key.keys = None;
             }
             ActionMode::KeyboardShortcut => {
                 key.action = None;
                 key.shortcut = shortcut;
+                key.keys = None;
+            }
+            ActionMode::Folder => {
+                key.action = None;
+                key.shortcut = None;
+                key.keys.get_or_insert_with(Vec::new);
+            }
+            ActionMode::Macro => {
+                key.action = None;
+                key.shortcut = None;
+                key.keys = None;
+                key.steps = steps;
+                key.stop_on_error = stop_on_error;
+            }
+            ActionMode::Text => {
+                key.action = None;
+                key.shortcut = None;
+                key.keys = None;
+                key.text = text;
             }
         }
         match mode {
             EditorMode::Blank => {
                 key.icon = CLOCK_BACKGROUND_ICON.to_string();
-                key.clock_background = None;
+                clear_clock_options(key);
+                clear_calendar_options(key);
                 key.status = None;
                 key.status_interval_seconds = None;
                 key.status_interval_ms = None;
@@ -511,6 +644,7 @@ pub(crate) fn apply_editor_to_selected_key(
             }
             EditorMode::Clock => {
                 key.icon = CLOCK_ICON_ALIAS.to_string();
+                clear_calendar_options(key);
                 let selected_bg =
                     dropdown_selected_icon(&widgets.clock_background_dropdown, clock_backgrounds);
                 key.clock_background = if selected_bg == CLOCK_BACKGROUND_ICON {
@@ -526,7 +660,7 @@ pub(crate) fn apply_editor_to_selected_key(
             }
             EditorMode::Calendar => {
                 key.icon = CALENDAR_ICON_ALIAS.to_string();
-                key.clock_background = None;
+                clear_clock_options(key);
                 key.status = None;
                 key.status_interval_seconds = None;
                 key.status_interval_ms = None;
@@ -535,7 +669,8 @@ pub(crate) fn apply_editor_to_selected_key(
             }
             EditorMode::Regular => {
                 key.icon = regular_icon;
-                key.clock_background = None;
+                clear_clock_options(key);
+                clear_calendar_options(key);
                 key.status = None;
                 key.status_interval_seconds = None;
                 key.status_interval_ms = None;
@@ -544,7 +679,8 @@ pub(crate) fn apply_editor_to_selected_key(
             }
             EditorMode::Status => {
                 key.icon = icon_off_selected.clone();
-                key.clock_background = None;
+                clear_clock_options(key);
+                clear_calendar_options(key);
                 key.status = status;
                 key.status_interval_seconds = key.status.as_ref().map(|_| interval);
                 key.status_interval_ms = None;
@@ -556,6 +692,25 @@ pub(crate) fn apply_editor_to_selected_key(
     true
 }
 
+fn clear_clock_options(key: &mut KeyBinding) {
+    key.clock_background = None;
+    key.clock_format = None;
+    key.clock_seconds = false;
+    key.clock_date = false;
+    key.clock_time_zone = None;
+    key.clock_label = None;
+    key.clock_style = None;
+}
+
+fn clear_calendar_options(key: &mut KeyBinding) {
+    key.calendar_weekday = false;
+    key.calendar_week_number = false;
+    key.calendar_first_weekday = None;
+    key.calendar_header_color = None;
+    key.calendar_body_color = None;
+    key.calendar_layout = None;
+}
+
 pub(crate) fn clear_selected_key(
     state: &Rc<RefCell<AppState>>,
     current_page: usize,
@@ -563,18 +718,52 @@ pub(crate) fn clear_selected_key(
 ) -> bool {
     let mut state = state.borrow_mut();
     normalize_config(&mut state.config);
-    let Some(key_index) = key_index_for_slot(&state.config, current_page, selected_slot) else {
+    let Some(key_index) =
+        key_index_for_slot(&state.config, &state.folder, current_page, selected_slot)
+    else {
         return false;
     };
-    let is_last = key_index + 1 == state.config.keys.len();
+    let keys = state.folder_keys_mut();
+    let is_last = key_index + 1 == keys.len();
     if is_last {
-        state.config.keys.remove(key_index);
+        keys.remove(key_index);
     } else {
-        state.config.keys[key_index] = KeyBinding::default();
+        keys[key_index] = KeyBinding::default();
     }
     true
 }
 
+// Turns the selected key into a folder if needed and makes it the edited level.
+pub(crate) fn open_folder_at_slot(
+    state: &Rc<RefCell<AppState>>,
+    current_page: usize,
+    selected_slot: usize,
+) -> bool {
+    let mut state = state.borrow_mut();
+    normalize_config(&mut state.config);
+    let Some(key_index) =
+        key_index_for_slot(&state.config, &state.folder, current_page, selected_slot)
+    else {
+        return false;
+    };
+    let key = &mut state.folder_keys_mut()[key_index];
+    if !key.is_folder() {
+        key.action = None;
+        key.shortcut = None;
+        key.keys = Some(Vec::new());
+    }
+    state.folder.push(key_index);
+    true
+}
+
+// Leaves the current folder and returns the page and slot of its key one level up.
+pub(crate) fn close_folder(state: &Rc<RefCell<AppState>>) -> Option<(usize, usize)> {
+    let mut state = state.borrow_mut();
+    normalize_config(&mut state.config);
+    let key_index = state.folder.pop()?;
+    locate_key_slot(&state.config, &state.folder, key_index)
+}
+
 pub(crate) fn swap_keys_between_slots(
     state: &Rc<RefCell<AppState>>,
     current_page: usize,
@@ -583,16 +772,20 @@ pub(crate) fn swap_keys_between_slots(
 ) -> bool {
     let mut state = state.borrow_mut();
     normalize_config(&mut state.config);
-    let Some(source_index) = key_index_for_slot(&state.config, current_page, source_slot) else {
+    let Some(source_index) =
+        key_index_for_slot(&state.config, &state.folder, current_page, source_slot)
+    else {
         return false;
     };
-    let Some(target_index) = key_index_for_slot(&state.config, current_page, target_slot) else {
+    let Some(target_index) =
+        key_index_for_slot(&state.config, &state.folder, current_page, target_slot)
+    else {
         return false;
     };
     if source_index == target_index {
         return true;
     }
-    state.config.keys.swap(source_index, target_index);
+    state.folder_keys_mut().swap(source_index, target_index);
     true
 }
 
@@ -605,17 +798,22 @@ pub(crate) fn move_key_between_slots(
 ) -> bool {
     let mut state = state.borrow_mut();
     normalize_config(&mut state.config);
-    let Some(source_index) = key_index_for_slot(&state.config, current_page, source_slot) else {
+    let Some(source_index) =
+        key_index_for_slot(&state.config, &state.folder, current_page, source_slot)
+    else {
         return false;
     };
-    let Some(target_index) = key_index_for_slot(&state.config, current_page, target_slot) else {
+    let Some(target_index) =
+        key_index_for_slot(&state.config, &state.folder, current_page, target_slot)
+    else {
         return false;
     };
     if source_index == target_index {
         return true;
     }
 
-    let key = state.config.keys.remove(source_index);
+    let keys = state.folder_keys_mut();
+    let key = keys.remove(source_index);
     let mut insert_index = if insert_after_target {
         target_index.saturating_add(1)
     } else {
@@ -624,8 +822,8 @@ pub(crate) fn move_key_between_slots(
     if source_index < insert_index {
         insert_index = insert_index.saturating_sub(1);
     }
-    insert_index = insert_index.min(state.config.keys.len());
-    state.config.keys.insert(insert_index, key);
+    insert_index = insert_index.min(keys.len());
+    keys.insert(insert_index, key);
     true
 }
 
@@ -695,11 +893,34 @@ pub(crate) fn key_uses_calendar(key: &KeyBinding) -> bool {
 }
 
 pub(crate) fn config_uses_clock(config: &Config) -> bool {
-    config.keys.iter().take(KEY_COUNT).any(key_uses_clock)
+    config
+        .keys
+        .iter()
+        .take(config.device_model().key_count)
+        .any(key_uses_clock)
+}
+
+// Changes whenever one of the config's clock faces would draw differently.
+pub(crate) fn config_clock_signature(config: &Config) -> String {
+    config
+        .keys
+        .iter()
+        .take(config.device_model().key_count)
+        .filter(|key| key_uses_clock(key))
+        .map(|key| {
+            let face = key_clock_face(key, &[]);
+            face.text(face.now())
+        })
+        .collect::<Vec<_>>()
+        .join("|")
 }
 
 pub(crate) fn config_uses_calendar(config: &Config) -> bool {
-    config.keys.iter().take(KEY_COUNT).any(key_uses_calendar)
+    config
+        .keys
+        .iter()
+        .take(config.device_model().key_count)
+        .any(key_uses_calendar)
 }
 
 #[cfg(test)]
@@ -723,9 +944,40 @@ mod editor_tests {
             profile: "default".to_string(),
             image_dirs: Vec::new(),
             writable_image_dir: PathBuf::new(),
+            folder: Vec::new(),
         }))
     }
 
+    #[test]
+    fn opening_a_folder_edits_its_nested_keys() {
+        let state = app_state_with_key_count(3);
+        assert!(open_folder_at_slot(&state, 0, 1));
+        {
+            let mut state = state.borrow_mut();
+            assert_eq!(state.folder, vec![1]);
+            assert_eq!(state.config.keys[1].action, None);
+            state.folder_keys_mut().push(KeyBinding {
+                action: Some("nested".to_string()),
+                ..KeyBinding::default()
+            });
+            assert_eq!(
+                key_index_for_slot(&state.config, &state.folder, 0, 0),
+                None,
+                "slot 0 holds the back key"
+            );
+            assert_eq!(
+                key_index_for_slot(&state.config, &state.folder, 0, 1),
+                Some(0)
+            );
+        }
+        assert!(clear_selected_key(&state, 0, 1));
+        assert_eq!(close_folder(&state), Some((0, 1)));
+
+        let state = state.borrow();
+        assert!(state.folder.is_empty());
+        assert_eq!(state.config.keys[1].keys, Some(Vec::new()));
+    }
+
     #[test]
     fn clear_selected_key_replaces_key_with_blank_without_shifting() {
         let state = app_state_with_key_count(KEY_COUNT + 1);
@@ -812,6 +1064,21 @@ mod editor_tests {
         assert!(!moved);
     }
 
+    #[test]
+    fn selected_key_binding_follows_the_key_in_the_slot() {
+        let state = app_state_with_key_count(KEY_COUNT + 1);
+        let state = state.borrow();
+        assert_eq!(
+            selected_key_binding(&state, 0, 1).and_then(|key| key.action),
+            Some("action-1".to_string())
+        );
+        assert_eq!(
+            selected_key_binding(&state, 1, 0).and_then(|key| key.action),
+            Some(format!("action-{}", KEY_COUNT - 1))
+        );
+        assert_eq!(selected_key_binding(&state, 0, KEY_COUNT - 1), None);
+    }
+
     #[test]
     fn editor_mode_index_mapping_includes_blank_mode() {
         assert_eq!(editor_mode_from_index(0), EditorMode::Blank);
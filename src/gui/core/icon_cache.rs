@@ -15,46 +15,132 @@ pub(crate) fn write_rounded_png(cache_key: &str, mut image: RgbaImage) -> Option
     write_cached_png(&rounded_icons_dir(), cache_key, &image)
 }
 
-pub(crate) fn render_clock_icon_png(
-    image_dirs: &[PathBuf],
-    background_name: Option<&str>,
-) -> Option<PathBuf> {
-    let clock_text = current_clock_text();
-    let background = background_name.unwrap_or(CLOCK_BACKGROUND_ICON);
-    let cache_key = format!("clock-{}-{}", clock_text, background);
-    if let Some(path) = cached_path_if_valid(&cache_key) {
-        return Some(path);
-    }
-
-    let background_dir = find_readable_background_dir(image_dirs, background);
+pub(crate) fn render_clock_icon_png(image_dirs: &[PathBuf], face: &ClockFace) -> Option<PathBuf> {
+    let time = face.now();
+    let mut face = face.clone();
+    let background = face
+        .background
+        .get_or_insert_with(|| CLOCK_BACKGROUND_ICON.to_string())
+        .clone();
+    let background_dir = find_readable_background_dir(image_dirs, &background);
     let resources_dir = background_dir.as_deref();
-    let svg = render_clock_segments_svg(
-        resources_dir.unwrap_or_else(|| Path::new(".")),
-        &clock_text,
-        Some(background),
-    );
+    let svg = render_clock_face_svg(resources_dir.unwrap_or_else(|| Path::new(".")), &face, time);
     let image = load_svg_image_data(CLOCK_ICON_ALIAS, svg.as_bytes(), resources_dir, 256, 256)
         .or_else(|_| {
-            let fallback_svg = render_clock_segments_svg(
-                Path::new("."),
-                &clock_text,
-                Some(CLOCK_MISSING_BACKGROUND_SENTINEL),
-            );
+            let fallback = ClockFace {
+                background: Some(CLOCK_MISSING_BACKGROUND_SENTINEL.to_string()),
+                ..face.clone()
+            };
+            let fallback_svg = render_clock_face_svg(Path::new("."), &fallback, time);
             load_svg_image_data(CLOCK_ICON_ALIAS, fallback_svg.as_bytes(), None, 256, 256)
         })
         .ok()?;
-    write_rounded_png(&cache_key, image)
+    let name = format!(
+        "clock-live-{:016x}.png",
+        cache_hash_key(&format!("{face:?}"))
+    );
+    write_live_png(image, &name)
 }
 
-pub(crate) fn render_calendar_icon_png() -> Option<PathBuf> {
-    let svg = render_calendar_svg();
+pub(crate) fn render_calendar_icon_png(face: &CalendarFace) -> Option<PathBuf> {
+    let svg = render_calendar_svg(face);
     let image = load_svg_image_data(CALENDAR_ICON_ALIAS, svg.as_bytes(), None, 256, 256).ok()?;
-    write_calendar_live_png(image)
+    write_calendar_live_png(image, face)
 }
 
-fn write_calendar_live_png(mut image: RgbaImage) -> Option<PathBuf> {
+// Shows the gauge at `level`; the daemon draws the deck's current brightness.
+pub(crate) fn render_brightness_icon_png(level: usize) -> Option<PathBuf> {
+    let cache_key = format!("brightness-{level}");
+    if let Some(path) = cached_path_if_valid(&cache_key) {
+        return Some(path);
+    }
+    let svg = render_brightness_svg(level);
+    let image = load_svg_image_data(BRIGHTNESS_ICON_ALIAS, svg.as_bytes(), None, 256, 256).ok()?;
+    write_rounded_png(&cache_key, image)
+}
+
+// Shows the timer as it is before its first press.
+pub(crate) fn render_timer_icon_png(kind: TimerKind) -> Option<PathBuf> {
+    let cache_key = format!("timer-{kind:?}");
+    if let Some(path) = cached_path_if_valid(&cache_key) {
+        return Some(path);
+    }
+    let svg = render_timer_svg(&KeyTimer::new(kind).face(std::time::Instant::now()));
+    let image = load_svg_image_data(TIMER_ICON_PREFIX, svg.as_bytes(), None, 256, 256).ok()?;
+    write_rounded_png(&cache_key, image)
+}
+
+// Shows the idle note; the daemon draws what the player is playing.
+pub(crate) fn render_media_icon_png() -> Option<PathBuf> {
+    let cache_key = "media-now-playing";
+    if let Some(path) = cached_path_if_valid(cache_key) {
+        return Some(path);
+    }
+    let svg = render_media_svg(PlaybackStatus::Stopped);
+    let image = load_svg_image_data(NOW_PLAYING_ICON, svg.as_bytes(), None, 256, 256).ok()?;
+    write_rounded_png(cache_key, image)
+}
+
+// Shows a single reading; the daemon keeps the key up to date.
+pub(crate) fn render_metric_icon_png(metric: Metric, face: &MetricFace) -> Option<PathBuf> {
+    let name = format!(
+        "metric-live-{:016x}.png",
+        cache_hash_key(&format!("{metric:?}-{face:?}"))
+    );
+    let svg = render_metric_snapshot_svg(metric, face);
+    let image = load_svg_image_data(METRIC_ICON_PREFIX, svg.as_bytes(), None, 256, 256).ok()?;
+    write_live_png(image, &name)
+}
+
+// Draws `title` over an already rounded icon. A `live` icon is rewritten in
+// place as it changes, so its titled copy is too; other icons are keyed on
+// their modification time.
+pub(crate) fn render_titled_icon_png(
+    icon_path: &Path,
+    title: &KeyTitle,
+    live: bool,
+) -> Option<PathBuf> {
+    if live {
+        let name = format!(
+            "titled-live-{:016x}.png",
+            cache_hash_key(&format!("{}-{title:?}", icon_path.display()))
+        );
+        let mut image = image::open(icon_path).ok()?.to_rgba8();
+        draw_title(&mut image, title).ok()?;
+        return save_live_png(&image, &name);
+    }
+    let modified = fs::metadata(icon_path)
+        .and_then(|meta| meta.modified())
+        .ok();
+    let cache_key = format!("titled-{}-{modified:?}-{title:?}", icon_path.display());
+    if let Some(path) = cached_path_if_valid(&cache_key) {
+        return Some(path);
+    }
+    let mut image = image::open(icon_path).ok()?.to_rgba8();
+    draw_title(&mut image, title).ok()?;
+    write_cached_png(&rounded_icons_dir(), &cache_key, &image)
+}
+
+// Each calendar face keeps one file that is rewritten as the date changes.
+fn write_calendar_live_png(image: RgbaImage, face: &CalendarFace) -> Option<PathBuf> {
+    let name = if *face == CalendarFace::default() {
+        "calendar-live.png".to_string()
+    } else {
+        format!(
+            "calendar-live-{:016x}.png",
+            cache_hash_key(&format!("{face:?}"))
+        )
+    };
+    write_live_png(image, &name)
+}
+
+fn write_live_png(mut image: RgbaImage, name: &str) -> Option<PathBuf> {
     apply_rounded_corners(&mut image, 0.17);
-    let path = rounded_icons_dir().join("calendar-live.png");
+    save_live_png(&image, name)
+}
+
+fn save_live_png(image: &RgbaImage, name: &str) -> Option<PathBuf> {
+    let path = rounded_icons_dir().join(name);
     let parent = path.parent()?;
     fs::create_dir_all(parent).ok()?;
     image.save(&path).ok()?;
@@ -160,7 +246,11 @@ mod tests {
             .save(system.join(background))
             .expect("valid system background should be saved");
 
-        let rendered = render_clock_icon_png(&[writable, system], Some(background));
+        let face = ClockFace {
+            background: Some(background.to_string()),
+            ..ClockFace::default()
+        };
+        let rendered = render_clock_icon_png(&[writable, system], &face);
         let path = rendered.expect("clock icon should still render");
         assert!(
             path.is_file(),
@@ -172,9 +262,71 @@ mod tests {
         );
     }
 
+    #[test]
+    fn render_clock_icon_png_overwrites_one_file_per_face() {
+        let root = test_temp_dir("clock-live");
+        let face = ClockFace {
+            seconds: true,
+            ..ClockFace::default()
+        };
+        let first = render_clock_icon_png(std::slice::from_ref(&root), &face)
+            .expect("clock icon should render");
+        let second = render_clock_icon_png(std::slice::from_ref(&root), &face)
+            .expect("clock icon should render again");
+        assert_eq!(first, second, "each clock face should keep one live file");
+
+        let other = render_clock_icon_png(&[root], &ClockFace::default())
+            .expect("another clock face should render");
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    fn render_titled_icon_png_draws_over_a_copy() {
+        let dir = test_temp_dir("titled");
+        let icon = dir.join("icon.png");
+        RgbaImage::from_pixel(64, 64, Rgba([0, 0, 0, 255]))
+            .save(&icon)
+            .expect("icon fixture should be saved");
+
+        let titled = render_titled_icon_png(&icon, &KeyTitle::new("Mic"), false)
+            .expect("titled icon should render");
+        assert_ne!(titled, icon);
+        let image = image::open(&titled)
+            .expect("titled icon should be valid")
+            .to_rgba8();
+        assert!(image.pixels().any(|pixel| pixel[0] > 200));
+        assert_eq!(
+            image::open(&icon).expect("icon should be valid").to_rgba8(),
+            RgbaImage::from_pixel(64, 64, Rgba([0, 0, 0, 255]))
+        );
+    }
+
+    #[test]
+    fn render_titled_icon_png_overwrites_one_file_per_live_icon() {
+        let dir = test_temp_dir("titled-live");
+        let icon = dir.join("live.png");
+        let title = KeyTitle::new("Now");
+        RgbaImage::from_pixel(64, 64, Rgba([0, 0, 0, 255]))
+            .save(&icon)
+            .expect("icon fixture should be saved");
+        let first = render_titled_icon_png(&icon, &title, true).expect("titled icon should render");
+
+        RgbaImage::from_pixel(64, 64, Rgba([0, 0, 255, 255]))
+            .save(&icon)
+            .expect("icon fixture should be rewritten");
+        let second =
+            render_titled_icon_png(&icon, &title, true).expect("titled icon should render again");
+
+        assert_eq!(first, second, "a live icon should keep one titled file");
+        let image = image::open(&second)
+            .expect("titled icon should be valid")
+            .to_rgba8();
+        assert_eq!(image.get_pixel(0, 0), &Rgba([0, 0, 255, 255]));
+    }
+
     #[test]
     fn render_calendar_icon_png_writes_live_image() {
-        let rendered = render_calendar_icon_png();
+        let rendered = render_calendar_icon_png(&CalendarFace::default());
         let path = rendered.expect("calendar icon should render");
         assert!(path.is_file(), "calendar render output should be written");
         assert!(
@@ -193,9 +345,11 @@ mod tests {
         let first = RgbaImage::from_pixel(8, 8, Rgba([10, 20, 30, 255]));
         let second = RgbaImage::from_pixel(8, 8, Rgba([200, 180, 160, 255]));
 
-        let first_path = write_calendar_live_png(first).expect("first calendar write should work");
+        let face = CalendarFace::default();
+        let first_path =
+            write_calendar_live_png(first, &face).expect("first calendar write should work");
         let second_path =
-            write_calendar_live_png(second).expect("second calendar write should work");
+            write_calendar_live_png(second, &face).expect("second calendar write should work");
 
         assert_eq!(
             first_path, second_path,
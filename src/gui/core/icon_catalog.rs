@@ -5,8 +5,7 @@ fn icon_display_name(name: &str) -> String {
         .file_stem()
         .and_then(|stem| stem.to_str())
         .unwrap_or(name)
-        .replace('-', " ")
-        .replace('_', " ")
+        .replace(['-', '_'], " ")
 }
 
 fn icon_dropdown_items(icon_names: &[String]) -> Vec<String> {
@@ -86,9 +85,11 @@ pub(crate) fn configure_icon_dropdown(dropdown: &DropDown, state: &Rc<RefCell<Ap
 
         let image_dirs = state_for_bind.borrow().image_dirs.clone();
         let preview_path = if icon_is_clock(&name) {
-            render_clock_icon_png(&image_dirs, Some(CLOCK_BACKGROUND_ICON))
+            render_clock_icon_png(&image_dirs, &ClockFace::default())
         } else if icon_is_calendar(&name) {
-            render_calendar_icon_png()
+            render_calendar_icon_png(&CalendarFace::default())
+        } else if icon_is_brightness(&name) {
+            render_brightness_icon_png(DEFAULT_BRIGHTNESS)
         } else {
             render_regular_icon_png(&image_dirs, &name)
                 .or_else(|| find_icon_file(&image_dirs, &name))
@@ -223,23 +224,81 @@ pub(crate) fn key_clock_background_name<'a>(
         .unwrap_or(CLOCK_BACKGROUND_ICON)
 }
 
+// The daemon rejects a key with bad clock options, so only the background
+// survives them here.
+pub(crate) fn key_clock_face(key: &KeyBinding, backgrounds: &[String]) -> ClockFace {
+    let background = key_clock_background_name(key, backgrounds);
+    let face = ClockFace::from_fields(
+        Some(background),
+        key.clock_format.as_deref(),
+        key.clock_seconds,
+        key.clock_date,
+        key.clock_time_zone.as_deref(),
+        key.clock_label.as_deref(),
+        key.clock_style.as_deref(),
+    );
+    face.unwrap_or_else(|_| ClockFace {
+        background: Some(background.to_string()),
+        ..ClockFace::default()
+    })
+}
+
+pub(crate) fn key_calendar_face(key: &KeyBinding) -> CalendarFace {
+    CalendarFace::from_fields(
+        key.calendar_weekday,
+        key.calendar_week_number,
+        key.calendar_first_weekday.as_deref(),
+        key.calendar_header_color.as_deref(),
+        key.calendar_body_color.as_deref(),
+        key.calendar_layout.as_deref(),
+    )
+    .unwrap_or_default()
+}
+
 pub(crate) fn set_picture_icon(
     picture: &Picture,
     image_dirs: &[PathBuf],
     key: &KeyBinding,
     clock_backgrounds: &[String],
 ) {
+    let live = icon_is_clock(&key.icon)
+        || icon_is_calendar(&key.icon)
+        || matches!(Metric::from_icon(&key.icon), Some(Ok(_)));
     let rounded = if icon_is_clock(&key.icon) {
-        let background = key_clock_background_name(key, clock_backgrounds);
-        render_clock_icon_png(image_dirs, Some(background))
+        render_clock_icon_png(image_dirs, &key_clock_face(key, clock_backgrounds))
     } else if icon_is_calendar(&key.icon) {
-        render_calendar_icon_png()
+        render_calendar_icon_png(&key_calendar_face(key))
+    } else if icon_is_brightness(&key.icon) {
+        render_brightness_icon_png(DEFAULT_BRIGHTNESS)
+    } else if let Some(Ok(kind)) = TimerKind::from_icon(&key.icon) {
+        render_timer_icon_png(kind)
+    } else if icon_is_now_playing(&key.icon) {
+        render_media_icon_png()
+    } else if let Some(Ok(metric)) = Metric::from_icon(&key.icon) {
+        render_metric_icon_png(
+            metric,
+            &MetricFace::from_fields(key.metric_style.as_deref(), key.metric_interval_seconds)
+                .unwrap_or_default(),
+        )
     } else if is_blank_background_icon_name(&key.icon) {
         None
     } else {
         render_regular_icon_png(image_dirs, &key.icon)
     };
 
+    let title = KeyTitle::from_fields(
+        key.title.as_deref(),
+        key.title_position.as_deref(),
+        key.title_color.as_deref(),
+        key.title_size,
+    )
+    .ok()
+    .flatten();
+    let rounded = match (rounded, title) {
+        (Some(path), Some(title)) => render_titled_icon_png(&path, &title, live).or(Some(path)),
+        (rounded, _) => rounded,
+    };
+
     if let Some(rounded_path) = rounded {
         update_picture_file(picture, Some(&rounded_path));
         picture.set_tooltip_text(Some(&key.icon));
@@ -0,0 +1,221 @@
+use super::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum MacroStepKind {
+    Command,
+    Shortcut,
+    Text,
+    Delay,
+    Page,
+}
+
+impl MacroStepKind {
+    pub(crate) const ALL: [Self; 5] = [
+        Self::Command,
+        Self::Shortcut,
+        Self::Text,
+        Self::Delay,
+        Self::Page,
+    ];
+
+    fn label(self) -> String {
+        match self {
+            Self::Command => tr("Command"),
+            Self::Shortcut => tr("Shortcut"),
+            Self::Text => tr("Text"),
+            Self::Delay => tr("Delay (ms)"),
+            Self::Page => tr("Page"),
+        }
+    }
+
+    fn from_index(index: u32) -> Self {
+        Self::ALL
+            .get(index as usize)
+            .copied()
+            .unwrap_or(Self::Command)
+    }
+
+    fn index(self) -> u32 {
+        Self::ALL.iter().position(|kind| *kind == self).unwrap_or(0) as u32
+    }
+}
+
+pub(crate) fn macro_step_parts(step: &MacroStep) -> (MacroStepKind, String) {
+    if let Some(shortcut) = &step.shortcut {
+        (MacroStepKind::Shortcut, shortcut.clone())
+    } else if let Some(text) = &step.text {
+        (MacroStepKind::Text, text.clone())
+    } else if let Some(delay_ms) = step.delay_ms {
+        (MacroStepKind::Delay, delay_ms.to_string())
+    } else if let Some(page) = &step.page {
+        (MacroStepKind::Page, page.clone())
+    } else {
+        (
+            MacroStepKind::Command,
+            step.action.clone().unwrap_or_default(),
+        )
+    }
+}
+
+// Empty values and delays that are not a number are dropped instead of saved.
+pub(crate) fn macro_step_from_parts(kind: MacroStepKind, value: &str) -> Option<MacroStep> {
+    if kind == MacroStepKind::Text {
+        return (!value.is_empty()).then(|| MacroStep {
+            text: Some(value.to_string()),
+            ..MacroStep::default()
+        });
+    }
+    let value = trimmed_or_none(value)?;
+    Some(match kind {
+        MacroStepKind::Command => MacroStep {
+            action: Some(value),
+            ..MacroStep::default()
+        },
+        MacroStepKind::Shortcut => MacroStep {
+            shortcut: Some(value),
+            ..MacroStep::default()
+        },
+        MacroStepKind::Delay => MacroStep {
+            delay_ms: Some(value.parse().ok()?),
+            ..MacroStep::default()
+        },
+        MacroStepKind::Page => MacroStep {
+            page: Some(value),
+            ..MacroStep::default()
+        },
+        MacroStepKind::Text => unreachable!("text steps are handled above"),
+    })
+}
+
+#[derive(Clone)]
+struct MacroStepRow {
+    row: ListBoxRow,
+    kind_dropdown: DropDown,
+    value_entry: gtk::Entry,
+}
+
+#[derive(Clone)]
+pub(crate) struct MacroStepEditor {
+    pub(crate) list: gtk::ListBox,
+    rows: Rc<RefCell<Vec<MacroStepRow>>>,
+}
+
+impl MacroStepEditor {
+    pub(crate) fn new() -> Self {
+        let list = gtk::ListBox::new();
+        list.set_selection_mode(gtk::SelectionMode::None);
+        list.add_css_class("boxed-list");
+        Self {
+            list,
+            rows: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+
+    pub(crate) fn steps(&self) -> Vec<MacroStep> {
+        self.rows
+            .borrow()
+            .iter()
+            .filter_map(|row| {
+                macro_step_from_parts(
+                    MacroStepKind::from_index(row.kind_dropdown.selected()),
+                    row.value_entry.text().as_str(),
+                )
+            })
+            .collect()
+    }
+
+    pub(crate) fn set_steps(&self, steps: &[MacroStep]) {
+        for row in self.rows.borrow_mut().drain(..) {
+            self.list.remove(&row.row);
+        }
+        for step in steps {
+            self.append_step(step);
+        }
+    }
+
+    pub(crate) fn append_step(&self, step: &MacroStep) {
+        let (kind, value) = macro_step_parts(step);
+        let labels = MacroStepKind::ALL.map(MacroStepKind::label);
+        let names: Vec<&str> = labels.iter().map(String::as_str).collect();
+        let kind_dropdown = DropDown::from_strings(&names);
+        kind_dropdown.set_selected(kind.index());
+        kind_dropdown.set_valign(Align::Center);
+
+        let value_entry = gtk::Entry::new();
+        value_entry.set_text(&value);
+        value_entry.set_hexpand(true);
+        value_entry.set_valign(Align::Center);
+
+        let remove_button = Button::builder().icon_name("list-remove-symbolic").build();
+        remove_button.set_tooltip_text(Some(&tr("Remove step")));
+        remove_button.add_css_class("flat");
+        remove_button.set_valign(Align::Center);
+
+        let content = GtkBox::new(Orientation::Horizontal, UI_SPACING_HORIZONTAL);
+        content.set_margin_top(6);
+        content.set_margin_bottom(6);
+        content.set_margin_start(8);
+        content.set_margin_end(8);
+        content.append(&kind_dropdown);
+        content.append(&value_entry);
+        content.append(&remove_button);
+
+        let row = ListBoxRow::new();
+        row.set_activatable(false);
+        row.set_child(Some(&content));
+        self.list.append(&row);
+
+        {
+            let editor = self.clone();
+            let row = row.clone();
+            remove_button.connect_clicked(move |_| {
+                editor.list.remove(&row);
+                editor.rows.borrow_mut().retain(|entry| entry.row != row);
+            });
+        }
+
+        self.rows.borrow_mut().push(MacroStepRow {
+            row,
+            kind_dropdown,
+            value_entry,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn macro_steps_round_trip_through_editor_parts() {
+        let steps = [
+            MacroStep {
+                action: Some("notify-send hi".to_string()),
+                ..MacroStep::default()
+            },
+            MacroStep {
+                delay_ms: Some(250),
+                ..MacroStep::default()
+            },
+            MacroStep {
+                text: Some(" padded ".to_string()),
+                ..MacroStep::default()
+            },
+            MacroStep {
+                page: Some("next".to_string()),
+                ..MacroStep::default()
+            },
+        ];
+        for step in steps {
+            let (kind, value) = macro_step_parts(&step);
+            assert_eq!(macro_step_from_parts(kind, &value), Some(step));
+        }
+    }
+
+    #[test]
+    fn incomplete_macro_steps_are_dropped() {
+        assert_eq!(macro_step_from_parts(MacroStepKind::Command, "  "), None);
+        assert_eq!(macro_step_from_parts(MacroStepKind::Delay, "soon"), None);
+        assert_eq!(macro_step_from_parts(MacroStepKind::Text, ""), None);
+    }
+}
@@ -1,46 +1,70 @@
 use super::*;
 
 pub(crate) fn normalize_config(config: &mut Config) {
-    config.keys_per_page = config
-        .keys_per_page
-        .clamp(streamrs::paging::MIN_KEYS_PER_PAGE, KEY_COUNT);
+    config.keys_per_page = config.keys_per_page.clamp(
+        streamrs::paging::MIN_KEYS_PER_PAGE,
+        config.device_model().key_count,
+    );
 }
 
-pub(crate) fn paging_layout(config: &Config) -> PagingLayout {
-    PagingLayout::new(KEY_COUNT, config.keys_per_page)
+pub(crate) fn paging_layout(config: &Config, folder: &[usize]) -> PagingLayout {
+    let mut layout = PagingLayout::for_model(config.device_model(), config.keys_per_page);
+    if config.manual_page_navigation {
+        layout = layout.without_navigation_keys();
+    }
+    if folder.is_empty() {
+        layout
+    } else {
+        layout.for_folder()
+    }
+}
+
+fn folder_key_count(config: &Config, folder: &[usize]) -> usize {
+    config.folder_keys(folder).map_or(0, <[KeyBinding]>::len)
 }
 
-pub(crate) fn page_count(config: &Config) -> usize {
-    paging_layout(config).page_count(config.keys.len())
+pub(crate) fn page_count(config: &Config, folder: &[usize]) -> usize {
+    paging_layout(config, folder).page_count(folder_key_count(config, folder))
 }
 
 pub(crate) fn navigation_slot_for_slot(
     config: &Config,
+    folder: &[usize],
     page: usize,
     total_pages: usize,
     slot: usize,
 ) -> Option<ReservedNavigationSlot> {
-    paging_layout(config).navigation_slot_for_slot(page, total_pages, slot)
+    paging_layout(config, folder).navigation_slot_for_slot(page, total_pages, slot)
 }
 
 pub(crate) fn navigation_icon_name(slot: ReservedNavigationSlot) -> &'static str {
     match slot {
         ReservedNavigationSlot::PreviousPage => NAV_PREVIOUS_ICON,
         ReservedNavigationSlot::NextPage => NAV_NEXT_ICON,
+        ReservedNavigationSlot::FolderBack => NAV_FOLDER_BACK_ICON,
     }
 }
 
-pub(crate) fn key_index_for_slot(config: &Config, page: usize, slot: usize) -> Option<usize> {
-    paging_layout(config).key_index_for_slot(config.keys.len(), page, slot)
+pub(crate) fn key_index_for_slot(
+    config: &Config,
+    folder: &[usize],
+    page: usize,
+    slot: usize,
+) -> Option<usize> {
+    paging_layout(config, folder).key_index_for_slot(folder_key_count(config, folder), page, slot)
 }
 
-pub(crate) fn locate_key_slot(config: &Config, key_index: usize) -> Option<(usize, usize)> {
-    paging_layout(config).locate_key_slot(config.keys.len(), key_index)
+pub(crate) fn locate_key_slot(
+    config: &Config,
+    folder: &[usize],
+    key_index: usize,
+) -> Option<(usize, usize)> {
+    paging_layout(config, folder).locate_key_slot(folder_key_count(config, folder), key_index)
 }
 
-pub(crate) fn first_editable_slot(config: &Config, page: usize) -> usize {
-    for slot in 0..KEY_COUNT {
-        if key_index_for_slot(config, page, slot).is_some() {
+pub(crate) fn first_editable_slot(config: &Config, folder: &[usize], page: usize) -> usize {
+    for slot in 0..paging_layout(config, folder).total_slots() {
+        if key_index_for_slot(config, folder, page, slot).is_some() {
             return slot;
         }
     }
@@ -52,21 +76,27 @@ pub(crate) fn clamp_page_and_selection(
     current_page: &Rc<Cell<usize>>,
     selected_slot: &Rc<Cell<usize>>,
 ) {
-    let (total_pages, page, selected_slot_fallback) = {
+    let (total_pages, page, selected_slot_fallback, key_count) = {
         let mut state = state.borrow_mut();
         normalize_config(&mut state.config);
-        let total_pages = page_count(&state.config).max(1);
+        state.normalize_folder();
+        let total_pages = page_count(&state.config, &state.folder).max(1);
         let page = current_page.get().min(total_pages.saturating_sub(1));
-        let fallback = first_editable_slot(&state.config, page);
-        (total_pages, page, fallback)
+        let fallback = first_editable_slot(&state.config, &state.folder, page);
+        (
+            total_pages,
+            page,
+            fallback,
+            state.config.device_model().key_count,
+        )
     };
 
     current_page.set(page.min(total_pages.saturating_sub(1)));
-    let selected = selected_slot.get().min(KEY_COUNT.saturating_sub(1));
+    let selected = selected_slot.get().min(key_count.saturating_sub(1));
     let has_selected_action = {
         let mut state = state.borrow_mut();
         normalize_config(&mut state.config);
-        key_index_for_slot(&state.config, page, selected).is_some()
+        key_index_for_slot(&state.config, &state.folder, page, selected).is_some()
     };
     if has_selected_action {
         selected_slot.set(selected);
@@ -82,18 +112,26 @@ pub(crate) fn refresh_page_controls(
     next_button: &Button,
     page_label: &Label,
 ) {
-    let total_pages = {
+    let (total_pages, in_folder) = {
         let mut state = state.borrow_mut();
         normalize_config(&mut state.config);
-        page_count(&state.config).max(1)
+        (
+            page_count(&state.config, &state.folder).max(1),
+            !state.folder.is_empty(),
+        )
     };
 
     let page = current_page.get().min(total_pages.saturating_sub(1));
     current_page.set(page);
     prev_button.set_sensitive(page > 0);
     next_button.set_sensitive(page + 1 < total_pages);
+    let label = if in_folder {
+        "Folder page {current}/{total}"
+    } else {
+        "Page {current}/{total}"
+    };
     page_label.set_text(&trf(
-        "Page {current}/{total}",
+        label,
         &[
             ("current", (page + 1).to_string()),
             ("total", total_pages.to_string()),
@@ -133,59 +171,118 @@ mod tests {
     #[test]
     fn navigation_slots_match_main_app_layout() {
         let config = Config::default();
-        assert_eq!(navigation_slot_for_slot(&config, 0, 1, KEY_COUNT - 1), None);
+        assert_eq!(
+            navigation_slot_for_slot(&config, &[], 0, 1, KEY_COUNT - 1),
+            None
+        );
 
         assert_eq!(
-            navigation_slot_for_slot(&config, 0, 2, KEY_COUNT - 1),
+            navigation_slot_for_slot(&config, &[], 0, 2, KEY_COUNT - 1),
             Some(ReservedNavigationSlot::NextPage)
         );
         assert_eq!(
-            navigation_slot_for_slot(&config, 1, 2, KEY_COUNT - 1),
+            navigation_slot_for_slot(&config, &[], 1, 2, KEY_COUNT - 1),
             Some(ReservedNavigationSlot::PreviousPage)
         );
 
         assert_eq!(
-            navigation_slot_for_slot(&config, 1, 3, KEY_COUNT - 2),
+            navigation_slot_for_slot(&config, &[], 1, 3, KEY_COUNT - 2),
             Some(ReservedNavigationSlot::PreviousPage)
         );
         assert_eq!(
-            navigation_slot_for_slot(&config, 1, 3, KEY_COUNT - 1),
+            navigation_slot_for_slot(&config, &[], 1, 3, KEY_COUNT - 1),
             Some(ReservedNavigationSlot::NextPage)
         );
-        assert_eq!(navigation_slot_for_slot(&config, 1, 3, 0), None);
+        assert_eq!(navigation_slot_for_slot(&config, &[], 1, 3, 0), None);
     }
 
     #[test]
     fn key_index_mapping_respects_reserved_navigation_slots() {
         let config = config_with_keys(30);
 
-        assert_eq!(page_count(&config), 3);
+        assert_eq!(page_count(&config, &[]), 3);
 
-        assert_eq!(key_index_for_slot(&config, 0, 0), Some(0));
-        assert_eq!(key_index_for_slot(&config, 0, KEY_COUNT - 2), Some(13));
-        assert_eq!(key_index_for_slot(&config, 0, KEY_COUNT - 1), None);
+        assert_eq!(key_index_for_slot(&config, &[], 0, 0), Some(0));
+        assert_eq!(key_index_for_slot(&config, &[], 0, KEY_COUNT - 2), Some(13));
+        assert_eq!(key_index_for_slot(&config, &[], 0, KEY_COUNT - 1), None);
 
-        assert_eq!(key_index_for_slot(&config, 1, 0), Some(14));
-        assert_eq!(key_index_for_slot(&config, 1, KEY_COUNT - 3), Some(26));
-        assert_eq!(key_index_for_slot(&config, 1, KEY_COUNT - 2), None);
-        assert_eq!(key_index_for_slot(&config, 1, KEY_COUNT - 1), None);
+        assert_eq!(key_index_for_slot(&config, &[], 1, 0), Some(14));
+        assert_eq!(key_index_for_slot(&config, &[], 1, KEY_COUNT - 3), Some(26));
+        assert_eq!(key_index_for_slot(&config, &[], 1, KEY_COUNT - 2), None);
+        assert_eq!(key_index_for_slot(&config, &[], 1, KEY_COUNT - 1), None);
 
-        assert_eq!(key_index_for_slot(&config, 2, 0), Some(27));
-        assert_eq!(key_index_for_slot(&config, 2, 1), Some(28));
-        assert_eq!(key_index_for_slot(&config, 2, 2), Some(29));
-        assert_eq!(key_index_for_slot(&config, 2, 3), None);
-        assert_eq!(key_index_for_slot(&config, 2, KEY_COUNT - 1), None);
+        assert_eq!(key_index_for_slot(&config, &[], 2, 0), Some(27));
+        assert_eq!(key_index_for_slot(&config, &[], 2, 1), Some(28));
+        assert_eq!(key_index_for_slot(&config, &[], 2, 2), Some(29));
+        assert_eq!(key_index_for_slot(&config, &[], 2, 3), None);
+        assert_eq!(key_index_for_slot(&config, &[], 2, KEY_COUNT - 1), None);
     }
 
     #[test]
     fn locate_key_slot_round_trips_each_existing_key() {
         let config = config_with_keys(36);
         for key_index in 0..config.keys.len() {
-            let (page, slot) = locate_key_slot(&config, key_index)
+            let (page, slot) = locate_key_slot(&config, &[], key_index)
                 .unwrap_or_else(|| panic!("missing slot for button index {key_index}"));
-            assert_eq!(key_index_for_slot(&config, page, slot), Some(key_index));
+            assert_eq!(
+                key_index_for_slot(&config, &[], page, slot),
+                Some(key_index)
+            );
         }
-        assert_eq!(locate_key_slot(&config, config.keys.len() + 1), None);
+        assert_eq!(locate_key_slot(&config, &[], config.keys.len() + 1), None);
+    }
+
+    #[test]
+    fn paging_follows_configured_device_model() {
+        let mut config = config_with_keys(10);
+        config.product_id = streamrs::streamdeck::STREAMDECK_MINI.product_ids[0];
+        normalize_config(&mut config);
+
+        assert_eq!(config.keys_per_page, 6);
+        assert_eq!(page_count(&config, &[]), 2);
+        assert_eq!(
+            navigation_slot_for_slot(&config, &[], 0, 2, 5),
+            Some(ReservedNavigationSlot::NextPage)
+        );
+        assert_eq!(key_index_for_slot(&config, &[], 1, 0), Some(5));
+    }
+
+    #[test]
+    fn manual_page_navigation_frees_the_reserved_slots() {
+        let config = Config {
+            manual_page_navigation: true,
+            ..config_with_keys(KEY_COUNT * 2 + 1)
+        };
+
+        assert_eq!(page_count(&config, &[]), 3);
+        assert_eq!(
+            navigation_slot_for_slot(&config, &[], 1, 3, KEY_COUNT - 1),
+            None
+        );
+        assert_eq!(
+            key_index_for_slot(&config, &[], 0, KEY_COUNT - 1),
+            Some(KEY_COUNT - 1)
+        );
+        assert_eq!(key_index_for_slot(&config, &[], 2, 0), Some(KEY_COUNT * 2));
+        assert_eq!(key_index_for_slot(&config, &[], 2, 1), None);
+
+        // Folders keep their back key.
+        let folder_config = Config {
+            keys: vec![KeyBinding {
+                keys: Some(vec![KeyBinding::default(); KEY_COUNT]),
+                ..KeyBinding::default()
+            }],
+            ..config
+        };
+        assert_eq!(page_count(&folder_config, &[0]), 2);
+        assert_eq!(
+            navigation_slot_for_slot(&folder_config, &[0], 0, 2, 0),
+            Some(ReservedNavigationSlot::FolderBack)
+        );
+        assert_eq!(
+            key_index_for_slot(&folder_config, &[0], 0, KEY_COUNT - 1),
+            Some(KEY_COUNT - 2)
+        );
     }
 
     #[test]
@@ -1,7 +1,7 @@
 pub(crate) use adw::prelude::*;
 pub(crate) use adw::{
     Application, ApplicationWindow, ComboRow, EntryRow, HeaderBar, PreferencesGroup,
-    ResponseAppearance, Toast, ToastOverlay,
+    ResponseAppearance, SwitchRow, Toast, ToastOverlay,
 };
 pub(crate) use gtk::{
     Align, Box as GtkBox, Button, CssProvider, DropDown, Fixed, Image, Label, Orientation,
@@ -23,11 +23,17 @@ pub(crate) use streamrs::config::current_profile::{
 };
 pub(crate) use streamrs::config::streamrs_profile;
 pub(crate) use streamrs::config::streamrs_schema::{
-    StreamrsConfig as Config, StreamrsKeyBinding as KeyBinding, default_icon_name,
+    DEFAULT_BRIGHTNESS, StreamrsConfig as Config, StreamrsKeyBinding as KeyBinding, StreamrsMacroStep as MacroStep,
+    default_icon_name,
+};
+pub(crate) use streamrs::image::brightness::{
+    BRIGHTNESS_ICON_ALIAS, is_brightness_icon as icon_is_brightness, render_brightness_svg,
+};
+pub(crate) use streamrs::image::cache_fs::{
+    cache_hash_key, cached_png_path_if_valid, write_cached_png,
 };
-pub(crate) use streamrs::image::cache_fs::{cached_png_path_if_valid, write_cached_png};
 pub(crate) use streamrs::image::calendar::{
-    CALENDAR_ICON_ALIAS, current_calendar_key, is_calendar_icon as icon_is_calendar,
+    CALENDAR_ICON_ALIAS, CalendarFace, current_calendar_key, is_calendar_icon as icon_is_calendar,
     render_calendar_svg,
 };
 pub(crate) use streamrs::image::catalog::{
@@ -35,25 +41,34 @@ pub(crate) use streamrs::image::catalog::{
     discover_png_backgrounds_with_prefix, is_blank_background_icon_name,
 };
 pub(crate) use streamrs::image::clock::{
-    CLOCK_BACKGROUND_ICON, CLOCK_ICON_ALIAS, current_clock_text, is_clock_icon as icon_is_clock,
-    render_clock_segments_svg,
+    CLOCK_BACKGROUND_ICON, CLOCK_ICON_ALIAS, ClockFace, is_clock_icon as icon_is_clock,
+    render_clock_face_svg,
 };
 pub(crate) use streamrs::image::effects::apply_rounded_corners;
-pub(crate) use streamrs::image::svg::load_svg_data as load_svg_image_data;
-pub(crate) use streamrs::paging::{
-    NavigationSlot as ReservedNavigationSlot, PagingLayout, STREAMDECK_KEY_COUNT,
+pub(crate) use streamrs::image::media::{
+    NOW_PLAYING_ICON, PlaybackStatus, is_now_playing_icon as icon_is_now_playing, render_media_svg,
 };
+pub(crate) use streamrs::image::metric::{MetricFace, render_metric_snapshot_svg};
+pub(crate) use streamrs::image::overlay::{KeyTitle, TitlePosition, draw_title, parse_title_position};
+pub(crate) use streamrs::image::svg::load_svg_data as load_svg_image_data;
+pub(crate) use streamrs::image::timer::{KeyTimer, TIMER_ICON_PREFIX, TimerKind, render_timer_svg};
+pub(crate) use streamrs::metrics::{METRIC_ICON_PREFIX, Metric};
+pub(crate) use streamrs::paging::{NavigationSlot as ReservedNavigationSlot, PagingLayout};
 pub(crate) use streamrs::paths::{
     default_config_path_for_profile, profile_from_config_path, writable_image_dir_for_profile,
 };
 
-pub(crate) const KEY_COUNT: usize = STREAMDECK_KEY_COUNT;
+pub(crate) use streamrs::streamdeck::DeviceModel;
+
+#[cfg(test)]
+pub(crate) const KEY_COUNT: usize = streamrs::streamdeck::DEFAULT_DEVICE_MODEL.key_count;
 pub(crate) const DEFAULT_STATUS_INTERVAL_SECONDS: u64 = 1;
 pub(crate) const MIN_STATUS_INTERVAL_SECONDS: u64 = 1;
 pub(crate) const MAX_STATUS_INTERVAL_SECONDS: u64 = 60;
 
 pub(crate) const NAV_PREVIOUS_ICON: &str = "stream-deck-previous-page.png";
 pub(crate) const NAV_NEXT_ICON: &str = "stream-deck-next-page.png";
+pub(crate) const NAV_FOLDER_BACK_ICON: &str = "stream-deck-folder-back.png";
 
 pub(crate) const TEMPLATE_RENDER_WIDTH: u32 = 1560;
 pub(crate) const TEMPLATE_RENDER_HEIGHT: u32 = 1108;
@@ -77,6 +92,30 @@ pub(crate) struct AppState {
     pub(crate) profile: String,
     pub(crate) image_dirs: Vec<PathBuf>,
     pub(crate) writable_image_dir: PathBuf,
+    // Key index of each opened folder from the top level down.
+    pub(crate) folder: Vec<usize>,
+}
+
+impl AppState {
+    pub(crate) fn folder_keys(&self) -> &[KeyBinding] {
+        self.config.folder_keys(&self.folder).unwrap_or_default()
+    }
+
+    // Falls back to the top level when the opened folder no longer exists.
+    pub(crate) fn normalize_folder(&mut self) {
+        if self.config.folder_keys(&self.folder).is_none() {
+            self.folder.clear();
+        }
+    }
+
+    pub(crate) fn folder_keys_mut(&mut self) -> &mut Vec<KeyBinding> {
+        self.normalize_folder();
+        let mut keys = &mut self.config.keys;
+        for &index in &self.folder {
+            keys = keys[index].keys.get_or_insert_with(Vec::new);
+        }
+        keys
+    }
 }
 
 #[derive(Clone)]
@@ -87,12 +126,19 @@ pub(crate) struct EditorWidgets {
     pub(crate) action_type_dropdown: ComboRow,
     pub(crate) action_entry: EntryRow,
     pub(crate) shortcut_entry: EntryRow,
+    pub(crate) text_entry: EntryRow,
+    pub(crate) open_folder_button: Button,
+    pub(crate) steps_group: PreferencesGroup,
+    pub(crate) steps_editor: super::MacroStepEditor,
+    pub(crate) stop_on_error_switch: SwitchRow,
     pub(crate) icon_kind_dropdown: ComboRow,
     pub(crate) icon_row: ListBoxRow,
     pub(crate) icon_dropdown: DropDown,
     pub(crate) clock_background_row: ListBoxRow,
     pub(crate) clock_background_dropdown: DropDown,
     pub(crate) clock_background_preview: Picture,
+    pub(crate) title_entry: EntryRow,
+    pub(crate) title_position_dropdown: ComboRow,
     pub(crate) status_group: PreferencesGroup,
     pub(crate) status_entry: EntryRow,
     pub(crate) icon_on_row: ListBoxRow,
@@ -137,6 +183,9 @@ pub(crate) enum ActionMode {
     None,
     Launch,
     KeyboardShortcut,
+    Folder,
+    Macro,
+    Text,
 }
 
 pub(crate) fn resolve_image_dirs(profile: &str, writable_dir: &Path) -> Vec<PathBuf> {
@@ -243,6 +243,7 @@ pub(crate) fn build_ui(app: &Application) {
         profile: default_profile,
         image_dirs,
         writable_image_dir,
+        folder: Vec::new(),
     }));
     let profile_names = Rc::new(RefCell::new(profiles));
     let selected_key = Rc::new(Cell::new(0usize));
@@ -346,8 +347,9 @@ pub(crate) fn build_ui(app: &Application) {
     deck_picture.set_keep_aspect_ratio(true);
     deck_picture.set_can_shrink(true);
     deck_picture.add_css_class("deck-image");
+    let deck_model = state.borrow().config.device_model();
     if let Some(background_path) =
-        write_deck_background_png(&deck_image_path, PREVIEW_WIDTH, PREVIEW_HEIGHT)
+        write_deck_background_png(&deck_image_path, deck_model, PREVIEW_WIDTH, PREVIEW_HEIGHT)
     {
         update_picture_file(&deck_picture, Some(&background_path));
     } else if deck_image_path.is_file() {
@@ -359,12 +361,17 @@ pub(crate) fn build_ui(app: &Application) {
     let key_layer = Fixed::new();
     deck_overlay.add_overlay(&key_layer);
 
-    let slots = key_slots_for_deck(&deck_image_path);
+    let slots = key_slots_for_deck(&deck_image_path, deck_model);
 
-    let mut key_buttons = Vec::with_capacity(KEY_COUNT);
-    let mut key_pictures = Vec::with_capacity(KEY_COUNT);
+    let mut key_buttons = Vec::with_capacity(deck_model.key_count);
+    let mut key_pictures = Vec::with_capacity(deck_model.key_count);
 
-    for (index, slot) in slots.iter().copied().enumerate().take(KEY_COUNT) {
+    for (index, slot) in slots
+        .iter()
+        .copied()
+        .enumerate()
+        .take(deck_model.key_count)
+    {
         let button = Button::new();
         button.add_css_class("key-button");
         button.set_tooltip_text(Some(&trf(
@@ -440,7 +447,14 @@ pub(crate) fn build_ui(app: &Application) {
     inspector_panel.add_css_class("deck-card");
     inspector_panel.add_css_class("inspector-panel");
 
-    let action_mode_labels = vec![tr("None"), tr("Launch command"), tr("Keyboard shortcut")];
+    let action_mode_labels = vec![
+        tr("None"),
+        tr("Launch command"),
+        tr("Keyboard shortcut"),
+        tr("Folder"),
+        tr("Macro"),
+        tr("Type text"),
+    ];
     let action_type_dropdown = combo_row_from_strings(&tr("Action type"), &action_mode_labels);
 
     let action_entry = EntryRow::new();
@@ -449,6 +463,27 @@ pub(crate) fn build_ui(app: &Application) {
     let shortcut_entry = EntryRow::new();
     shortcut_entry.set_title(&tr("Keyboard shortcut"));
 
+    let text_entry = EntryRow::new();
+    text_entry.set_title(&tr("Text to type"));
+
+    let open_folder_button = Button::with_label(&tr("Open folder"));
+    open_folder_button.set_tooltip_text(Some(&tr("Edit the buttons inside this folder")));
+    open_folder_button.set_halign(Align::Start);
+    open_folder_button.set_margin_top(8);
+
+    let steps_editor = MacroStepEditor::new();
+    let add_step_button = Button::with_label(&tr("Add step"));
+    add_step_button.set_halign(Align::Start);
+    add_step_button.set_margin_top(8);
+    {
+        let steps_editor = steps_editor.clone();
+        add_step_button.connect_clicked(move |_| {
+            steps_editor.append_step(&MacroStep::default());
+        });
+    }
+    let stop_on_error_switch = SwitchRow::new();
+    stop_on_error_switch.set_title(&tr("Stop on first failure"));
+
     let mode_labels = vec![
         tr("Blank"),
         tr("Regular"),
@@ -490,6 +525,13 @@ pub(crate) fn build_ui(app: &Application) {
         &clock_background_preview,
     );
 
+    let title_entry = EntryRow::new();
+    title_entry.set_title(&tr("Title"));
+    let title_position_labels = vec![tr("Top"), tr("Middle"), tr("Bottom")];
+    let title_position_dropdown =
+        combo_row_from_strings(&tr("Title position"), &title_position_labels);
+    title_position_dropdown.set_selected(2);
+
     let status_entry = EntryRow::new();
     status_entry.set_title(&tr("Status command"));
 
@@ -557,6 +599,8 @@ pub(crate) fn build_ui(app: &Application) {
     behavior_group.add(&action_type_dropdown);
     behavior_group.add(&action_entry);
     behavior_group.add(&shortcut_entry);
+    behavior_group.add(&text_entry);
+    behavior_group.add(&open_folder_button);
 
     let appearance_group = PreferencesGroup::builder().title(tr("Appearance")).build();
     appearance_group.set_margin_bottom(8);
@@ -565,6 +609,14 @@ pub(crate) fn build_ui(app: &Application) {
     appearance_group.add(&icon_on_row);
     appearance_group.add(&icon_off_row);
     appearance_group.add(&clock_background_row);
+    appearance_group.add(&title_entry);
+    appearance_group.add(&title_position_dropdown);
+
+    let steps_group = PreferencesGroup::builder().title(tr("Steps")).build();
+    steps_group.set_margin_bottom(8);
+    steps_group.add(&steps_editor.list);
+    steps_group.add(&add_step_button);
+    steps_group.add(&stop_on_error_switch);
 
     let status_group = PreferencesGroup::builder().title(tr("Status")).build();
     status_group.add(&status_entry);
@@ -574,6 +626,7 @@ pub(crate) fn build_ui(app: &Application) {
     editor_groups.set_hexpand(true);
     editor_groups.set_vexpand(true);
     editor_groups.append(&behavior_group);
+    editor_groups.append(&steps_group);
     editor_groups.append(&appearance_group);
     editor_groups.append(&status_group);
 
@@ -735,12 +788,19 @@ pub(crate) fn build_ui(app: &Application) {
         action_type_dropdown,
         action_entry,
         shortcut_entry,
+        text_entry,
+        open_folder_button,
+        steps_group,
+        steps_editor,
+        stop_on_error_switch,
         icon_kind_dropdown,
         icon_row,
         icon_dropdown,
         clock_background_row,
         clock_background_dropdown,
         clock_background_preview,
+        title_entry,
+        title_position_dropdown,
         status_group,
         status_entry,
         icon_on_row,
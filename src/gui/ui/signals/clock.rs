@@ -24,14 +24,38 @@ pub(crate) fn wire_clock_refresh_signal(ctx: &UiCtx) {
         let editor_syncing_for_clock = editor_syncing.clone();
 
         gtk::glib::timeout_add_seconds_local(1, move || {
-            let has_live_icon = {
+            let page = current_page_for_clock.get();
+            let slot = selected_for_clock.get();
+            let (has_live_icon, now_clock, has_selected_key, selected_key_changed) = {
                 let mut state = state_for_clock.borrow_mut();
+                let before = selected_key_binding(&state, page, slot);
                 normalize_config(&mut state.config);
-                config_uses_clock(&state.config) || config_uses_calendar(&state.config)
+                let after = selected_key_binding(&state, page, slot);
+                (
+                    config_uses_clock(&state.config) || config_uses_calendar(&state.config),
+                    config_clock_signature(&state.config),
+                    after.is_some(),
+                    before != after,
+                )
             };
 
+            let icons = icons_for_clock.borrow();
+            let backgrounds = backgrounds_for_clock.borrow();
+            // Rewriting the editor would throw away what the user is typing,
+            // so it is only refreshed when another key now sits in the slot.
+            if selected_key_changed {
+                populate_editor_guarded(
+                    &state_for_clock,
+                    page,
+                    slot,
+                    &widgets_for_clock,
+                    icons.as_slice(),
+                    backgrounds.as_slice(),
+                    &editor_syncing_for_clock,
+                );
+            }
+
             if has_live_icon {
-                let now_clock = current_clock_text();
                 let now_calendar = current_calendar_key();
                 let signature = format!("{now_clock}|{now_calendar}");
                 if *last_live_for_tick.borrow() == signature {
@@ -39,24 +63,21 @@ pub(crate) fn wire_clock_refresh_signal(ctx: &UiCtx) {
                 }
                 *last_live_for_tick.borrow_mut() = signature;
 
-                let icons = icons_for_clock.borrow();
-                let backgrounds = backgrounds_for_clock.borrow();
                 refresh_key_grid(
                     &state_for_clock,
                     &key_buttons_for_clock,
                     &key_pictures_for_clock,
-                    current_page_for_clock.get(),
+                    page,
                     backgrounds.as_slice(),
                 );
-                populate_editor_guarded(
-                    &state_for_clock,
-                    current_page_for_clock.get(),
-                    selected_for_clock.get(),
-                    &widgets_for_clock,
-                    icons.as_slice(),
-                    backgrounds.as_slice(),
-                    &editor_syncing_for_clock,
-                );
+                if has_selected_key {
+                    editor_refresh_preview(
+                        &state_for_clock,
+                        &widgets_for_clock,
+                        icons.as_slice(),
+                        backgrounds.as_slice(),
+                    );
+                }
             }
 
             gtk::glib::ControlFlow::Continue
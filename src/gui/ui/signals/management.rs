@@ -106,6 +106,7 @@ fn load_profile_into_ui(
         let mut state = state.borrow_mut();
         update_state_profile_paths(&mut state, &path);
         state.config = config;
+        state.folder.clear();
     }
     current_page.set(0);
     selected_key.set(0);
@@ -194,8 +195,8 @@ pub(crate) fn wire_management_signals(
         let key_pictures_for_select = key_pictures.clone();
         let editor_syncing_for_select = editor_syncing.clone();
         widgets.profile_dropdown.connect_selected_notify(move |_| {
-            if let Some(profile) = selected_profile_name(&widgets_for_select) {
-                if let Err(err) = load_profile_into_ui(
+            if let Some(profile) = selected_profile_name(&widgets_for_select)
+                && let Err(err) = load_profile_into_ui(
                     &profile,
                     &state_for_select,
                     &current_page_for_select,
@@ -209,9 +210,9 @@ pub(crate) fn wire_management_signals(
                     &key_buttons_for_select,
                     &key_pictures_for_select,
                     &editor_syncing_for_select,
-                ) {
-                    announce_status(&widgets_for_select, &err);
-                }
+                )
+            {
+                announce_status(&widgets_for_select, &err);
             }
         });
     }
@@ -600,10 +601,12 @@ pub(crate) fn wire_management_signals(
             let (new_key_index, target_page, target_slot) = {
                 let mut state = state_for_add_key.borrow_mut();
                 normalize_config(&mut state.config);
-                state.config.keys.push(KeyBinding::default());
-                let new_key_index = state.config.keys.len().saturating_sub(1);
+                let keys = state.folder_keys_mut();
+                keys.push(KeyBinding::default());
+                let new_key_index = keys.len().saturating_sub(1);
                 let (target_page, target_slot) =
-                    locate_key_slot(&state.config, new_key_index).unwrap_or((0, 0));
+                    locate_key_slot(&state.config, &state.folder, new_key_index)
+                        .unwrap_or((0, 0));
                 (new_key_index, target_page, target_slot)
             };
 
@@ -680,12 +683,12 @@ pub(crate) fn wire_management_signals(
 
             button.connect_clicked(move |_| {
             let dialog = gtk::FileChooserNative::builder()
-                .title(&tr("Add icon"))
+                .title(tr("Add icon"))
                 .transient_for(&window_for_add_icon)
                 .modal(true)
                 .action(gtk::FileChooserAction::Open)
-                .accept_label(&tr("Add"))
-                .cancel_label(&tr("Cancel"))
+                .accept_label(tr("Add"))
+                .cancel_label(tr("Cancel"))
                 .build();
             let filter = gtk::FileFilter::new();
             filter.set_name(Some(&tr("Images")));
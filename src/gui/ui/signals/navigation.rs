@@ -63,7 +63,8 @@ pub(crate) fn wire_navigation_signals(ctx: &UiCtx) {
             let (page, total_pages) = {
                 let mut state = state_for_click.borrow_mut();
                 normalize_config(&mut state.config);
-                let total_pages = page_count(&state.config).max(1);
+                state.normalize_folder();
+                let total_pages = page_count(&state.config, &state.folder).max(1);
                 let page = current_page_for_click
                     .get()
                     .min(total_pages.saturating_sub(1));
@@ -73,16 +74,25 @@ pub(crate) fn wire_navigation_signals(ctx: &UiCtx) {
             let nav_slot = {
                 let mut state = state_for_click.borrow_mut();
                 normalize_config(&mut state.config);
-                navigation_slot_for_slot(&state.config, page, total_pages, index)
+                navigation_slot_for_slot(&state.config, &state.folder, page, total_pages, index)
             };
             if let Some(nav_slot) = nav_slot {
-                let target_page = match nav_slot {
-                    ReservedNavigationSlot::PreviousPage => page.saturating_sub(1),
+                let target = match nav_slot {
+                    ReservedNavigationSlot::PreviousPage => {
+                        Some(page.saturating_sub(1)).filter(|&target| target != page)
+                    }
                     ReservedNavigationSlot::NextPage => {
-                        (page + 1).min(total_pages.saturating_sub(1))
+                        Some((page + 1).min(total_pages.saturating_sub(1)))
+                            .filter(|&target| target != page)
+                    }
+                    ReservedNavigationSlot::FolderBack => {
+                        close_folder(&state_for_click).map(|(parent_page, folder_slot)| {
+                            selected_for_click.set(folder_slot);
+                            parent_page
+                        })
                     }
                 };
-                if target_page != page {
+                if let Some(target_page) = target {
                     current_page_for_click.set(target_page);
                     clamp_page_and_selection(
                         &state_for_click,
@@ -241,6 +251,63 @@ pub(crate) fn wire_navigation_signals(ctx: &UiCtx) {
         }
     }
 
+    {
+        let state_for_open = state.clone();
+        let current_page_for_open = current_page.clone();
+        let selected_for_open = selected_key.clone();
+        let widgets_for_open = widgets.clone();
+        let icons_for_open = icon_names.clone();
+        let backgrounds_for_open = clock_backgrounds.clone();
+        let key_buttons_for_open = key_buttons.clone();
+        let key_pictures_for_open = key_pictures.clone();
+        let prev_for_open = prev_page_button.clone();
+        let next_for_open = next_page_button.clone();
+        let page_label_for_open = page_label.clone();
+        let editor_syncing_for_open = editor_syncing.clone();
+
+        widgets.open_folder_button.connect_clicked(move |_| {
+            if !open_folder_at_slot(
+                &state_for_open,
+                current_page_for_open.get(),
+                selected_for_open.get(),
+            ) {
+                return;
+            }
+            current_page_for_open.set(0);
+            selected_for_open.set(0);
+            clamp_page_and_selection(&state_for_open, &current_page_for_open, &selected_for_open);
+            refresh_page_controls(
+                &state_for_open,
+                &current_page_for_open,
+                &prev_for_open,
+                &next_for_open,
+                &page_label_for_open,
+            );
+
+            let selected = selected_for_open.get();
+            refresh_selected_button_state(&key_buttons_for_open, selected);
+            let icons = icons_for_open.borrow();
+            let backgrounds = backgrounds_for_open.borrow();
+            refresh_key_grid(
+                &state_for_open,
+                &key_buttons_for_open,
+                &key_pictures_for_open,
+                current_page_for_open.get(),
+                backgrounds.as_slice(),
+            );
+            populate_editor_guarded(
+                &state_for_open,
+                current_page_for_open.get(),
+                selected,
+                &widgets_for_open,
+                icons.as_slice(),
+                backgrounds.as_slice(),
+                &editor_syncing_for_open,
+            );
+            announce_status(&widgets_for_open, &tr("Opened folder"));
+        });
+    }
+
     {
         let state_for_prev_page = state.clone();
         let current_page_for_prev_page = current_page.clone();
@@ -87,7 +87,8 @@ pub(crate) fn wire_primary_action_signals(
                         state.config_path.clone()
                     };
                     (
-                        key_index_for_slot(&state.config, page, slot).map(|index| index + 1),
+                        key_index_for_slot(&state.config, &state.folder, page, slot)
+                            .map(|index| index + 1),
                         state.config.clone(),
                         config_path,
                     )
@@ -118,16 +118,6 @@ fn default_output_path(logo: &Path) -> PathBuf {
     unique_output_path(&default_output_dir(), &base_stem)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn default_output_dir_uses_shared_icons_path() {
-        assert!(default_output_dir().ends_with("streamrs/icons"));
-    }
-}
-
 fn parse_args() -> Result<CliArgs, String> {
     let program = env::args()
         .next()
@@ -574,3 +564,13 @@ pub(crate) fn run() {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_output_dir_uses_shared_icons_path() {
+        assert!(default_output_dir().ends_with("streamrs/icons"));
+    }
+}
@@ -0,0 +1,153 @@
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_IDLE_DIM_BRIGHTNESS: usize = 10;
+
+// Ordered from awake to the deepest idle level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum IdleState {
+    Awake,
+    Dimmed,
+    Off,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IdleThresholds {
+    pub dim_after: Option<Duration>,
+    pub off_after: Option<Duration>,
+}
+
+impl IdleThresholds {
+    fn levels(&self) -> impl Iterator<Item = (IdleState, Duration)> {
+        [
+            (IdleState::Dimmed, self.dim_after),
+            (IdleState::Off, self.off_after),
+        ]
+        .into_iter()
+        .filter_map(|(state, after)| Some((state, after?)))
+    }
+
+    // The level a locked session drops to right away.
+    fn deepest(&self) -> IdleState {
+        self.levels()
+            .map(|(state, _)| state)
+            .max()
+            .unwrap_or(IdleState::Awake)
+    }
+}
+
+// Tracks how long a deck has gone without key presses. Every method takes the
+// current time, so callers and tests supply the clock.
+#[derive(Clone, Debug)]
+pub struct IdleTracker {
+    thresholds: IdleThresholds,
+    last_activity: Instant,
+    // Set by a session lock; the next activity clears it.
+    forced: bool,
+}
+
+impl IdleTracker {
+    pub fn new(thresholds: IdleThresholds, now: Instant) -> Self {
+        Self {
+            thresholds,
+            last_activity: now,
+            forced: false,
+        }
+    }
+
+    pub fn set_thresholds(&mut self, thresholds: IdleThresholds) {
+        self.thresholds = thresholds;
+    }
+
+    pub fn activity(&mut self, now: Instant) {
+        self.last_activity = now;
+        self.forced = false;
+    }
+
+    pub fn force_idle(&mut self) {
+        self.forced = true;
+    }
+
+    pub fn state(&self, now: Instant) -> IdleState {
+        if self.forced {
+            return self.thresholds.deepest();
+        }
+        let idle_for = now.saturating_duration_since(self.last_activity);
+        self.thresholds
+            .levels()
+            .filter(|&(_, after)| idle_for >= after)
+            .map(|(state, _)| state)
+            .max()
+            .unwrap_or(IdleState::Awake)
+    }
+
+    // When the deck next drops below `state`, for callers that sleep until then.
+    pub fn next_deadline(&self, state: IdleState) -> Option<Instant> {
+        if self.forced {
+            return None;
+        }
+        self.thresholds
+            .levels()
+            .filter(|&(level, _)| level > state)
+            .map(|(_, after)| self.last_activity + after)
+            .min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minutes(value: u64) -> Duration {
+        Duration::from_secs(value * 60)
+    }
+
+    #[test]
+    fn deck_dims_then_turns_off_and_wakes_on_activity() {
+        let start = Instant::now();
+        let mut tracker = IdleTracker::new(
+            IdleThresholds {
+                dim_after: Some(minutes(2)),
+                off_after: Some(minutes(10)),
+            },
+            start,
+        );
+        assert_eq!(tracker.state(start + minutes(1)), IdleState::Awake);
+        assert_eq!(
+            tracker.next_deadline(IdleState::Awake),
+            Some(start + minutes(2))
+        );
+        assert_eq!(tracker.state(start + minutes(2)), IdleState::Dimmed);
+        assert_eq!(
+            tracker.next_deadline(IdleState::Dimmed),
+            Some(start + minutes(10))
+        );
+        assert_eq!(tracker.state(start + minutes(10)), IdleState::Off);
+        assert_eq!(tracker.next_deadline(IdleState::Off), None);
+
+        tracker.activity(start + minutes(11));
+        assert_eq!(tracker.state(start + minutes(12)), IdleState::Awake);
+        assert_eq!(tracker.state(start + minutes(13)), IdleState::Dimmed);
+    }
+
+    #[test]
+    fn lock_drops_to_the_deepest_configured_level_until_activity() {
+        let start = Instant::now();
+        let mut tracker = IdleTracker::new(
+            IdleThresholds {
+                dim_after: Some(minutes(5)),
+                off_after: None,
+            },
+            start,
+        );
+        tracker.force_idle();
+        assert_eq!(tracker.state(start), IdleState::Dimmed);
+        assert_eq!(tracker.next_deadline(IdleState::Dimmed), None);
+
+        tracker.activity(start + minutes(1));
+        assert_eq!(tracker.state(start + minutes(1)), IdleState::Awake);
+
+        tracker.set_thresholds(IdleThresholds::default());
+        tracker.force_idle();
+        assert_eq!(tracker.state(start + minutes(60)), IdleState::Awake);
+    }
+}
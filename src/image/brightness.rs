@@ -0,0 +1,104 @@
+use std::fmt::Write;
+
+pub const BRIGHTNESS_ICON_ALIAS: &str = "brightness.svg";
+pub const BRIGHTNESS_ICON_PREFIX: &str = "brightness://gauge";
+
+pub(super) const GAUGE_CENTER: (f32, f32) = (36.0, 34.0);
+const GAUGE_RADIUS: f32 = 24.0;
+// The gauge opens at the bottom and fills clockwise from its lower left end.
+const GAUGE_START_DEGREES: f32 = 135.0;
+pub(super) const GAUGE_SWEEP_DEGREES: f32 = 270.0;
+
+pub fn is_brightness_icon(icon: &str) -> bool {
+    icon.eq_ignore_ascii_case(BRIGHTNESS_ICON_ALIAS)
+        || icon.eq_ignore_ascii_case(BRIGHTNESS_ICON_PREFIX)
+}
+
+fn gauge_point(degrees: f32) -> (f32, f32) {
+    let radians = degrees.to_radians();
+    (
+        GAUGE_CENTER.0 + GAUGE_RADIUS * radians.cos(),
+        GAUGE_CENTER.1 + GAUGE_RADIUS * radians.sin(),
+    )
+}
+
+pub(super) fn gauge_arc(sweep_degrees: f32, stroke: &str) -> String {
+    let (x1, y1) = gauge_point(GAUGE_START_DEGREES);
+    let (x2, y2) = gauge_point(GAUGE_START_DEGREES + sweep_degrees);
+    let large_arc = u8::from(sweep_degrees > 180.0);
+    format!(
+        r##"<path d="M {x1:.2} {y1:.2} A {GAUGE_RADIUS} {GAUGE_RADIUS} 0 {large_arc} 1 {x2:.2} {y2:.2}" fill="none" stroke="{stroke}" stroke-width="7" stroke-linecap="round"/>"##
+    )
+}
+
+fn sun_glyph(cx: f32, cy: f32) -> String {
+    let mut svg = format!(r##"<circle cx="{cx}" cy="{cy}" r="2.5" fill="#f9e2af"/>"##);
+    for ray in 0..8 {
+        let radians = (ray as f32 * 45.0).to_radians();
+        let (sin, cos) = radians.sin_cos();
+        let _ = write!(
+            svg,
+            r##"<line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" stroke="#f9e2af" stroke-width="1.2" stroke-linecap="round"/>"##,
+            cx + 4.0 * cos,
+            cy + 4.0 * sin,
+            cx + 5.5 * cos,
+            cy + 5.5 * sin,
+        );
+    }
+    svg
+}
+
+// `level` is a brightness percentage; values above 100 show as 100.
+pub fn render_brightness_svg(level: usize) -> String {
+    let level = level.min(100);
+    let filled = if level == 0 {
+        String::new()
+    } else {
+        gauge_arc(GAUGE_SWEEP_DEGREES * level as f32 / 100.0, "#f9e2af")
+    };
+    let track = gauge_arc(GAUGE_SWEEP_DEGREES, "#313244");
+    let sun = sun_glyph(GAUGE_CENTER.0, 62.0);
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="72" height="72" viewBox="0 0 72 72">
+<rect x="0" y="0" width="72" height="72" rx="12" ry="12" fill="#11111b"/>
+{track}
+{filled}
+<text x="36" y="35" text-anchor="middle" dominant-baseline="middle"
+      font-family="DejaVu Sans, Arial, sans-serif" font-size="15" font-weight="700" fill="#cdd6f4">{level}%</text>
+{sun}
+</svg>"##
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::svg::load_svg_data;
+
+    #[test]
+    fn brightness_alias_and_prefix_are_case_insensitive() {
+        assert!(is_brightness_icon("brightness.svg"));
+        assert!(is_brightness_icon("BRIGHTNESS.SVG"));
+        assert!(is_brightness_icon("brightness://gauge"));
+        assert!(!is_brightness_icon("brightness.png"));
+    }
+
+    #[test]
+    fn brightness_gauge_fills_with_the_level() {
+        assert!(render_brightness_svg(40).contains(">40%<"));
+        assert!(render_brightness_svg(250).contains(">100%<"));
+        assert_eq!(render_brightness_svg(0).matches("<path").count(), 1);
+        assert_eq!(render_brightness_svg(60).matches("<path").count(), 2);
+
+        let yellow_pixels = |level| {
+            let svg = render_brightness_svg(level);
+            let image = load_svg_data(BRIGHTNESS_ICON_ALIAS, svg.as_bytes(), None, 72, 72)
+                .expect("brightness SVG should rasterize");
+            image
+                .pixels()
+                .filter(|p| p[0] > 200 && p[1] > 180 && p[2] < 200)
+                .count()
+        };
+        assert!(yellow_pixels(90) > yellow_pixels(20));
+    }
+}
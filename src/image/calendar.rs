@@ -1,7 +1,26 @@
-use chrono::{Datelike, Local, NaiveDate};
+use super::overlay::escape_xml;
+use chrono::{Datelike, Local, NaiveDate, Weekday};
+use std::ffi::{CStr, c_char, c_int, c_void};
+use std::fmt::Write;
+use std::sync::OnceLock;
 
 pub const CALENDAR_ICON_ALIAS: &str = "calendar.svg";
 pub const CALENDAR_ICON_PREFIX: &str = "calendar://month-day";
+pub const CALENDAR_HEADER_COLOR: &str = "#d20f39";
+pub const CALENDAR_BODY_COLOR: &str = "#11111b";
+const CALENDAR_TEXT_COLOR: &str = "#cdd6f4";
+
+// glibc and musl share these `langinfo.h` values.
+const LC_TIME_MASK: c_int = 1 << 2;
+const ABDAY_1: c_int = 0x20000;
+const DAY_1: c_int = 0x20007;
+const ABMON_1: c_int = 0x2000E;
+
+unsafe extern "C" {
+    fn newlocale(category_mask: c_int, locale: *const c_char, base: *mut c_void) -> *mut c_void;
+    fn nl_langinfo_l(item: c_int, locale: *mut c_void) -> *const c_char;
+    fn freelocale(locale: *mut c_void);
+}
 
 pub fn is_calendar_icon(icon: &str) -> bool {
     icon.eq_ignore_ascii_case(CALENDAR_ICON_ALIAS)
@@ -18,30 +37,192 @@ pub fn current_calendar_key() -> String {
     )
 }
 
-fn month_name_en(month: u32) -> &'static str {
-    match month {
-        1 => "JAN",
-        2 => "FEB",
-        3 => "MAR",
-        4 => "APR",
-        5 => "MAY",
-        6 => "JUN",
-        7 => "JUL",
-        8 => "AUG",
-        9 => "SEP",
-        10 => "OCT",
-        11 => "NOV",
-        12 => "DEC",
-        _ => "MON",
+// Month and weekday names; weekdays start on Monday.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CalendarNames {
+    pub months: [String; 12],
+    pub weekdays: [String; 7],
+    pub short_weekdays: [String; 7],
+}
+
+impl CalendarNames {
+    pub fn english() -> Self {
+        let names = |list: &[&str]| list.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+        Self {
+            months: names(&[
+                "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+            ])
+            .try_into()
+            .unwrap_or_default(),
+            weekdays: names(&[
+                "Monday",
+                "Tuesday",
+                "Wednesday",
+                "Thursday",
+                "Friday",
+                "Saturday",
+                "Sunday",
+            ])
+            .try_into()
+            .unwrap_or_default(),
+            short_weekdays: names(&["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"])
+                .try_into()
+                .unwrap_or_default(),
+        }
+    }
+
+    // Names from the `LC_TIME` locale of the environment, read once; English
+    // when that locale is not installed.
+    pub fn system() -> &'static Self {
+        static NAMES: OnceLock<CalendarNames> = OnceLock::new();
+        NAMES.get_or_init(|| Self::from_locale("").unwrap_or_else(Self::english))
+    }
+
+    fn from_locale(name: &str) -> Option<Self> {
+        let name = std::ffi::CString::new(name).ok()?;
+        // SAFETY: newlocale copies what it needs from `name`; a null result
+        // means the locale is not available.
+        let locale = unsafe { newlocale(LC_TIME_MASK, name.as_ptr(), std::ptr::null_mut()) };
+        if locale.is_null() {
+            return None;
+        }
+        let item = |item: c_int| {
+            // SAFETY: `locale` stays valid until freed below and the returned
+            // string is copied before then.
+            let text = unsafe { nl_langinfo_l(item, locale) };
+            if text.is_null() {
+                String::new()
+            } else {
+                unsafe { CStr::from_ptr(text) }
+                    .to_string_lossy()
+                    .into_owned()
+            }
+        };
+        // langinfo counts weekdays from Sunday.
+        let weekdays =
+            |first: c_int| std::array::from_fn(|day| item(first + (day as c_int + 1) % 7));
+        let names = Self {
+            months: std::array::from_fn(|month| item(ABMON_1 + month as c_int)),
+            weekdays: weekdays(DAY_1),
+            short_weekdays: weekdays(ABDAY_1),
+        };
+        // SAFETY: `locale` came from newlocale and is not used afterwards.
+        unsafe { freelocale(locale) };
+        let complete = names.months.iter().all(|name| !name.is_empty())
+            && names.short_weekdays.iter().all(|name| !name.is_empty());
+        complete.then_some(names)
+    }
+
+    pub(super) fn month(&self, date: NaiveDate) -> &str {
+        &self.months[date.month0() as usize]
+    }
+
+    fn weekday(&self, day: Weekday) -> &str {
+        &self.weekdays[day.num_days_from_monday() as usize]
+    }
+
+    pub(super) fn short_weekday(&self, day: Weekday) -> &str {
+        &self.short_weekdays[day.num_days_from_monday() as usize]
     }
 }
 
-pub fn render_calendar_svg_for_date(date: NaiveDate) -> String {
-    let month_name = month_name_en(date.month());
-    let day = date.day();
-    let red_height = 22.0f32;
-    let month_font_size = 12.0f32;
-    let month_text_y = (red_height / 2.0) + 1.0;
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum CalendarLayout {
+    // The month over the day of the month.
+    #[default]
+    Day,
+    // The whole month as a grid with today marked.
+    Month,
+}
+
+// How a calendar key shows the date.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CalendarFace {
+    pub weekday: bool,
+    pub week_number: bool,
+    pub first_weekday: Weekday,
+    pub header_color: String,
+    pub body_color: String,
+    pub layout: CalendarLayout,
+}
+
+impl Default for CalendarFace {
+    fn default() -> Self {
+        Self {
+            weekday: false,
+            week_number: false,
+            first_weekday: Weekday::Mon,
+            header_color: CALENDAR_HEADER_COLOR.to_string(),
+            body_color: CALENDAR_BODY_COLOR.to_string(),
+            layout: CalendarLayout::default(),
+        }
+    }
+}
+
+impl CalendarFace {
+    // Builds a face from a key's `calendar_*` fields. `first_weekday` is a day
+    // name such as `sunday` and `layout` is `day` or `month`.
+    pub fn from_fields(
+        weekday: bool,
+        week_number: bool,
+        first_weekday: Option<&str>,
+        header_color: Option<&str>,
+        body_color: Option<&str>,
+        layout: Option<&str>,
+    ) -> Result<Self, String> {
+        let color = |value: Option<&str>, default: &str| {
+            value
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .unwrap_or(default)
+                .to_string()
+        };
+        let first_weekday = match first_weekday.map(str::trim) {
+            None => Weekday::Mon,
+            Some(day) => day.parse::<Weekday>().map_err(|_| {
+                format!("invalid calendar_first_weekday '{day}'; expected a day such as monday")
+            })?,
+        };
+        let layout = match layout.map(|layout| layout.trim().to_ascii_lowercase()) {
+            None => CalendarLayout::Day,
+            Some(layout) => match layout.as_str() {
+                "day" => CalendarLayout::Day,
+                "month" => CalendarLayout::Month,
+                _ => {
+                    return Err(format!(
+                        "invalid calendar_layout '{layout}'; expected day or month"
+                    ));
+                }
+            },
+        };
+        Ok(Self {
+            weekday,
+            week_number,
+            first_weekday,
+            header_color: color(header_color, CALENDAR_HEADER_COLOR),
+            body_color: color(body_color, CALENDAR_BODY_COLOR),
+            layout,
+        })
+    }
+
+    // The weekday and week number line of the day layout.
+    fn caption(&self, date: NaiveDate, names: &CalendarNames) -> Option<String> {
+        let week = self
+            .week_number
+            .then(|| format!("W{}", date.iso_week().week()));
+        match (self.weekday, week) {
+            (true, Some(week)) => Some(format!("{} {week}", names.short_weekday(date.weekday()))),
+            (true, None) => Some(names.weekday(date.weekday()).to_string()),
+            (false, week) => week,
+        }
+    }
+}
+
+fn fitted_text_size(text: &str, width: f32, max_size: f32) -> f32 {
+    (width / (text.chars().count().max(1) as f32 * 0.6)).min(max_size)
+}
+
+fn calendar_frame_svg(face: &CalendarFace, header_height: f32, body: &str) -> String {
     format!(
         r##"<svg xmlns="http://www.w3.org/2000/svg" width="72" height="72" viewBox="0 0 72 72">
 <defs>
@@ -50,20 +231,130 @@ pub fn render_calendar_svg_for_date(date: NaiveDate) -> String {
   </clipPath>
 </defs>
 <g clip-path="url(#calendar-clip)">
-  <rect x="0" y="0" width="72" height="72" fill="#11111b"/>
-  <rect x="0" y="0" width="72" height="22" fill="#d20f39"/>
-  <rect x="0" y="22" width="72" height="50" fill="#11111b"/>
+  <rect x="0" y="0" width="72" height="72" fill="{body_color}"/>
+  <rect x="0" y="0" width="72" height="{header_height}" fill="{header_color}"/>
+  <rect x="0" y="{header_height}" width="72" height="{body_height}" fill="{body_color}"/>
 </g>
-<text x="36" y="{month_text_y:.1}" text-anchor="middle" dominant-baseline="middle"
-      font-family="DejaVu Sans, Arial, sans-serif" font-size="{month_font_size:.1}" font-weight="700" fill="#cdd6f4">{month_name}</text>
-<text x="36" y="49" text-anchor="middle" dominant-baseline="middle"
-      font-family="DejaVu Sans, Arial, sans-serif" font-size="34" font-weight="500" fill="#cdd6f4">{day}</text>
-</svg>"##
+{body}</svg>"##,
+        header_color = escape_xml(&face.header_color),
+        body_color = escape_xml(&face.body_color),
+        body_height = 72.0 - header_height
     )
 }
 
-pub fn render_calendar_svg() -> String {
-    render_calendar_svg_for_date(Local::now().date_naive())
+fn render_day_svg(face: &CalendarFace, date: NaiveDate, names: &CalendarNames) -> String {
+    let month_name = escape_xml(&names.month(date).to_uppercase());
+    let day = date.day();
+    let red_height = 22.0f32;
+    let month_font_size = fitted_text_size(&month_name, 64.0, 12.0);
+    let month_text_y = (red_height / 2.0) + 1.0;
+    let mut body = format!(
+        r##"<text x="36" y="{month_text_y:.1}" text-anchor="middle" dominant-baseline="middle"
+      font-family="DejaVu Sans, Arial, sans-serif" font-size="{month_font_size:.1}" font-weight="700" fill="{CALENDAR_TEXT_COLOR}">{month_name}</text>
+"##
+    );
+    let (day_y, day_size) = match face.caption(date, names) {
+        Some(caption) => {
+            let _ = write!(
+                body,
+                r##"<text x="36" y="64" text-anchor="middle" dominant-baseline="middle"
+      font-family="DejaVu Sans, Arial, sans-serif" font-size="{size:.1}" font-weight="700" fill="{CALENDAR_TEXT_COLOR}">{caption}</text>
+"##,
+                size = fitted_text_size(&caption, 64.0, 10.0),
+                caption = escape_xml(&caption)
+            );
+            (41, 28)
+        }
+        None => (49, 34),
+    };
+    let _ = write!(
+        body,
+        r##"<text x="36" y="{day_y}" text-anchor="middle" dominant-baseline="middle"
+      font-family="DejaVu Sans, Arial, sans-serif" font-size="{day_size}" font-weight="500" fill="{CALENDAR_TEXT_COLOR}">{day}</text>
+"##
+    );
+    calendar_frame_svg(face, red_height, &body)
+}
+
+fn render_month_svg(face: &CalendarFace, date: NaiveDate, names: &CalendarNames) -> String {
+    let header_height = 14.0f32;
+    let mut header = names.month(date).to_uppercase();
+    if face.week_number {
+        let _ = write!(header, " W{}", date.iso_week().week());
+    }
+    let mut body = format!(
+        r##"<text x="36" y="7.5" text-anchor="middle" dominant-baseline="middle"
+      font-family="DejaVu Sans, Arial, sans-serif" font-size="{size:.1}" font-weight="700" fill="{CALENDAR_TEXT_COLOR}">{header}</text>
+"##,
+        size = fitted_text_size(&header, 64.0, 9.0),
+        header = escape_xml(&header)
+    );
+
+    let column_x = |column: u32| 6.0 + column as f32 * 10.0;
+    let mut day = face.first_weekday;
+    for column in 0..7 {
+        let initial = names
+            .short_weekday(day)
+            .chars()
+            .next()
+            .map(|initial| initial.to_uppercase().to_string())
+            .unwrap_or_default();
+        let _ = writeln!(
+            body,
+            r##"<text x="{x:.1}" y="20" text-anchor="middle" font-family="DejaVu Sans, Arial, sans-serif" font-size="5.5" font-weight="700" fill="{CALENDAR_TEXT_COLOR}" fill-opacity="0.7">{initial}</text>"##,
+            x = column_x(column),
+            initial = escape_xml(&initial)
+        );
+        day = day.succ();
+    }
+
+    let first = date.with_day(1).unwrap_or(date);
+    let offset = (first.weekday().num_days_from_monday() + 7
+        - face.first_weekday.num_days_from_monday())
+        % 7;
+    let days = first
+        .checked_add_months(chrono::Months::new(1))
+        .and_then(|next| next.pred_opt())
+        .map_or(28, |last| last.day());
+    let rows = (offset + days).div_ceil(7);
+    let row_height = (48.0 / rows as f32).min(9.0);
+    for number in 1..=days {
+        let cell = offset + number - 1;
+        let x = column_x(cell % 7);
+        let y = 28.0 + (cell / 7) as f32 * row_height;
+        if number == date.day() {
+            let _ = writeln!(
+                body,
+                r##"<rect x="{left:.1}" y="{top:.1}" width="10" height="{height:.1}" rx="2" fill="{color}"/>"##,
+                left = x - 5.0,
+                top = y - row_height * 0.72,
+                height = row_height,
+                color = escape_xml(&face.header_color)
+            );
+        }
+        let _ = writeln!(
+            body,
+            r##"<text x="{x:.1}" y="{y:.1}" text-anchor="middle" font-family="DejaVu Sans, Arial, sans-serif" font-size="6" font-weight="{weight}" fill="{CALENDAR_TEXT_COLOR}">{number}</text>"##,
+            weight = if number == date.day() { 700 } else { 400 }
+        );
+    }
+    calendar_frame_svg(face, header_height, &body)
+}
+
+pub fn render_calendar_face_svg(
+    face: &CalendarFace,
+    date: NaiveDate,
+    names: &CalendarNames,
+) -> String {
+    match face.layout {
+        CalendarLayout::Day => render_day_svg(face, date, names),
+        CalendarLayout::Month => render_month_svg(face, date, names),
+    }
+}
+
+// Today's face in the names of the environment's locale.
+pub fn render_calendar_svg(face: &CalendarFace) -> String {
+    render_calendar_face_svg(face, Local::now().date_naive(), CalendarNames::system())
 }
 
 #[cfg(test)]
@@ -71,6 +362,10 @@ mod tests {
     use super::*;
     use crate::image::svg::load_svg_data;
 
+    fn render_calendar_svg_for_date(date: NaiveDate) -> String {
+        render_calendar_face_svg(&CalendarFace::default(), date, &CalendarNames::english())
+    }
+
     #[test]
     fn calendar_alias_and_prefix_are_case_insensitive() {
         assert!(is_calendar_icon("calendar.svg"));
@@ -122,4 +417,99 @@ mod tests {
             "calendar day digits should produce visible light pixels on the dark bottom area"
         );
     }
+
+    #[test]
+    fn calendar_names_come_from_the_locale() {
+        assert_eq!(
+            CalendarNames::from_locale("C").expect("the C locale always exists"),
+            CalendarNames::english()
+        );
+        assert!(CalendarNames::from_locale("xx_NOWHERE.UTF-8").is_none());
+
+        let mut names = CalendarNames::english();
+        names.months[2] = "marts".to_string();
+        names.weekdays[5] = "sestdiena".to_string();
+        let face = CalendarFace {
+            weekday: true,
+            ..CalendarFace::default()
+        };
+        let date = NaiveDate::from_ymd_opt(2024, 3, 9).expect("test date should be valid");
+        let svg = render_calendar_face_svg(&face, date, &names);
+        assert!(svg.contains(">MARTS</text>"));
+        assert!(svg.contains(">sestdiena</text>"));
+    }
+
+    #[test]
+    fn calendar_fields_parse_and_reject_unknown_values() {
+        let face = CalendarFace::from_fields(
+            true,
+            true,
+            Some("Sunday"),
+            Some("teal"),
+            Some(" "),
+            Some("MONTH"),
+        )
+        .expect("calendar fields should parse");
+        assert_eq!(face.first_weekday, Weekday::Sun);
+        assert_eq!(face.header_color, "teal");
+        assert_eq!(face.body_color, CALENDAR_BODY_COLOR);
+        assert_eq!(face.layout, CalendarLayout::Month);
+
+        assert!(
+            CalendarFace::from_fields(false, false, Some("someday"), None, None, None).is_err()
+        );
+        assert!(CalendarFace::from_fields(false, false, None, None, None, Some("year")).is_err());
+    }
+
+    #[test]
+    fn calendar_day_layout_adds_weekday_and_week_number() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 9).expect("test date should be valid");
+        let names = CalendarNames::english();
+        let face = |weekday, week_number| CalendarFace {
+            weekday,
+            week_number,
+            ..CalendarFace::default()
+        };
+        let svg = render_calendar_face_svg(&face(false, true), date, &names);
+        assert!(svg.contains(">W10</text>"));
+        let svg = render_calendar_face_svg(&face(true, true), date, &names);
+        assert!(svg.contains(">Sat W10</text>"));
+        assert!(svg.contains(r#"font-size="28""#));
+        let svg = render_calendar_face_svg(&face(true, false), date, &names);
+        assert!(svg.contains(">Saturday</text>"));
+    }
+
+    #[test]
+    fn calendar_month_layout_starts_weeks_on_the_first_weekday() {
+        // March 2024 starts on a Friday and today is the 9th.
+        let date = NaiveDate::from_ymd_opt(2024, 3, 9).expect("test date should be valid");
+        let names = CalendarNames::english();
+        let face = CalendarFace {
+            layout: CalendarLayout::Month,
+            header_color: "#123456".to_string(),
+            ..CalendarFace::default()
+        };
+        let svg = render_calendar_face_svg(&face, date, &names);
+        let initials = svg
+            .lines()
+            .filter(|line| line.contains(r#"font-size="5.5""#))
+            .filter_map(|line| line.split('>').nth(1))
+            .map(|text| text.trim_end_matches("</text"))
+            .collect::<String>();
+        assert_eq!(initials, "MTWTFSS");
+        assert!(svg.contains(r#"<text x="46.0" y="28.0""#));
+        assert!(svg.contains(r##"rx="2" fill="#123456""##));
+        assert_eq!(
+            svg.matches(r##"font-weight="700" fill="#cdd6f4">"##)
+                .count(),
+            2
+        );
+
+        let sunday_first = CalendarFace {
+            first_weekday: Weekday::Sun,
+            ..face
+        };
+        let svg = render_calendar_face_svg(&sunday_first, date, &names);
+        assert!(svg.contains(r#"<text x="56.0" y="28.0""#));
+    }
 }
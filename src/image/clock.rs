@@ -1,5 +1,11 @@
-use chrono::Local;
+use super::calendar::CalendarNames;
+use super::overlay::escape_xml;
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, Timelike, Utc};
+use chrono_tz::Tz;
+use std::f32::consts::PI;
+use std::fmt::Write;
 use std::path::Path;
+use std::time::Duration;
 
 pub const CLOCK_ICON_ALIAS: &str = "clock.svg";
 pub const CLOCK_ICON_PREFIX: &str = "clock://hh:mm";
@@ -10,9 +16,173 @@ pub const CLOCK_DIGIT_WIDTH: i32 = 12;
 pub const CLOCK_DIGIT_HEIGHT: i32 = 24;
 pub const CLOCK_COLON_WIDTH: i32 = 4;
 pub const CLOCK_CHAR_GAP: i32 = 2;
+// Faces that change once a minute still look at the time this often, so a
+// resume from suspend or a clock change shows up quickly.
+pub const CLOCK_MAX_REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+const CLOCK_CAPTION_COLOR: &str = "#cdd6f4";
+const CLOCK_MAX_DIGITS_WIDTH: i32 = 68;
 
-pub fn current_clock_text() -> String {
-    Local::now().format("%H:%M").to_string()
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ClockStyle {
+    #[default]
+    Digital,
+    Analog,
+}
+
+// How a clock key shows the time. The default is the 24-hour `HH:MM` face in
+// local time.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ClockFace {
+    pub background: Option<String>,
+    pub hour12: bool,
+    pub seconds: bool,
+    pub date: bool,
+    // An IANA name such as `America/New_York`; local time without one.
+    pub time_zone: Option<String>,
+    pub label: Option<String>,
+    pub style: ClockStyle,
+}
+
+fn named_time_zone(name: &str) -> Result<Tz, String> {
+    name.parse()
+        .map_err(|_| format!("unknown time zone '{name}'"))
+}
+
+impl ClockFace {
+    // Builds a face from a key's `clock_*` fields. `format` is `24h` or `12h`
+    // and `style` is `digital` or `analog`; the time zone must exist.
+    pub fn from_fields(
+        background: Option<&str>,
+        format: Option<&str>,
+        seconds: bool,
+        date: bool,
+        time_zone: Option<&str>,
+        label: Option<&str>,
+        style: Option<&str>,
+    ) -> Result<Self, String> {
+        let non_empty = |value: Option<&str>| {
+            value
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+        let hour12 = match format.map(|format| format.trim().to_ascii_lowercase()) {
+            None => false,
+            Some(format) => match format.as_str() {
+                "24h" | "24" => false,
+                "12h" | "12" => true,
+                _ => {
+                    return Err(format!(
+                        "invalid clock_format '{format}'; expected 24h or 12h"
+                    ));
+                }
+            },
+        };
+        let style = match style.map(|style| style.trim().to_ascii_lowercase()) {
+            None => ClockStyle::Digital,
+            Some(style) => match style.as_str() {
+                "digital" => ClockStyle::Digital,
+                "analog" => ClockStyle::Analog,
+                _ => {
+                    return Err(format!(
+                        "invalid clock_style '{style}'; expected digital or analog"
+                    ));
+                }
+            },
+        };
+        let time_zone = non_empty(time_zone);
+        if let Some(zone) = &time_zone {
+            named_time_zone(zone)?;
+        }
+        Ok(Self {
+            background: non_empty(background),
+            hour12,
+            seconds,
+            date,
+            time_zone,
+            label: non_empty(label),
+            style,
+        })
+    }
+
+    pub fn local_time(&self, utc: DateTime<Utc>) -> Result<NaiveDateTime, String> {
+        match &self.time_zone {
+            Some(zone) => Ok(utc.with_timezone(&named_time_zone(zone)?).naive_local()),
+            None => Ok(utc.with_timezone(&Local).naive_local()),
+        }
+    }
+
+    // Falls back to local time if the time zone went missing since it was checked.
+    pub fn now(&self) -> NaiveDateTime {
+        let now = Utc::now();
+        self.local_time(now)
+            .unwrap_or_else(|_| now.with_timezone(&Local).naive_local())
+    }
+
+    // Changes exactly when the face drawn for `time` does.
+    pub fn text(&self, time: NaiveDateTime) -> String {
+        let mut text = self.digits(time);
+        for part in [self.meridiem(time), self.date_text(time)]
+            .into_iter()
+            .flatten()
+        {
+            text.push(' ');
+            text.push_str(&part);
+        }
+        text
+    }
+
+    // How long after `time` to look at the clock again.
+    pub fn refresh_delay(&self, time: NaiveDateTime) -> Duration {
+        let until_next_second = Duration::from_secs(1)
+            - Duration::from_nanos(u64::from(time.nanosecond() % 1_000_000_000));
+        if self.seconds {
+            return until_next_second;
+        }
+        let rest_of_minute = Duration::from_secs(u64::from(59 - time.second().min(59)));
+        (until_next_second + rest_of_minute).min(CLOCK_MAX_REFRESH_INTERVAL)
+    }
+
+    fn digits(&self, time: NaiveDateTime) -> String {
+        let mut digits = if self.hour12 {
+            format!("{}:{:02}", (time.hour() + 11) % 12 + 1, time.minute())
+        } else {
+            time.format("%H:%M").to_string()
+        };
+        if self.seconds {
+            let _ = write!(digits, ":{:02}", time.second());
+        }
+        digits
+    }
+
+    fn meridiem(&self, time: NaiveDateTime) -> Option<String> {
+        self.hour12
+            .then(|| if time.hour() < 12 { "AM" } else { "PM" }.to_string())
+    }
+
+    fn date_text(&self, time: NaiveDateTime) -> Option<String> {
+        self.date
+            .then(|| clock_date(time.date(), CalendarNames::system()))
+    }
+
+    // The line under the time: AM/PM and the date.
+    fn caption(&self, time: NaiveDateTime) -> Option<String> {
+        let parts = [self.meridiem(time), self.date_text(time)]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        (!parts.is_empty()).then(|| parts.join(" "))
+    }
+}
+
+// Weekday, day and month, named like the calendar icon names them.
+fn clock_date(date: NaiveDate, names: &CalendarNames) -> String {
+    format!(
+        "{} {} {}",
+        names.short_weekday(date.weekday()),
+        date.day(),
+        names.month(date)
+    )
 }
 
 pub fn is_clock_icon(icon: &str) -> bool {
@@ -79,6 +249,18 @@ pub fn render_clock_segments_svg(
     text: &str,
     background_name: Option<&str>,
 ) -> String {
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="72" height="72" viewBox="0 0 72 72">
+{background}
+{glyphs}
+</svg>"##,
+        background = clock_background_svg(image_dir, background_name),
+        glyphs = clock_digits_svg(text)
+    )
+}
+
+// Seven-segment digits centered on the key, narrowed to fit when needed.
+pub(super) fn clock_digits_svg(text: &str) -> String {
     let chars: Vec<char> = text.chars().collect();
     let gaps = chars.len().saturating_sub(1) as i32;
     let total_width =
@@ -104,13 +286,98 @@ pub fn render_clock_segments_svg(
         x += clock_char_width(ch) + CLOCK_CHAR_GAP;
     }
 
+    if total_width <= CLOCK_MAX_DIGITS_WIDTH {
+        return glyphs;
+    }
+    let scale = CLOCK_MAX_DIGITS_WIDTH as f32 / total_width as f32;
+    format!(
+        r##"<g transform="translate(36 36) scale({scale:.3}) translate(-36 -36)">{glyphs}</g>"##
+    )
+}
+
+fn clock_caption_svg(text: &str, y: f32, max_size: f32) -> String {
+    let size = (66.0 / (text.chars().count().max(1) as f32 * 0.6)).min(max_size);
+    format!(
+        r##"<text x="36" y="{y:.1}" text-anchor="middle" font-family="DejaVu Sans, Arial, sans-serif" font-size="{size:.1}" font-weight="700" fill="{CLOCK_CAPTION_COLOR}">{text}</text>"##,
+        text = escape_xml(text)
+    )
+}
+
+// Where a hand `turns` of the way round the dial from 12 ends.
+fn dial_point(turns: f32, radius: f32) -> (f32, f32) {
+    let angle = turns * 2.0 * PI;
+    (36.0 + radius * angle.sin(), 36.0 - radius * angle.cos())
+}
+
+fn analog_dial_svg() -> String {
+    let mut dial = String::from(
+        r##"<circle cx="36" cy="36" r="31" fill="#11111b" fill-opacity="0.85" stroke="#cdd6f4" stroke-width="2"/>"##,
+    );
+    for hour in 0..12 {
+        let inner = if hour % 3 == 0 { 23.0 } else { 26.0 };
+        let (x1, y1) = dial_point(hour as f32 / 12.0, inner);
+        let (x2, y2) = dial_point(hour as f32 / 12.0, 29.0);
+        let _ = write!(
+            dial,
+            r##"<line x1="{x1:.2}" y1="{y1:.2}" x2="{x2:.2}" y2="{y2:.2}" stroke="#cdd6f4" stroke-width="{width}" stroke-linecap="round"/>"##,
+            width = if hour % 3 == 0 { 2 } else { 1 }
+        );
+    }
+    dial
+}
+
+fn analog_hands_svg(time: NaiveDateTime, seconds: bool) -> String {
+    let minutes = time.minute() as f32 + time.second() as f32 / 60.0;
+    let hours = (time.hour() % 12) as f32 + minutes / 60.0;
+    let mut hands = String::new();
+    let mut hand = |turns: f32, length: f32, width: f32, color: &str| {
+        let (x, y) = dial_point(turns, length);
+        let _ = write!(
+            hands,
+            r##"<line x1="36" y1="36" x2="{x:.2}" y2="{y:.2}" stroke="{color}" stroke-width="{width}" stroke-linecap="round"/>"##
+        );
+    };
+    hand(hours / 12.0, 15.0, 4.0, "#ffffff");
+    hand(minutes / 60.0, 23.0, 3.0, "#ffffff");
+    if seconds {
+        hand(time.second() as f32 / 60.0, 26.0, 1.0, "#f38ba8");
+    }
+    hands.push_str(r##"<circle cx="36" cy="36" r="2.5" fill="#ffffff"/>"##);
+    hands
+}
+
+// The face for `time`, which is already in the face's time zone.
+pub fn render_clock_face_svg(image_dir: &Path, face: &ClockFace, time: NaiveDateTime) -> String {
+    let caption = face.caption(time);
+    let body = match face.style {
+        ClockStyle::Digital => {
+            let mut body = clock_digits_svg(&face.digits(time));
+            if let Some(label) = &face.label {
+                body.push_str(&clock_caption_svg(label, 14.0, 10.0));
+            }
+            if let Some(caption) = &caption {
+                body.push_str(&clock_caption_svg(caption, 63.0, 10.0));
+            }
+            body
+        }
+        ClockStyle::Analog => {
+            let mut body = analog_dial_svg();
+            if let Some(label) = &face.label {
+                body.push_str(&clock_caption_svg(label, 26.0, 7.0));
+            }
+            if let Some(caption) = &caption {
+                body.push_str(&clock_caption_svg(caption, 53.0, 7.0));
+            }
+            body.push_str(&analog_hands_svg(time, face.seconds));
+            body
+        }
+    };
     format!(
         r##"<svg xmlns="http://www.w3.org/2000/svg" width="72" height="72" viewBox="0 0 72 72">
 {background}
-{glyphs}
+{body}
 </svg>"##,
-        background = clock_background_svg(image_dir, background_name),
-        glyphs = glyphs
+        background = clock_background_svg(image_dir, face.background.as_deref())
     )
 }
 
@@ -160,4 +427,124 @@ mod tests {
         assert!(svg.contains(CLOCK_FALLBACK_BACKGROUND_COLOR));
         assert!(!svg.contains(r#"href="missing.png""#));
     }
+
+    fn at(hour: u32, minute: u32, second: u32) -> NaiveDateTime {
+        chrono::NaiveDate::from_ymd_opt(2024, 3, 9)
+            .and_then(|date| date.and_hms_milli_opt(hour, minute, second, 250))
+            .expect("test time should be valid")
+    }
+
+    #[test]
+    fn clock_fields_parse_and_reject_unknown_values() {
+        let face = ClockFace::from_fields(
+            Some(" "),
+            Some("12H"),
+            true,
+            true,
+            Some(" UTC "),
+            Some("London"),
+            Some("Analog"),
+        )
+        .expect("clock fields should parse");
+        assert_eq!(face.background, None);
+        assert!(face.hour12 && face.seconds && face.date);
+        assert_eq!(face.time_zone.as_deref(), Some("UTC"));
+        assert_eq!(face.style, ClockStyle::Analog);
+
+        let field = |format, zone, style| {
+            ClockFace::from_fields(None, format, false, false, zone, None, style)
+        };
+        assert!(field(Some("13h"), None, None).is_err());
+        assert!(field(None, None, Some("sundial")).is_err());
+        assert!(field(None, Some("Nowhere/Atlantis"), None).is_err());
+        assert!(field(None, Some("../etc/passwd"), None).is_err());
+    }
+
+    #[test]
+    fn clock_time_zones_follow_daylight_saving_time() {
+        let local = |zone: &str, month: u32| {
+            let face = ClockFace {
+                time_zone: Some(zone.to_string()),
+                ..ClockFace::default()
+            };
+            let utc = NaiveDate::from_ymd_opt(2026, month, 10)
+                .and_then(|date| date.and_hms_opt(12, 0, 0))
+                .expect("test time should exist")
+                .and_utc();
+            face.local_time(utc)
+                .expect("time zone should exist")
+                .time()
+                .hour()
+        };
+        assert_eq!(local("America/New_York", 1), 7);
+        assert_eq!(local("America/New_York", 7), 8);
+        // Southern zones are in daylight time across the new year.
+        assert_eq!(local("Australia/Sydney", 1), 23);
+        assert_eq!(local("Australia/Sydney", 7), 22);
+        assert_eq!(local("UTC", 7), 12);
+    }
+
+    #[test]
+    fn clock_text_follows_format_seconds_and_date() {
+        let mut face = ClockFace::default();
+        assert_eq!(face.text(at(0, 5, 9)), "00:05");
+        face.hour12 = true;
+        assert_eq!(face.text(at(0, 5, 9)), "12:05 AM");
+        assert_eq!(face.text(at(13, 5, 9)), "1:05 PM");
+        face.seconds = true;
+        assert_eq!(face.text(at(13, 5, 9)), "1:05:09 PM");
+        face.date = true;
+        let date = at(13, 5, 9).date();
+        assert_eq!(
+            face.text(at(13, 5, 9)),
+            format!("1:05:09 PM {}", clock_date(date, CalendarNames::system()))
+        );
+
+        assert_eq!(clock_date(date, &CalendarNames::english()), "Sat 9 Mar");
+        let mut names = CalendarNames::english();
+        names.months[2] = "marts".to_string();
+        names.short_weekdays[5] = "S".to_string();
+        assert_eq!(clock_date(date, &names), "S 9 marts");
+    }
+
+    #[test]
+    fn clock_refreshes_on_the_next_second_or_minute() {
+        let mut face = ClockFace::default();
+        assert_eq!(
+            face.refresh_delay(at(9, 0, 58)),
+            Duration::from_millis(1750)
+        );
+        assert_eq!(face.refresh_delay(at(9, 0, 10)), CLOCK_MAX_REFRESH_INTERVAL);
+        face.seconds = true;
+        assert_eq!(face.refresh_delay(at(9, 0, 10)), Duration::from_millis(750));
+    }
+
+    #[test]
+    fn clock_faces_draw_labels_captions_and_hands() {
+        let dir = test_temp_dir("faces");
+        let face = ClockFace {
+            hour12: true,
+            seconds: true,
+            label: Some("Tokyo & co".to_string()),
+            ..ClockFace::default()
+        };
+        let svg = render_clock_face_svg(&dir, &face, at(21, 30, 45));
+        assert!(svg.contains(">Tokyo &amp; co</text>"));
+        assert!(svg.contains(">PM</text>"));
+        assert!(svg.contains("scale("));
+        assert!(!svg.contains("<line"));
+
+        let analog = ClockFace {
+            style: ClockStyle::Analog,
+            ..face
+        };
+        let svg = render_clock_face_svg(&dir, &analog, at(21, 30, 45));
+        // Twelve ticks and three hands.
+        assert_eq!(svg.matches("<line").count(), 15);
+        assert!(!svg.contains("scale("));
+        assert_eq!(
+            render_clock_face_svg(&dir, &ClockFace::default(), at(12, 34, 0)),
+            render_clock_segments_svg(&dir, "12:34", None)
+        );
+    }
 }
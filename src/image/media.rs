@@ -0,0 +1,144 @@
+use std::path::PathBuf;
+
+pub const NOW_PLAYING_ICON: &str = "media://now-playing";
+const MEDIA_BACKGROUND_COLOR: &str = "#11111b";
+const MEDIA_ACTIVE_COLOR: &str = "#cdd6f4";
+const MEDIA_IDLE_COLOR: &str = "#6c7086";
+const MEDIA_BADGE_COLOR: &str = "#a6e3a1";
+
+pub fn is_now_playing_icon(icon: &str) -> bool {
+    icon.trim().eq_ignore_ascii_case(NOW_PLAYING_ICON)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum PlaybackStatus {
+    Playing,
+    Paused,
+    #[default]
+    Stopped,
+}
+
+impl PlaybackStatus {
+    // MPRIS `PlaybackStatus` values; anything else counts as stopped.
+    pub fn from_mpris(value: &str) -> Self {
+        match value {
+            "Playing" => Self::Playing,
+            "Paused" => Self::Paused,
+            _ => Self::Stopped,
+        }
+    }
+}
+
+// Album art is only shown from local files; `file://` URLs are percent-decoded.
+pub fn art_path_from_url(url: &str) -> Option<PathBuf> {
+    let path = url.strip_prefix("file://")?;
+    let path = path.strip_prefix("localhost").unwrap_or(path);
+    if !path.starts_with('/') {
+        return None;
+    }
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%')
+            .then(|| bytes.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    Some(PathBuf::from(String::from_utf8(decoded).ok()?))
+}
+
+fn status_badge_svg(status: PlaybackStatus) -> String {
+    match status {
+        PlaybackStatus::Playing => {
+            format!(r##"<path d="M 50 48 L 64 56 L 50 64 Z" fill="{MEDIA_BADGE_COLOR}"/>"##)
+        }
+        PlaybackStatus::Paused => format!(
+            r##"<rect x="50" y="48" width="5" height="16" rx="1" fill="{MEDIA_BADGE_COLOR}"/><rect x="59" y="48" width="5" height="16" rx="1" fill="{MEDIA_BADGE_COLOR}"/>"##
+        ),
+        PlaybackStatus::Stopped => format!(
+            r##"<rect x="50" y="49" width="14" height="14" rx="2" fill="{MEDIA_IDLE_COLOR}"/>"##
+        ),
+    }
+}
+
+// Drawn when no player runs or the track has no local album art: a note with
+// the playback state in the corner.
+pub fn render_media_svg(status: PlaybackStatus) -> String {
+    let note = if status == PlaybackStatus::Stopped {
+        MEDIA_IDLE_COLOR
+    } else {
+        MEDIA_ACTIVE_COLOR
+    };
+    let badge = status_badge_svg(status);
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="72" height="72" viewBox="0 0 72 72">
+<rect x="0" y="0" width="72" height="72" rx="12" ry="12" fill="{MEDIA_BACKGROUND_COLOR}"/>
+<path d="M 30 14 L 50 10 L 50 38 M 30 14 L 30 44" fill="none" stroke="{note}" stroke-width="4" stroke-linejoin="round"/>
+<ellipse cx="24" cy="45" rx="7" ry="5.5" fill="{note}"/>
+<ellipse cx="44" cy="39" rx="7" ry="5.5" fill="{note}"/>
+{badge}
+</svg>"##
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::svg::load_svg_data;
+
+    #[test]
+    fn art_urls_only_resolve_local_files() {
+        assert_eq!(
+            art_path_from_url("file:///home/me/Music/Cover%20Art.jpg"),
+            Some(PathBuf::from("/home/me/Music/Cover Art.jpg"))
+        );
+        assert_eq!(
+            art_path_from_url("file://localhost/tmp/a%2"),
+            Some(PathBuf::from("/tmp/a%2"))
+        );
+        assert_eq!(
+            art_path_from_url("file:///tmp/%C3%A4.png"),
+            Some(PathBuf::from("/tmp/ä.png"))
+        );
+        assert_eq!(art_path_from_url("https://example.com/art.jpg"), None);
+        assert_eq!(art_path_from_url("file://host/art.jpg"), None);
+    }
+
+    #[test]
+    fn media_faces_show_the_playback_state() {
+        assert_eq!(
+            PlaybackStatus::from_mpris("Playing"),
+            PlaybackStatus::Playing
+        );
+        assert_eq!(PlaybackStatus::from_mpris("Paused"), PlaybackStatus::Paused);
+        assert_eq!(PlaybackStatus::from_mpris("bogus"), PlaybackStatus::Stopped);
+        assert!(is_now_playing_icon("Media://Now-Playing"));
+        assert!(!is_now_playing_icon("media.png"));
+
+        let faces = [
+            PlaybackStatus::Playing,
+            PlaybackStatus::Paused,
+            PlaybackStatus::Stopped,
+        ]
+        .map(render_media_svg);
+        assert!(faces[0].contains("<path d=\"M 50 48"));
+        assert_eq!(faces[1].matches("<rect").count(), 3);
+        assert!(faces[2].contains(MEDIA_IDLE_COLOR));
+        for svg in faces {
+            load_svg_data(NOW_PLAYING_ICON, svg.as_bytes(), None, 72, 72)
+                .expect("media SVG should rasterize");
+        }
+    }
+}
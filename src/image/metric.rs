@@ -0,0 +1,260 @@
+use super::brightness::{GAUGE_CENTER, GAUGE_SWEEP_DEGREES, gauge_arc};
+use super::overlay::escape_xml;
+use crate::metrics::{METRIC_HISTORY_LEN, Metric, MetricSample, MetricSampler};
+use std::fmt::Write;
+use std::time::Duration;
+
+pub const METRIC_DEFAULT_INTERVAL: Duration = Duration::from_secs(2);
+const METRIC_MIN_INTERVAL_SECONDS: u64 = 1;
+const METRIC_MAX_INTERVAL_SECONDS: u64 = 60;
+const METRIC_TRACK_COLOR: &str = "#313244";
+const METRIC_TEXT_COLOR: &str = "#cdd6f4";
+const METRIC_LABEL_COLOR: &str = "#a6adc8";
+const SPARKLINE_LEFT: f32 = 6.0;
+const SPARKLINE_WIDTH: f32 = 60.0;
+const SPARKLINE_BOTTOM: f32 = 64.0;
+const SPARKLINE_HEIGHT: f32 = 22.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MetricStyle {
+    Gauge,
+    Sparkline,
+}
+
+// How a metric key draws its readings and how often it takes them.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MetricFace {
+    // `None` picks the style that suits the metric.
+    pub style: Option<MetricStyle>,
+    pub interval: Duration,
+}
+
+impl Default for MetricFace {
+    fn default() -> Self {
+        Self {
+            style: None,
+            interval: METRIC_DEFAULT_INTERVAL,
+        }
+    }
+}
+
+impl MetricFace {
+    // Builds a face from a key's `metric_*` fields. `style` is `gauge` or
+    // `sparkline`; the interval is clamped to 1..=60 seconds.
+    pub fn from_fields(style: Option<&str>, interval_seconds: Option<u64>) -> Result<Self, String> {
+        let style = match style.map(|style| style.trim().to_ascii_lowercase()) {
+            None => None,
+            Some(style) => Some(match style.as_str() {
+                "gauge" => MetricStyle::Gauge,
+                "sparkline" => MetricStyle::Sparkline,
+                _ => {
+                    return Err(format!(
+                        "invalid metric_style '{style}'; expected gauge or sparkline"
+                    ));
+                }
+            }),
+        };
+        let interval = interval_seconds.map_or(METRIC_DEFAULT_INTERVAL, |seconds| {
+            Duration::from_secs(
+                seconds.clamp(METRIC_MIN_INTERVAL_SECONDS, METRIC_MAX_INTERVAL_SECONDS),
+            )
+        });
+        Ok(Self { style, interval })
+    }
+
+    // CPU and load move quickly and read best as a trend; the others are levels.
+    pub fn style_for(&self, metric: &Metric) -> MetricStyle {
+        self.style.unwrap_or(match metric {
+            Metric::Cpu | Metric::Load => MetricStyle::Sparkline,
+            Metric::Memory | Metric::Disk(_) | Metric::Temperature(_) => MetricStyle::Gauge,
+        })
+    }
+}
+
+fn level_color(fraction: f32) -> &'static str {
+    if fraction < 0.6 {
+        "#a6e3a1"
+    } else if fraction < 0.85 {
+        "#f9e2af"
+    } else {
+        "#f38ba8"
+    }
+}
+
+fn fitted_text_size(text: &str, width: f32, max_size: f32) -> f32 {
+    (width / (text.chars().count().max(1) as f32 * 0.6)).min(max_size)
+}
+
+fn text_svg(text: &str, y: f32, size: f32, weight: u32, fill: &str) -> String {
+    format!(
+        r##"<text x="36" y="{y}" text-anchor="middle" dominant-baseline="middle" font-family="DejaVu Sans, Arial, sans-serif" font-size="{size:.1}" font-weight="{weight}" fill="{fill}">{}</text>"##,
+        escape_xml(text)
+    )
+}
+
+fn gauge_svg(sample: &MetricSample) -> String {
+    let mut body = gauge_arc(GAUGE_SWEEP_DEGREES, METRIC_TRACK_COLOR);
+    if sample.fraction > 0.0 {
+        body.push_str(&gauge_arc(
+            GAUGE_SWEEP_DEGREES * sample.fraction,
+            level_color(sample.fraction),
+        ));
+    }
+    body.push_str(&text_svg(
+        &sample.text,
+        GAUGE_CENTER.1 + 1.0,
+        fitted_text_size(&sample.text, 34.0, 15.0),
+        700,
+        METRIC_TEXT_COLOR,
+    ));
+    body.push_str(&text_svg(
+        &sample.label,
+        62.0,
+        fitted_text_size(&sample.label, 40.0, 11.0),
+        600,
+        METRIC_LABEL_COLOR,
+    ));
+    body
+}
+
+// `history` holds gauge fractions, oldest first; the newest sits at the right edge.
+fn sparkline_svg(sample: &MetricSample, history: &[f32]) -> String {
+    let mut body = text_svg(
+        &sample.label,
+        12.0,
+        fitted_text_size(&sample.label, 60.0, 11.0),
+        600,
+        METRIC_LABEL_COLOR,
+    );
+    body.push_str(&text_svg(
+        &sample.text,
+        30.0,
+        fitted_text_size(&sample.text, 60.0, 18.0),
+        700,
+        METRIC_TEXT_COLOR,
+    ));
+    let step = SPARKLINE_WIDTH / (METRIC_HISTORY_LEN - 1) as f32;
+    let history = &history[history.len().saturating_sub(METRIC_HISTORY_LEN)..];
+    let first_x = SPARKLINE_LEFT + SPARKLINE_WIDTH - step * history.len().saturating_sub(1) as f32;
+    let points = history
+        .iter()
+        .enumerate()
+        .map(|(index, fraction)| {
+            (
+                first_x + step * index as f32,
+                SPARKLINE_BOTTOM - SPARKLINE_HEIGHT * fraction.clamp(0.0, 1.0),
+            )
+        })
+        .collect::<Vec<_>>();
+    let _ = write!(
+        body,
+        r##"<line x1="{SPARKLINE_LEFT}" y1="{SPARKLINE_BOTTOM}" x2="{}" y2="{SPARKLINE_BOTTOM}" stroke="{METRIC_TRACK_COLOR}" stroke-width="2"/>"##,
+        SPARKLINE_LEFT + SPARKLINE_WIDTH
+    );
+    let Some(&(last_x, last_y)) = points.last() else {
+        return body;
+    };
+    let color = level_color(sample.fraction);
+    let mut line = String::new();
+    for (x, y) in &points {
+        let _ = write!(line, "{x:.2},{y:.2} ");
+    }
+    let _ = write!(
+        body,
+        r##"<polygon points="{first_x:.2},{SPARKLINE_BOTTOM} {line}{last_x:.2},{SPARKLINE_BOTTOM}" fill="{color}" fill-opacity="0.25"/><polyline points="{}" fill="none" stroke="{color}" stroke-width="2" stroke-linejoin="round"/><circle cx="{last_x:.2}" cy="{last_y:.2}" r="2" fill="{color}"/>"##,
+        line.trim_end()
+    );
+    body
+}
+
+pub fn render_metric_svg(style: MetricStyle, sample: &MetricSample, history: &[f32]) -> String {
+    let body = match style {
+        MetricStyle::Gauge => gauge_svg(sample),
+        MetricStyle::Sparkline => sparkline_svg(sample, history),
+    };
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="72" height="72" viewBox="0 0 72 72">
+<rect x="0" y="0" width="72" height="72" rx="12" ry="12" fill="#11111b"/>
+{body}
+</svg>"##
+    )
+}
+
+// Takes a single reading and draws it, with `--` when the metric can't be read.
+pub fn render_metric_snapshot_svg(metric: Metric, face: &MetricFace) -> String {
+    let style = face.style_for(&metric);
+    let mut sampler = MetricSampler::new(metric);
+    let sample = sampler
+        .sample()
+        .unwrap_or_else(|_| MetricSample::unavailable(sampler.metric()));
+    render_metric_svg(style, &sample, &sampler.history())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::svg::load_svg_data;
+    use std::path::PathBuf;
+
+    fn sample(text: &str, fraction: f32) -> MetricSample {
+        MetricSample {
+            label: "CPU".to_string(),
+            text: text.to_string(),
+            fraction,
+        }
+    }
+
+    #[test]
+    fn metric_faces_parse_styles_and_clamp_intervals() {
+        assert_eq!(
+            MetricFace::from_fields(None, None),
+            Ok(MetricFace::default())
+        );
+        let face = MetricFace::from_fields(Some(" Gauge "), Some(0)).expect("face should parse");
+        assert_eq!(face.style, Some(MetricStyle::Gauge));
+        assert_eq!(face.interval, Duration::from_secs(1));
+        assert_eq!(
+            MetricFace::from_fields(None, Some(600)).map(|face| face.interval),
+            Ok(Duration::from_secs(60))
+        );
+        assert!(MetricFace::from_fields(Some("bars"), None).is_err());
+
+        let default = MetricFace::default();
+        assert_eq!(default.style_for(&Metric::Cpu), MetricStyle::Sparkline);
+        assert_eq!(
+            default.style_for(&Metric::Disk(PathBuf::from("/"))),
+            MetricStyle::Gauge
+        );
+        assert_eq!(face.style_for(&Metric::Load), MetricStyle::Gauge);
+    }
+
+    #[test]
+    fn metric_faces_draw_the_value_and_rasterize() {
+        let gauge = render_metric_svg(MetricStyle::Gauge, &sample("75%", 0.75), &[]);
+        assert!(gauge.contains(">75%<"));
+        assert_eq!(gauge.matches("<path").count(), 2);
+        assert_eq!(
+            render_metric_svg(MetricStyle::Gauge, &sample("--", 0.0), &[])
+                .matches("<path")
+                .count(),
+            1
+        );
+
+        let empty = render_metric_svg(MetricStyle::Sparkline, &sample("--", 0.0), &[]);
+        assert!(!empty.contains("<polyline"));
+        let history = vec![0.5; METRIC_HISTORY_LEN + 4];
+        let sparkline = render_metric_svg(MetricStyle::Sparkline, &sample("50%", 0.5), &history);
+        assert!(sparkline.contains(">50%<") && sparkline.contains(">CPU<"));
+        let points = sparkline
+            .split("<polyline points=\"")
+            .nth(1)
+            .and_then(|rest| rest.split('"').next())
+            .expect("sparkline should have points");
+        assert_eq!(points.split(' ').count(), METRIC_HISTORY_LEN);
+
+        for svg in [gauge, sparkline] {
+            load_svg_data("metric://cpu", svg.as_bytes(), None, 72, 72)
+                .expect("metric SVG should rasterize");
+        }
+    }
+}
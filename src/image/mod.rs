@@ -1,6 +1,11 @@
+pub mod brightness;
 pub mod cache_fs;
 pub mod calendar;
 pub mod catalog;
 pub mod clock;
 pub mod effects;
+pub mod media;
+pub mod metric;
+pub mod overlay;
 pub mod svg;
+pub mod timer;
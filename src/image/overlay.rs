@@ -0,0 +1,273 @@
+use super::svg::load_svg_data;
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, RgbaImage};
+use std::fmt::Write;
+
+pub const DEFAULT_TITLE_COLOR: &str = "#ffffff";
+pub const MIN_TITLE_SIZE: u32 = 6;
+pub const MAX_TITLE_SIZE: u32 = 48;
+
+const OVERLAY_VIEWBOX_SIZE: f32 = 72.0;
+const TITLE_AUTO_MAX_SIZE: f32 = 16.0;
+// DejaVu Sans averages a little over half an em per character.
+const TITLE_CHAR_WIDTH_EM: f32 = 0.6;
+
+pub(crate) fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+// `color` is any SVG color, such as `#1e1e2e` or `teal`.
+pub fn render_background_svg(color: &str) -> String {
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="72" height="72" viewBox="0 0 72 72">
+<rect x="0" y="0" width="72" height="72" fill="{color}"/>
+</svg>"##,
+        color = escape_xml(color.trim())
+    )
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum TitlePosition {
+    Top,
+    Middle,
+    #[default]
+    Bottom,
+}
+
+pub fn parse_title_position(value: &str) -> Result<TitlePosition, String> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "top" => Ok(TitlePosition::Top),
+        "middle" | "center" => Ok(TitlePosition::Middle),
+        "bottom" => Ok(TitlePosition::Bottom),
+        _ => Err(format!(
+            "invalid title_position '{}'; expected top, middle or bottom",
+            value.trim()
+        )),
+    }
+}
+
+// Text drawn over a key's icon. `size` is in pixels of a 72 pixel key; without
+// it the text shrinks to fit its longest line.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeyTitle {
+    pub text: String,
+    pub position: TitlePosition,
+    pub color: String,
+    pub size: Option<u32>,
+}
+
+impl KeyTitle {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            position: TitlePosition::default(),
+            color: DEFAULT_TITLE_COLOR.to_string(),
+            size: None,
+        }
+    }
+
+    // Builds a title from a key's `title*` fields; blank text means no title.
+    pub fn from_fields(
+        text: Option<&str>,
+        position: Option<&str>,
+        color: Option<&str>,
+        size: Option<u32>,
+    ) -> Result<Option<Self>, String> {
+        let Some(text) = text.map(str::trim).filter(|text| !text.is_empty()) else {
+            return Ok(None);
+        };
+        let position = match position {
+            Some(position) => parse_title_position(position)?,
+            None => TitlePosition::default(),
+        };
+        if let Some(size) = size.filter(|size| !(MIN_TITLE_SIZE..=MAX_TITLE_SIZE).contains(size)) {
+            return Err(format!(
+                "invalid title_size {size}; expected {MIN_TITLE_SIZE}..={MAX_TITLE_SIZE}"
+            ));
+        }
+        Ok(Some(Self {
+            text: text.to_string(),
+            position,
+            color: color
+                .map(str::trim)
+                .filter(|color| !color.is_empty())
+                .unwrap_or(DEFAULT_TITLE_COLOR)
+                .to_string(),
+            size,
+        }))
+    }
+
+    // The same style with other text, such as a status command's output.
+    pub fn with_text(&self, text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..self.clone()
+        }
+    }
+}
+
+// Outlined so it reads over any icon; each line of `text` is its own row.
+pub fn render_title_svg(title: &KeyTitle) -> String {
+    let lines = title.text.trim().lines().map(str::trim).collect::<Vec<_>>();
+    let longest = lines
+        .iter()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(1) as f32;
+    let font_size = match title.size {
+        Some(size) => size.clamp(MIN_TITLE_SIZE, MAX_TITLE_SIZE) as f32,
+        None => ((OVERLAY_VIEWBOX_SIZE - 6.0) / (longest * TITLE_CHAR_WIDTH_EM))
+            .clamp(MIN_TITLE_SIZE as f32, TITLE_AUTO_MAX_SIZE),
+    };
+    let line_height = font_size * 1.15;
+    let block = line_height * lines.len().saturating_sub(1) as f32;
+    // Baselines of the first line; capitals rise about 0.72 em above them.
+    let first_baseline = match title.position {
+        TitlePosition::Top => 4.0 + font_size * 0.72,
+        TitlePosition::Middle => (OVERLAY_VIEWBOX_SIZE - block) / 2.0 + font_size * 0.36,
+        TitlePosition::Bottom => OVERLAY_VIEWBOX_SIZE - 6.0 - block,
+    };
+    let mut rows = String::new();
+    for (index, line) in lines.iter().enumerate() {
+        let _ = write!(
+            rows,
+            r##"<text x="36" y="{y:.1}" text-anchor="middle" font-family="DejaVu Sans, Arial, sans-serif"
+      font-size="{font_size:.1}" font-weight="700" fill="{color}" stroke="#000000"
+      stroke-width="{stroke:.1}" stroke-linejoin="round" paint-order="stroke">{text}</text>
+"##,
+            y = first_baseline + line_height * index as f32,
+            color = escape_xml(title.color.trim()),
+            stroke = font_size / 6.0,
+            text = escape_xml(line)
+        );
+    }
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="72" height="72" viewBox="0 0 72 72">
+{rows}</svg>"##
+    )
+}
+
+// Draws `title` over a square `image`.
+pub fn draw_title(image: &mut RgbaImage, title: &KeyTitle) -> Result<(), String> {
+    if title.text.trim().is_empty() {
+        return Ok(());
+    }
+    let text = load_svg_data(
+        "title",
+        render_title_svg(title).as_bytes(),
+        None,
+        image.width(),
+        image.height(),
+    )?;
+    imageops::overlay(image, &text, 0, 0);
+    Ok(())
+}
+
+// Layers a background color and the icon cropped to a square into one
+// `size` x `size` image.
+pub fn compose_key_face(
+    icon: Option<&RgbaImage>,
+    background: Option<&str>,
+    size: u32,
+) -> Result<RgbaImage, String> {
+    let mut face = match background {
+        Some(color) => load_svg_data(
+            "background",
+            render_background_svg(color).as_bytes(),
+            None,
+            size,
+            size,
+        )?,
+        None => RgbaImage::new(size, size),
+    };
+    if let Some(icon) = icon {
+        let crop = icon.width().min(icon.height());
+        let square = DynamicImage::ImageRgba8(icon.clone()).crop_imm(
+            (icon.width() - crop) / 2,
+            (icon.height() - crop) / 2,
+            crop,
+            crop,
+        );
+        let scaled = imageops::resize(&square, size, size, FilterType::Lanczos3);
+        imageops::overlay(&mut face, &scaled, 0, 0);
+    }
+    Ok(face)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn title_text_is_escaped_and_shrinks_with_length() {
+        let svg = render_title_svg(&KeyTitle::new("<5 & up>"));
+        assert!(svg.contains(">&lt;5 &amp; up&gt;</text>"));
+        assert!(render_title_svg(&KeyTitle::new("7")).contains(r#"font-size="16.0""#));
+        let long = KeyTitle::new("a much longer title than fits");
+        assert!(render_title_svg(&long).contains(r#"font-size="6.0""#));
+        let sized = KeyTitle {
+            size: Some(100),
+            ..KeyTitle::new("7")
+        };
+        assert!(render_title_svg(&sized).contains(r#"font-size="48.0""#));
+    }
+
+    #[test]
+    fn titles_stack_lines_at_their_position() {
+        let title = KeyTitle {
+            position: TitlePosition::Top,
+            color: "teal".to_string(),
+            ..KeyTitle::new("Mic\nOff")
+        };
+        let svg = render_title_svg(&title);
+        assert_eq!(svg.matches("<text").count(), 2);
+        assert!(svg.contains(r#"fill="teal""#));
+        assert_eq!(
+            parse_title_position(" Middle ").expect("position should parse"),
+            TitlePosition::Middle
+        );
+        assert!(parse_title_position("left").is_err());
+
+        let white_rows = |position| {
+            let mut image = RgbaImage::new(72, 72);
+            let title = KeyTitle {
+                position,
+                ..KeyTitle::new("88")
+            };
+            draw_title(&mut image, &title).expect("title should render");
+            (0..72)
+                .filter(|&y| (0..72).any(|x| image.get_pixel(x, y)[0] > 200))
+                .collect::<Vec<_>>()
+        };
+        assert!(white_rows(TitlePosition::Top).iter().all(|&y| y < 24));
+        assert!(white_rows(TitlePosition::Bottom).iter().all(|&y| y > 48));
+        let middle = white_rows(TitlePosition::Middle);
+        assert!(middle.contains(&36) && !middle.contains(&10) && !middle.contains(&62));
+    }
+
+    #[test]
+    fn key_face_layers_background_and_icon() {
+        // A wide icon keeps its middle square, whose left half is transparent.
+        let icon = RgbaImage::from_fn(30, 10, |x, _| match x {
+            10..15 => Rgba([0, 0, 0, 0]),
+            _ => Rgba([0, 0, 255, 255]),
+        });
+        let face =
+            compose_key_face(Some(&icon), Some("#ff0000"), 72).expect("key face should compose");
+        assert_eq!(face.get_pixel(10, 36), &Rgba([255, 0, 0, 255]));
+        assert_eq!(face.get_pixel(60, 36), &Rgba([0, 0, 255, 255]));
+    }
+}
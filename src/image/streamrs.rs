@@ -1,52 +1,125 @@
 use super::config::{
-    key_clock_background, key_status_command, key_status_icon_off, key_status_icon_on,
+    LiveFaces, StatusMode, key_live_faces, key_status_command, key_status_icon_off,
+    key_status_icon_on, key_status_mode, key_title,
 };
+use super::media::NowPlaying;
 use super::{
-    Config, ImageCache, ImageCacheKey, LoadedKeyImage, MIN_GIF_FRAME_DELAY_MS, NEXT_PAGE_ICON,
-    PREVIOUS_PAGE_ICON, SVG_RENDER_SIZE, page_count,
+    Config, FOLDER_BACK_ICON, ImageCache, ImageCacheKey, KeyBinding, LoadedKeyImage,
+    MIN_GIF_FRAME_DELAY_MS, NEXT_PAGE_ICON, PREVIOUS_PAGE_ICON, SVG_RENDER_SIZE, page_count,
 };
+use chrono::NaiveDateTime;
+use image::codecs::bmp::BmpEncoder;
 use image::codecs::gif::GifDecoder;
 use image::codecs::jpeg::JpegEncoder;
 use image::codecs::png::PngDecoder;
 use image::codecs::webp::WebPDecoder;
 use image::imageops::FilterType::Lanczos3;
-use image::imageops::{crop_imm, resize, rotate180};
+use image::imageops::{
+    crop_imm, flip_horizontal, flip_vertical, resize, rotate90, rotate180, rotate270,
+};
 use image::{
-    AnimationDecoder, DynamicImage, Frame as ImageFrame, GenericImageView, RgbImage,
-    load_from_memory,
+    AnimationDecoder, DynamicImage, ExtendedColorType, Frame as ImageFrame, GenericImageView,
+    RgbImage, RgbaImage, load_from_memory,
 };
 use std::cmp::min;
 use std::fs;
 use std::io::Cursor;
 use std::path::Path;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use streamrs::image::brightness::{
+    BRIGHTNESS_ICON_ALIAS, is_brightness_icon, render_brightness_svg,
+};
 use streamrs::image::calendar::{
-    CALENDAR_ICON_ALIAS, current_calendar_key as generic_current_calendar_key, is_calendar_icon,
-    render_calendar_svg as generic_render_calendar_svg,
+    CALENDAR_ICON_ALIAS, CalendarFace, current_calendar_key as generic_current_calendar_key,
+    is_calendar_icon, render_calendar_svg as generic_render_calendar_svg,
 };
 use streamrs::image::catalog::is_blank_background_icon_name;
 use streamrs::image::clock::{
-    CLOCK_ICON_ALIAS, current_clock_text as generic_current_clock_text, is_clock_icon,
-    render_clock_segments_svg as generic_render_clock_segments_svg,
+    CLOCK_ICON_ALIAS, ClockFace, is_clock_icon,
+    render_clock_face_svg as generic_render_clock_face_svg,
 };
+use streamrs::image::media::{NOW_PLAYING_ICON, is_now_playing_icon, render_media_svg};
+use streamrs::image::metric::{MetricFace, render_metric_svg};
+use streamrs::image::overlay::{KeyTitle, compose_key_face, draw_title};
 use streamrs::image::svg::{load_svg_data as load_svg_data_generic, load_svg_dynamic};
+use streamrs::image::timer::{KeyTimer, TIMER_ICON_PREFIX, TimerFace, TimerKind, render_timer_svg};
+use streamrs::metrics::{METRIC_ICON_PREFIX, Metric, MetricSample, MetricSampler};
+use streamrs::status::StatusOutput;
+use streamrs::streamdeck::{DeviceModel, ImageFormat, ImageRotation};
+
+fn orient_for_model(img: &RgbaImage, model: &DeviceModel) -> RgbaImage {
+    let mut img = match model.image_rotation {
+        ImageRotation::None => img.clone(),
+        ImageRotation::Rotate90 => rotate90(img),
+        ImageRotation::Rotate180 => rotate180(img),
+        ImageRotation::Rotate270 => rotate270(img),
+    };
+    if model.image_flip_horizontal {
+        img = flip_horizontal(&img);
+    }
+    if model.image_flip_vertical {
+        img = flip_vertical(&img);
+    }
+    img
+}
 
-fn encode_streamdeck_image(img: DynamicImage) -> Result<Vec<u8>, String> {
+fn encode_for_model(img: &RgbImage, model: &DeviceModel) -> Result<Vec<u8>, String> {
+    let mut data = Vec::new();
+    match model.image_format {
+        ImageFormat::Jpeg => JpegEncoder::new_with_quality(&mut data, 100)
+            .encode_image(img)
+            .map_err(|err| format!("Failed to encode button image: {err}"))?,
+        ImageFormat::Bmp => BmpEncoder::new(&mut data)
+            .encode(
+                img.as_raw(),
+                img.width(),
+                img.height(),
+                ExtendedColorType::Rgb8,
+            )
+            .map_err(|err| format!("Failed to encode button image: {err}"))?,
+    }
+    Ok(data)
+}
+
+pub(super) fn encode_streamdeck_image(
+    img: DynamicImage,
+    title: Option<&KeyTitle>,
+    model: &DeviceModel,
+) -> Result<Vec<u8>, String> {
     let (width, height) = img.dimensions();
     let crop_size = min(width, height);
     let x_offset = (width - crop_size) / 2;
     let y_offset = (height - crop_size) / 2;
-    let mut img = crop_imm(&img, x_offset, y_offset, crop_size, crop_size).to_image();
-    img = resize(&rotate180(&img), 72, 72, Lanczos3);
+    let img = crop_imm(&img, x_offset, y_offset, crop_size, crop_size).to_image();
+    let img = match title {
+        // Drawn at the deck's resolution so the text stays sharp.
+        Some(title) => {
+            let mut img = resize(&img, model.image_size, model.image_size, Lanczos3);
+            draw_title(&mut img, title)?;
+            orient_for_model(&img, model)
+        }
+        None => resize(
+            &orient_for_model(&img, model),
+            model.image_size,
+            model.image_size,
+            Lanczos3,
+        ),
+    };
 
-    let mut data = Vec::new();
-    JpegEncoder::new_with_quality(&mut data, 100)
-        .encode_image(&img)
-        .map_err(|err| format!("Failed to encode button image: {err}"))?;
-    Ok(data)
+    encode_for_model(&DynamicImage::ImageRgba8(img).to_rgb8(), model)
+}
+
+pub(super) fn get_image_data(
+    icon_path: &Path,
+    img_data: &[u8],
+    title: Option<&KeyTitle>,
+    model: &DeviceModel,
+) -> Result<Vec<u8>, String> {
+    encode_streamdeck_image(decode_icon_image(icon_path, img_data)?, title, model)
 }
 
-pub(super) fn get_image_data(icon_path: &Path, img_data: &[u8]) -> Result<Vec<u8>, String> {
+// Animated icons decode to their first frame.
+fn decode_icon_image(icon_path: &Path, img_data: &[u8]) -> Result<DynamicImage, String> {
     let ext = icon_path
         .extension()
         .and_then(|ext| ext.to_str())
@@ -54,23 +127,33 @@ pub(super) fn get_image_data(icon_path: &Path, img_data: &[u8]) -> Result<Vec<u8
         .to_ascii_lowercase();
 
     match ext.as_str() {
-        "svg" => {
-            let img = load_svg_image(icon_path, img_data)?;
-            encode_streamdeck_image(img)
-        }
-        "gif" => {
-            let img = load_gif_first_frame(icon_path, img_data)?;
-            encode_streamdeck_image(img)
-        }
-        _ => {
-            let img = load_from_memory(img_data).map_err(|err| {
-                format!("Invalid image data for '{}': {err}", icon_path.display())
-            })?;
-            encode_streamdeck_image(img)
-        }
+        "svg" => load_svg_image(icon_path, img_data),
+        "gif" => load_gif_first_frame(icon_path, img_data),
+        _ => load_from_memory(img_data)
+            .map_err(|err| format!("Invalid image data for '{}': {err}", icon_path.display())),
     }
 }
 
+// Draws a status face with a title or background over a still copy of its icon.
+pub(super) fn render_status_face(
+    image_dir: &Path,
+    face: &StatusOutput,
+    title: Option<&KeyTitle>,
+    model: &DeviceModel,
+) -> Result<Vec<u8>, String> {
+    let icon = match face.icon.as_deref() {
+        Some(icon) if !is_blank_background_icon_name(icon) => {
+            let icon_path = image_dir.join(icon);
+            let img_data = fs::read(&icon_path)
+                .map_err(|err| format!("Failed to read icon '{}': {err}", icon_path.display()))?;
+            Some(decode_icon_image(&icon_path, &img_data)?.to_rgba8())
+        }
+        _ => None,
+    };
+    let image = compose_key_face(icon.as_ref(), face.background.as_deref(), SVG_RENDER_SIZE)?;
+    encode_streamdeck_image(DynamicImage::ImageRgba8(image), title, model)
+}
+
 fn load_svg_image(icon_path: &Path, img_data: &[u8]) -> Result<DynamicImage, String> {
     load_svg_dynamic(
         &icon_path.display().to_string(),
@@ -112,6 +195,8 @@ pub(super) fn delay_to_duration_ms(delay: image::Delay) -> Duration {
 pub(super) fn encode_animated_frames(
     frames: Vec<ImageFrame>,
     icon_path: &Path,
+    title: Option<&KeyTitle>,
+    model: &DeviceModel,
 ) -> Result<LoadedKeyImage, String> {
     if frames.is_empty() {
         return Err(format!(
@@ -126,7 +211,7 @@ pub(super) fn encode_animated_frames(
     for frame in frames {
         let delay = delay_to_duration_ms(frame.delay());
         let image = DynamicImage::ImageRgba8(frame.into_buffer());
-        encoded_frames.push(encode_streamdeck_image(image)?);
+        encoded_frames.push(encode_streamdeck_image(image, title, model)?);
         delays.push(delay);
     }
 
@@ -143,6 +228,8 @@ pub(super) fn encode_animated_frames(
 pub(super) fn load_animated_gif(
     icon_path: &Path,
     img_data: &[u8],
+    title: Option<&KeyTitle>,
+    model: &DeviceModel,
 ) -> Result<LoadedKeyImage, String> {
     let decoder = GifDecoder::new(Cursor::new(img_data))
         .map_err(|err| format!("Failed to decode GIF icon '{}': {err}", icon_path.display()))?;
@@ -152,10 +239,15 @@ pub(super) fn load_animated_gif(
             icon_path.display()
         )
     })?;
-    encode_animated_frames(frames, icon_path)
+    encode_animated_frames(frames, icon_path, title, model)
 }
 
-fn load_apng_or_static_png(icon_path: &Path, img_data: &[u8]) -> Result<LoadedKeyImage, String> {
+fn load_apng_or_static_png(
+    icon_path: &Path,
+    img_data: &[u8],
+    title: Option<&KeyTitle>,
+    model: &DeviceModel,
+) -> Result<LoadedKeyImage, String> {
     let decoder = PngDecoder::new(Cursor::new(img_data))
         .map_err(|err| format!("Failed to decode PNG icon '{}': {err}", icon_path.display()))?;
     let is_apng = decoder.is_apng().map_err(|err| {
@@ -165,7 +257,9 @@ fn load_apng_or_static_png(icon_path: &Path, img_data: &[u8]) -> Result<LoadedKe
         )
     })?;
     if !is_apng {
-        return Ok(LoadedKeyImage::Static(get_image_data(icon_path, img_data)?));
+        return Ok(LoadedKeyImage::Static(get_image_data(
+            icon_path, img_data, title, model,
+        )?));
     }
 
     let apng_decoder = decoder.apng().map_err(|err| {
@@ -180,12 +274,14 @@ fn load_apng_or_static_png(icon_path: &Path, img_data: &[u8]) -> Result<LoadedKe
             icon_path.display()
         )
     })?;
-    encode_animated_frames(frames, icon_path)
+    encode_animated_frames(frames, icon_path, title, model)
 }
 
 fn load_animated_webp_or_static(
     icon_path: &Path,
     img_data: &[u8],
+    title: Option<&KeyTitle>,
+    model: &DeviceModel,
 ) -> Result<LoadedKeyImage, String> {
     let decoder = WebPDecoder::new(Cursor::new(img_data)).map_err(|err| {
         format!(
@@ -194,7 +290,9 @@ fn load_animated_webp_or_static(
         )
     })?;
     if !decoder.has_animation() {
-        return Ok(LoadedKeyImage::Static(get_image_data(icon_path, img_data)?));
+        return Ok(LoadedKeyImage::Static(get_image_data(
+            icon_path, img_data, title, model,
+        )?));
     }
 
     let frames: Vec<ImageFrame> = decoder.into_frames().collect_frames().map_err(|err| {
@@ -203,19 +301,7 @@ fn load_animated_webp_or_static(
             icon_path.display()
         )
     })?;
-    encode_animated_frames(frames, icon_path)
-}
-
-pub(super) fn render_clock_segments_svg(
-    image_dir: &Path,
-    text: &str,
-    background_name: Option<&str>,
-) -> String {
-    generic_render_clock_segments_svg(image_dir, text, background_name)
-}
-
-pub(super) fn current_clock_text() -> String {
-    generic_current_clock_text()
+    encode_animated_frames(frames, icon_path, title, model)
 }
 
 pub(super) fn current_calendar_key() -> String {
@@ -224,10 +310,12 @@ pub(super) fn current_calendar_key() -> String {
 
 pub(super) fn render_clock_svg(
     image_dir: &Path,
-    text: &str,
-    background_name: Option<&str>,
+    face: &ClockFace,
+    time: NaiveDateTime,
+    title: Option<&KeyTitle>,
+    model: &DeviceModel,
 ) -> Result<Vec<u8>, String> {
-    let svg = render_clock_segments_svg(image_dir, text, background_name);
+    let svg = generic_render_clock_face_svg(image_dir, face, time);
     let img = load_svg_data_generic(
         CLOCK_ICON_ALIAS,
         svg.as_bytes(),
@@ -235,11 +323,15 @@ pub(super) fn render_clock_svg(
         SVG_RENDER_SIZE,
         SVG_RENDER_SIZE,
     )?;
-    encode_streamdeck_image(DynamicImage::ImageRgba8(img))
+    encode_streamdeck_image(DynamicImage::ImageRgba8(img), title, model)
 }
 
-pub(super) fn render_calendar_icon() -> Result<Vec<u8>, String> {
-    let svg = generic_render_calendar_svg();
+pub(super) fn render_calendar_icon(
+    face: &CalendarFace,
+    title: Option<&KeyTitle>,
+    model: &DeviceModel,
+) -> Result<Vec<u8>, String> {
+    let svg = generic_render_calendar_svg(face);
     let img = load_svg_data_generic(
         CALENDAR_ICON_ALIAS,
         svg.as_bytes(),
@@ -247,44 +339,195 @@ pub(super) fn render_calendar_icon() -> Result<Vec<u8>, String> {
         SVG_RENDER_SIZE,
         SVG_RENDER_SIZE,
     )?;
-    encode_streamdeck_image(DynamicImage::ImageRgba8(img))
+    encode_streamdeck_image(DynamicImage::ImageRgba8(img), title, model)
+}
+
+pub(super) fn render_brightness_icon(level: usize, model: &DeviceModel) -> Result<Vec<u8>, String> {
+    let svg = render_brightness_svg(level);
+    let img = load_svg_data_generic(
+        BRIGHTNESS_ICON_ALIAS,
+        svg.as_bytes(),
+        None,
+        SVG_RENDER_SIZE,
+        SVG_RENDER_SIZE,
+    )?;
+    encode_streamdeck_image(DynamicImage::ImageRgba8(img), None, model)
+}
+
+pub(super) fn render_timer_icon(
+    face: &TimerFace,
+    title: Option<&KeyTitle>,
+    model: &DeviceModel,
+) -> Result<Vec<u8>, String> {
+    let svg = render_timer_svg(face);
+    let img = load_svg_data_generic(
+        TIMER_ICON_PREFIX,
+        svg.as_bytes(),
+        None,
+        SVG_RENDER_SIZE,
+        SVG_RENDER_SIZE,
+    )?;
+    encode_streamdeck_image(DynamicImage::ImageRgba8(img), title, model)
+}
+
+// Shows the track's album art, or a note with the playback state when there is
+// none, and the track title in the style of the key's own title.
+pub(super) fn render_now_playing_icon(
+    now_playing: Option<&NowPlaying>,
+    title: Option<&KeyTitle>,
+    model: &DeviceModel,
+) -> Result<Vec<u8>, String> {
+    let art = now_playing
+        .and_then(|now| now.art.as_deref())
+        .and_then(|path| {
+            let data = fs::read(path).ok()?;
+            decode_icon_image(path, &data).ok()
+        });
+    let image = match art {
+        Some(art) => compose_key_face(Some(&art.to_rgba8()), None, SVG_RENDER_SIZE)?,
+        None => {
+            let status = now_playing.map(|now| now.status).unwrap_or_default();
+            load_svg_data_generic(
+                NOW_PLAYING_ICON,
+                render_media_svg(status).as_bytes(),
+                None,
+                SVG_RENDER_SIZE,
+                SVG_RENDER_SIZE,
+            )?
+        }
+    };
+    let track_title = now_playing
+        .and_then(|now| now.title.as_deref())
+        .map(|text| title.map_or_else(|| KeyTitle::new(text), |title| title.with_text(text)));
+    encode_streamdeck_image(
+        DynamicImage::ImageRgba8(image),
+        track_title.as_ref().or(title),
+        model,
+    )
+}
+
+// Takes a reading and draws it. A failed reading draws `--` and is returned
+// next to the image so callers can decide whether to report it.
+pub(super) fn render_metric_icon(
+    sampler: &MetricSampler,
+    sampled: Result<MetricSample, String>,
+    face: &MetricFace,
+    title: Option<&KeyTitle>,
+    model: &DeviceModel,
+) -> Result<(Vec<u8>, Option<String>), String> {
+    let (sample, error) = match sampled {
+        Ok(sample) => (sample, None),
+        Err(err) => (MetricSample::unavailable(sampler.metric()), Some(err)),
+    };
+    let svg = render_metric_svg(
+        face.style_for(sampler.metric()),
+        &sample,
+        &sampler.history(),
+    );
+    let img = load_svg_data_generic(
+        METRIC_ICON_PREFIX,
+        svg.as_bytes(),
+        None,
+        SVG_RENDER_SIZE,
+        SVG_RENDER_SIZE,
+    )?;
+    let image = encode_streamdeck_image(DynamicImage::ImageRgba8(img), title, model)?;
+    Ok((image, error))
+}
+
+fn load_metric_icon(
+    metric: Metric,
+    face: &MetricFace,
+    title: Option<&KeyTitle>,
+    model: &DeviceModel,
+) -> Result<LoadedKeyImage, String> {
+    let mut sampler = MetricSampler::new(metric);
+    // Disk readings can hang on a stale network mount, so the event loop
+    // takes them on another thread; until then the key shows no value.
+    let sampled = match sampler.disk_path() {
+        Some(_) => Ok(MetricSample::unavailable(sampler.metric())),
+        None => sampler.sample(),
+    };
+    let (image, error) = render_metric_icon(&sampler, sampled, face, title, model)?;
+    if let Some(err) = &error {
+        eprintln!("{err}");
+    }
+    Ok(LoadedKeyImage::Metric {
+        image,
+        sampler,
+        face: face.clone(),
+        error,
+    })
 }
 
 fn load_clock_icon(
     image_dir: &Path,
-    background_name: Option<&str>,
+    face: &ClockFace,
+    title: Option<&KeyTitle>,
+    model: &DeviceModel,
 ) -> Result<LoadedKeyImage, String> {
-    let text = current_clock_text();
-    let image = render_clock_svg(image_dir, &text, background_name)?;
+    let time = face.now();
+    let image = render_clock_svg(image_dir, face, time, title, model)?;
     Ok(LoadedKeyImage::Clock {
         image,
-        current_text: text,
-        background_name: background_name.map(|name| name.to_string()),
+        current_text: face.text(time),
+        face: face.clone(),
     })
 }
 
-fn load_calendar_icon() -> Result<LoadedKeyImage, String> {
+fn load_calendar_icon(
+    face: &CalendarFace,
+    title: Option<&KeyTitle>,
+    model: &DeviceModel,
+) -> Result<LoadedKeyImage, String> {
     let key = current_calendar_key();
-    let image = render_calendar_icon()?;
+    let image = render_calendar_icon(face, title, model)?;
     Ok(LoadedKeyImage::Calendar {
         image,
         current_key: key,
+        face: face.clone(),
     })
 }
 
 pub(super) fn load_key_image(
     image_dir: &Path,
     icon: &str,
-    clock_background: Option<&str>,
+    faces: &LiveFaces,
+    title: Option<&KeyTitle>,
+    model: &DeviceModel,
 ) -> Result<LoadedKeyImage, String> {
     if is_clock_icon(icon) {
-        return load_clock_icon(image_dir, clock_background);
+        return load_clock_icon(image_dir, &faces.clock, title, model);
     }
     if is_calendar_icon(icon) {
-        return load_calendar_icon();
+        return load_calendar_icon(&faces.calendar, title, model);
+    }
+    // The gauge depends on the deck it is shown on, which draws it itself.
+    if is_brightness_icon(icon) {
+        return Ok(LoadedKeyImage::Brightness);
+    }
+    if is_now_playing_icon(icon) {
+        return Ok(LoadedKeyImage::NowPlaying);
+    }
+    // Timer keys are drawn by their deck, which keeps their state; anywhere
+    // else a timer shows as not yet started.
+    if let Some(kind) = TimerKind::from_icon(icon) {
+        let face = KeyTimer::new(kind?).face(Instant::now());
+        return Ok(LoadedKeyImage::Static(render_timer_icon(
+            &face, title, model,
+        )?));
+    }
+    if let Some(metric) = Metric::from_icon(icon) {
+        return load_metric_icon(metric?, &faces.metric, title, model);
     }
     if is_blank_background_icon_name(icon) {
-        return Ok(LoadedKeyImage::Static(blank_image_data()?));
+        return Ok(LoadedKeyImage::Static(match title {
+            Some(title) => {
+                let black = RgbImage::new(model.image_size, model.image_size);
+                encode_streamdeck_image(DynamicImage::ImageRgb8(black), Some(title), model)?
+            }
+            None => blank_image_data(model)?,
+        }));
     }
 
     let icon_path = image_dir.join(icon);
@@ -297,43 +540,54 @@ pub(super) fn load_key_image(
         .to_ascii_lowercase();
 
     match ext.as_str() {
-        "gif" => load_animated_gif(&icon_path, &img_data),
-        "png" => load_apng_or_static_png(&icon_path, &img_data),
-        "webp" => load_animated_webp_or_static(&icon_path, &img_data),
+        "gif" => load_animated_gif(&icon_path, &img_data, title, model),
+        "png" => load_apng_or_static_png(&icon_path, &img_data, title, model),
+        "webp" => load_animated_webp_or_static(&icon_path, &img_data, title, model),
         _ => Ok(LoadedKeyImage::Static(get_image_data(
-            &icon_path, &img_data,
+            &icon_path, &img_data, title, model,
         )?)),
     }
 }
 
-fn image_cache_key(icon: &str, clock_background: Option<&str>) -> ImageCacheKey {
+fn image_cache_key(icon: &str, faces: &LiveFaces, title: Option<&KeyTitle>) -> ImageCacheKey {
     ImageCacheKey {
         icon: icon.to_string(),
-        clock_background: clock_background.map(|name| name.to_string()),
+        faces: faces.clone(),
+        title: title.cloned(),
     }
 }
 
-fn refresh_cached_live_icon(image_dir: &Path, cached: &mut LoadedKeyImage) -> Result<(), String> {
+fn refresh_cached_live_icon(
+    image_dir: &Path,
+    cached: &mut LoadedKeyImage,
+    title: Option<&KeyTitle>,
+    model: &DeviceModel,
+) -> Result<(), String> {
     match cached {
         LoadedKeyImage::Clock {
             image,
             current_text,
-            background_name,
+            face,
         } => {
-            let next_text = current_clock_text();
+            let time = face.now();
+            let next_text = face.text(time);
             if *current_text == next_text {
                 return Ok(());
             }
-            *image = render_clock_svg(image_dir, &next_text, background_name.as_deref())?;
+            *image = render_clock_svg(image_dir, face, time, title, model)?;
             *current_text = next_text;
             Ok(())
         }
-        LoadedKeyImage::Calendar { image, current_key } => {
+        LoadedKeyImage::Calendar {
+            image,
+            current_key,
+            face,
+        } => {
             let next_key = current_calendar_key();
             if *current_key == next_key {
                 return Ok(());
             }
-            *image = render_calendar_icon()?;
+            *image = render_calendar_icon(face, title, model)?;
             *current_key = next_key;
             Ok(())
         }
@@ -345,15 +599,21 @@ pub(super) fn load_key_image_cached(
     image_dir: &Path,
     image_cache: &mut ImageCache,
     icon: &str,
-    clock_background: Option<&str>,
+    faces: &LiveFaces,
+    title: Option<&KeyTitle>,
+    model: &DeviceModel,
 ) -> Result<LoadedKeyImage, String> {
-    let cache_key = image_cache_key(icon, clock_background);
+    // Metrics take a fresh reading every time they are shown.
+    if Metric::from_icon(icon).is_some() {
+        return load_key_image(image_dir, icon, faces, title, model);
+    }
+    let cache_key = image_cache_key(icon, faces, title);
     if let Some(cached) = image_cache.get_mut(&cache_key) {
-        refresh_cached_live_icon(image_dir, cached)?;
+        refresh_cached_live_icon(image_dir, cached, title, model)?;
         return Ok(cached.clone());
     }
 
-    let loaded = load_key_image(image_dir, icon, clock_background)?;
+    let loaded = load_key_image(image_dir, icon, faces, title, model)?;
     image_cache.insert(cache_key, loaded.clone());
     Ok(loaded)
 }
@@ -362,56 +622,95 @@ fn warm_cached_icon(
     image_dir: &Path,
     image_cache: &mut ImageCache,
     icon: &str,
-    clock_background: Option<&str>,
+    faces: &LiveFaces,
+    title: Option<&KeyTitle>,
+    model: &DeviceModel,
 ) {
-    if let Err(err) = load_key_image_cached(image_dir, image_cache, icon, clock_background) {
+    if let Err(err) = load_key_image_cached(image_dir, image_cache, icon, faces, title, model) {
         eprintln!("{err}");
     }
 }
 
-pub(super) fn build_image_cache(config: &Config, image_dir: &Path) -> ImageCache {
-    let mut image_cache = ImageCache::new();
-
-    for key in &config.keys {
-        let clock_background = key_clock_background(key);
+// Warms every icon reachable from `keys`, including nested folders, and
+// returns whether any folder was found.
+fn warm_key_icons(
+    image_dir: &Path,
+    image_cache: &mut ImageCache,
+    keys: &[KeyBinding],
+    model: &DeviceModel,
+) -> bool {
+    let mut has_folders = false;
+    for key in keys {
+        let faces = key_live_faces(key).unwrap_or_default();
+        let title = key_title(key).ok().flatten();
         warm_cached_icon(
             image_dir,
-            &mut image_cache,
+            image_cache,
             &key.icon,
-            clock_background.as_deref(),
+            &faces,
+            title.as_ref(),
+            model,
         );
 
-        if key_status_command(key).is_some() {
-            let icon_on = key_status_icon_on(key);
-            let icon_off = key_status_icon_off(key);
-            warm_cached_icon(
-                image_dir,
-                &mut image_cache,
-                &icon_on,
-                clock_background.as_deref(),
-            );
-            warm_cached_icon(
-                image_dir,
-                &mut image_cache,
-                &icon_off,
-                clock_background.as_deref(),
-            );
+        if let Some(folder_keys) = &key.keys {
+            warm_key_icons(image_dir, image_cache, folder_keys, model);
+            has_folders = true;
+            continue;
+        }
+
+        if key_status_command(key).is_none() {
+            continue;
+        }
+        let status_icons = match key_status_mode(key) {
+            Ok(StatusMode::Output) => key.status_icons.values().cloned().collect(),
+            _ => vec![key_status_icon_on(key), key_status_icon_off(key)],
+        };
+        for icon in status_icons {
+            warm_cached_icon(image_dir, image_cache, &icon, &faces, title.as_ref(), model);
         }
     }
+    has_folders
+}
 
-    if page_count(config) > 1 {
-        warm_cached_icon(image_dir, &mut image_cache, PREVIOUS_PAGE_ICON, None);
-        warm_cached_icon(image_dir, &mut image_cache, NEXT_PAGE_ICON, None);
+pub(super) fn build_image_cache(config: &Config, image_dir: &Path) -> ImageCache {
+    let model = config.device_model();
+    let mut image_cache = ImageCache::new();
+
+    let has_folders = warm_key_icons(image_dir, &mut image_cache, &config.keys, model);
+
+    if has_folders || page_count(config, &[]) > 1 {
+        warm_cached_icon(
+            image_dir,
+            &mut image_cache,
+            PREVIOUS_PAGE_ICON,
+            &LiveFaces::default(),
+            None,
+            model,
+        );
+        warm_cached_icon(
+            image_dir,
+            &mut image_cache,
+            NEXT_PAGE_ICON,
+            &LiveFaces::default(),
+            None,
+            model,
+        );
+    }
+    if has_folders {
+        warm_cached_icon(
+            image_dir,
+            &mut image_cache,
+            FOLDER_BACK_ICON,
+            &LiveFaces::default(),
+            None,
+            model,
+        );
     }
 
     image_cache
 }
 
-pub(super) fn blank_image_data() -> Result<Vec<u8>, String> {
-    let img = RgbImage::new(72, 72);
-    let mut data = Vec::new();
-    JpegEncoder::new_with_quality(&mut data, 100)
-        .encode_image(&img)
-        .map_err(|err| format!("Failed to encode blank button image: {err}"))?;
-    Ok(data)
+pub(super) fn blank_image_data(model: &DeviceModel) -> Result<Vec<u8>, String> {
+    let img = RgbImage::new(model.image_size, model.image_size);
+    encode_for_model(&img, model)
 }
@@ -0,0 +1,469 @@
+use super::clock::{CLOCK_FALLBACK_BACKGROUND_COLOR, clock_digits_svg};
+use std::time::{Duration, Instant};
+
+pub const TIMER_ICON_PREFIX: &str = "timer://";
+pub const STOPWATCH_ICON_PREFIX: &str = "stopwatch://";
+// A finished countdown flashes this long, switching every `TIMER_FLASH_INTERVAL`.
+pub const TIMER_FLASH_DURATION: Duration = Duration::from_secs(10);
+pub const TIMER_FLASH_INTERVAL: Duration = Duration::from_millis(500);
+const TIMER_MAX_SECONDS: u64 = 99 * 3600 + 59 * 60 + 59;
+const TIMER_FLASH_COLOR: &str = "#d20f39";
+const TIMER_RUNNING_COLOR: &str = "#a6e3a1";
+const TIMER_PAUSED_COLOR: &str = "#f9e2af";
+const TIMER_READY_COLOR: &str = "#6c7086";
+const TIMER_TRACK_COLOR: &str = "#313244";
+const TIMER_BAR_WIDTH: u64 = 56;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimerKind {
+    Countdown(Duration),
+    Stopwatch,
+}
+
+impl TimerKind {
+    // `None` for icons that are not timers, which keeps bad durations apart
+    // from regular icon names.
+    pub fn from_icon(icon: &str) -> Option<Result<Self, String>> {
+        if let Some(length) = strip_prefix_ignore_case(icon, TIMER_ICON_PREFIX) {
+            return Some(parse_timer_duration(length).map(Self::Countdown));
+        }
+        let rest = strip_prefix_ignore_case(icon, STOPWATCH_ICON_PREFIX)?;
+        Some(if rest.trim().is_empty() {
+            Ok(Self::Stopwatch)
+        } else {
+            Err(format!(
+                "invalid stopwatch icon '{icon}'; expected {STOPWATCH_ICON_PREFIX}"
+            ))
+        })
+    }
+}
+
+pub fn is_timer_icon(icon: &str) -> bool {
+    TimerKind::from_icon(icon).is_some()
+}
+
+fn strip_prefix_ignore_case<'a>(text: &'a str, prefix: &str) -> Option<&'a str> {
+    text.get(..prefix.len())
+        .filter(|head| head.eq_ignore_ascii_case(prefix))
+        .map(|_| &text[prefix.len()..])
+}
+
+// Accepts unit forms such as `90s`, `5m` or `1h30m`, and `mm:ss` or `h:mm:ss`.
+pub fn parse_timer_duration(text: &str) -> Result<Duration, String> {
+    let text = text.trim().to_ascii_lowercase();
+    let invalid = || {
+        format!("invalid timer length '{text}'; expected a length such as 90s, 5m, 1h30m or 25:00")
+    };
+    let seconds = if text.contains(':') {
+        let parts = text
+            .split(':')
+            .map(|part| {
+                part.bytes()
+                    .all(|byte| byte.is_ascii_digit())
+                    .then(|| part.parse::<u64>().ok())
+                    .flatten()
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(invalid)?;
+        match parts[..] {
+            [minutes, seconds] if seconds < 60 => minutes.saturating_mul(60) + seconds,
+            [hours, minutes, seconds] if minutes < 60 && seconds < 60 => {
+                hours.saturating_mul(3600) + minutes * 60 + seconds
+            }
+            _ => return Err(invalid()),
+        }
+    } else {
+        let mut total = 0u64;
+        let mut number = String::new();
+        for ch in text.chars() {
+            if ch.is_ascii_digit() {
+                number.push(ch);
+                continue;
+            }
+            let unit = match ch {
+                'h' => 3600,
+                'm' => 60,
+                's' => 1,
+                _ => return Err(invalid()),
+            };
+            let value = number.parse::<u64>().map_err(|_| invalid())?;
+            total = total.saturating_add(value.saturating_mul(unit));
+            number.clear();
+        }
+        if !number.is_empty() || text.is_empty() {
+            return Err(invalid());
+        }
+        total
+    };
+    if seconds == 0 || seconds > TIMER_MAX_SECONDS {
+        return Err(format!(
+            "invalid timer length '{text}'; expected 1 second to 99:59:59"
+        ));
+    }
+    Ok(Duration::from_secs(seconds))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimerState {
+    Ready,
+    Running,
+    Paused,
+    // A countdown that reached zero; it stays there until pressed or reset.
+    Finished,
+}
+
+// What a timer key shows; equal faces draw the same image.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TimerFace {
+    // Remaining seconds of a countdown, rounded up, or elapsed seconds.
+    pub seconds: u64,
+    // The countdown's length in seconds; `None` for stopwatches.
+    pub total: Option<u64>,
+    pub state: TimerState,
+    pub flash: bool,
+}
+
+// A countdown or stopwatch driven by key presses. Every method takes the
+// current time, so callers and tests supply the clock.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyTimer {
+    kind: TimerKind,
+    // Time counted by earlier runs, before the last pause.
+    banked: Duration,
+    started_at: Option<Instant>,
+    finished_at: Option<Instant>,
+}
+
+impl KeyTimer {
+    pub fn new(kind: TimerKind) -> Self {
+        Self {
+            kind,
+            banked: Duration::ZERO,
+            started_at: None,
+            finished_at: None,
+        }
+    }
+
+    pub fn kind(&self) -> TimerKind {
+        self.kind
+    }
+
+    pub fn state(&self) -> TimerState {
+        if self.finished_at.is_some() {
+            TimerState::Finished
+        } else if self.started_at.is_some() {
+            TimerState::Running
+        } else if self.banked.is_zero() {
+            TimerState::Ready
+        } else {
+            TimerState::Paused
+        }
+    }
+
+    // Countdowns never count past their length.
+    pub fn elapsed(&self, now: Instant) -> Duration {
+        let running = self.started_at.map_or(Duration::ZERO, |started| {
+            now.saturating_duration_since(started)
+        });
+        let elapsed = self.banked + running;
+        match self.kind {
+            TimerKind::Countdown(length) => elapsed.min(length),
+            TimerKind::Stopwatch => elapsed,
+        }
+    }
+
+    // Starts or resumes a stopped timer, pauses a running one and resets a
+    // finished countdown.
+    pub fn press(&mut self, now: Instant) {
+        match self.state() {
+            TimerState::Ready | TimerState::Paused => self.started_at = Some(now),
+            TimerState::Running => {
+                self.banked = self.elapsed(now);
+                self.started_at = None;
+            }
+            TimerState::Finished => self.reset(),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new(self.kind);
+    }
+
+    // When a running countdown reaches zero.
+    pub fn finishes_at(&self) -> Option<Instant> {
+        match (self.kind, self.started_at) {
+            (TimerKind::Countdown(length), Some(started)) => {
+                Some(started + length.saturating_sub(self.banked))
+            }
+            _ => None,
+        }
+    }
+
+    // Stops a countdown that has reached zero. Returns true only for the call
+    // that finishes it.
+    pub fn finish_if_due(&mut self, now: Instant) -> bool {
+        let Some(finishes_at) = self.finishes_at().filter(|&at| now >= at) else {
+            return false;
+        };
+        self.banked = self.elapsed(now);
+        self.started_at = None;
+        self.finished_at = Some(finishes_at);
+        true
+    }
+
+    pub fn face(&self, now: Instant) -> TimerFace {
+        let elapsed = self.elapsed(now);
+        let (seconds, total) = match self.kind {
+            TimerKind::Countdown(length) => {
+                let remaining = length - elapsed;
+                let seconds = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+                (seconds, Some(length.as_secs()))
+            }
+            TimerKind::Stopwatch => (elapsed.as_secs(), None),
+        };
+        let flash = self.finished_at.is_some_and(|finished| {
+            let since = now.saturating_duration_since(finished);
+            since < TIMER_FLASH_DURATION
+                && (since.as_millis() / TIMER_FLASH_INTERVAL.as_millis()).is_multiple_of(2)
+        });
+        TimerFace {
+            seconds,
+            total,
+            state: self.state(),
+            flash,
+        }
+    }
+
+    // When the face may next differ from the one shown at `now`; `None` while
+    // it stays the same until the next press.
+    pub fn next_change(&self, now: Instant) -> Option<Instant> {
+        match self.state() {
+            TimerState::Ready | TimerState::Paused => None,
+            TimerState::Running => {
+                let counted = match self.kind {
+                    TimerKind::Countdown(length) => length - self.elapsed(now),
+                    TimerKind::Stopwatch => self.elapsed(now),
+                };
+                let into_second = Duration::from_nanos(u64::from(counted.subsec_nanos()));
+                let delay = match self.kind {
+                    TimerKind::Countdown(_) if !into_second.is_zero() => into_second,
+                    _ => Duration::from_secs(1) - into_second,
+                };
+                Some(now + delay)
+            }
+            TimerState::Finished => {
+                let finished = self.finished_at?;
+                let since = now.saturating_duration_since(finished);
+                if since >= TIMER_FLASH_DURATION {
+                    return None;
+                }
+                let flashes = since.as_millis() / TIMER_FLASH_INTERVAL.as_millis() + 1;
+                Some(finished + TIMER_FLASH_INTERVAL * flashes as u32)
+            }
+        }
+    }
+}
+
+fn timer_text(seconds: u64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes:02}:{seconds:02}")
+    }
+}
+
+fn timer_state_svg(state: TimerState) -> String {
+    let play = |color: &str| format!(r##"<path d="M 32 9 L 41 14 L 32 19 Z" fill="{color}"/>"##);
+    match state {
+        TimerState::Ready => play(TIMER_READY_COLOR),
+        TimerState::Running => play(TIMER_RUNNING_COLOR),
+        TimerState::Paused => format!(
+            r##"<rect x="31" y="9" width="3" height="10" fill="{TIMER_PAUSED_COLOR}"/><rect x="38" y="9" width="3" height="10" fill="{TIMER_PAUSED_COLOR}"/>"##
+        ),
+        TimerState::Finished => String::new(),
+    }
+}
+
+// Seven-segment time with the timer's state above it and, for countdowns,
+// the share of time left below it.
+pub fn render_timer_svg(face: &TimerFace) -> String {
+    let background = if face.flash {
+        TIMER_FLASH_COLOR
+    } else {
+        CLOCK_FALLBACK_BACKGROUND_COLOR
+    };
+    let mut body = timer_state_svg(face.state);
+    body.push_str(&clock_digits_svg(&timer_text(face.seconds)));
+    if let Some(total) = face.total.filter(|&total| total > 0) {
+        let left = TIMER_BAR_WIDTH * face.seconds.min(total) / total;
+        body.push_str(&format!(
+            r##"<rect x="8" y="56" width="{TIMER_BAR_WIDTH}" height="4" rx="2" fill="{TIMER_TRACK_COLOR}"/><rect x="8" y="56" width="{left}" height="4" rx="2" fill="{TIMER_RUNNING_COLOR}"/>"##
+        ));
+    }
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="72" height="72" viewBox="0 0 72 72">
+<rect x="0" y="0" width="72" height="72" fill="{background}"/>
+{body}
+</svg>"##
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::svg::load_svg_data;
+
+    fn secs(value: u64) -> Duration {
+        Duration::from_secs(value)
+    }
+
+    #[test]
+    fn timer_icons_parse_lengths_case_insensitively() {
+        assert_eq!(
+            TimerKind::from_icon("timer://5m"),
+            Some(Ok(TimerKind::Countdown(secs(300))))
+        );
+        assert_eq!(
+            TimerKind::from_icon("TIMER://1h30m"),
+            Some(Ok(TimerKind::Countdown(secs(5400))))
+        );
+        assert_eq!(
+            TimerKind::from_icon("timer://25:00"),
+            Some(Ok(TimerKind::Countdown(secs(1500))))
+        );
+        assert_eq!(
+            TimerKind::from_icon("timer://1:02:03"),
+            Some(Ok(TimerKind::Countdown(secs(3723))))
+        );
+        assert_eq!(
+            TimerKind::from_icon("Stopwatch://"),
+            Some(Ok(TimerKind::Stopwatch))
+        );
+        assert_eq!(TimerKind::from_icon("timer.png"), None);
+        assert!(is_timer_icon("timer://bogus"));
+
+        for bad in [
+            "timer://",
+            "timer://5",
+            "timer://0s",
+            "timer://5x",
+            "timer://1:60",
+            "timer://m",
+            "timer://100h",
+            "timer://-5m",
+            "stopwatch://5m",
+        ] {
+            assert!(
+                matches!(TimerKind::from_icon(bad), Some(Err(_))),
+                "{bad} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn countdown_starts_pauses_finishes_and_flashes() {
+        let start = Instant::now();
+        let mut timer = KeyTimer::new(TimerKind::Countdown(secs(10)));
+        assert_eq!(timer.state(), TimerState::Ready);
+        assert_eq!(timer.face(start).seconds, 10);
+        assert_eq!(timer.next_change(start), None);
+
+        timer.press(start);
+        assert_eq!(timer.state(), TimerState::Running);
+        assert_eq!(timer.finishes_at(), Some(start + secs(10)));
+        let later = start + Duration::from_millis(2500);
+        assert_eq!(timer.face(later).seconds, 8);
+        assert_eq!(timer.next_change(later), Some(start + secs(3)));
+
+        timer.press(later);
+        assert_eq!(timer.state(), TimerState::Paused);
+        assert_eq!(timer.finishes_at(), None);
+        assert_eq!(timer.face(start + secs(60)).seconds, 8);
+
+        let resumed = start + secs(60);
+        timer.press(resumed);
+        let due = resumed + Duration::from_millis(7500);
+        assert_eq!(timer.finishes_at(), Some(due));
+        assert!(!timer.finish_if_due(due - Duration::from_millis(1)));
+        assert!(timer.finish_if_due(due + secs(1)));
+        assert!(!timer.finish_if_due(due + secs(2)));
+
+        // The flash counts from the moment the countdown reached zero.
+        let face = timer.face(due + secs(1));
+        assert_eq!(
+            (face.seconds, face.state, face.flash),
+            (0, TimerState::Finished, true)
+        );
+        assert!(!timer.face(due + Duration::from_millis(1700)).flash);
+        assert_eq!(
+            timer.next_change(due + Duration::from_millis(1700)),
+            Some(due + secs(2))
+        );
+        assert!(!timer.face(due + TIMER_FLASH_DURATION).flash);
+        assert_eq!(timer.next_change(due + TIMER_FLASH_DURATION), None);
+
+        timer.press(due + secs(20));
+        assert_eq!(timer, KeyTimer::new(TimerKind::Countdown(secs(10))));
+    }
+
+    #[test]
+    fn stopwatch_counts_up_until_reset() {
+        let start = Instant::now();
+        let mut timer = KeyTimer::new(TimerKind::Stopwatch);
+        timer.press(start);
+        let later = start + Duration::from_millis(3_725_400);
+        assert_eq!(timer.face(later).seconds, 3725);
+        assert_eq!(timer.face(later).total, None);
+        assert_eq!(timer.next_change(later), Some(start + secs(3726)));
+        assert_eq!(timer.finishes_at(), None);
+        assert!(!timer.finish_if_due(later));
+
+        timer.reset();
+        assert_eq!(timer.state(), TimerState::Ready);
+        assert_eq!(timer.elapsed(later), Duration::ZERO);
+    }
+
+    #[test]
+    fn timer_svg_shows_time_progress_and_flash() {
+        let face = TimerFace {
+            seconds: 3725,
+            total: None,
+            state: TimerState::Running,
+            flash: false,
+        };
+        assert_eq!(timer_text(face.seconds), "1:02:05");
+        assert_eq!(timer_text(59), "00:59");
+        let svg = render_timer_svg(&face);
+        assert!(svg.contains(CLOCK_FALLBACK_BACKGROUND_COLOR));
+        assert!(svg.contains(TIMER_RUNNING_COLOR));
+        assert!(!svg.contains(TIMER_TRACK_COLOR));
+
+        let countdown = TimerFace {
+            seconds: 15,
+            total: Some(60),
+            state: TimerState::Paused,
+            flash: false,
+        };
+        let svg = render_timer_svg(&countdown);
+        assert!(svg.contains(r#"width="14" height="4""#));
+        assert!(svg.contains(TIMER_PAUSED_COLOR));
+
+        let red_pixels = |flash| {
+            let svg = render_timer_svg(&TimerFace {
+                seconds: 0,
+                state: TimerState::Finished,
+                flash,
+                ..countdown.clone()
+            });
+            let image = load_svg_data("timer.svg", svg.as_bytes(), None, 72, 72)
+                .expect("timer SVG should rasterize");
+            image
+                .pixels()
+                .filter(|p| p[0] > 180 && p[1] < 60 && p[2] < 90)
+                .count()
+        };
+        assert!(red_pixels(true) > 2000);
+        assert_eq!(red_pixels(false), 0);
+    }
+}
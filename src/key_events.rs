@@ -0,0 +1,231 @@
+use crate::streamdeck::MAX_KEY_COUNT;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_LONG_PRESS_MS: u64 = 500;
+pub const DEFAULT_DOUBLE_PRESS_MS: u64 = 300;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyEvent {
+    KeyDown(usize),
+    KeyUp(usize),
+    LongPress(usize),
+    DoublePress(usize),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyEventThresholds {
+    pub long_press: Duration,
+    pub double_press: Duration,
+}
+
+impl Default for KeyEventThresholds {
+    fn default() -> Self {
+        Self {
+            long_press: Duration::from_millis(DEFAULT_LONG_PRESS_MS),
+            double_press: Duration::from_millis(DEFAULT_DOUBLE_PRESS_MS),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct KeyTiming {
+    pressed_at: Option<Instant>,
+    long_press_sent: bool,
+    double_press_sent: bool,
+    released_at: Option<Instant>,
+}
+
+// Turns pressed-key bitmaps into per-key events. `LongPress` fires once while a
+// key is still held; `DoublePress` follows the `KeyDown` of a second press that
+// starts within the double-press window after a short first press.
+#[derive(Clone, Debug)]
+pub struct KeyEventTracker {
+    thresholds: KeyEventThresholds,
+    pressed: u32,
+    // Keys held at the last `reset`, ignored until they are released.
+    suppressed: u32,
+    keys: [KeyTiming; MAX_KEY_COUNT],
+}
+
+impl KeyEventTracker {
+    pub fn new(thresholds: KeyEventThresholds) -> Self {
+        Self {
+            thresholds,
+            pressed: 0,
+            suppressed: 0,
+            keys: [KeyTiming::default(); MAX_KEY_COUNT],
+        }
+    }
+
+    pub fn thresholds(&self) -> KeyEventThresholds {
+        self.thresholds
+    }
+
+    pub fn set_thresholds(&mut self, thresholds: KeyEventThresholds) {
+        self.thresholds = thresholds;
+    }
+
+    pub fn pressed(&self) -> u32 {
+        self.pressed
+    }
+
+    // Forgets held keys without emitting `KeyUp`, e.g. after a page change or
+    // reconnect where the old key meaning no longer applies. They report
+    // nothing until released and pressed again.
+    pub fn reset(&mut self) {
+        self.suppressed |= self.pressed;
+        self.pressed = 0;
+        self.keys = [KeyTiming::default(); MAX_KEY_COUNT];
+    }
+
+    pub fn update(&mut self, pressed: u32, now: Instant) -> Vec<KeyEvent> {
+        self.suppressed &= pressed;
+        let pressed = pressed & !self.suppressed;
+        let mut events = self.tick(now);
+        let changed = self.pressed ^ pressed;
+        for key in (0..MAX_KEY_COUNT).filter(|key| changed & (1 << key) != 0) {
+            let timing = &mut self.keys[key];
+            if pressed & (1 << key) != 0 {
+                events.push(KeyEvent::KeyDown(key));
+                let double = timing.released_at.is_some_and(|released| {
+                    now.saturating_duration_since(released) <= self.thresholds.double_press
+                });
+                *timing = KeyTiming {
+                    pressed_at: Some(now),
+                    double_press_sent: double,
+                    ..KeyTiming::default()
+                };
+                if double {
+                    events.push(KeyEvent::DoublePress(key));
+                }
+            } else {
+                events.push(KeyEvent::KeyUp(key));
+                let starts_sequence = timing.pressed_at.is_some()
+                    && !timing.long_press_sent
+                    && !timing.double_press_sent;
+                timing.released_at = starts_sequence.then_some(now);
+                timing.pressed_at = None;
+            }
+        }
+        self.pressed = pressed;
+        events
+    }
+
+    // When `tick` next has a `LongPress` to report, for callers that sleep
+    // between key reports.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.keys
+            .iter()
+            .filter(|timing| !timing.long_press_sent)
+            .filter_map(|timing| timing.pressed_at)
+            .min()
+            .map(|pressed_at| pressed_at + self.thresholds.long_press)
+    }
+
+    pub fn tick(&mut self, now: Instant) -> Vec<KeyEvent> {
+        let mut events = Vec::new();
+        for (key, timing) in self.keys.iter_mut().enumerate() {
+            if let Some(pressed_at) = timing.pressed_at
+                && !timing.long_press_sent
+                && now.saturating_duration_since(pressed_at) >= self.thresholds.long_press
+            {
+                timing.long_press_sent = true;
+                events.push(KeyEvent::LongPress(key));
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker() -> (KeyEventTracker, Instant) {
+        (
+            KeyEventTracker::new(KeyEventThresholds::default()),
+            Instant::now(),
+        )
+    }
+
+    fn ms(value: u64) -> Duration {
+        Duration::from_millis(value)
+    }
+
+    #[test]
+    fn chords_emit_events_for_every_key() {
+        let (mut tracker, start) = tracker();
+        assert_eq!(
+            tracker.update(0b101, start),
+            vec![KeyEvent::KeyDown(0), KeyEvent::KeyDown(2)]
+        );
+        assert_eq!(
+            tracker.update(0b001, start + ms(50)),
+            vec![KeyEvent::KeyUp(2)]
+        );
+        assert_eq!(tracker.pressed(), 0b001);
+    }
+
+    #[test]
+    fn holding_a_key_emits_one_long_press() {
+        let (mut tracker, start) = tracker();
+        tracker.update(1 << 3, start);
+        assert!(tracker.tick(start + ms(499)).is_empty());
+        assert_eq!(tracker.next_deadline(), Some(start + ms(500)));
+        assert_eq!(tracker.tick(start + ms(500)), vec![KeyEvent::LongPress(3)]);
+        assert_eq!(tracker.next_deadline(), None);
+        assert!(tracker.tick(start + ms(900)).is_empty());
+        assert_eq!(tracker.update(0, start + ms(950)), vec![KeyEvent::KeyUp(3)]);
+
+        // A long press never starts a double press.
+        assert_eq!(
+            tracker.update(1 << 3, start + ms(1000)),
+            vec![KeyEvent::KeyDown(3)]
+        );
+    }
+
+    #[test]
+    fn keys_held_across_a_reset_stay_silent_until_released() {
+        let (mut tracker, start) = tracker();
+        tracker.update(1, start);
+        tracker.reset();
+        assert_eq!(
+            tracker.update(0b11, start + ms(50)),
+            vec![KeyEvent::KeyDown(1)]
+        );
+        assert_eq!(tracker.tick(start + ms(600)), vec![KeyEvent::LongPress(1)]);
+        assert_eq!(
+            tracker.update(0b10, start + ms(650)),
+            Vec::<KeyEvent>::new()
+        );
+        assert_eq!(tracker.pressed(), 0b10);
+
+        assert_eq!(
+            tracker.update(0b11, start + ms(700)),
+            vec![KeyEvent::KeyDown(0)]
+        );
+    }
+
+    #[test]
+    fn second_short_press_inside_window_is_a_double_press() {
+        let (mut tracker, start) = tracker();
+        tracker.update(1, start);
+        tracker.update(0, start + ms(80));
+        assert_eq!(
+            tracker.update(1, start + ms(200)),
+            vec![KeyEvent::KeyDown(0), KeyEvent::DoublePress(0)]
+        );
+        tracker.update(0, start + ms(260));
+
+        // The press after a double press starts a fresh sequence.
+        assert_eq!(
+            tracker.update(1, start + ms(300)),
+            vec![KeyEvent::KeyDown(0)]
+        );
+        tracker.update(0, start + ms(350));
+        assert_eq!(
+            tracker.update(1, start + ms(700)),
+            vec![KeyEvent::KeyDown(0)]
+        );
+    }
+}
@@ -1,6 +1,11 @@
 pub mod config;
+pub mod control;
+pub mod idle;
 pub mod image;
+pub mod key_events;
+pub mod metrics;
 pub mod paging;
 pub mod paths;
 pub mod process;
+pub mod status;
 pub mod streamdeck;
@@ -0,0 +1,107 @@
+use super::LoopEvent;
+use std::fs;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread;
+use streamrs::control::{CONTROL_REQUEST_TIMEOUT, ControlRequest, ControlResponse};
+
+// A request handed to the event loop together with the channel its answer goes back on.
+pub(crate) struct ControlMessage {
+    pub(crate) request: ControlRequest,
+    pub(crate) reply: mpsc::Sender<ControlResponse>,
+}
+
+// A socket file nobody answers on is left over from a crashed daemon and is replaced.
+pub(crate) fn bind_control_socket(path: &Path) -> Result<UnixListener, String> {
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            return Err(format!(
+                "Another streamrs daemon is listening on '{}'",
+                path.display()
+            ));
+        }
+        fs::remove_file(path).map_err(|err| {
+            format!(
+                "Failed to remove stale control socket '{}': {err}",
+                path.display()
+            )
+        })?;
+    }
+    let listener = UnixListener::bind(path)
+        .map_err(|err| format!("Failed to bind control socket '{}': {err}", path.display()))?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600)).map_err(|err| {
+        format!(
+            "Failed to restrict control socket '{}': {err}",
+            path.display()
+        )
+    })?;
+    Ok(listener)
+}
+
+pub(crate) fn serve_control_socket(listener: UnixListener, sender: mpsc::Sender<LoopEvent>) {
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let sender = sender.clone();
+                    thread::spawn(move || handle_control_connection(stream, &sender));
+                }
+                Err(err) => eprintln!("Control socket accept failed: {err}"),
+            }
+        }
+    });
+}
+
+fn handle_control_connection(stream: UnixStream, sender: &mpsc::Sender<LoopEvent>) {
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(err) => {
+            eprintln!("Control socket connection failed: {err}");
+            return;
+        }
+    };
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(err) => {
+                if err.kind() != ErrorKind::ConnectionReset {
+                    eprintln!("Control socket read failed: {err}");
+                }
+                return;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<ControlRequest>(&line) {
+            Ok(request) => dispatch_control_request(request, sender),
+            Err(err) => ControlResponse::failure(format!("Invalid control request: {err}")),
+        };
+        let Ok(mut encoded) = serde_json::to_string(&response) else {
+            return;
+        };
+        encoded.push('\n');
+        if writer.write_all(encoded.as_bytes()).is_err() {
+            return;
+        }
+    }
+}
+
+pub(crate) fn dispatch_control_request(
+    request: ControlRequest,
+    sender: &mpsc::Sender<LoopEvent>,
+) -> ControlResponse {
+    let (reply, response) = mpsc::channel();
+    if sender
+        .send(LoopEvent::Control(ControlMessage { request, reply }))
+        .is_err()
+    {
+        return ControlResponse::failure("streamrs daemon is shutting down");
+    }
+    response
+        .recv_timeout(CONTROL_REQUEST_TIMEOUT)
+        .unwrap_or_else(|_| ControlResponse::failure("streamrs daemon did not answer in time"))
+}
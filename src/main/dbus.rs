@@ -0,0 +1,447 @@
+use super::LoopEvent;
+use super::config::MediaCommand;
+use super::control_socket::dispatch_control_request;
+use super::media::{send_media_command, watch_media_players};
+use gtk::gio;
+use gtk::glib::{self, variant::ToVariant};
+use std::sync::mpsc;
+use std::thread;
+use streamrs::control::{ControlRequest, DaemonState};
+
+pub(crate) const DBUS_NAME: &str = "lv.apps.streamrs.Daemon";
+pub(crate) const DBUS_OBJECT_PATH: &str = "/lv/apps/streamrs/Daemon";
+pub(crate) const DBUS_INTERFACE: &str = "lv.apps.streamrs.Daemon";
+const DBUS_ERROR_FAILED: &str = "lv.apps.streamrs.Daemon.Error.Failed";
+const DBUS_ERROR_INVALID_ARGS: &str = "org.freedesktop.DBus.Error.InvalidArgs";
+const DBUS_NAME_FLAG_DO_NOT_QUEUE: u32 = 4;
+const DBUS_REQUEST_NAME_PRIMARY_OWNER: u32 = 1;
+const SCREENSAVER_INTERFACE: &str = "org.freedesktop.ScreenSaver";
+const LOGIND_NAME: &str = "org.freedesktop.login1";
+const LOGIND_SESSION_INTERFACE: &str = "org.freedesktop.login1.Session";
+const LOGIND_USER_INTERFACE: &str = "org.freedesktop.login1.User";
+const LOGIND_USER_PATH: &str = "/org/freedesktop/login1/user/self";
+
+// An empty serial addresses every connected deck, like a missing `serial` on the socket.
+const DBUS_INTROSPECTION_XML: &str = r#"<node>
+  <interface name="lv.apps.streamrs.Daemon">
+    <method name="Reload"/>
+    <method name="SwitchProfile">
+      <arg name="profile" type="s" direction="in"/>
+    </method>
+    <method name="SetPage">
+      <arg name="page" type="s" direction="in"/>
+      <arg name="serial" type="s" direction="in"/>
+    </method>
+    <method name="PressKey">
+      <arg name="key" type="u" direction="in"/>
+      <arg name="serial" type="s" direction="in"/>
+    </method>
+    <method name="SetBrightness">
+      <arg name="brightness" type="u" direction="in"/>
+      <arg name="serial" type="s" direction="in"/>
+    </method>
+    <method name="SetKeyImage">
+      <arg name="key" type="u" direction="in"/>
+      <arg name="icon" type="s" direction="in"/>
+      <arg name="seconds" type="u" direction="in"/>
+      <arg name="serial" type="s" direction="in"/>
+    </method>
+    <method name="GetState">
+      <arg name="profile" type="s" direction="out"/>
+      <arg name="decks" type="a(sssuuu)" direction="out"/>
+    </method>
+    <signal name="KeyPressed">
+      <arg name="serial" type="s"/>
+      <arg name="key" type="u"/>
+    </signal>
+    <signal name="PageChanged">
+      <arg name="serial" type="s"/>
+      <arg name="page" type="u"/>
+      <arg name="pages" type="u"/>
+    </signal>
+    <signal name="ProfileSwitched">
+      <arg name="profile" type="s"/>
+    </signal>
+    <signal name="DeviceConnected">
+      <arg name="serial" type="s"/>
+      <arg name="model" type="s"/>
+    </signal>
+    <signal name="DeviceDisconnected">
+      <arg name="serial" type="s"/>
+    </signal>
+  </interface>
+</node>"#;
+
+// Reported by the event loop and sent out on D-Bus, as signals apart from media
+// commands, which are calls to a player. Keys and pages are 1-based, like in
+// control requests.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum DaemonEvent {
+    KeyPressed {
+        serial: String,
+        key: usize,
+    },
+    PageChanged {
+        serial: String,
+        page: usize,
+        pages: usize,
+    },
+    ProfileSwitched {
+        profile: String,
+    },
+    DeviceConnected {
+        serial: String,
+        model: String,
+    },
+    DeviceDisconnected {
+        serial: String,
+    },
+    MediaCommand {
+        player: String,
+        command: MediaCommand,
+    },
+}
+
+impl DaemonEvent {
+    // `None` for media commands.
+    fn signal(&self) -> Option<(&'static str, glib::Variant)> {
+        let signal = match self {
+            Self::KeyPressed { serial, key } => {
+                ("KeyPressed", (serial, dbus_u32(*key)).to_variant())
+            }
+            Self::PageChanged {
+                serial,
+                page,
+                pages,
+            } => (
+                "PageChanged",
+                (serial, dbus_u32(*page), dbus_u32(*pages)).to_variant(),
+            ),
+            Self::ProfileSwitched { profile } => ("ProfileSwitched", (profile,).to_variant()),
+            Self::DeviceConnected { serial, model } => {
+                ("DeviceConnected", (serial, model).to_variant())
+            }
+            Self::DeviceDisconnected { serial } => ("DeviceDisconnected", (serial,).to_variant()),
+            Self::MediaCommand { .. } => return None,
+        };
+        Some(signal)
+    }
+}
+
+fn dbus_u32(value: usize) -> u32 {
+    u32::try_from(value).unwrap_or(u32::MAX)
+}
+
+fn optional_serial(serial: String) -> Option<String> {
+    (!serial.is_empty()).then_some(serial)
+}
+
+// GDBus checks the argument types against the introspection data before the
+// call reaches us, so a mismatch here means the method is unknown.
+fn dbus_method_request(method: &str, parameters: &glib::Variant) -> Option<ControlRequest> {
+    let request = match method {
+        "Reload" => ControlRequest::Reload,
+        "SwitchProfile" => {
+            let (profile,) = parameters.get::<(String,)>()?;
+            ControlRequest::SwitchProfile { profile }
+        }
+        "SetPage" => {
+            let (page, serial) = parameters.get::<(String, String)>()?;
+            ControlRequest::Page {
+                page,
+                serial: optional_serial(serial),
+            }
+        }
+        "PressKey" => {
+            let (key, serial) = parameters.get::<(u32, String)>()?;
+            ControlRequest::PressKey {
+                key: key as usize,
+                serial: optional_serial(serial),
+            }
+        }
+        "SetBrightness" => {
+            let (brightness, serial) = parameters.get::<(u32, String)>()?;
+            ControlRequest::SetBrightness {
+                brightness: brightness as usize,
+                serial: optional_serial(serial),
+            }
+        }
+        "SetKeyImage" => {
+            let (key, icon, seconds, serial) = parameters.get::<(u32, String, u32, String)>()?;
+            ControlRequest::SetKeyImage {
+                key: key as usize,
+                icon,
+                seconds: (seconds > 0).then_some(u64::from(seconds)),
+                serial: optional_serial(serial),
+            }
+        }
+        "GetState" => ControlRequest::State,
+        _ => return None,
+    };
+    Some(request)
+}
+
+fn dbus_state(state: &DaemonState) -> glib::Variant {
+    let decks = state
+        .decks
+        .iter()
+        .map(|deck| {
+            (
+                deck.serial.clone(),
+                deck.model.clone(),
+                deck.profile.clone(),
+                dbus_u32(deck.page),
+                dbus_u32(deck.pages),
+                dbus_u32(deck.brightness),
+            )
+        })
+        .collect::<Vec<_>>();
+    (state.profile.clone(), decks).to_variant()
+}
+
+fn handle_dbus_method_call(
+    method: &str,
+    parameters: &glib::Variant,
+    invocation: gio::DBusMethodInvocation,
+    sender: &mpsc::Sender<LoopEvent>,
+) {
+    let Some(request) = dbus_method_request(method, parameters) else {
+        invocation.return_dbus_error(
+            DBUS_ERROR_INVALID_ARGS,
+            &format!("Unsupported call {method}{}", parameters.type_()),
+        );
+        return;
+    };
+    match dispatch_control_request(request, sender).into_result() {
+        Ok(state) => invocation.return_value(state.as_ref().map(dbus_state).as_ref()),
+        Err(err) => invocation.return_dbus_error(DBUS_ERROR_FAILED, &err),
+    }
+}
+
+fn open_dbus_connection(
+    bus: gio::BusType,
+    address: Option<&str>,
+) -> Result<gio::DBusConnection, String> {
+    match address {
+        None => gio::bus_get_sync(bus, gio::Cancellable::NONE)
+            .map_err(|err| format!("Failed to connect to {bus:?} bus: {err}")),
+        Some(address) => gio::DBusConnection::for_address_sync(
+            address,
+            gio::DBusConnectionFlags::AUTHENTICATION_CLIENT
+                | gio::DBusConnectionFlags::MESSAGE_BUS_CONNECTION,
+            None,
+            gio::Cancellable::NONE,
+        )
+        .map_err(|err| format!("Failed to connect to D-Bus at '{address}': {err}")),
+    }
+}
+
+fn register_dbus_service(
+    connection: &gio::DBusConnection,
+    sender: mpsc::Sender<LoopEvent>,
+) -> Result<(), String> {
+    let node = gio::DBusNodeInfo::for_xml(DBUS_INTROSPECTION_XML)
+        .map_err(|err| format!("Invalid D-Bus introspection data: {err}"))?;
+    let interface = node
+        .lookup_interface(DBUS_INTERFACE)
+        .ok_or_else(|| format!("D-Bus introspection data lacks '{DBUS_INTERFACE}'"))?;
+    connection
+        .register_object(DBUS_OBJECT_PATH, &interface)
+        .method_call(move |_, _, _, _, method, parameters, invocation| {
+            handle_dbus_method_call(method, &parameters, invocation, &sender);
+        })
+        .build()
+        .map_err(|err| format!("Failed to export '{DBUS_OBJECT_PATH}' on D-Bus: {err}"))?;
+
+    let reply = connection
+        .call_sync(
+            Some("org.freedesktop.DBus"),
+            "/org/freedesktop/DBus",
+            "org.freedesktop.DBus",
+            "RequestName",
+            Some(&(DBUS_NAME, DBUS_NAME_FLAG_DO_NOT_QUEUE).to_variant()),
+            Some(glib::VariantTy::new("(u)").expect("reply type should be valid")),
+            gio::DBusCallFlags::NONE,
+            -1,
+            gio::Cancellable::NONE,
+        )
+        .map_err(|err| format!("Failed to request D-Bus name '{DBUS_NAME}': {err}"))?;
+    if reply.get::<(u32,)>() != Some((DBUS_REQUEST_NAME_PRIMARY_OWNER,)) {
+        return Err(format!(
+            "Another streamrs daemon owns the D-Bus name '{DBUS_NAME}'"
+        ));
+    }
+    Ok(())
+}
+
+// Signals are only emitted while the daemon owns its name; media commands go
+// out either way.
+fn emit_daemon_events(
+    connection: gio::DBusConnection,
+    events: mpsc::Receiver<DaemonEvent>,
+    owns_name: bool,
+) {
+    thread::spawn(move || {
+        for event in events {
+            if let DaemonEvent::MediaCommand { player, command } = &event {
+                send_media_command(&connection, player, *command);
+                continue;
+            }
+            let Some((signal, parameters)) = event.signal().filter(|_| owns_name) else {
+                continue;
+            };
+            if let Err(err) = connection.emit_signal(
+                None,
+                DBUS_OBJECT_PATH,
+                DBUS_INTERFACE,
+                signal,
+                Some(&parameters),
+            ) {
+                eprintln!("Failed to emit D-Bus signal {signal}: {err}");
+            }
+        }
+    });
+}
+
+// Reports lock changes of the desktop session: `ActiveChanged` of the screen
+// saver on `connection` and `LockedHint` of the user's graphical logind
+// session on `system`. Dropping the subscriptions stops the reports.
+fn watch_session_lock(
+    connection: &gio::DBusConnection,
+    system: Option<&gio::DBusConnection>,
+    sender: mpsc::Sender<LoopEvent>,
+) -> Vec<gio::SignalSubscription> {
+    let screensaver_sender = sender.clone();
+    let mut subscriptions = vec![connection.subscribe_to_signal(
+        None,
+        Some(SCREENSAVER_INTERFACE),
+        Some("ActiveChanged"),
+        None,
+        None,
+        gio::DBusSignalFlags::NONE,
+        move |signal| {
+            if let Some((active,)) = signal.parameters.get::<(bool,)>() {
+                let _ = screensaver_sender.send(LoopEvent::SessionLocked(active));
+            }
+        },
+    )];
+    if let Some(system) = system {
+        match watch_logind_lock(system, sender) {
+            Ok(subscription) => subscriptions.push(subscription),
+            Err(err) => eprintln!("{err}"),
+        }
+    }
+    subscriptions
+}
+
+fn logind_property(
+    connection: &gio::DBusConnection,
+    path: &str,
+    interface: &str,
+    property: &str,
+) -> Result<glib::Variant, String> {
+    let reply = connection
+        .call_sync(
+            Some(LOGIND_NAME),
+            path,
+            "org.freedesktop.DBus.Properties",
+            "Get",
+            Some(&(interface, property).to_variant()),
+            Some(glib::VariantTy::new("(v)").expect("reply type should be valid")),
+            gio::DBusCallFlags::NONE,
+            -1,
+            gio::Cancellable::NONE,
+        )
+        .map_err(|err| format!("Failed to read logind property {property}: {err}"))?;
+    reply
+        .child_value(0)
+        .as_variant()
+        .ok_or_else(|| format!("logind property {property} has no value"))
+}
+
+// The daemon runs as a user service outside any session, so the session is
+// the user's graphical one rather than the caller's.
+fn watch_logind_lock(
+    connection: &gio::DBusConnection,
+    sender: mpsc::Sender<LoopEvent>,
+) -> Result<gio::SignalSubscription, String> {
+    let display = logind_property(
+        connection,
+        LOGIND_USER_PATH,
+        LOGIND_USER_INTERFACE,
+        "Display",
+    )?;
+    let session_path = display
+        .get::<(String, glib::variant::ObjectPath)>()
+        .map(|(_, path)| path.as_str().to_string())
+        .filter(|path| path != "/")
+        .ok_or_else(|| "The user has no graphical logind session".to_string())?;
+    let locked = logind_property(
+        connection,
+        &session_path,
+        LOGIND_SESSION_INTERFACE,
+        "LockedHint",
+    )?;
+    if locked.get::<bool>() == Some(true) {
+        let _ = sender.send(LoopEvent::SessionLocked(true));
+    }
+    Ok(connection.subscribe_to_signal(
+        Some(LOGIND_NAME),
+        Some("org.freedesktop.DBus.Properties"),
+        Some("PropertiesChanged"),
+        Some(&session_path),
+        Some(LOGIND_SESSION_INTERFACE),
+        gio::DBusSignalFlags::NONE,
+        move |signal| {
+            let Some(changed) = signal.parameters.try_child_value(1) else {
+                return;
+            };
+            if let Ok(Some(locked)) =
+                glib::VariantDict::new(Some(&changed)).lookup::<bool>("LockedHint")
+            {
+                let _ = sender.send(LoopEvent::SessionLocked(locked));
+            }
+        },
+    ))
+}
+
+// Method calls are dispatched on a dedicated thread with its own main loop and
+// wait there for the event loop's answer; MPRIS players and the session lock
+// are followed there too. `address` and `system_address` replace the session
+// and system bus. When another process owns the daemon's name, the error is
+// returned but the lock and media players are still followed.
+pub(crate) fn serve_dbus(
+    address: Option<String>,
+    system_address: Option<String>,
+    sender: mpsc::Sender<LoopEvent>,
+    events: mpsc::Receiver<DaemonEvent>,
+) -> Result<(), String> {
+    let (started_tx, started_rx) = mpsc::channel();
+    thread::spawn(move || {
+        let context = glib::MainContext::new();
+        let result = context.with_thread_default(|| {
+            let connection = match open_dbus_connection(gio::BusType::Session, address.as_deref()) {
+                Ok(connection) => connection,
+                Err(err) => {
+                    let _ = started_tx.send(Err(err));
+                    return;
+                }
+            };
+            let system = open_dbus_connection(gio::BusType::System, system_address.as_deref())
+                .inspect_err(|err| eprintln!("{err}"))
+                .ok();
+            let _lock_watches = watch_session_lock(&connection, system.as_ref(), sender.clone());
+            let _media_watches = watch_media_players(&connection, sender.clone());
+            let registered = register_dbus_service(&connection, sender);
+            emit_daemon_events(connection, events, registered.is_ok());
+            let _ = started_tx.send(registered);
+            glib::MainLoop::new(Some(&context), false).run();
+        });
+        if let Err(err) = result {
+            let _ = started_tx.send(Err(format!("Failed to start D-Bus main loop: {err}")));
+        }
+    });
+    started_rx
+        .recv()
+        .map_err(|_| "D-Bus service thread exited during startup".to_string())?
+}
@@ -0,0 +1,1447 @@
+use super::*;
+use dbus::{DBUS_INTERFACE, DBUS_OBJECT_PATH};
+use gtk::gio;
+use gtk::glib::{self, variant::ToVariant};
+use std::cell::RefCell;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::rc::Rc;
+use std::sync::atomic::AtomicUsize;
+use streamrs::control::send_control_request_to;
+use streamrs::image::media::PlaybackStatus;
+use streamrs::streamdeck::STREAMDECK_MK2;
+use streamrs::streamdeck::mock::MockDeck;
+
+const WAIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+struct RunningDaemon {
+    deck: MockDeck,
+    decks: Vec<MockDeck>,
+    config_path: PathBuf,
+    image_dir: PathBuf,
+    control_socket: PathBuf,
+    loop_tx: mpsc::Sender<LoopEvent>,
+    events: Option<mpsc::Receiver<DaemonEvent>>,
+    // How often the event loop probed for decks.
+    probes: Arc<AtomicUsize>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl RunningDaemon {
+    fn start(name: &str, config_raw: &str) -> Self {
+        Self::start_with_decks(name, config_raw, &[], vec![MockDeck::new(&STREAMDECK_MK2)])
+    }
+
+    fn start_with_decks(
+        name: &str,
+        config_raw: &str,
+        extra_profiles: &[(&str, &str)],
+        decks: Vec<MockDeck>,
+    ) -> Self {
+        let dir = test_temp_dir(name);
+        let image_dir = dir.join("icons");
+        fs::create_dir_all(&image_dir).expect("icon dir should be creatable");
+        for (icon, color) in [
+            ("red.png", [255, 0, 0, 255]),
+            ("green.png", [0, 255, 0, 255]),
+            ("blue.png", [0, 0, 255, 255]),
+        ] {
+            write_test_png(&image_dir.join(icon), color);
+        }
+        let config_path = dir.join("test.toml");
+        fs::write(&config_path, config_raw).expect("test config should be written");
+        for (profile, raw) in extra_profiles {
+            fs::write(dir.join(format!("{profile}.toml")), raw)
+                .expect("extra profile config should be written");
+        }
+        let config =
+            load_profile_config("test", &config_path).expect("test config should be valid");
+
+        let session = DaemonSession {
+            profile: "test".to_string(),
+            profile_locked: true,
+            config_path: config_path.clone(),
+            image_dir: image_dir.clone(),
+            config,
+            config_raw: config_raw.to_string(),
+            debug: false,
+            files_watched: true,
+            state_path: dir.join("state.toml"),
+        };
+        let control_socket = dir.join("control.sock");
+        let (loop_tx, loop_rx) = mpsc::channel();
+        serve_control_socket(
+            bind_control_socket(&control_socket).expect("control socket should bind"),
+            loop_tx.clone(),
+        );
+        let (event_tx, event_rx) = mpsc::channel();
+        let probes = Arc::new(AtomicUsize::new(0));
+        let handle = {
+            let decks = decks.clone();
+            let loop_tx = loop_tx.clone();
+            let probes = Arc::clone(&probes);
+            thread::spawn(move || {
+                let connect = |_: &Config, wanted: &mut dyn FnMut(&str, u16) -> bool| {
+                    probes.fetch_add(1, Ordering::SeqCst);
+                    decks
+                        .iter()
+                        .filter(|deck| {
+                            deck.is_connected() && wanted(deck.serial(), deck.product_id())
+                        })
+                        .map(|deck| {
+                            ConnectedDeck::new(
+                                Box::new(deck.clone()),
+                                Box::new(deck.clone()),
+                                deck.model(),
+                                deck.serial().to_string(),
+                            )
+                        })
+                        .collect()
+                };
+                run_event_loop(session, connect, &loop_tx, &loop_rx, &event_tx);
+            })
+        };
+
+        Self {
+            deck: decks[0].clone(),
+            decks,
+            config_path,
+            image_dir,
+            control_socket,
+            loop_tx,
+            events: Some(event_rx),
+            probes,
+            handle: Some(handle),
+        }
+    }
+
+    fn control(&self, request: ControlRequest) -> ControlResponse {
+        send_control_request_to(&self.control_socket, &request)
+            .expect("control request should get a response")
+    }
+
+    fn serve_dbus(&mut self, address: &str) {
+        self.try_serve_dbus(address)
+            .expect("D-Bus service should start");
+    }
+
+    fn try_serve_dbus(&mut self, address: &str) -> Result<(), String> {
+        let events = self.events.take().expect("D-Bus is served once per daemon");
+        serve_dbus(
+            Some(address.to_string()),
+            Some(address.to_string()),
+            self.loop_tx.clone(),
+            events,
+        )
+    }
+
+    fn icon_image(&self, deck: &MockDeck, icon: &str) -> Vec<u8> {
+        let path = self.image_dir.join(icon);
+        let bytes = fs::read(&path).expect("test icon should be readable");
+        get_image_data(&path, &bytes, None, deck.model()).expect("test icon should encode")
+    }
+
+    fn wait_for_key_image(&self, key: usize, icon: &str) {
+        self.wait_for_deck_image(&self.deck, key, icon);
+    }
+
+    fn wait_for_deck_image(&self, deck: &MockDeck, key: usize, icon: &str) {
+        let expected = self.icon_image(deck, icon);
+        wait_until(
+            &format!("key {key} of '{}' to show '{icon}'", deck.serial()),
+            || deck.last_key_image(key).as_ref() == Some(&expected),
+        );
+    }
+}
+
+impl Drop for RunningDaemon {
+    fn drop(&mut self) {
+        let _ = self.loop_tx.send(LoopEvent::Shutdown);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+// A private message bus, so D-Bus tests neither need nor disturb a session bus.
+struct PrivateBus {
+    child: Child,
+    address: String,
+}
+
+impl PrivateBus {
+    fn start() -> Option<Self> {
+        let mut child = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+        let mut address = String::new();
+        let read = child
+            .stdout
+            .take()
+            .map(|stdout| BufReader::new(stdout).read_line(&mut address));
+        let bus = Self {
+            child,
+            address: address.trim().to_string(),
+        };
+        (matches!(read, Some(Ok(_))) && !bus.address.is_empty()).then_some(bus)
+    }
+
+    fn connect(&self) -> gio::DBusConnection {
+        gio::DBusConnection::for_address_sync(
+            &self.address,
+            gio::DBusConnectionFlags::AUTHENTICATION_CLIENT
+                | gio::DBusConnectionFlags::MESSAGE_BUS_CONNECTION,
+            None,
+            gio::Cancellable::NONE,
+        )
+        .expect("private bus should accept connections")
+    }
+
+    // Connects and takes the daemon's name, as a second daemon would.
+    fn connect_owning_daemon_name(&self) -> gio::DBusConnection {
+        let connection = self.connect();
+        let reply = connection
+            .call_sync(
+                Some("org.freedesktop.DBus"),
+                "/org/freedesktop/DBus",
+                "org.freedesktop.DBus",
+                "RequestName",
+                Some(&(DBUS_NAME, 4u32).to_variant()),
+                Some(glib::VariantTy::new("(u)").expect("reply type should be valid")),
+                gio::DBusCallFlags::NONE,
+                -1,
+                gio::Cancellable::NONE,
+            )
+            .expect("name request should succeed");
+        assert_eq!(reply.get::<(u32,)>(), Some((1,)));
+        connection
+    }
+}
+
+impl Drop for PrivateBus {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn call_daemon(
+    client: &gio::DBusConnection,
+    method: &str,
+    parameters: Option<glib::Variant>,
+) -> Result<glib::Variant, glib::Error> {
+    client.call_sync(
+        Some(DBUS_NAME),
+        DBUS_OBJECT_PATH,
+        DBUS_INTERFACE,
+        method,
+        parameters.as_ref(),
+        None,
+        gio::DBusCallFlags::NONE,
+        -1,
+        gio::Cancellable::NONE,
+    )
+}
+
+fn wait_until(description: &str, mut condition: impl FnMut() -> bool) {
+    let started = Instant::now();
+    while !condition() {
+        assert!(
+            started.elapsed() < WAIT_TIMEOUT,
+            "timed out waiting for {description}"
+        );
+        sleep(Duration::from_millis(20));
+    }
+}
+
+fn keys_toml(icons: &[&str]) -> String {
+    icons
+        .iter()
+        .map(|icon| format!("[[keys]]\nicon = \"{icon}\"\n"))
+        .collect()
+}
+
+#[test]
+fn event_loop_draws_first_page_and_sets_brightness() {
+    let daemon = RunningDaemon::start(
+        "event-loop-initial",
+        &format!("brightness = 40\n{}", keys_toml(&["red.png", "green.png"])),
+    );
+
+    daemon.wait_for_key_image(0, "red.png");
+    daemon.wait_for_key_image(1, "green.png");
+    assert_eq!(daemon.deck.brightness_levels(), vec![40]);
+    let blank = blank_image_data(daemon.deck.model()).expect("blank image should encode");
+    assert_eq!(daemon.deck.last_key_image(14), Some(blank));
+}
+
+#[test]
+fn event_loop_pages_with_navigation_key_presses() {
+    let mut icons = vec!["red.png"; 14];
+    icons.extend(["blue.png", "green.png"]);
+    let daemon = RunningDaemon::start("event-loop-paging", &keys_toml(&icons));
+    daemon.wait_for_key_image(0, "red.png");
+
+    daemon.deck.press_key(14);
+    daemon.wait_for_key_image(0, "blue.png");
+    daemon.wait_for_key_image(1, "green.png");
+
+    daemon.deck.press_key(14);
+    wait_until("first page to be redrawn", || {
+        daemon.deck.key_images(0).len() >= 3
+    });
+    daemon.wait_for_key_image(0, "red.png");
+}
+
+#[test]
+fn event_loop_ignores_a_key_held_across_a_page_change() {
+    let dir = test_temp_dir("event-loop-held-key");
+    let flag = dir.join("flag");
+    let _ = fs::remove_file(&flag);
+    let config = format!(
+        "{}[[keys]]\nicon = \"green.png\"\naction = \"touch {}\"\n",
+        keys_toml(&[&["red.png"; 14][..], &["blue.png"]].concat()),
+        flag.display()
+    );
+    let daemon = RunningDaemon::start("event-loop-held-key", &config);
+    daemon.wait_for_key_image(0, "red.png");
+
+    daemon.deck.queue_key_state(&[14]);
+    daemon.wait_for_key_image(0, "blue.png");
+    let redraws = daemon.deck.key_images(0).len();
+
+    // Key 14 is the previous page key here, but it is still the press that
+    // turned the page.
+    daemon.deck.queue_key_state(&[14, 1]);
+    daemon.deck.queue_key_state(&[14]);
+    daemon.deck.queue_key_state(&[]);
+    wait_until("the newly pressed key to run its action", || flag.exists());
+    sleep(Duration::from_millis(200));
+    assert_eq!(daemon.deck.key_images(0).len(), redraws);
+}
+
+#[test]
+fn event_loop_opens_folders_and_returns_with_the_back_key() {
+    let config = format!(
+        "[[keys]]\nicon = \"blue.png\"\n{}[[keys]]\nicon = \"red.png\"\n",
+        keys_toml(&["green.png"]).replace("[[keys]]", "[[keys.keys]]")
+    );
+    let daemon = RunningDaemon::start("event-loop-folders", &config);
+    daemon.wait_for_key_image(0, "blue.png");
+    daemon.wait_for_key_image(1, "red.png");
+
+    daemon.deck.press_key(0);
+    daemon.wait_for_key_image(1, "green.png");
+
+    daemon.deck.press_key(0);
+    wait_until("top level to be redrawn", || {
+        daemon.deck.key_images(1).len() >= 3
+    });
+    daemon.wait_for_key_image(0, "blue.png");
+    daemon.wait_for_key_image(1, "red.png");
+}
+
+#[test]
+fn event_loop_keeps_timers_running_across_page_changes() {
+    let dir = test_temp_dir("event-loop-timer-mark");
+    let done = dir.join("done");
+    let config = format!(
+        "[[keys]]\nicon = \"timer://1s\"\ntimer_command = \"touch {}\"\n[[keys]]\nicon = \"red.png\"\n[[keys.keys]]\nicon = \"green.png\"\n[[keys]]\nicon = \"stopwatch://\"\n",
+        done.display()
+    );
+    let daemon = RunningDaemon::start("event-loop-timers", &config);
+    let model = daemon.deck.model();
+    let ready_image = |kind| {
+        render_timer_icon(&KeyTimer::new(kind).face(Instant::now()), None, model)
+            .expect("timer face should render")
+    };
+    let ready_countdown = ready_image(TimerKind::Countdown(Duration::from_secs(1)));
+    let ready_stopwatch = ready_image(TimerKind::Stopwatch);
+    wait_until("timer keys to be drawn", || {
+        daemon.deck.last_key_image(0) == Some(ready_countdown.clone())
+            && daemon.deck.last_key_image(2) == Some(ready_stopwatch.clone())
+    });
+
+    // The countdown finishes and runs its command while its page is hidden.
+    daemon.deck.press_key(0);
+    daemon.deck.press_key(1);
+    daemon.wait_for_key_image(1, "green.png");
+    wait_until("countdown command", || done.exists());
+
+    daemon.deck.press_key(0);
+    let finished = |flash: bool| {
+        let face = TimerFace {
+            seconds: 0,
+            total: Some(1),
+            state: TimerState::Finished,
+            flash,
+        };
+        render_timer_icon(&face, None, model).expect("finished face should render")
+    };
+    let (flash_on, flash_off) = (finished(true), finished(false));
+    wait_until("finished countdown to flash", || {
+        daemon.deck.last_key_image(0) == Some(flash_on.clone())
+    });
+    wait_until("flash to switch off", || {
+        daemon.deck.last_key_image(0) == Some(flash_off.clone())
+    });
+
+    daemon.deck.press_key(2);
+    wait_until("stopwatch to start", || {
+        daemon.deck.last_key_image(2) != Some(ready_stopwatch.clone())
+    });
+    daemon.deck.queue_key_state(&[2]);
+    sleep(Duration::from_millis(700));
+    daemon.deck.queue_key_state(&[]);
+    wait_until("long press to reset the stopwatch", || {
+        daemon.deck.last_key_image(2) == Some(ready_stopwatch.clone())
+    });
+}
+
+#[test]
+fn event_loop_samples_metric_keys_on_their_interval() {
+    let config = "[[keys]]\nicon = \"metric://load\"\nmetric_interval_seconds = 1\n";
+    let daemon = RunningDaemon::start("event-loop-metrics", config);
+    // Each reading adds a point to the sparkline, so every redraw differs.
+    wait_until("metric key to be redrawn", || {
+        daemon.deck.key_images(0).len() >= 3
+    });
+}
+
+#[test]
+fn event_loop_reads_disk_metrics_off_the_loop_thread() {
+    let config = "[[keys]]\nicon = \"metric://disk/\"\n[[keys]]\nicon = \"red.png\"\n";
+    let daemon = RunningDaemon::start("event-loop-disk-metric", config);
+    let sampler = MetricSampler::new(Metric::Disk(PathBuf::from("/")));
+    let (placeholder, _) = render_metric_icon(
+        &sampler,
+        Ok(MetricSample::unavailable(sampler.metric())),
+        &MetricFace::default(),
+        None,
+        daemon.deck.model(),
+    )
+    .expect("placeholder should render");
+    wait_until("disk metric key to show its first reading", || {
+        let images = daemon.deck.key_images(0);
+        images.first() == Some(&placeholder) && images.last() != Some(&placeholder)
+    });
+}
+
+#[test]
+fn event_loop_runs_actions_and_refreshes_status_icons() {
+    let dir = test_temp_dir("event-loop-status-flag");
+    let flag = dir.join("flag");
+    let _ = fs::remove_file(&flag);
+    let config = format!(
+        "[[keys]]\nicon = \"red.png\"\naction = \"touch {flag}\"\nstatus = \"test -e {flag}\"\nicon_on = \"green.png\"\nicon_off = \"red.png\"\nstatus_interval_seconds = 60\n",
+        flag = flag.display()
+    );
+    let daemon = RunningDaemon::start("event-loop-status", &config);
+    daemon.wait_for_key_image(0, "red.png");
+
+    daemon.deck.press_key(0);
+    wait_until("action to create the status flag", || flag.exists());
+    daemon.wait_for_key_image(0, "green.png");
+}
+
+#[test]
+fn event_loop_keeps_handling_keys_while_a_status_check_hangs() {
+    let dir = test_temp_dir("event-loop-status-hang");
+    let flag = dir.join("pressed");
+    let _ = fs::remove_file(&flag);
+    let config = format!(
+        "[[keys]]\nicon = \"red.png\"\nstatus = \"sleep 3\"\nicon_on = \"green.png\"\n\
+         [[keys]]\nicon = \"blue.png\"\naction = \"touch {flag}\"\n",
+        flag = flag.display()
+    );
+    let daemon = RunningDaemon::start("event-loop-status-hang", &config);
+    daemon.wait_for_key_image(1, "blue.png");
+
+    let pressed = Instant::now();
+    daemon.deck.press_key(1);
+    wait_until("action to run beside the status check", || flag.exists());
+    assert!(pressed.elapsed() < Duration::from_secs(2));
+}
+
+#[test]
+fn event_loop_shows_status_output_states_and_titles() {
+    let dir = test_temp_dir("event-loop-status-output");
+    let output = dir.join("output");
+    fs::write(&output, "on\n").expect("status output should be written");
+    let config = format!(
+        "[[keys]]\nicon = \"red.png\"\nstatus = \"cat {output}\"\nstatus_mode = \"output\"\nstatus_interval_seconds = 1\n\
+         [keys.status_icons]\non = \"green.png\"\n",
+        output = output.display()
+    );
+    let daemon = RunningDaemon::start("event-loop-status-output", &config);
+    daemon.wait_for_key_image(0, "green.png");
+
+    fs::write(
+        &output,
+        r#"{"icon": "blue.png", "title": "42", "background": "black"}"#,
+    )
+    .expect("status output should be written");
+    let face = StatusOutput {
+        icon: Some("blue.png".to_string()),
+        title: Some("42".to_string()),
+        background: Some("black".to_string()),
+    };
+    let title = KeyTitle::new("42");
+    let expected = render_status_face(&daemon.image_dir, &face, Some(&title), daemon.deck.model())
+        .expect("status face should render");
+    wait_until("key 0 to show the status title", || {
+        daemon.deck.last_key_image(0).as_ref() == Some(&expected)
+    });
+}
+
+#[test]
+fn event_loop_draws_key_titles_over_icons() {
+    let config = "[[keys]]\nicon = \"red.png\"\ntitle = \"Mic\"\ntitle_position = \"top\"\n";
+    let daemon = RunningDaemon::start("event-loop-titles", config);
+    let path = daemon.image_dir.join("red.png");
+    let bytes = fs::read(&path).expect("test icon should be readable");
+    let title = KeyTitle {
+        position: TitlePosition::Top,
+        ..KeyTitle::new("Mic")
+    };
+    let expected = get_image_data(&path, &bytes, Some(&title), daemon.deck.model())
+        .expect("titled icon should encode");
+    assert_ne!(expected, daemon.icon_image(&daemon.deck, "red.png"));
+    wait_until("key 0 to show its title", || {
+        daemon.deck.last_key_image(0).as_ref() == Some(&expected)
+    });
+}
+
+#[test]
+fn event_loop_runs_macro_steps_in_order_and_switches_pages() {
+    let dir = test_temp_dir("event-loop-macro-marks");
+    let (first, second, skipped) = (dir.join("first"), dir.join("second"), dir.join("skipped"));
+    for mark in [&first, &second, &skipped] {
+        let _ = fs::remove_file(mark);
+    }
+    let mut config = format!(
+        "[[keys]]\nicon = \"red.png\"\n\
+         [[keys.steps]]\naction = \"touch {}\"\n\
+         [[keys.steps]]\ndelay_ms = 100\n\
+         [[keys.steps]]\naction = \"touch {}\"\n\
+         [[keys.steps]]\npage = \"next\"\n\
+         [[keys]]\nicon = \"red.png\"\nstop_on_error = true\n\
+         [[keys.steps]]\naction = \"false\"\n\
+         [[keys.steps]]\naction = \"touch {}\"\n",
+        first.display(),
+        second.display(),
+        skipped.display(),
+    );
+    let mut icons = vec!["red.png"; 12];
+    icons.extend(["blue.png", "green.png"]);
+    config.push_str(&keys_toml(&icons));
+    let daemon = RunningDaemon::start("event-loop-macro", &config);
+    daemon.wait_for_key_image(0, "red.png");
+
+    daemon.deck.press_key(1);
+    daemon.deck.press_key(0);
+    wait_until("first macro command", || first.exists());
+    assert!(!second.exists(), "delay step runs before the next command");
+    wait_until("second macro command", || second.exists());
+    daemon.wait_for_key_image(0, "blue.png");
+    assert!(!skipped.exists(), "stop_on_error skips the remaining steps");
+}
+
+#[test]
+fn event_loop_reloads_config_on_request() {
+    let daemon = RunningDaemon::start("event-loop-reload", &keys_toml(&["red.png", "green.png"]));
+    daemon.wait_for_key_image(1, "green.png");
+    daemon.deck.clear_recorded();
+
+    fs::write(&daemon.config_path, keys_toml(&["blue.png", "green.png"]))
+        .expect("config should be updated");
+    daemon
+        .loop_tx
+        .send(LoopEvent::Reload)
+        .expect("event loop should be running");
+    daemon.wait_for_key_image(0, "blue.png");
+    // Answered once the reload has finished; unchanged keys were not uploaded again.
+    assert!(daemon.control(ControlRequest::State).ok);
+    assert!(daemon.deck.key_images(1).is_empty());
+    assert!(daemon.deck.key_images(14).is_empty());
+}
+
+#[test]
+fn event_loop_redraws_after_reconnect() {
+    let daemon = RunningDaemon::start("event-loop-reconnect", &keys_toml(&["red.png"]));
+    daemon.wait_for_key_image(0, "red.png");
+
+    daemon.deck.disconnect();
+    sleep(Duration::from_millis(100));
+    daemon.deck.clear_recorded();
+    daemon.deck.reconnect();
+
+    daemon.wait_for_key_image(0, "red.png");
+    wait_until("brightness to be restored", || {
+        !daemon.deck.brightness_levels().is_empty()
+    });
+}
+
+#[test]
+fn event_loop_stops_probing_once_every_deck_is_connected() {
+    let daemon = RunningDaemon::start("event-loop-probe", &keys_toml(&["red.png"]));
+    daemon.wait_for_key_image(0, "red.png");
+    let probes = daemon.probes.load(Ordering::SeqCst);
+    sleep(DEVICE_PROBE_INTERVAL * 3);
+    assert_eq!(daemon.probes.load(Ordering::SeqCst), probes);
+
+    daemon
+        .loop_tx
+        .send(LoopEvent::DevicesChanged)
+        .expect("event loop should be running");
+    wait_until("a hotplugged device to be probed for", || {
+        daemon.probes.load(Ordering::SeqCst) == probes + 1
+    });
+    sleep(DEVICE_PROBE_INTERVAL * 3);
+    assert_eq!(daemon.probes.load(Ordering::SeqCst), probes + 1);
+
+    daemon.deck.disconnect();
+    sleep(Duration::from_millis(100));
+    daemon.deck.reconnect();
+    daemon.wait_for_key_image(0, "red.png");
+    wait_until("the lost deck to be probed for", || {
+        daemon.probes.load(Ordering::SeqCst) > probes + 1
+    });
+}
+
+#[test]
+fn event_loop_runs_mapped_profiles_per_deck_serial() {
+    let desk = MockDeck::with_serial(&STREAMDECK_MK2, "DESK");
+    let side = MockDeck::with_serial(&STREAMDECK_MINI, "SIDE");
+    let mut icons = vec!["red.png"; 14];
+    icons.extend(["blue.png", "green.png"]);
+    let config = format!("[devices]\nSIDE = \"side\"\n{}", keys_toml(&icons));
+    let side_config = format!("product_id = 0x0063\n{}", keys_toml(&["green.png"]));
+    let daemon = RunningDaemon::start_with_decks(
+        "event-loop-multi",
+        &config,
+        &[("side", &side_config)],
+        vec![desk.clone(), side.clone()],
+    );
+
+    daemon.wait_for_deck_image(&desk, 0, "red.png");
+    daemon.wait_for_deck_image(&side, 0, "green.png");
+
+    desk.press_key(14);
+    daemon.wait_for_deck_image(&desk, 0, "blue.png");
+    assert_eq!(side.key_images(0).len(), 1, "other deck keeps its page");
+
+    side.disconnect();
+    sleep(Duration::from_millis(100));
+    side.clear_recorded();
+    side.reconnect();
+    daemon.wait_for_deck_image(&side, 0, "green.png");
+    assert_eq!(
+        daemon
+            .decks
+            .iter()
+            .filter(|deck| deck.is_connected())
+            .count(),
+        2
+    );
+    daemon.wait_for_deck_image(&desk, 0, "blue.png");
+}
+
+#[test]
+fn event_loop_dispatches_long_double_and_chorded_presses() {
+    let dir = test_temp_dir("event-loop-gestures-marks");
+    let mark = |name: &str| {
+        let path = dir.join(name);
+        let _ = fs::remove_file(&path);
+        path
+    };
+    let (tap, long, tap_double_key, double, chord_a, chord_b) = (
+        mark("tap"),
+        mark("long"),
+        mark("tap-double-key"),
+        mark("double"),
+        mark("chord-a"),
+        mark("chord-b"),
+    );
+    let config = format!(
+        "long_press_ms = 200\ndouble_press_ms = 150\n\
+         [[keys]]\nicon = \"red.png\"\naction = \"touch {}\"\nlong_press_action = \"touch {}\"\n\
+         [[keys]]\nicon = \"red.png\"\naction = \"touch {}\"\ndouble_press_action = \"touch {}\"\n\
+         [[keys]]\nicon = \"red.png\"\naction = \"touch {}\"\n\
+         [[keys]]\nicon = \"red.png\"\naction = \"touch {}\"\n",
+        tap.display(),
+        long.display(),
+        tap_double_key.display(),
+        double.display(),
+        chord_a.display(),
+        chord_b.display(),
+    );
+    let daemon = RunningDaemon::start("event-loop-gestures", &config);
+    daemon.wait_for_key_image(3, "red.png");
+
+    daemon.deck.queue_key_state(&[0]);
+    sleep(Duration::from_millis(400));
+    daemon.deck.queue_key_state(&[]);
+    wait_until("long press action", || long.exists());
+
+    daemon.deck.queue_key_state(&[1]);
+    daemon.deck.queue_key_state(&[]);
+    daemon.deck.queue_key_state(&[1]);
+    daemon.deck.queue_key_state(&[]);
+    wait_until("double press action", || double.exists());
+
+    daemon.deck.queue_key_state(&[2, 3]);
+    daemon.deck.queue_key_state(&[]);
+    wait_until("both chorded actions", || {
+        chord_a.exists() && chord_b.exists()
+    });
+
+    daemon.deck.press_key(0);
+    wait_until("short press action", || tap.exists());
+    sleep(Duration::from_millis(300));
+    assert!(
+        !tap_double_key.exists(),
+        "double press replaces the regular action"
+    );
+}
+
+#[test]
+fn event_loop_answers_control_socket_requests() {
+    let dir = test_temp_dir("event-loop-control-flag");
+    let flag = dir.join("flag");
+    let _ = fs::remove_file(&flag);
+    let mut config = format!(
+        "[[keys]]\nicon = \"red.png\"\naction = \"touch {}\"\n",
+        flag.display()
+    );
+    let mut icons = vec!["red.png"; 13];
+    icons.extend(["blue.png", "green.png"]);
+    config.push_str(&keys_toml(&icons));
+    let daemon = RunningDaemon::start("event-loop-control", &config);
+    daemon.wait_for_key_image(0, "red.png");
+
+    let state = daemon
+        .control(ControlRequest::State)
+        .into_result()
+        .expect("state request should succeed")
+        .expect("state request should return state");
+    assert_eq!(state.profile, "test");
+    assert_eq!(
+        (
+            state.decks[0].page,
+            state.decks[0].pages,
+            state.decks[0].brightness
+        ),
+        (1, 2, 60)
+    );
+
+    assert!(
+        daemon
+            .control(ControlRequest::PressKey {
+                key: 1,
+                serial: None
+            })
+            .ok
+    );
+    wait_until("pressed key action", || flag.exists());
+    let out_of_range = daemon.control(ControlRequest::PressKey {
+        key: 16,
+        serial: None,
+    });
+    assert!(!out_of_range.ok);
+
+    assert!(
+        daemon
+            .control(ControlRequest::Page {
+                page: "2".to_string(),
+                serial: None,
+            })
+            .ok
+    );
+    daemon.wait_for_key_image(0, "blue.png");
+    let unknown_serial = daemon.control(ControlRequest::Page {
+        page: "1".to_string(),
+        serial: Some("missing".to_string()),
+    });
+    assert_eq!(
+        unknown_serial.error.as_deref(),
+        Some("No connected Stream Deck has serial 'missing'")
+    );
+
+    assert!(
+        daemon
+            .control(ControlRequest::SetBrightness {
+                brightness: 30,
+                serial: None,
+            })
+            .ok
+    );
+    assert_eq!(daemon.deck.brightness_levels().last(), Some(&30));
+
+    assert!(
+        daemon
+            .control(ControlRequest::SetKeyImage {
+                key: 1,
+                icon: "green.png".to_string(),
+                seconds: Some(1),
+                serial: None,
+            })
+            .ok
+    );
+    daemon.wait_for_key_image(0, "green.png");
+    daemon.wait_for_key_image(0, "blue.png");
+
+    let switch = daemon.control(ControlRequest::SwitchProfile {
+        profile: "other".to_string(),
+    });
+    assert!(!switch.ok, "profile is locked in tests");
+
+    fs::write(&daemon.config_path, keys_toml(&["green.png"])).expect("config should be updated");
+    assert!(daemon.control(ControlRequest::Reload).ok);
+    daemon.wait_for_key_image(0, "green.png");
+}
+
+#[test]
+fn event_loop_serves_dbus_methods_and_signals() {
+    let Some(bus) = PrivateBus::start() else {
+        eprintln!("dbus-daemon is not available; skipping D-Bus test");
+        return;
+    };
+    let mut icons = vec!["red.png"; 14];
+    icons.extend(["blue.png", "green.png"]);
+    let mut daemon = RunningDaemon::start("event-loop-dbus", &keys_toml(&icons));
+    daemon.serve_dbus(&bus.address);
+    daemon.wait_for_key_image(0, "red.png");
+
+    let context = glib::MainContext::new();
+    context
+        .with_thread_default(|| {
+            let client = bus.connect();
+            let signals = Rc::new(RefCell::new(Vec::<(String, glib::Variant)>::new()));
+            let _subscription = {
+                let signals = Rc::clone(&signals);
+                client.subscribe_to_signal(
+                    None,
+                    Some(DBUS_INTERFACE),
+                    None,
+                    Some(DBUS_OBJECT_PATH),
+                    None,
+                    gio::DBusSignalFlags::NONE,
+                    move |signal| {
+                        signals
+                            .borrow_mut()
+                            .push((signal.signal_name.to_string(), signal.parameters.clone()));
+                    },
+                )
+            };
+            let wait_for_signal = |name: &str, parameters: glib::Variant| {
+                wait_until(&format!("D-Bus signal {name}{parameters}"), || {
+                    while context.iteration(false) {}
+                    signals
+                        .borrow()
+                        .iter()
+                        .any(|signal| signal == &(name.to_string(), parameters.clone()))
+                });
+            };
+            let serial = daemon.deck.serial().to_string();
+            let model = daemon.deck.model().name.to_string();
+
+            let state = call_daemon(&client, "GetState", None)
+                .expect("GetState should succeed")
+                .get::<(String, Vec<(String, String, String, u32, u32, u32)>)>()
+                .expect("GetState should return the profile and decks");
+            assert_eq!(
+                state,
+                (
+                    "test".to_string(),
+                    vec![(serial.clone(), model.clone(), "test".to_string(), 1, 2, 60)]
+                )
+            );
+
+            call_daemon(&client, "SetPage", Some(("next", "").to_variant()))
+                .expect("SetPage should succeed");
+            wait_for_signal("PageChanged", (&serial, 2u32, 2u32).to_variant());
+            daemon.wait_for_key_image(0, "blue.png");
+
+            daemon.deck.press_key(0);
+            wait_for_signal("KeyPressed", (&serial, 1u32).to_variant());
+
+            let err = call_daemon(&client, "SetBrightness", Some((101u32, "").to_variant()))
+                .expect_err("out of range brightness should fail");
+            assert_eq!(
+                gio::DBusError::remote_error(&err).as_deref(),
+                Some("lv.apps.streamrs.Daemon.Error.Failed")
+            );
+
+            daemon.deck.disconnect();
+            wait_for_signal("DeviceDisconnected", (&serial,).to_variant());
+            daemon.deck.reconnect();
+            wait_for_signal("DeviceConnected", (&serial, &model).to_variant());
+        })
+        .expect("test main context should be acquirable");
+
+    let (second_tx, _) = mpsc::channel();
+    let (_, second_events) = mpsc::channel();
+    let second = serve_dbus(
+        Some(bus.address.clone()),
+        Some(bus.address.clone()),
+        second_tx,
+        second_events,
+    );
+    assert_eq!(
+        second,
+        Err(format!(
+            "Another streamrs daemon owns the D-Bus name '{DBUS_NAME}'"
+        ))
+    );
+}
+
+#[test]
+fn event_loop_turns_off_on_session_lock_and_wakes_on_press() {
+    let Some(bus) = PrivateBus::start() else {
+        eprintln!("dbus-daemon is not available; skipping session lock test");
+        return;
+    };
+    let dir = test_temp_dir("event-loop-idle-flag");
+    let flag = dir.join("flag");
+    let _ = fs::remove_file(&flag);
+    let config = format!(
+        "brightness = 40\nidle_off_minutes = 30\nidle_blank_keys = true\n[[keys]]\nicon = \"red.png\"\naction = \"touch {}\"\n",
+        flag.display()
+    );
+    let mut daemon = RunningDaemon::start("event-loop-idle", &config);
+    daemon.serve_dbus(&bus.address);
+    daemon.wait_for_key_image(0, "red.png");
+
+    let client = bus.connect();
+    client
+        .emit_signal(
+            None,
+            "/org/freedesktop/ScreenSaver",
+            "org.freedesktop.ScreenSaver",
+            "ActiveChanged",
+            Some(&(true,).to_variant()),
+        )
+        .expect("lock signal should be sent");
+    client
+        .flush_sync(gio::Cancellable::NONE)
+        .expect("lock signal should be flushed");
+    let blank = blank_image_data(daemon.deck.model()).expect("blank image should encode");
+    wait_until("deck to turn off", || {
+        daemon.deck.brightness_levels().last() == Some(&0)
+            && daemon.deck.last_key_image(0).as_ref() == Some(&blank)
+    });
+
+    daemon.deck.press_key(0);
+    wait_until("deck to wake up", || {
+        daemon.deck.brightness_levels().last() == Some(&40)
+    });
+    daemon.wait_for_key_image(0, "red.png");
+    sleep(Duration::from_millis(200));
+    assert!(!flag.exists(), "the waking press should not run the action");
+
+    daemon.deck.press_key(0);
+    wait_until("action to run once awake", || flag.exists());
+}
+
+const FAKE_PLAYER_XML: &str = r#"<node>
+  <interface name="org.mpris.MediaPlayer2.Player">
+    <method name="PlayPause"/>
+    <property name="PlaybackStatus" type="s" access="read"/>
+    <property name="Metadata" type="a{sv}" access="read"/>
+  </interface>
+</node>"#;
+
+fn mpris_metadata(title: &str, art_url: Option<&str>) -> glib::Variant {
+    let metadata = glib::VariantDict::new(None);
+    metadata.insert_value("xesam:title", &title.to_variant());
+    if let Some(url) = art_url {
+        metadata.insert_value("mpris:artUrl", &url.to_variant());
+    }
+    metadata.end()
+}
+
+#[test]
+fn event_loop_shows_the_playing_track_and_sends_media_commands() {
+    for owns_name in [true, false] {
+        let Some(bus) = PrivateBus::start() else {
+            eprintln!("dbus-daemon is not available; skipping MPRIS test");
+            return;
+        };
+        let mut daemon = RunningDaemon::start(
+            &format!("event-loop-media-{owns_name}"),
+            "[[keys]]\nicon = \"media://now-playing\"\n[[keys]]\nicon = \"red.png\"\n",
+        );
+        // Players are followed whether or not the daemon gets its own name.
+        let _other_daemon = (!owns_name).then(|| bus.connect_owning_daemon_name());
+        assert_eq!(daemon.try_serve_dbus(&bus.address).is_ok(), owns_name);
+        daemon.wait_for_key_image(1, "red.png");
+        let model = daemon.deck.model();
+        let idle =
+            render_now_playing_icon(None, None, model).expect("idle media face should render");
+        wait_until("the idle media face", || {
+            daemon.deck.last_key_image(0).as_ref() == Some(&idle)
+        });
+
+        let art = daemon.image_dir.join("red.png");
+        let art_url = format!("file://{}", art.display());
+        let context = glib::MainContext::new();
+        context
+            .with_thread_default(|| {
+                let player = bus.connect();
+                let calls = Rc::new(RefCell::new(Vec::<String>::new()));
+                let node =
+                    gio::DBusNodeInfo::for_xml(FAKE_PLAYER_XML).expect("player XML should parse");
+                let interface = node
+                    .lookup_interface("org.mpris.MediaPlayer2.Player")
+                    .expect("player XML should declare the interface");
+                let _registration = {
+                    let calls = Rc::clone(&calls);
+                    let art_url = art_url.clone();
+                    player
+                        .register_object("/org/mpris/MediaPlayer2", &interface)
+                        .method_call(move |_, _, _, _, method, _, invocation| {
+                            calls.borrow_mut().push(method.to_string());
+                            invocation.return_value(None);
+                        })
+                        .property(move |_, _, _, _, property| match property {
+                            "PlaybackStatus" => "Playing".to_variant(),
+                            _ => mpris_metadata("Fake Song", Some(&art_url)),
+                        })
+                        .build()
+                        .expect("fake player should register")
+                };
+                let call_bus = |method: &str, parameters: glib::Variant| {
+                    player
+                        .call_sync(
+                            Some("org.freedesktop.DBus"),
+                            "/org/freedesktop/DBus",
+                            "org.freedesktop.DBus",
+                            method,
+                            Some(&parameters),
+                            None,
+                            gio::DBusCallFlags::NONE,
+                            -1,
+                            gio::Cancellable::NONE,
+                        )
+                        .unwrap_or_else(|err| panic!("{method} should succeed: {err}"));
+                };
+                // A player with two names still counts once.
+                for name in [
+                    "org.mpris.MediaPlayer2.fake",
+                    "org.mpris.MediaPlayer2.fake.instance2",
+                ] {
+                    call_bus("RequestName", (name, 4u32).to_variant());
+                }
+                let emit_changes = |status: &str, metadata: Option<glib::Variant>| {
+                    let changed = glib::VariantDict::new(None);
+                    changed.insert_value("PlaybackStatus", &status.to_variant());
+                    if let Some(metadata) = metadata {
+                        changed.insert_value("Metadata", &metadata);
+                    }
+                    player
+                        .emit_signal(
+                            None,
+                            "/org/mpris/MediaPlayer2",
+                            "org.freedesktop.DBus.Properties",
+                            "PropertiesChanged",
+                            Some(&glib::Variant::tuple_from_iter([
+                                "org.mpris.MediaPlayer2.Player".to_variant(),
+                                changed.end(),
+                                Vec::<String>::new().to_variant(),
+                            ])),
+                        )
+                        .expect("PropertiesChanged should be sent");
+                };
+                let unique_name = player
+                    .unique_name()
+                    .expect("bus connection should have a unique name")
+                    .to_string();
+                let wait_for_face = |description: &str, now_playing: NowPlaying| {
+                    let expected = render_now_playing_icon(Some(&now_playing), None, model)
+                        .expect("now playing face should render");
+                    wait_until(description, || {
+                        while context.iteration(false) {}
+                        daemon.deck.last_key_image(0).as_ref() == Some(&expected)
+                    });
+                };
+
+                wait_for_face(
+                    "the album art of the playing track",
+                    NowPlaying {
+                        player: unique_name.clone(),
+                        status: PlaybackStatus::Playing,
+                        title: Some("Fake Song".to_string()),
+                        art: Some(art.clone()),
+                    },
+                );
+                daemon.deck.press_key(0);
+                wait_until("PlayPause to reach the player", || {
+                    while context.iteration(false) {}
+                    calls.borrow().as_slice() == ["PlayPause"]
+                });
+
+                emit_changes("Paused", Some(mpris_metadata("Other Song", None)));
+                wait_for_face(
+                    "the paused face of a track without art",
+                    NowPlaying {
+                        player: unique_name.clone(),
+                        status: PlaybackStatus::Paused,
+                        title: Some("Other Song".to_string()),
+                        art: None,
+                    },
+                );
+
+                // Dropping one name keeps the player and the track it plays.
+                call_bus("ReleaseName", ("org.mpris.MediaPlayer2.fake",).to_variant());
+                emit_changes("Playing", None);
+                wait_for_face(
+                    "the track to keep playing under the remaining name",
+                    NowPlaying {
+                        player: unique_name,
+                        status: PlaybackStatus::Playing,
+                        title: Some("Other Song".to_string()),
+                        art: None,
+                    },
+                );
+                call_bus(
+                    "ReleaseName",
+                    ("org.mpris.MediaPlayer2.fake.instance2",).to_variant(),
+                );
+                wait_until("the idle face once the player is gone", || {
+                    while context.iteration(false) {}
+                    daemon.deck.last_key_image(0).as_ref() == Some(&idle)
+                });
+            })
+            .expect("test main context should be acquirable");
+    }
+}
+
+#[test]
+fn event_loop_follows_session_lock_when_another_process_owns_the_name() {
+    let Some(bus) = PrivateBus::start() else {
+        eprintln!("dbus-daemon is not available; skipping session lock test");
+        return;
+    };
+    let client = bus.connect_owning_daemon_name();
+    let mut daemon = RunningDaemon::start(
+        "event-loop-idle-unnamed",
+        &format!(
+            "brightness = 40\nidle_off_minutes = 30\n{}",
+            keys_toml(&["red.png"])
+        ),
+    );
+    assert_eq!(
+        daemon.try_serve_dbus(&bus.address),
+        Err(format!(
+            "Another streamrs daemon owns the D-Bus name '{DBUS_NAME}'"
+        ))
+    );
+    daemon.wait_for_key_image(0, "red.png");
+
+    client
+        .emit_signal(
+            None,
+            "/org/freedesktop/ScreenSaver",
+            "org.freedesktop.ScreenSaver",
+            "ActiveChanged",
+            Some(&(true,).to_variant()),
+        )
+        .expect("lock signal should be sent");
+    client
+        .flush_sync(gio::Cancellable::NONE)
+        .expect("lock signal should be flushed");
+    wait_until("deck to turn off", || {
+        daemon.deck.brightness_levels().last() == Some(&0)
+    });
+}
+
+const FAKE_LOGIND_XML: &str = r#"<node>
+  <interface name="org.freedesktop.login1.Manager">
+    <method name="GetSession">
+      <arg name="session_id" type="s" direction="in"/>
+      <arg name="object_path" type="o" direction="out"/>
+    </method>
+  </interface>
+  <interface name="org.freedesktop.login1.User">
+    <property name="Display" type="(so)" access="read"/>
+  </interface>
+  <interface name="org.freedesktop.login1.Session">
+    <property name="LockedHint" type="b" access="read"/>
+  </interface>
+</node>"#;
+
+#[test]
+fn event_loop_follows_the_graphical_logind_session_of_a_user_service() {
+    let Some(bus) = PrivateBus::start() else {
+        eprintln!("dbus-daemon is not available; skipping logind test");
+        return;
+    };
+    let context = glib::MainContext::new();
+    context
+        .with_thread_default(|| {
+            let logind = bus.connect();
+            let node =
+                gio::DBusNodeInfo::for_xml(FAKE_LOGIND_XML).expect("logind XML should parse");
+            let interface = |name: &str| {
+                node.lookup_interface(name)
+                    .expect("logind XML should declare the interface")
+            };
+            let session_path = "/org/freedesktop/login1/session/c2";
+            // Like a user service, the caller has no session of its own.
+            let _manager = logind
+                .register_object(
+                    "/org/freedesktop/login1",
+                    &interface("org.freedesktop.login1.Manager"),
+                )
+                .method_call(|_, _, _, _, _, _, invocation| {
+                    invocation.return_dbus_error(
+                        "org.freedesktop.login1.NoSessionForPID",
+                        "Caller does not belong to any known session",
+                    );
+                })
+                .build()
+                .expect("fake logind manager should register");
+            let _user = logind
+                .register_object(
+                    "/org/freedesktop/login1/user/self",
+                    &interface("org.freedesktop.login1.User"),
+                )
+                .property(move |_, _, _, _, _| {
+                    (
+                        "c2",
+                        glib::variant::ObjectPath::try_from(session_path.to_string())
+                            .expect("session path should be valid"),
+                    )
+                        .to_variant()
+                })
+                .build()
+                .expect("fake logind user should register");
+            let _session = logind
+                .register_object(session_path, &interface("org.freedesktop.login1.Session"))
+                .property(|_, _, _, _, _| true.to_variant())
+                .build()
+                .expect("fake logind session should register");
+            logind
+                .call_sync(
+                    Some("org.freedesktop.DBus"),
+                    "/org/freedesktop/DBus",
+                    "org.freedesktop.DBus",
+                    "RequestName",
+                    Some(&("org.freedesktop.login1", 4u32).to_variant()),
+                    None,
+                    gio::DBusCallFlags::NONE,
+                    -1,
+                    gio::Cancellable::NONE,
+                )
+                .expect("logind name should be taken");
+
+            let mut daemon = RunningDaemon::start(
+                "event-loop-logind",
+                &format!(
+                    "brightness = 40\nidle_off_minutes = 30\n{}",
+                    keys_toml(&["red.png"])
+                ),
+            );
+            // The properties are served from this thread while the daemon starts.
+            let (served_tx, served_rx) = mpsc::channel();
+            let address = bus.address.clone();
+            let events = daemon
+                .events
+                .take()
+                .expect("D-Bus is served once per daemon");
+            let loop_tx = daemon.loop_tx.clone();
+            thread::spawn(move || {
+                let _ = served_tx.send(serve_dbus(
+                    Some(address.clone()),
+                    Some(address),
+                    loop_tx,
+                    events,
+                ));
+            });
+            wait_until("the D-Bus service to start", || {
+                while context.iteration(false) {}
+                served_rx.try_recv().is_ok()
+            });
+            wait_until("a deck started while locked to turn off", || {
+                while context.iteration(false) {}
+                daemon.deck.brightness_levels().last() == Some(&0)
+            });
+
+            let changed = glib::VariantDict::new(None);
+            changed.insert_value("LockedHint", &false.to_variant());
+            logind
+                .emit_signal(
+                    None,
+                    session_path,
+                    "org.freedesktop.DBus.Properties",
+                    "PropertiesChanged",
+                    Some(&glib::Variant::tuple_from_iter([
+                        "org.freedesktop.login1.Session".to_variant(),
+                        changed.end(),
+                        Vec::<String>::new().to_variant(),
+                    ])),
+                )
+                .expect("unlock signal should be sent");
+            wait_until("deck to wake on unlock", || {
+                while context.iteration(false) {}
+                daemon.deck.brightness_levels().last() == Some(&40)
+            });
+        })
+        .expect("test main context should be acquirable");
+}
+
+#[test]
+fn event_loop_follows_auto_profile_rules_for_the_focused_app() {
+    let desk = MockDeck::with_serial(&STREAMDECK_MK2, "DESK");
+    let side = MockDeck::with_serial(&STREAMDECK_MINI, "SIDE");
+    let config = format!(
+        "[devices]\nSIDE = \"side\"\n{}[[auto_profile]]\napp_id = \"org.gimp.GIMP\"\nprofile = \"gimp\"\n",
+        keys_toml(&["red.png", "blue.png"])
+    );
+    let side_config = format!("product_id = 0x0063\n{}", keys_toml(&["green.png"]));
+    let daemon = RunningDaemon::start_with_decks(
+        "event-loop-auto-profile",
+        &config,
+        &[
+            ("gimp", &keys_toml(&["green.png", "blue.png"])),
+            ("side", &side_config),
+        ],
+        vec![desk.clone(), side.clone()],
+    );
+    daemon.wait_for_deck_image(&desk, 0, "red.png");
+    daemon.wait_for_deck_image(&side, 0, "green.png");
+    let side_brightness = side.brightness_levels().len();
+    let focus = |app_id: Option<&str>| {
+        assert!(
+            daemon
+                .control(ControlRequest::FocusChanged {
+                    app_id: app_id.map(str::to_string),
+                })
+                .ok
+        );
+    };
+    let profile = || daemon.control(ControlRequest::State).state.unwrap().profile;
+
+    focus(Some("ORG.GIMP.GIMP"));
+    daemon.wait_for_deck_image(&desk, 0, "green.png");
+    assert_eq!(profile(), "gimp");
+
+    // The rules of the base profile stay active while the other profile runs.
+    focus(Some("firefox"));
+    daemon.wait_for_deck_image(&desk, 0, "red.png");
+    assert_eq!(profile(), "test");
+    focus(Some("org.gimp.GIMP"));
+    daemon.wait_for_deck_image(&desk, 0, "green.png");
+    focus(None);
+    daemon.wait_for_deck_image(&desk, 0, "red.png");
+
+    // Only the changed key was redrawn, and the deck mapped by the base
+    // profile kept its session.
+    sleep(DEVICE_PROBE_INTERVAL * 2);
+    assert_eq!(desk.key_images(1).len(), 1);
+    assert_eq!(side.key_images(0).len(), 1);
+    assert_eq!(side.brightness_levels().len(), side_brightness);
+}
+
+#[test]
+fn event_loop_runs_page_builtins_without_reserved_navigation_keys() {
+    let mut config = "manual_page_navigation = true\n".to_string();
+    for index in 0..31 {
+        let (icon, builtin) = match index {
+            0 => ("red.png", "page:last"),
+            15 => ("green.png", "page:back"),
+            30 => ("blue.png", "page:back"),
+            _ => ("blank.png", ""),
+        };
+        config.push_str(&format!(
+            "[[keys]]\nicon = \"{icon}\"\nbuiltin = \"{builtin}\"\n"
+        ));
+    }
+    let daemon = RunningDaemon::start("event-loop-page-builtins", &config);
+    daemon.wait_for_key_image(0, "red.png");
+    // The last slot holds a key instead of a next page key.
+    assert_eq!(
+        daemon.deck.last_key_image(14),
+        daemon.deck.last_key_image(1)
+    );
+
+    daemon.deck.press_key(0);
+    daemon.wait_for_key_image(0, "blue.png");
+    assert!(
+        daemon
+            .control(ControlRequest::Page {
+                page: "2".to_string(),
+                serial: None,
+            })
+            .ok
+    );
+    daemon.wait_for_key_image(0, "green.png");
+
+    // Back walks the history: page 2 is left for page 3, then page 3 for page 1.
+    daemon.deck.press_key(0);
+    daemon.wait_for_key_image(0, "blue.png");
+    daemon.deck.press_key(0);
+    daemon.wait_for_key_image(0, "red.png");
+}
+
+#[test]
+fn event_loop_changes_and_remembers_brightness_from_keys() {
+    let config = |brightness: usize| {
+        format!(
+            "brightness = {brightness}\n[[keys]]\nicon = \"red.png\"\nbuiltin = \"brightness_up\"\nbrightness_step = 20\n[[keys]]\nicon = \"brightness.svg\"\n"
+        )
+    };
+    let daemon = RunningDaemon::start("event-loop-brightness", &config(40));
+    let state_path = daemon.config_path.with_file_name("state.toml");
+    let serial = daemon.deck.serial().to_string();
+    let gauge =
+        |level| render_brightness_icon(level, daemon.deck.model()).expect("gauge should render");
+    wait_until("gauge to show the config brightness", || {
+        daemon.deck.last_key_image(1) == Some(gauge(40))
+    });
+
+    daemon.deck.press_key(0);
+    wait_until("brightness to go up", || {
+        daemon.deck.brightness_levels().last() == Some(&60)
+            && daemon.deck.last_key_image(1) == Some(gauge(60))
+    });
+    assert_eq!(load_deck_brightness(&state_path, &serial), Ok(Some(60)));
+
+    // The chosen level survives reloads until the config's brightness changes.
+    assert!(daemon.control(ControlRequest::Reload).ok);
+    assert_eq!(daemon.deck.brightness_levels().last(), Some(&60));
+    fs::write(&daemon.config_path, config(30)).expect("config should be updated");
+    assert!(daemon.control(ControlRequest::Reload).ok);
+    assert_eq!(daemon.deck.brightness_levels().last(), Some(&30));
+    assert_eq!(daemon.deck.last_key_image(1), Some(gauge(30)));
+    assert_eq!(load_deck_brightness(&state_path, &serial), Ok(None));
+}
+
+#[test]
+fn event_loop_reloads_when_watched_files_change() {
+    let daemon = RunningDaemon::start("event-loop-watch", &keys_toml(&["red.png", "green.png"]));
+    daemon.wait_for_key_image(0, "red.png");
+    let config_dir = daemon
+        .config_path
+        .parent()
+        .expect("test config should have a parent")
+        .to_path_buf();
+    watch::watch_files(
+        &[config_dir.clone(), daemon.image_dir.clone()],
+        daemon.loop_tx.clone(),
+    )
+    .expect("test dirs should be watchable");
+
+    // Saved the way editors do it: a temporary file renamed over the config.
+    let temp = config_dir.join(".test.toml.swp");
+    fs::write(&temp, keys_toml(&["blue.png", "green.png"])).expect("temp config should be written");
+    fs::rename(&temp, &daemon.config_path).expect("config should be replaced");
+    daemon.wait_for_key_image(0, "blue.png");
+
+    write_test_png(&daemon.image_dir.join("green.png"), [255, 255, 255, 255]);
+    daemon.wait_for_key_image(1, "green.png");
+}
@@ -0,0 +1,144 @@
+use super::LoopEvent;
+use std::env;
+use std::sync::mpsc;
+use std::thread;
+use streamrs::config::streamrs_schema::StreamrsAutoProfile as AutoProfile;
+use x11rb::connection::Connection;
+use x11rb::properties::WmClass;
+use x11rb::protocol::Event;
+use x11rb::protocol::xproto::{
+    Atom, AtomEnum, ChangeWindowAttributesAux, ConnectionExt, EventMask, Window,
+};
+
+// Names the focused window: a Wayland app_id, or the instance and class of an
+// X11 window's WM_CLASS. Empty while nothing has focus.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct FocusedApp {
+    pub(crate) app_ids: Vec<String>,
+}
+
+impl FocusedApp {
+    pub(crate) fn from_app_id(app_id: Option<&str>) -> Self {
+        let app_ids = app_id
+            .map(str::trim)
+            .filter(|app_id| !app_id.is_empty())
+            .map(str::to_string)
+            .into_iter()
+            .collect();
+        Self { app_ids }
+    }
+}
+
+// A source of focus changes inside the daemon. Compositors without one, such
+// as Sway, Hyprland or a GNOME Shell extension, report focus through the
+// control socket's `focus_changed` request instead.
+pub(crate) trait FocusProvider {
+    fn name(&self) -> &'static str;
+
+    // Reports the focused app right away and then after every change.
+    fn start(self: Box<Self>, sender: mpsc::Sender<LoopEvent>) -> Result<(), String>;
+}
+
+pub(crate) fn default_focus_providers() -> Vec<Box<dyn FocusProvider>> {
+    // Under Wayland `_NET_ACTIVE_WINDOW` only follows XWayland windows.
+    if env::var_os("DISPLAY").is_none() || env::var_os("WAYLAND_DISPLAY").is_some() {
+        return Vec::new();
+    }
+    vec![Box::new(X11FocusProvider)]
+}
+
+// The profile of the first rule naming the focused app.
+pub(crate) fn auto_profile_for(rules: &[AutoProfile], focused: &FocusedApp) -> Option<String> {
+    rules
+        .iter()
+        .find(|rule| {
+            focused
+                .app_ids
+                .iter()
+                .any(|app_id| app_id.eq_ignore_ascii_case(rule.app_id.trim()))
+        })
+        .map(|rule| rule.profile.clone())
+}
+
+// Follows `_NET_ACTIVE_WINDOW` on the root window of the default screen.
+pub(crate) struct X11FocusProvider;
+
+impl FocusProvider for X11FocusProvider {
+    fn name(&self) -> &'static str {
+        "X11"
+    }
+
+    fn start(self: Box<Self>, sender: mpsc::Sender<LoopEvent>) -> Result<(), String> {
+        let (conn, screen) = x11rb::connect(None)
+            .map_err(|err| format!("Failed to connect to the X server: {err}"))?;
+        let root = conn.setup().roots[screen].root;
+        let active_window = conn
+            .intern_atom(false, b"_NET_ACTIVE_WINDOW")
+            .map_err(|err| err.to_string())
+            .and_then(|cookie| cookie.reply().map_err(|err| err.to_string()))
+            .map_err(|err| format!("Failed to look up _NET_ACTIVE_WINDOW: {err}"))?
+            .atom;
+        conn.change_window_attributes(
+            root,
+            &ChangeWindowAttributesAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+        )
+        .map_err(|err| err.to_string())
+        .and_then(|cookie| cookie.check().map_err(|err| err.to_string()))
+        .map_err(|err| format!("Failed to watch the active X11 window: {err}"))?;
+
+        thread::spawn(move || {
+            let mut reported = None;
+            loop {
+                let focused = focused_x11_app(&conn, root, active_window);
+                if reported.as_ref() != Some(&focused) {
+                    if sender
+                        .send(LoopEvent::FocusChanged(focused.clone()))
+                        .is_err()
+                    {
+                        return;
+                    }
+                    reported = Some(focused);
+                }
+                loop {
+                    match conn.wait_for_event() {
+                        Ok(Event::PropertyNotify(event)) if event.atom == active_window => break,
+                        Ok(_) => {}
+                        Err(err) => {
+                            eprintln!("X11 focus tracking stopped: {err}");
+                            return;
+                        }
+                    }
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+// Windows can close between the notification and these requests, so errors
+// read as nothing focused.
+fn focused_x11_app(conn: &impl Connection, root: Window, active_window: Atom) -> FocusedApp {
+    let window = conn
+        .get_property(false, root, active_window, AtomEnum::WINDOW, 0, 1)
+        .ok()
+        .and_then(|cookie| cookie.reply().ok())
+        .and_then(|reply| reply.value32().and_then(|mut values| values.next()))
+        .filter(|&window| window != 0);
+    let Some(window) = window else {
+        return FocusedApp::default();
+    };
+    let Some(class) = WmClass::get(conn, window)
+        .ok()
+        .and_then(|cookie| cookie.reply().ok().flatten())
+    else {
+        return FocusedApp::default();
+    };
+    let mut app_ids = Vec::new();
+    for name in [class.instance(), class.class()] {
+        let name = String::from_utf8_lossy(name).into_owned();
+        if !name.is_empty() && !app_ids.contains(&name) {
+            app_ids.push(name);
+        }
+    }
+    FocusedApp { app_ids }
+}
@@ -1,17 +1,28 @@
 #[cfg(test)]
 use chrono::FixedOffset;
 use chrono::{Duration as ChronoDuration, Local, LocalResult, TimeZone};
-use hidapi::HidDevice;
 #[cfg(test)]
 use image::codecs::gif::GifDecoder;
 #[cfg(test)]
 use image::{AnimationDecoder, Frame as ImageFrame, RgbaImage};
-use std::cmp::min;
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::cmp::{Reverse, min};
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
 use std::env;
-use std::path::Path;
+use std::hash::{DefaultHasher, Hash, Hasher};
+#[cfg(unix)]
+use std::io::{ErrorKind, Read};
+use std::iter;
+use std::mem;
+#[cfg(unix)]
+use std::os::fd::IntoRawFd;
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+#[cfg(unix)]
+use std::sync::atomic::AtomicI32;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc;
+use std::sync::{Arc, mpsc};
 use std::thread;
 use std::thread::sleep;
 use std::time::{Duration, Instant};
@@ -20,41 +31,65 @@ use streamrs::image::calendar::CALENDAR_ICON_ALIAS;
 #[cfg(test)]
 use streamrs::image::clock::{
     CLOCK_BACKGROUND_ICON, CLOCK_FALLBACK_BACKGROUND_COLOR, CLOCK_ICON_ALIAS,
+    render_clock_segments_svg,
 };
 
 #[path = "../config/streamrs.rs"]
 mod config;
+#[path = "control.rs"]
+mod control_socket;
+#[path = "dbus.rs"]
+mod dbus;
+#[path = "focus.rs"]
+mod focus;
 #[path = "../init/streamrs.rs"]
 mod init;
 #[cfg(test)]
 #[path = "tests.rs"]
 mod main_tests;
+#[path = "media.rs"]
+mod media;
+#[path = "status_pool.rs"]
+mod status_pool;
 #[path = "../image/streamrs.rs"]
 mod stream_image;
+#[cfg(target_os = "linux")]
+#[path = "watch.rs"]
+mod watch;
 
 #[cfg(test)]
 use config::key_launch_action;
 #[cfg(test)]
 use config::parse_config;
 use config::{
-    ConfiguredAction, is_launcher_like_command, key_clock_background, key_configured_action,
-    key_status_command, key_status_icon_off, key_status_icon_on, key_status_interval, load_config,
-    read_config_file,
+    BrightnessChange, BuiltinAction, ConfiguredAction, KeyMacro, LiveFaces, MacroStep,
+    MediaCommand, PageTarget, StatusMode, idle_dim_brightness, idle_thresholds,
+    is_launcher_like_command, key_configured_action, key_double_press_action, key_event_thresholds,
+    key_live_faces, key_long_press_action, key_status_command, key_status_icon_off,
+    key_status_icon_on, key_status_interval, key_status_mode, key_timer, key_timer_command,
+    key_title, load_config, parse_page_target, read_config_file,
 };
+use control_socket::{ControlMessage, bind_control_socket, serve_control_socket};
+use dbus::{DBUS_NAME, DaemonEvent, serve_dbus};
+use focus::{FocusedApp, auto_profile_for, default_focus_providers};
 use init::{
     default_config_path, default_image_dir, ensure_profile_initialized, initialize_profile,
     parse_args, print_post_init_service_hint, print_usage,
 };
+use media::NowPlaying;
+use status_pool::{STATUS_WORKERS, StatusCheck, StatusPool};
 use stream_image::{
-    blank_image_data, build_image_cache, current_calendar_key, current_clock_text,
-    load_key_image_cached, render_calendar_icon, render_clock_svg,
+    blank_image_data, build_image_cache, current_calendar_key, load_key_image_cached,
+    render_brightness_icon, render_calendar_icon, render_clock_svg, render_metric_icon,
+    render_now_playing_icon, render_status_face, render_timer_icon,
 };
 #[cfg(test)]
 use stream_image::{
-    delay_to_duration_ms, encode_animated_frames, get_image_data, load_animated_gif,
-    load_key_image, render_clock_segments_svg,
+    delay_to_duration_ms, encode_animated_frames, get_image_data, load_animated_gif, load_key_image,
+};
+use streamrs::config::current_profile::{
+    BLANK_PROFILE, discover_profiles, load_current_profile, save_current_profile,
 };
-use streamrs::config::current_profile::{BLANK_PROFILE, discover_profiles, load_current_profile};
 use streamrs::config::streamrs_schema::{
     StreamrsConfig as Config, StreamrsKeyBinding as KeyBinding, blank_profile_config,
 };
@@ -65,39 +100,194 @@ use streamrs::config::streamrs_schema::{
     default_product_id as schema_default_product_id, default_usage as schema_default_usage,
     default_usage_page as schema_default_usage_page, default_vendor_id as schema_default_vendor_id,
 };
+use streamrs::control::{ControlRequest, ControlResponse, DaemonState, DeckState};
+use streamrs::idle::{IdleState, IdleTracker};
+use streamrs::image::calendar::CalendarFace;
+use streamrs::image::clock::ClockFace;
+use streamrs::image::media::is_now_playing_icon;
+use streamrs::image::metric::MetricFace;
+use streamrs::image::overlay::KeyTitle;
+use streamrs::image::timer::{KeyTimer, TimerFace, TimerKind};
+use streamrs::key_events::{KeyEvent, KeyEventTracker};
+use streamrs::metrics::{MetricSample, MetricSampler};
 use streamrs::paging::PagingLayout;
-use streamrs::process::{run_shell_status, send_keyboard_shortcut, wait_argv_command_success};
-use streamrs::streamdeck::{get_device, read_states, set_brightness, set_key_image_data};
+use streamrs::paths::{control_socket_path, current_profile_path, streamrs_state_path};
+use streamrs::process::{
+    STATUS_CHECK_TIMEOUT, launch_argv_command, load_deck_brightness, run_shell_status,
+    run_shell_status_output, save_deck_brightness, send_keyboard_shortcut, type_text,
+    wait_argv_command_success,
+};
+use streamrs::status::StatusOutput;
+use streamrs::streamdeck::{
+    DeckTransport, DeviceModel, get_devices, read_key_states, set_brightness, set_key_image_data,
+};
 
-const KEY_COUNT: usize = streamrs::paging::STREAMDECK_KEY_COUNT;
-const MAX_KEYS_PER_PAGE: usize = KEY_COUNT;
+const MAX_KEYS_PER_PAGE: usize = streamrs::paging::MAX_KEYS_PER_PAGE;
 const MIN_KEYS_PER_PAGE: usize = streamrs::paging::MIN_KEYS_PER_PAGE;
 const NEXT_PAGE_ICON: &str = "stream-deck-next-page.png";
 const PREVIOUS_PAGE_ICON: &str = "stream-deck-previous-page.png";
+const FOLDER_BACK_ICON: &str = "stream-deck-folder-back.png";
 const SVG_RENDER_SIZE: u32 = 256;
 const MIN_GIF_FRAME_DELAY_MS: u64 = 66;
 const DEFAULT_STATUS_CHECK_INTERVAL_SECONDS: u64 = 1;
 const MIN_STATUS_CHECK_INTERVAL_SECONDS: u64 = 1;
 const MAX_STATUS_CHECK_INTERVAL_SECONDS: u64 = 60;
+const MIN_LONG_PRESS_MS: u64 = 100;
+const MAX_LONG_PRESS_MS: u64 = 5000;
+const MIN_DOUBLE_PRESS_MS: u64 = 50;
+const MAX_DOUBLE_PRESS_MS: u64 = 1000;
+const MAX_MACRO_DELAY_MS: u64 = 60_000;
 const POST_ACTION_STATUS_CHECK_DELAY: Duration = Duration::from_secs(1);
 const RELOAD_RETRY_INTERVAL: Duration = Duration::from_secs(10);
+const DEVICE_PROBE_INTERVAL: Duration = Duration::from_millis(500);
+const MAX_DEVICE_PROBE_INTERVAL: Duration = Duration::from_secs(30);
+const DEVICE_HOTPLUG_DEBOUNCE: Duration = Duration::from_millis(200);
+// Editors and the GUI save in several steps; one reload follows the last of them.
+const FILE_CHANGE_DEBOUNCE: Duration = Duration::from_millis(200);
+// Only bounds how long a key reader outlives its session; reports arrive immediately.
+const KEY_READ_TIMEOUT_MS: i32 = 1000;
+const DEFAULT_KEY_IMAGE_OVERRIDE_SECONDS: u64 = 5;
+const DEFAULT_BRIGHTNESS_STEP: usize = 10;
+const DEFAULT_BRIGHTNESS_LEVELS: [usize; 4] = [10, 40, 70, 100];
+const MAX_KEY_IMAGE_OVERRIDE_SECONDS: u64 = 3600;
+const MAX_PAGE_HISTORY: usize = 32;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum DeckRequest {
+    RefreshStatus(usize),
+    Page(PageTarget),
+}
+
+enum LoopEvent {
+    Deck(String, DeckRequest),
+    KeyStates(String, Result<u32, String>),
+    Control(ControlMessage),
+    FileChanged(PathBuf),
+    DevicesChanged,
+    SessionLocked(bool),
+    SwitchProfile(String),
+    FocusChanged(FocusedApp),
+    StatusChecked(StatusCheck, Result<StatusReading, String>),
+    DiskRead(PathBuf, Result<f32, String>),
+    MediaChanged(Option<NowPlaying>),
+    Reload,
+    #[cfg(test)]
+    Shutdown,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Timer {
+    DeviceProbe,
+    ReloadCheck,
+    FileChanges,
+    Deck(String),
+}
+
+// Deadlines are replaced rather than removed from the heap; entries that no
+// longer match `deadlines` are skipped when they reach the top.
+#[derive(Default)]
+struct Scheduler {
+    heap: BinaryHeap<Reverse<(Instant, Timer)>>,
+    deadlines: HashMap<Timer, Instant>,
+}
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 enum ButtonAction {
     Launch(String),
     KeyboardShortcut(String),
+    Text(String),
+    Macro(KeyMacro),
     PreviousPage,
     NextPage,
+    OpenFolder(usize),
+    FolderBack,
+    Builtin(BuiltinAction),
+    ToggleTimer(TimerId),
+    ResetTimer(TimerId),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct TimerId {
+    folder: Vec<usize>,
+    index: usize,
+    kind: TimerKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum StatusReading {
+    Exit(bool),
+    Output(String),
+}
+
+impl StatusReading {
+    fn mode(&self) -> StatusMode {
+        match self {
+            Self::Exit(_) => StatusMode::Exit,
+            Self::Output(_) => StatusMode::Output,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum StatusIcons {
+    Exit {
+        icon_on: String,
+        icon_off: String,
+    },
+    Output {
+        icon: String,
+        state_icons: BTreeMap<String, String>,
+    },
+}
+
+impl StatusIcons {
+    fn mode(&self) -> StatusMode {
+        match self {
+            Self::Exit { .. } => StatusMode::Exit,
+            Self::Output { .. } => StatusMode::Output,
+        }
+    }
+
+    fn names(&self) -> Vec<&str> {
+        match self {
+            Self::Exit { icon_on, icon_off } => vec![icon_on, icon_off],
+            Self::Output { icon, state_icons } => std::iter::once(icon)
+                .chain(state_icons.values())
+                .map(String::as_str)
+                .collect(),
+        }
+    }
+
+    fn face(&self, reading: Option<&StatusReading>) -> StatusOutput {
+        match (self, reading) {
+            (Self::Exit { icon_on, .. }, Some(StatusReading::Exit(true))) => StatusOutput {
+                icon: Some(icon_on.clone()),
+                ..StatusOutput::default()
+            },
+            (Self::Exit { icon_off, .. }, _) => StatusOutput {
+                icon: Some(icon_off.clone()),
+                ..StatusOutput::default()
+            },
+            (Self::Output { icon, state_icons }, reading) => {
+                let mut face = match reading {
+                    Some(StatusReading::Output(stdout)) => StatusOutput::parse(stdout, state_icons),
+                    _ => StatusOutput::default(),
+                };
+                face.icon.get_or_insert_with(|| icon.clone());
+                face
+            }
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 struct PlannedStatusKey {
     command: String,
-    icon_on: String,
-    icon_off: String,
-    clock_background: Option<String>,
+    icons: StatusIcons,
+    faces: LiveFaces,
+    title: Option<KeyTitle>,
     check_interval: Duration,
-    current_on: Option<bool>,
+    current: Option<StatusReading>,
     poll_now: bool,
 }
 
@@ -111,9 +301,13 @@ enum PagePlanWarning {
 struct PageLayoutPlan {
     page: usize,
     total_pages: usize,
-    icons: [Option<(String, Option<String>)>; KEY_COUNT],
-    button_actions: [Option<ButtonAction>; KEY_COUNT],
-    status_slots: [Option<PlannedStatusKey>; KEY_COUNT],
+    icons: Vec<Option<(String, LiveFaces)>>,
+    titles: Vec<Option<KeyTitle>>,
+    button_actions: Vec<Option<ButtonAction>>,
+    long_press_actions: Vec<Option<ButtonAction>>,
+    double_press_actions: Vec<Option<ButtonAction>>,
+    status_slots: Vec<Option<PlannedStatusKey>>,
+    timer_slots: Vec<Option<TimerId>>,
     warnings: Vec<PagePlanWarning>,
 }
 
@@ -126,35 +320,1032 @@ struct AnimatedKeyState {
 
 struct ClockKeyState {
     current_text: String,
-    background_name: Option<String>,
+    face: ClockFace,
+    title: Option<KeyTitle>,
     next_update_at: Instant,
 }
 
 struct CalendarKeyState {
     current_key: String,
+    face: CalendarFace,
+    title: Option<KeyTitle>,
     next_update_at: Instant,
 }
 
+struct MetricKeyState {
+    sampler: MetricSampler,
+    face: MetricFace,
+    title: Option<KeyTitle>,
+    next_update_at: Instant,
+    last_error: Option<String>,
+}
+
+struct TimerKeyState {
+    id: TimerId,
+    title: Option<KeyTitle>,
+    shown: Option<TimerFace>,
+    next_update_at: Option<Instant>,
+}
+
 struct StatusKeyState {
     command: String,
-    icon_on: String,
-    icon_off: String,
-    clock_background: Option<String>,
+    icons: StatusIcons,
+    faces: LiveFaces,
+    title: Option<KeyTitle>,
     check_interval: Duration,
     next_check_at: Instant,
-    current_on: Option<bool>,
+    current: Option<StatusReading>,
 }
 
 enum DynamicKeyState {
     Animated(AnimatedKeyState),
     Clock(ClockKeyState),
     Calendar(CalendarKeyState),
+    Metric(MetricKeyState),
+    Brightness,
+    Timer(TimerKeyState),
+    NowPlaying { title: Option<KeyTitle> },
 }
 
 struct PageState {
-    button_actions: [Option<ButtonAction>; KEY_COUNT],
-    dynamic_states: [Option<DynamicKeyState>; KEY_COUNT],
-    status_states: [Option<StatusKeyState>; KEY_COUNT],
+    button_actions: Vec<Option<ButtonAction>>,
+    long_press_actions: Vec<Option<ButtonAction>>,
+    double_press_actions: Vec<Option<ButtonAction>>,
+    dynamic_states: Vec<Option<DynamicKeyState>>,
+    status_states: Vec<Option<StatusKeyState>>,
+}
+
+struct ConnectedDeck {
+    device: Box<dyn DeckTransport>,
+    input: Option<Box<dyn DeckTransport + Send>>,
+    model: &'static DeviceModel,
+    serial: String,
+    shown_images: RefCell<Vec<Option<u64>>>,
+}
+
+impl ConnectedDeck {
+    fn new(
+        device: Box<dyn DeckTransport>,
+        input: Box<dyn DeckTransport + Send>,
+        model: &'static DeviceModel,
+        serial: String,
+    ) -> Self {
+        Self {
+            device,
+            input: Some(input),
+            model,
+            serial,
+            shown_images: RefCell::new(vec![None; model.key_count]),
+        }
+    }
+
+    fn set_key_image(&self, key_index: usize, data: &[u8]) -> Result<(), String> {
+        let mut hasher = DefaultHasher::new();
+        data.hash(&mut hasher);
+        let hash = hasher.finish();
+        let mut shown_images = self.shown_images.borrow_mut();
+        let Some(shown) = shown_images.get_mut(key_index) else {
+            return set_key_image_data(self.device.as_ref(), self.model, key_index as u8, data);
+        };
+        if *shown == Some(hash) {
+            return Ok(());
+        }
+        *shown = None;
+        set_key_image_data(self.device.as_ref(), self.model, key_index as u8, data)?;
+        *shown = Some(hash);
+        Ok(())
+    }
+
+    fn set_brightness(&self, brightness: usize) -> Result<(), String> {
+        set_brightness(self.device.as_ref(), self.model, brightness.clamp(0, 100))
+    }
+}
+
+struct DeckSession {
+    deck: ConnectedDeck,
+    profile: Option<String>,
+    image_cache: ImageCache,
+    status_cache: StatusCache,
+    blank_image: Vec<u8>,
+    current_page: usize,
+    folder_stack: Vec<(usize, usize)>,
+    page_history: Vec<(Vec<usize>, usize)>,
+    brightness: usize,
+    // The config's `brightness` as last applied, and the level picked with
+    // brightness keys, which is kept in `state_path` and wins over the config.
+    config_brightness: usize,
+    chosen_brightness: Option<usize>,
+    state_path: PathBuf,
+    brightness_gauge: Option<(usize, Vec<u8>)>,
+    timers: HashMap<TimerId, KeyTimer>,
+    now_playing: Option<NowPlaying>,
+    image_overrides: Vec<Option<Instant>>,
+    page_state: PageState,
+    key_events: KeyEventTracker,
+    pending_taps: Vec<Option<Instant>>,
+    consumed_keys: u32,
+    idle: IdleTracker,
+    idle_state: IdleState,
+    idle_dim_brightness: usize,
+    idle_blank_keys: bool,
+    keys_blanked: bool,
+    // Keys whose press woke the deck; their events are dropped until release.
+    woken_keys: u32,
+    events: mpsc::Sender<DaemonEvent>,
+    announced_page: Option<(Vec<usize>, usize)>,
+    stop_key_reader: Arc<AtomicBool>,
+}
+
+struct MappedProfile {
+    config_path: PathBuf,
+    config: Config,
+    config_raw: String,
+}
+
+struct DaemonSession {
+    profile: String,
+    profile_locked: bool,
+    config_path: PathBuf,
+    image_dir: PathBuf,
+    config: Config,
+    config_raw: String,
+    debug: bool,
+    files_watched: bool,
+    state_path: PathBuf,
+}
+
+impl PageState {
+    fn empty(key_count: usize) -> Self {
+        Self {
+            button_actions: vec![None; key_count],
+            long_press_actions: vec![None; key_count],
+            double_press_actions: vec![None; key_count],
+            dynamic_states: (0..key_count).map(|_| None).collect(),
+            status_states: (0..key_count).map(|_| None).collect(),
+        }
+    }
+}
+
+impl Scheduler {
+    fn schedule(&mut self, timer: Timer, deadline: Option<Instant>) {
+        match deadline {
+            Some(deadline) => {
+                if self.deadlines.insert(timer.clone(), deadline) != Some(deadline) {
+                    self.heap.push(Reverse((deadline, timer)));
+                }
+            }
+            None => {
+                self.deadlines.remove(&timer);
+            }
+        }
+    }
+
+    fn is_scheduled(&self, timer: &Timer) -> bool {
+        self.deadlines.contains_key(timer)
+    }
+
+    fn next_deadline(&mut self) -> Option<Instant> {
+        while let Some(Reverse((deadline, timer))) = self.heap.peek() {
+            if self.deadlines.get(timer) == Some(deadline) {
+                return Some(*deadline);
+            }
+            self.heap.pop();
+        }
+        None
+    }
+
+    fn take_due(&mut self, now: Instant) -> Vec<Timer> {
+        let mut due = Vec::new();
+        while self.next_deadline().is_some_and(|deadline| deadline <= now) {
+            if let Some(Reverse((_, timer))) = self.heap.pop() {
+                self.deadlines.remove(&timer);
+                due.push(timer);
+            }
+        }
+        due
+    }
+}
+
+fn spawn_key_reader(
+    input: Box<dyn DeckTransport + Send>,
+    model: &'static DeviceModel,
+    serial: String,
+    sender: mpsc::Sender<LoopEvent>,
+    stop: Arc<AtomicBool>,
+) {
+    thread::spawn(move || {
+        while !stop.load(Ordering::Relaxed) {
+            let result = match read_key_states(input.as_ref(), model, KEY_READ_TIMEOUT_MS) {
+                Ok(Some(pressed)) => Ok(pressed),
+                Ok(None) => continue,
+                Err(err) => Err(err),
+            };
+            let failed = result.is_err();
+            if stop.load(Ordering::Relaxed)
+                || sender
+                    .send(LoopEvent::KeyStates(serial.clone(), result))
+                    .is_err()
+                || failed
+            {
+                return;
+            }
+        }
+    });
+}
+
+impl DeckSession {
+    #[allow(clippy::too_many_arguments)]
+    fn open(
+        mut deck: ConnectedDeck,
+        profile: Option<String>,
+        config: &Config,
+        image_dir: &Path,
+        page: usize,
+        state_path: &Path,
+        loop_tx: &mpsc::Sender<LoopEvent>,
+        events: mpsc::Sender<DaemonEvent>,
+    ) -> Result<Self, String> {
+        let blank_image = blank_image_data(deck.model)?;
+        let stop_key_reader = Arc::new(AtomicBool::new(false));
+        if let Some(input) = deck.input.take() {
+            spawn_key_reader(
+                input,
+                deck.model,
+                deck.serial.clone(),
+                loop_tx.clone(),
+                Arc::clone(&stop_key_reader),
+            );
+        }
+        let _ = events.send(DaemonEvent::DeviceConnected {
+            serial: deck.serial.clone(),
+            model: deck.model.name.to_string(),
+        });
+        let chosen_brightness =
+            load_deck_brightness(state_path, &deck.serial).unwrap_or_else(|err| {
+                eprintln!("{err}");
+                None
+            });
+        let mut session = Self {
+            page_state: PageState::empty(deck.model.key_count),
+            pending_taps: vec![None; deck.model.key_count],
+            image_overrides: vec![None; deck.model.key_count],
+            deck,
+            profile,
+            image_cache: ImageCache::new(),
+            status_cache: StatusCache::new(),
+            blank_image,
+            current_page: page,
+            folder_stack: Vec::new(),
+            page_history: Vec::new(),
+            brightness: config.brightness,
+            config_brightness: config.brightness,
+            chosen_brightness,
+            state_path: state_path.to_path_buf(),
+            brightness_gauge: None,
+            timers: HashMap::new(),
+            now_playing: None,
+            key_events: KeyEventTracker::new(key_event_thresholds(config)),
+            consumed_keys: 0,
+            idle: IdleTracker::new(idle_thresholds(config), Instant::now()),
+            idle_state: IdleState::Awake,
+            idle_dim_brightness: idle_dim_brightness(config),
+            idle_blank_keys: config.idle_blank_keys,
+            keys_blanked: false,
+            woken_keys: 0,
+            events,
+            announced_page: None,
+            stop_key_reader,
+        };
+        session.apply_config(config, image_dir);
+        Ok(session)
+    }
+
+    fn apply_config(&mut self, config: &Config, image_dir: &Path) {
+        self.image_cache = build_image_cache(config, image_dir);
+        self.apply_settings(config, image_dir);
+    }
+
+    // Images stay cached across auto profile switches, which happen on every
+    // change of the focused window.
+    fn switch_profile(&mut self, config: &Config, image_dir: &Path) {
+        self.current_page = 0;
+        self.status_cache.clear();
+        self.apply_settings(config, image_dir);
+    }
+
+    fn apply_settings(&mut self, config: &Config, image_dir: &Path) {
+        self.idle.set_thresholds(idle_thresholds(config));
+        self.idle_dim_brightness = idle_dim_brightness(config);
+        self.idle_blank_keys = config.idle_blank_keys;
+        // Editing `brightness` in the config replaces the level picked on the deck.
+        if config.brightness != self.config_brightness && self.chosen_brightness.take().is_some() {
+            self.save_chosen_brightness();
+        }
+        self.config_brightness = config.brightness;
+        self.set_brightness(self.chosen_brightness.unwrap_or(config.brightness));
+        self.key_events.set_thresholds(key_event_thresholds(config));
+        self.timers.retain(|id, _| {
+            config
+                .folder_keys(&id.folder)
+                .and_then(|keys| keys.get(id.index))
+                .is_some_and(|key| key_timer(key) == Ok(Some(id.kind)))
+        });
+        if config.folder_keys(&self.folder_path()).is_none() {
+            let root_page = self.folder_stack.first().map(|&(_, page)| page);
+            self.folder_stack.clear();
+            self.current_page = root_page.unwrap_or(self.current_page);
+        }
+        let page = min(
+            self.current_page,
+            page_count(config, &self.folder_path()).saturating_sub(1),
+        );
+        self.show_page(config, image_dir, page);
+        self.update_idle(config, image_dir, Instant::now());
+    }
+
+    fn set_brightness(&mut self, brightness: usize) {
+        self.brightness = brightness.min(100);
+        let shown = match self.idle_state {
+            IdleState::Awake => self.brightness,
+            IdleState::Dimmed => self.brightness.min(self.idle_dim_brightness),
+            IdleState::Off => 0,
+        };
+        if let Err(err) = self.deck.set_brightness(shown) {
+            eprintln!("{err}");
+        }
+        self.draw_brightness_keys();
+    }
+
+    fn run_builtin_action(
+        &mut self,
+        action: &BuiltinAction,
+        config: &Config,
+        image_dir: &Path,
+        loop_tx: &mpsc::Sender<LoopEvent>,
+    ) {
+        match action {
+            BuiltinAction::Brightness(change) => self.change_brightness(change),
+            BuiltinAction::Page(target) => self.go_to_page(config, image_dir, *target),
+            BuiltinAction::Profile(profile) => {
+                let _ = loop_tx.send(LoopEvent::SwitchProfile(profile.clone()));
+            }
+            BuiltinAction::Media(command) => match &self.now_playing {
+                Some(now) => {
+                    let _ = self.events.send(DaemonEvent::MediaCommand {
+                        player: now.player.clone(),
+                        command: *command,
+                    });
+                }
+                None => eprintln!("No MPRIS media player is running"),
+            },
+        }
+    }
+
+    fn change_brightness(&mut self, change: &BrightnessChange) {
+        let brightness = match change {
+            BrightnessChange::Up(step) => (self.brightness + step).min(100),
+            BrightnessChange::Down(step) => self.brightness.saturating_sub(*step),
+            BrightnessChange::Cycle(levels) => {
+                match levels.iter().position(|&level| level == self.brightness) {
+                    Some(index) => levels[(index + 1) % levels.len()],
+                    None => levels
+                        .iter()
+                        .copied()
+                        .find(|&level| level > self.brightness)
+                        .unwrap_or(levels[0]),
+                }
+            }
+        };
+        self.chosen_brightness = Some(brightness);
+        self.save_chosen_brightness();
+        self.set_brightness(brightness);
+    }
+
+    fn save_chosen_brightness(&self) {
+        if let Err(err) =
+            save_deck_brightness(&self.state_path, &self.deck.serial, self.chosen_brightness)
+        {
+            eprintln!("{err}");
+        }
+    }
+
+    fn draw_brightness_keys(&mut self) {
+        if self.keys_blanked {
+            return;
+        }
+        let keys = (0..self.page_state.dynamic_states.len())
+            .filter(|&key| {
+                matches!(
+                    self.page_state.dynamic_states[key],
+                    Some(DynamicKeyState::Brightness)
+                )
+            })
+            .collect::<Vec<_>>();
+        if keys.is_empty() {
+            return;
+        }
+        if self.brightness_gauge.as_ref().map(|(level, _)| *level) != Some(self.brightness) {
+            match render_brightness_icon(self.brightness, self.deck.model) {
+                Ok(image) => self.brightness_gauge = Some((self.brightness, image)),
+                Err(err) => {
+                    eprintln!("{err}");
+                    return;
+                }
+            }
+        }
+        let Some((_, image)) = &self.brightness_gauge else {
+            return;
+        };
+        for key in keys {
+            if let Err(err) = self.deck.set_key_image(key, image) {
+                eprintln!("{err}");
+            }
+        }
+    }
+
+    fn set_now_playing(&mut self, now_playing: Option<NowPlaying>) {
+        self.now_playing = now_playing;
+        self.draw_now_playing_keys();
+    }
+
+    fn draw_now_playing_keys(&self) {
+        if self.keys_blanked {
+            return;
+        }
+        for (key, dynamic_state) in self.page_state.dynamic_states.iter().enumerate() {
+            let Some(DynamicKeyState::NowPlaying { title }) = dynamic_state else {
+                continue;
+            };
+            let result =
+                render_now_playing_icon(self.now_playing.as_ref(), title.as_ref(), self.deck.model)
+                    .and_then(|image| self.deck.set_key_image(key, &image));
+            if let Err(err) = result {
+                eprintln!("{err}");
+            }
+        }
+    }
+
+    fn draw_timer_keys(&mut self, now: Instant) {
+        if self.keys_blanked {
+            return;
+        }
+        for (key, dynamic_state) in self.page_state.dynamic_states.iter_mut().enumerate() {
+            let Some(DynamicKeyState::Timer(state)) = dynamic_state else {
+                continue;
+            };
+            let timer = self
+                .timers
+                .entry(state.id.clone())
+                .or_insert_with(|| KeyTimer::new(state.id.kind));
+            state.next_update_at = timer.next_change(now);
+            let face = timer.face(now);
+            if state.shown.as_ref() == Some(&face) {
+                continue;
+            }
+            match render_timer_icon(&face, state.title.as_ref(), self.deck.model) {
+                Ok(image) => match self.deck.set_key_image(key, &image) {
+                    Ok(()) => state.shown = Some(face),
+                    Err(err) => eprintln!("{err}"),
+                },
+                Err(err) => eprintln!("{err}"),
+            }
+        }
+    }
+
+    fn finish_timers(&mut self, config: &Config, debug: bool, now: Instant) {
+        for (id, timer) in &mut self.timers {
+            if !timer.finish_if_due(now) {
+                continue;
+            }
+            let command = config
+                .folder_keys(&id.folder)
+                .and_then(|keys| keys.get(id.index))
+                .and_then(key_timer_command);
+            if let Some(command) = command
+                && let Err(err) = launch_argv_command(&command, debug)
+            {
+                eprintln!("{err}");
+            }
+        }
+    }
+
+    fn press_timer(&mut self, id: &TimerId, reset: bool, config: &Config, debug: bool) {
+        let now = Instant::now();
+        self.finish_timers(config, debug, now);
+        let timer = self
+            .timers
+            .entry(id.clone())
+            .or_insert_with(|| KeyTimer::new(id.kind));
+        if reset {
+            timer.reset();
+        } else {
+            timer.press(now);
+        }
+        self.draw_timer_keys(now);
+    }
+
+    fn update_idle(&mut self, config: &Config, image_dir: &Path, now: Instant) {
+        let state = self.idle.state(now);
+        let blank = state == IdleState::Off && self.idle_blank_keys;
+        if state == self.idle_state && blank == self.keys_blanked {
+            return;
+        }
+        self.idle_state = state;
+        self.set_brightness(self.brightness);
+        if blank && !self.keys_blanked {
+            self.keys_blanked = true;
+            self.blank_keys();
+        } else if !blank && self.keys_blanked {
+            self.keys_blanked = false;
+            self.show_page(config, image_dir, self.current_page);
+        }
+    }
+
+    fn set_session_locked(&mut self, locked: bool, config: &Config, image_dir: &Path) {
+        let now = Instant::now();
+        if locked {
+            self.idle.force_idle();
+        } else {
+            self.idle.activity(now);
+        }
+        self.update_idle(config, image_dir, now);
+    }
+
+    fn blank_keys(&self) {
+        for key in 0..self.deck.model.key_count {
+            if let Err(err) = self.deck.set_key_image(key, &self.blank_image) {
+                eprintln!("{err}");
+            }
+        }
+    }
+
+    fn folder_path(&self) -> Vec<usize> {
+        self.folder_stack.iter().map(|&(key, _)| key).collect()
+    }
+
+    // Keys held across a page change keep their old meaning, so the tracker
+    // ignores them on the new page until they are released.
+    fn show_page(&mut self, config: &Config, image_dir: &Path, page: usize) {
+        self.current_page = page;
+        let plan = plan_page_layout(config, &self.status_cache, &self.folder_path(), page);
+        self.page_state = set_page(
+            &self.deck,
+            &plan,
+            image_dir,
+            &mut self.image_cache,
+            &self.blank_image,
+        );
+        self.draw_brightness_keys();
+        self.draw_timer_keys(Instant::now());
+        self.draw_now_playing_keys();
+        if self.keys_blanked {
+            self.blank_keys();
+        }
+        self.key_events.reset();
+        self.pending_taps.fill(None);
+        self.image_overrides.fill(None);
+        self.consumed_keys = 0;
+        self.woken_keys = 0;
+
+        let location = (self.folder_path(), page);
+        if self.announced_page.as_ref() != Some(&location) {
+            let _ = self.events.send(DaemonEvent::PageChanged {
+                serial: self.deck.serial.clone(),
+                page: page + 1,
+                pages: plan.total_pages,
+            });
+            self.announced_page = Some(location);
+        }
+    }
+
+    fn override_key_image(
+        &mut self,
+        image_dir: &Path,
+        key: usize,
+        icon: &str,
+        duration: Duration,
+    ) -> Result<(), String> {
+        apply_icon_to_key(
+            &self.deck,
+            image_dir,
+            &mut self.image_cache,
+            &mut self.page_state,
+            key,
+            icon,
+            &LiveFaces::default(),
+            None,
+        )?;
+        self.page_state.status_states[key] = None;
+        self.image_overrides[key] = Some(Instant::now() + duration);
+        Ok(())
+    }
+
+    fn restore_expired_key_images(&mut self, config: &Config, image_dir: &Path) {
+        let now = Instant::now();
+        let expired = (0..self.image_overrides.len())
+            .filter(|&key| self.image_overrides[key].is_some_and(|until| now >= until))
+            .collect::<Vec<_>>();
+        if expired.is_empty() {
+            return;
+        }
+        let plan = plan_page_layout(
+            config,
+            &self.status_cache,
+            &self.folder_path(),
+            self.current_page,
+        );
+        for key in expired {
+            self.image_overrides[key] = None;
+            let result = match &plan.icons[key] {
+                Some(_) => apply_planned_icon(
+                    &self.deck,
+                    image_dir,
+                    &mut self.image_cache,
+                    &mut self.page_state,
+                    &plan,
+                    key,
+                ),
+                None => {
+                    self.page_state.dynamic_states[key] = None;
+                    self.deck.set_key_image(key, &self.blank_image)
+                }
+            };
+            if let Err(err) = result {
+                eprintln!("{err}");
+            }
+            self.page_state.status_states[key] =
+                plan.status_slots[key].as_ref().map(status_key_state);
+        }
+        self.draw_brightness_keys();
+        self.draw_timer_keys(now);
+        self.draw_now_playing_keys();
+    }
+
+    fn refresh_icons(&mut self, config: &Config, image_dir: &Path, icons: &HashSet<String>) {
+        let changed = |name: &str| icons.contains(name);
+        self.image_cache.retain(|key, _| {
+            !changed(&key.icon) && !key.faces.clock.background.as_deref().is_some_and(changed)
+        });
+        let plan = plan_page_layout(
+            config,
+            &self.status_cache,
+            &self.folder_path(),
+            self.current_page,
+        );
+        let shown = plan.icons.iter().flatten().any(|(icon, faces)| {
+            changed(icon) || faces.clock.background.as_deref().is_some_and(changed)
+        }) || plan
+            .status_slots
+            .iter()
+            .flatten()
+            .any(|status| status.icons.names().into_iter().any(changed));
+        if shown {
+            self.show_page(config, image_dir, self.current_page);
+        }
+    }
+
+    fn go_to_page(&mut self, config: &Config, image_dir: &Path, target: PageTarget) {
+        let total_pages = page_count(config, &self.folder_path());
+        let page = match target {
+            PageTarget::Next => Some(self.current_page + 1),
+            PageTarget::Previous => self.current_page.checked_sub(1),
+            PageTarget::First => Some(0),
+            PageTarget::Last => total_pages.checked_sub(1),
+            PageTarget::Back => self.pop_page_history(total_pages),
+            PageTarget::Page(page) => Some(page),
+        };
+        if let Some(page) = page
+            && page < total_pages
+            && page != self.current_page
+        {
+            if target != PageTarget::Back {
+                if self.page_history.len() == MAX_PAGE_HISTORY {
+                    self.page_history.remove(0);
+                }
+                self.page_history
+                    .push((self.folder_path(), self.current_page));
+            }
+            self.show_page(config, image_dir, page);
+        }
+    }
+
+    fn pop_page_history(&mut self, total_pages: usize) -> Option<usize> {
+        let folder = self.folder_path();
+        while let Some((path, page)) = self.page_history.pop() {
+            if path == folder && page < total_pages && page != self.current_page {
+                return Some(page);
+            }
+        }
+        None
+    }
+
+    fn has_gestures(&self, key: usize) -> bool {
+        self.page_state.long_press_actions[key].is_some()
+            || self.page_state.double_press_actions[key].is_some()
+    }
+
+    fn handle_key_states(
+        &mut self,
+        pressed: u32,
+        config: &Config,
+        image_dir: &Path,
+        debug: bool,
+        loop_tx: &mpsc::Sender<LoopEvent>,
+    ) {
+        let now = Instant::now();
+        let events = self.key_events.update(pressed, now);
+        self.idle.activity(now);
+        if self.idle_state != IdleState::Awake {
+            self.woken_keys |= pressed;
+            self.update_idle(config, image_dir, now);
+            self.woken_keys &= self.key_events.pressed();
+            return;
+        }
+        self.handle_key_events(events, now, config, image_dir, debug, loop_tx);
+    }
+
+    // Returns false when an event changed the page; the rest of the batch
+    // belonged to the old page and is dropped.
+    fn handle_key_events(
+        &mut self,
+        events: Vec<KeyEvent>,
+        now: Instant,
+        config: &Config,
+        image_dir: &Path,
+        debug: bool,
+        loop_tx: &mpsc::Sender<LoopEvent>,
+    ) -> bool {
+        let page = (self.current_page, self.folder_stack.len());
+        for event in events {
+            self.handle_key_event(event, now, config, image_dir, debug, loop_tx);
+            if (self.current_page, self.folder_stack.len()) != page {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn run_timers(
+        &mut self,
+        config: &Config,
+        image_dir: &Path,
+        debug: bool,
+        status_pool: &mut StatusPool,
+        loop_tx: &mpsc::Sender<LoopEvent>,
+    ) {
+        let now = Instant::now();
+        self.update_idle(config, image_dir, now);
+        self.finish_timers(config, debug, now);
+        if !self.keys_blanked {
+            self.restore_expired_key_images(config, image_dir);
+            advance_dynamic_keys(&self.deck, image_dir, status_pool, &mut self.page_state);
+            self.draw_brightness_keys();
+            self.draw_timer_keys(now);
+        }
+
+        let events = self.key_events.tick(now);
+        if !self.handle_key_events(events, now, config, image_dir, debug, loop_tx) {
+            return;
+        }
+
+        let page = (self.current_page, self.folder_stack.len());
+        for key in 0..self.pending_taps.len() {
+            if self.pending_taps[key].is_some_and(|deadline| now >= deadline) {
+                self.pending_taps[key] = None;
+                let action = self.page_state.button_actions[key].clone();
+                self.run_action(action, key, config, image_dir, debug, loop_tx);
+                if (self.current_page, self.folder_stack.len()) != page {
+                    break;
+                }
+            }
+        }
+    }
+
+    fn apply_status_reading(
+        &mut self,
+        image_dir: &Path,
+        check: &StatusCheck,
+        reading: &StatusReading,
+    ) {
+        for key in 0..self.page_state.status_states.len() {
+            let Some(status) = self.page_state.status_states[key].as_mut() else {
+                continue;
+            };
+            if status.command != check.command || status.icons.mode() != check.mode {
+                continue;
+            }
+            self.status_cache
+                .insert(check.command.clone(), reading.clone());
+            if status.current.as_ref() == Some(reading) {
+                continue;
+            }
+            status.current = Some(reading.clone());
+            if self.keys_blanked {
+                continue;
+            }
+            let face = status.icons.face(Some(reading));
+            let faces = status.faces.clone();
+            let title = status.title.clone();
+            if let Err(err) = apply_status_face_to_key(
+                &self.deck,
+                image_dir,
+                &mut self.image_cache,
+                &mut self.page_state,
+                key,
+                &face,
+                &faces,
+                title.as_ref(),
+            ) {
+                eprintln!("{err}");
+            }
+        }
+        self.draw_brightness_keys();
+    }
+
+    fn apply_disk_usage(&mut self, path: &Path, usage: &Result<f32, String>) {
+        for (key, dynamic_state) in self.page_state.dynamic_states.iter_mut().enumerate() {
+            let Some(DynamicKeyState::Metric(metric)) = dynamic_state else {
+                continue;
+            };
+            if metric.sampler.disk_path().as_deref() != Some(path) {
+                continue;
+            }
+            let sampled = metric.sampler.record_disk(usage.clone());
+            if !self.keys_blanked {
+                draw_metric_sample(&self.deck, key, metric, sampled);
+            }
+        }
+    }
+
+    fn next_deadline(&self) -> Option<Instant> {
+        let idle = self
+            .idle
+            .next_deadline(self.idle_state)
+            .into_iter()
+            .chain(self.timers.values().filter_map(KeyTimer::finishes_at))
+            .min();
+        if self.keys_blanked {
+            return idle;
+        }
+        let dynamic =
+            self.page_state
+                .dynamic_states
+                .iter()
+                .flatten()
+                .filter_map(|state| match state {
+                    DynamicKeyState::Animated(animation) => Some(animation.next_frame_at),
+                    DynamicKeyState::Clock(clock) => Some(clock.next_update_at),
+                    DynamicKeyState::Calendar(calendar) => Some(calendar.next_update_at),
+                    DynamicKeyState::Metric(metric) => Some(metric.next_update_at),
+                    DynamicKeyState::Brightness | DynamicKeyState::NowPlaying { .. } => None,
+                    DynamicKeyState::Timer(timer) => timer.next_update_at,
+                });
+        let status = self
+            .page_state
+            .status_states
+            .iter()
+            .flatten()
+            .map(|status| status.next_check_at);
+        dynamic
+            .chain(status)
+            .chain(self.image_overrides.iter().flatten().copied())
+            .chain(self.pending_taps.iter().flatten().copied())
+            .chain(self.key_events.next_deadline())
+            .chain(idle)
+            .min()
+    }
+
+    // Keys without long or double press actions keep firing on key down. Keys
+    // with gestures fire their regular action on release, after the double
+    // press window when one is configured.
+    fn handle_key_event(
+        &mut self,
+        event: KeyEvent,
+        now: Instant,
+        config: &Config,
+        image_dir: &Path,
+        debug: bool,
+        loop_tx: &mpsc::Sender<LoopEvent>,
+    ) {
+        let key = match event {
+            KeyEvent::KeyDown(key)
+            | KeyEvent::KeyUp(key)
+            | KeyEvent::LongPress(key)
+            | KeyEvent::DoublePress(key) => key,
+        };
+        if key >= self.page_state.button_actions.len() {
+            return;
+        }
+        let bit = 1u32 << key;
+        if self.woken_keys & bit != 0 {
+            if event == KeyEvent::KeyUp(key) {
+                self.woken_keys &= !bit;
+            }
+            return;
+        }
+
+        let action = match event {
+            KeyEvent::KeyDown(_) => {
+                self.consumed_keys &= !bit;
+                let _ = self.events.send(DaemonEvent::KeyPressed {
+                    serial: self.deck.serial.clone(),
+                    key: key + 1,
+                });
+                if self.has_gestures(key) {
+                    return;
+                }
+                self.page_state.button_actions[key].clone()
+            }
+            KeyEvent::LongPress(_) => {
+                let action = self.page_state.long_press_actions[key].clone();
+                if action.is_some() {
+                    self.consumed_keys |= bit;
+                    self.pending_taps[key] = None;
+                }
+                action
+            }
+            KeyEvent::DoublePress(_) => {
+                let action = self.page_state.double_press_actions[key].clone();
+                if action.is_some() {
+                    self.consumed_keys |= bit;
+                    self.pending_taps[key] = None;
+                }
+                action
+            }
+            KeyEvent::KeyUp(_) => {
+                if !self.has_gestures(key) || self.consumed_keys & bit != 0 {
+                    return;
+                }
+                if self.page_state.double_press_actions[key].is_some() {
+                    self.pending_taps[key] = Some(now + self.key_events.thresholds().double_press);
+                    return;
+                }
+                self.page_state.button_actions[key].clone()
+            }
+        };
+        self.run_action(action, key, config, image_dir, debug, loop_tx);
+    }
+
+    fn run_action(
+        &mut self,
+        action: Option<ButtonAction>,
+        key: usize,
+        config: &Config,
+        image_dir: &Path,
+        debug: bool,
+        loop_tx: &mpsc::Sender<LoopEvent>,
+    ) {
+        let Some(action) = action else {
+            return;
+        };
+        match action {
+            ButtonAction::Launch(action) => {
+                watch_action_completion(&action, debug, &self.deck.serial, key, loop_tx);
+            }
+            ButtonAction::KeyboardShortcut(shortcut) => {
+                if let Err(err) = send_shortcut(&shortcut) {
+                    eprintln!("{err}");
+                } else {
+                    request_immediate_status_check(&mut self.page_state, key);
+                }
+            }
+            ButtonAction::Text(text) => {
+                if let Err(err) = type_text(&text) {
+                    eprintln!("{err}");
+                } else {
+                    request_immediate_status_check(&mut self.page_state, key);
+                }
+            }
+            ButtonAction::Macro(key_macro) => {
+                run_macro(key_macro, debug, &self.deck.serial, key, loop_tx);
+            }
+            ButtonAction::PreviousPage => self.go_to_page(config, image_dir, PageTarget::Previous),
+            ButtonAction::NextPage => self.go_to_page(config, image_dir, PageTarget::Next),
+            ButtonAction::OpenFolder(folder_key) => {
+                self.folder_stack.push((folder_key, self.current_page));
+                self.show_page(config, image_dir, 0);
+            }
+            ButtonAction::FolderBack => {
+                if let Some((_, parent_page)) = self.folder_stack.pop() {
+                    self.show_page(config, image_dir, parent_page);
+                }
+            }
+            ButtonAction::Builtin(action) => {
+                self.run_builtin_action(&action, config, image_dir, loop_tx)
+            }
+            ButtonAction::ToggleTimer(id) => self.press_timer(&id, false, config, debug),
+            ButtonAction::ResetTimer(id) => self.press_timer(&id, true, config, debug),
+        }
+    }
+}
+
+impl Drop for DeckSession {
+    fn drop(&mut self) {
+        self.stop_key_reader.store(true, Ordering::Relaxed);
+        let _ = self.events.send(DaemonEvent::DeviceDisconnected {
+            serial: self.deck.serial.clone(),
+        });
+    }
 }
 
 #[derive(Clone)]
@@ -167,49 +1358,98 @@ enum LoadedKeyImage {
     Clock {
         image: Vec<u8>,
         current_text: String,
-        background_name: Option<String>,
+        face: ClockFace,
     },
     Calendar {
         image: Vec<u8>,
         current_key: String,
+        face: CalendarFace,
+    },
+    Brightness,
+    NowPlaying,
+    Metric {
+        image: Vec<u8>,
+        sampler: MetricSampler,
+        face: MetricFace,
+        error: Option<String>,
     },
 }
 
 #[derive(Clone, Eq, Hash, PartialEq)]
 struct ImageCacheKey {
     icon: String,
-    clock_background: Option<String>,
+    faces: LiveFaces,
+    title: Option<KeyTitle>,
 }
 
 type ImageCache = HashMap<ImageCacheKey, LoadedKeyImage>;
-type StatusCache = HashMap<String, bool>;
+type StatusCache = HashMap<String, StatusReading>;
 
-static RELOAD_REQUESTED: AtomicBool = AtomicBool::new(false);
+// The signal handler only writes a byte to this socket; a thread reading the
+// other end turns it into `LoopEvent::Reload`.
+#[cfg(unix)]
+static RELOAD_SIGNAL_FD: AtomicI32 = AtomicI32::new(-1);
 
 #[cfg(unix)]
 const SIGHUP_SIGNAL: i32 = 1;
 
 #[cfg(unix)]
 extern "C" fn handle_reload_signal(_signal: i32) {
-    RELOAD_REQUESTED.store(true, Ordering::Relaxed);
+    unsafe extern "C" {
+        fn write(fd: i32, buf: *const u8, count: usize) -> isize;
+    }
+
+    let fd = RELOAD_SIGNAL_FD.load(Ordering::Relaxed);
+    if fd >= 0 {
+        let byte = 1u8;
+        // SAFETY: write(2) is async-signal-safe and `byte` outlives the call.
+        let _ = unsafe { write(fd, &byte, 1) };
+    }
 }
 
 #[cfg(unix)]
-fn install_reload_signal_handler() {
+fn install_reload_signal_handler(sender: mpsc::Sender<LoopEvent>) {
     unsafe extern "C" {
         fn signal(signum: i32, handler: extern "C" fn(i32)) -> extern "C" fn(i32);
     }
 
-    // SAFETY: Registering a simple signal handler that only stores to an AtomicBool.
+    let (mut reader, writer) = match UnixStream::pair() {
+        Ok(pair) => pair,
+        Err(err) => {
+            eprintln!("Failed to set up SIGHUP reload handling: {err}");
+            return;
+        }
+    };
+    if let Err(err) = writer.set_nonblocking(true) {
+        eprintln!("Failed to set up SIGHUP reload handling: {err}");
+        return;
+    }
+    RELOAD_SIGNAL_FD.store(writer.into_raw_fd(), Ordering::Relaxed);
+    thread::spawn(move || {
+        let mut buf = [0u8; 64];
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) => return,
+                Ok(_) => {
+                    if sender.send(LoopEvent::Reload).is_err() {
+                        return;
+                    }
+                }
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(err) => {
+                    eprintln!("SIGHUP reload handling stopped: {err}");
+                    return;
+                }
+            }
+        }
+    });
+
+    // SAFETY: The handler only calls write(2) on a non-blocking socket.
     let _ = unsafe { signal(SIGHUP_SIGNAL, handle_reload_signal) };
 }
 
 #[cfg(not(unix))]
-fn install_reload_signal_handler() {}
-
-fn take_reload_request() -> bool {
-    RELOAD_REQUESTED.swap(false, Ordering::Relaxed)
-}
+fn install_reload_signal_handler(_sender: mpsc::Sender<LoopEvent>) {}
 
 #[cfg(test)]
 fn default_vendor_id() -> u16 {
@@ -242,22 +1482,36 @@ fn default_keys_per_page() -> usize {
 }
 
 fn paging_layout(config: &Config) -> PagingLayout {
-    PagingLayout::new(KEY_COUNT, config.keys_per_page)
+    let layout = PagingLayout::for_model(config.device_model(), config.keys_per_page);
+    if config.manual_page_navigation {
+        layout.without_navigation_keys()
+    } else {
+        layout
+    }
+}
+
+fn folder_paging_layout(config: &Config, folder: &[usize]) -> PagingLayout {
+    if folder.is_empty() {
+        paging_layout(config)
+    } else {
+        paging_layout(config).for_folder()
+    }
 }
 
 fn apply_loaded_key_image(
-    device: &HidDevice,
+    deck: &ConnectedDeck,
     state: &mut PageState,
     key_index: usize,
     loaded: LoadedKeyImage,
+    title: Option<&KeyTitle>,
 ) -> Result<(), String> {
     match loaded {
         LoadedKeyImage::Static(data) => {
-            set_key_image_data(device, key_index as u8, &data)?;
+            deck.set_key_image(key_index, &data)?;
             state.dynamic_states[key_index] = None;
         }
         LoadedKeyImage::Animated { frames, delays } => {
-            set_key_image_data(device, key_index as u8, &frames[0])?;
+            deck.set_key_image(key_index, &frames[0])?;
             let initial_delay = delays[0];
             state.dynamic_states[key_index] = Some(DynamicKeyState::Animated(AnimatedKeyState {
                 frames,
@@ -269,22 +1523,57 @@ fn apply_loaded_key_image(
         LoadedKeyImage::Clock {
             image,
             current_text,
-            background_name,
+            face,
         } => {
-            set_key_image_data(device, key_index as u8, &image)?;
+            deck.set_key_image(key_index, &image)?;
+            let next_update_at = Instant::now() + face.refresh_delay(face.now());
             state.dynamic_states[key_index] = Some(DynamicKeyState::Clock(ClockKeyState {
                 current_text,
-                background_name,
-                next_update_at: Instant::now() + Duration::from_secs(1),
+                face,
+                title: title.cloned(),
+                next_update_at,
             }));
         }
-        LoadedKeyImage::Calendar { image, current_key } => {
-            set_key_image_data(device, key_index as u8, &image)?;
+        LoadedKeyImage::Calendar {
+            image,
+            current_key,
+            face,
+        } => {
+            deck.set_key_image(key_index, &image)?;
             state.dynamic_states[key_index] = Some(DynamicKeyState::Calendar(CalendarKeyState {
                 current_key,
+                face,
+                title: title.cloned(),
                 next_update_at: next_midnight_instant(),
             }));
         }
+        LoadedKeyImage::Brightness => {
+            state.dynamic_states[key_index] = Some(DynamicKeyState::Brightness);
+        }
+        LoadedKeyImage::NowPlaying => {
+            state.dynamic_states[key_index] = Some(DynamicKeyState::NowPlaying {
+                title: title.cloned(),
+            });
+        }
+        LoadedKeyImage::Metric {
+            image,
+            sampler,
+            face,
+            error,
+        } => {
+            deck.set_key_image(key_index, &image)?;
+            let next_update_at = match sampler.disk_path() {
+                Some(_) => Instant::now(),
+                None => Instant::now() + face.interval,
+            };
+            state.dynamic_states[key_index] = Some(DynamicKeyState::Metric(MetricKeyState {
+                sampler,
+                face,
+                title: title.cloned(),
+                next_update_at,
+                last_error: error,
+            }));
+        }
     }
     Ok(())
 }
@@ -329,15 +1618,20 @@ fn next_midnight_instant() -> Instant {
 fn watch_action_completion(
     action: &str,
     debug: bool,
+    serial: &str,
     key_index: usize,
-    refresh_sender: &mpsc::Sender<usize>,
+    refresh_sender: &mpsc::Sender<LoopEvent>,
 ) {
     let action = action.to_string();
+    let serial = serial.to_string();
     let refresh_sender = refresh_sender.clone();
     thread::spawn(
         move || match wait_argv_command_success(action.as_str(), debug) {
             Ok(true) => {
-                let _ = refresh_sender.send(key_index);
+                let _ = refresh_sender.send(LoopEvent::Deck(
+                    serial,
+                    DeckRequest::RefreshStatus(key_index),
+                ));
             }
             Ok(false) => {
                 eprintln!("Action command '{action}' exited with a non-zero status");
@@ -347,13 +1641,70 @@ fn watch_action_completion(
     );
 }
 
+fn run_macro(
+    key_macro: KeyMacro,
+    debug: bool,
+    serial: &str,
+    key_index: usize,
+    request_sender: &mpsc::Sender<LoopEvent>,
+) {
+    let serial = serial.to_string();
+    let request_sender = request_sender.clone();
+    thread::spawn(move || {
+        for step in key_macro.steps {
+            let result = match step {
+                MacroStep::Launch(action) => match wait_argv_command_success(&action, debug) {
+                    Ok(true) => Ok(()),
+                    Ok(false) => Err(format!(
+                        "Macro command '{action}' exited with a non-zero status"
+                    )),
+                    Err(err) => Err(err),
+                },
+                MacroStep::KeyboardShortcut(shortcut) => send_shortcut(&shortcut),
+                MacroStep::Text(text) => type_text(&text),
+                MacroStep::Delay(delay) => {
+                    sleep(delay);
+                    Ok(())
+                }
+                MacroStep::Page(target) => {
+                    let _ = request_sender
+                        .send(LoopEvent::Deck(serial.clone(), DeckRequest::Page(target)));
+                    Ok(())
+                }
+            };
+            if let Err(err) = result {
+                eprintln!("{err}");
+                if key_macro.stop_on_error {
+                    eprintln!(
+                        "Stopping macro on key {} after a failed step",
+                        key_index + 1
+                    );
+                    return;
+                }
+            }
+        }
+        let _ = request_sender.send(LoopEvent::Deck(
+            serial,
+            DeckRequest::RefreshStatus(key_index),
+        ));
+    });
+}
+
 fn send_shortcut(shortcut: &str) -> Result<(), String> {
     eprintln!("Triggering keyboard shortcut action '{shortcut}'");
     send_keyboard_shortcut(shortcut)
 }
 
-fn run_status_check(command: &str) -> Result<bool, String> {
-    run_shell_status(command)
+fn run_status_check(
+    command: &str,
+    mode: StatusMode,
+    timeout: Duration,
+) -> Result<StatusReading, String> {
+    match mode {
+        StatusMode::Exit => run_shell_status(command, timeout).map(StatusReading::Exit),
+        StatusMode::Output => run_shell_status_output(command, timeout)
+            .map(|stdout| StatusReading::Output(stdout.trim().to_string())),
+    }
 }
 
 fn request_immediate_status_check(state: &mut PageState, key_index: usize) {
@@ -362,49 +1713,155 @@ fn request_immediate_status_check(state: &mut PageState, key_index: usize) {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn apply_icon_to_key(
-    device: &HidDevice,
+    deck: &ConnectedDeck,
     image_dir: &Path,
     image_cache: &mut ImageCache,
     state: &mut PageState,
     key_index: usize,
     icon: &str,
-    clock_background: Option<&str>,
+    faces: &LiveFaces,
+    title: Option<&KeyTitle>,
+) -> Result<(), String> {
+    let loaded = load_key_image_cached(image_dir, image_cache, icon, faces, title, deck.model)?;
+    apply_loaded_key_image(deck, state, key_index, loaded, title)
+}
+
+fn apply_planned_icon(
+    deck: &ConnectedDeck,
+    image_dir: &Path,
+    image_cache: &mut ImageCache,
+    state: &mut PageState,
+    plan: &PageLayoutPlan,
+    key_index: usize,
+) -> Result<(), String> {
+    let Some((icon, faces)) = &plan.icons[key_index] else {
+        return Ok(());
+    };
+    let title = plan.titles[key_index].as_ref();
+    if let Some(id) = &plan.timer_slots[key_index] {
+        state.dynamic_states[key_index] = Some(DynamicKeyState::Timer(TimerKeyState {
+            id: id.clone(),
+            title: title.cloned(),
+            shown: None,
+            next_update_at: None,
+        }));
+        return Ok(());
+    }
+    match &plan.status_slots[key_index] {
+        Some(status) => apply_status_face_to_key(
+            deck,
+            image_dir,
+            image_cache,
+            state,
+            key_index,
+            &status.icons.face(status.current.as_ref()),
+            faces,
+            title,
+        ),
+        None => apply_icon_to_key(
+            deck,
+            image_dir,
+            image_cache,
+            state,
+            key_index,
+            icon,
+            faces,
+            title,
+        ),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn apply_status_face_to_key(
+    deck: &ConnectedDeck,
+    image_dir: &Path,
+    image_cache: &mut ImageCache,
+    state: &mut PageState,
+    key_index: usize,
+    face: &StatusOutput,
+    faces: &LiveFaces,
+    title: Option<&KeyTitle>,
 ) -> Result<(), String> {
-    let loaded = load_key_image_cached(image_dir, image_cache, icon, clock_background)?;
-    apply_loaded_key_image(device, state, key_index, loaded)
+    if face.title.is_none() && face.background.is_none() {
+        let icon = face.icon.as_deref().unwrap_or_default();
+        return apply_icon_to_key(
+            deck,
+            image_dir,
+            image_cache,
+            state,
+            key_index,
+            icon,
+            faces,
+            title,
+        );
+    }
+    let face_title = face
+        .title
+        .as_deref()
+        .map(|text| title.map_or_else(|| KeyTitle::new(text), |title| title.with_text(text)));
+    let image = render_status_face(image_dir, face, face_title.as_ref().or(title), deck.model)?;
+    state.dynamic_states[key_index] = None;
+    deck.set_key_image(key_index, &image)
 }
 
-fn page_count(config: &Config) -> usize {
-    paging_layout(config).page_count(config.keys.len())
+fn page_count(config: &Config, folder: &[usize]) -> usize {
+    let key_count = config.folder_keys(folder).map_or(0, <[KeyBinding]>::len);
+    folder_paging_layout(config, folder).page_count(key_count)
 }
 
 fn load_profile_config(profile: &str, config_path: &Path) -> Result<Config, String> {
     load_config(config_path, profile)
 }
 
-fn plan_page_layout(config: &Config, status_cache: &StatusCache, page: usize) -> PageLayoutPlan {
-    let mut icons = std::array::from_fn(|_| None);
-    let mut button_actions = std::array::from_fn(|_| None);
-    let mut status_slots = std::array::from_fn(|_| None);
+fn plan_page_layout(
+    config: &Config,
+    status_cache: &StatusCache,
+    folder: &[usize],
+    page: usize,
+) -> PageLayoutPlan {
+    let layout = folder_paging_layout(config, folder);
+    let keys = config.folder_keys(folder).unwrap_or_default();
+    let mut icons = vec![None; layout.total_slots()];
+    let mut titles = vec![None; layout.total_slots()];
+    let mut button_actions = vec![None; layout.total_slots()];
+    let mut long_press_actions = vec![None; layout.total_slots()];
+    let mut double_press_actions = vec![None; layout.total_slots()];
+    let mut status_slots = vec![None; layout.total_slots()];
+    let mut timer_slots = vec![None; layout.total_slots()];
     let mut warnings = Vec::new();
 
-    let layout = paging_layout(config);
-    let total_pages = layout.page_count(config.keys.len());
+    let total_pages = layout.page_count(keys.len());
     let page = min(page, total_pages.saturating_sub(1));
     let keys_per_page = layout.page_capacity(page, total_pages);
     let offset = (0..page)
         .map(|page_index| layout.page_capacity(page_index, total_pages))
         .sum::<usize>();
 
-    for (index, key) in config
-        .keys
-        .iter()
-        .skip(offset)
-        .take(keys_per_page)
-        .enumerate()
-    {
-        let clock_background = key_clock_background(key);
+    for (index, key) in keys.iter().skip(offset).take(keys_per_page).enumerate() {
+        let slot = layout.first_slot() + index;
+        titles[slot] = key_title(key).ok().flatten();
+        if key.is_folder() {
+            icons[slot] = Some((key.icon.clone(), key_live_faces(key).unwrap_or_default()));
+            button_actions[slot] = Some(ButtonAction::OpenFolder(offset + index));
+            continue;
+        }
+
+        let faces = key_live_faces(key).unwrap_or_default();
+        if let Ok(Some(kind)) = key_timer(key) {
+            let id = TimerId {
+                folder: folder.to_vec(),
+                index: offset + index,
+                kind,
+            };
+            icons[slot] = Some((key.icon.clone(), faces));
+            button_actions[slot] = Some(ButtonAction::ToggleTimer(id.clone()));
+            long_press_actions[slot] = Some(ButtonAction::ResetTimer(id.clone()));
+            double_press_actions[slot] = key_double_press_action(key).map(ButtonAction::Launch);
+            timer_slots[slot] = Some(id);
+            continue;
+        }
         let configured_action = key_configured_action(key);
         let status_command = key_status_command(key);
         let status_is_launcher = status_command
@@ -414,28 +1871,34 @@ fn plan_page_layout(config: &Config, status_cache: &StatusCache, page: usize) ->
         if let Some(command) = status_command.clone()
             && !status_is_launcher
         {
-            let icon_on = key_status_icon_on(key);
-            let icon_off = key_status_icon_off(key);
-            let check_interval = key_status_interval(key);
-            let cached_state = status_cache.get(&command).copied();
-            let initial_state = cached_state.unwrap_or(false);
-            let initial_icon = if initial_state {
-                icon_on.clone()
-            } else {
-                icon_off.clone()
+            let status_icons = match key_status_mode(key) {
+                Ok(StatusMode::Output) => StatusIcons::Output {
+                    icon: key.icon.clone(),
+                    state_icons: key.status_icons.clone(),
+                },
+                _ => StatusIcons::Exit {
+                    icon_on: key_status_icon_on(key),
+                    icon_off: key_status_icon_off(key),
+                },
             };
-            icons[index] = Some((initial_icon, clock_background.clone()));
-            status_slots[index] = Some(PlannedStatusKey {
+            let check_interval = key_status_interval(key);
+            let cached = status_cache
+                .get(&command)
+                .filter(|reading| reading.mode() == status_icons.mode())
+                .cloned();
+            let initial_icon = status_icons.face(cached.as_ref()).icon.unwrap_or_default();
+            icons[slot] = Some((initial_icon, faces.clone()));
+            status_slots[slot] = Some(PlannedStatusKey {
                 command,
-                icon_on,
-                icon_off,
-                clock_background,
+                icons: status_icons,
+                faces,
+                title: titles[slot].clone(),
                 check_interval,
-                current_on: cached_state,
-                poll_now: cached_state.is_none(),
+                poll_now: cached.is_none(),
+                current: cached,
             });
         } else {
-            icons[index] = Some((key.icon.clone(), clock_background.clone()));
+            icons[slot] = Some((key.icon.clone(), faces));
         }
 
         if status_is_launcher {
@@ -445,7 +1908,7 @@ fn plan_page_layout(config: &Config, status_cache: &StatusCache, page: usize) ->
                         key_number: offset + index + 1,
                         command: command.clone(),
                     });
-                    button_actions[index] = Some(ButtonAction::Launch(command));
+                    button_actions[slot] = Some(ButtonAction::Launch(command));
                 }
             } else {
                 warnings.push(PagePlanWarning::LauncherLikeStatusIgnored {
@@ -455,16 +1918,25 @@ fn plan_page_layout(config: &Config, status_cache: &StatusCache, page: usize) ->
         }
 
         if let Some(action) = configured_action {
-            button_actions[index] = Some(match action {
+            button_actions[slot] = Some(match action {
                 ConfiguredAction::Launch(action) => ButtonAction::Launch(action),
                 ConfiguredAction::KeyboardShortcut(shortcut) => {
                     ButtonAction::KeyboardShortcut(shortcut)
                 }
+                ConfiguredAction::Text(text) => ButtonAction::Text(text),
+                ConfiguredAction::Macro(key_macro) => ButtonAction::Macro(key_macro),
+                ConfiguredAction::Builtin(action) => ButtonAction::Builtin(action),
             });
+        } else if is_now_playing_icon(&key.icon) && button_actions[slot].is_none() {
+            button_actions[slot] = Some(ButtonAction::Builtin(BuiltinAction::Media(
+                MediaCommand::PlayPause,
+            )));
         }
+        long_press_actions[slot] = key_long_press_action(key).map(ButtonAction::Launch);
+        double_press_actions[slot] = key_double_press_action(key).map(ButtonAction::Launch);
     }
 
-    if total_pages > 1 {
+    if layout.has_navigation_keys() && total_pages > 1 {
         let has_prev = page > 0;
         let has_next = page + 1 < total_pages;
 
@@ -474,42 +1946,47 @@ fn plan_page_layout(config: &Config, status_cache: &StatusCache, page: usize) ->
             } else {
                 layout.next_page_key()
             };
-            icons[key] = Some((PREVIOUS_PAGE_ICON.to_string(), None));
+            icons[key] = Some((PREVIOUS_PAGE_ICON.to_string(), LiveFaces::default()));
             button_actions[key] = Some(ButtonAction::PreviousPage);
         }
 
         if has_next {
             let next_key = layout.next_page_key();
-            icons[next_key] = Some((NEXT_PAGE_ICON.to_string(), None));
+            icons[next_key] = Some((NEXT_PAGE_ICON.to_string(), LiveFaces::default()));
             button_actions[next_key] = Some(ButtonAction::NextPage);
         }
     }
 
+    if let Some(back_key) = layout.folder_back_key() {
+        icons[back_key] = Some((FOLDER_BACK_ICON.to_string(), LiveFaces::default()));
+        button_actions[back_key] = Some(ButtonAction::FolderBack);
+    }
+
     PageLayoutPlan {
         page,
         total_pages,
         icons,
+        titles,
         button_actions,
+        long_press_actions,
+        double_press_actions,
         status_slots,
+        timer_slots,
         warnings,
     }
 }
 
 fn set_page(
-    device: &HidDevice,
-    config: &Config,
+    deck: &ConnectedDeck,
+    plan: &PageLayoutPlan,
     image_dir: &Path,
     image_cache: &mut ImageCache,
-    status_cache: &StatusCache,
-    page: usize,
     blank_image: &[u8],
 ) -> PageState {
-    let mut state = PageState {
-        button_actions: std::array::from_fn(|_| None),
-        dynamic_states: std::array::from_fn(|_| None),
-        status_states: std::array::from_fn(|_| None),
-    };
-    let plan = plan_page_layout(config, status_cache, page);
+    let mut state = PageState::empty(deck.model.key_count);
+    state.button_actions = plan.button_actions.clone();
+    state.long_press_actions = plan.long_press_actions.clone();
+    state.double_press_actions = plan.double_press_actions.clone();
 
     for warning in &plan.warnings {
         match warning {
@@ -526,46 +2003,20 @@ fn set_page(
             ),
         }
     }
-
-    state.button_actions = plan.button_actions.clone();
-
-    for key in 0..KEY_COUNT {
-        if let Some((ref icon, ref clock_background)) = plan.icons[key]
-            && let Err(err) = apply_icon_to_key(
-                device,
-                image_dir,
-                image_cache,
-                &mut state,
-                key,
-                icon,
-                clock_background.as_deref(),
-            )
-        {
-            eprintln!("{err}");
-        }
-
-        if let Some(status) = &plan.status_slots[key] {
-            state.status_states[key] = Some(StatusKeyState {
-                command: status.command.clone(),
-                icon_on: status.icon_on.clone(),
-                icon_off: status.icon_off.clone(),
-                clock_background: status.clock_background.clone(),
-                check_interval: status.check_interval,
-                next_check_at: if status.poll_now {
-                    Instant::now()
-                } else {
-                    Instant::now() + status.check_interval
-                },
-                current_on: status.current_on,
-            });
+
+    for key in 0..plan.icons.len() {
+        if let Err(err) = apply_planned_icon(deck, image_dir, image_cache, &mut state, plan, key) {
+            eprintln!("{err}");
         }
+
+        state.status_states[key] = plan.status_slots[key].as_ref().map(status_key_state);
     }
 
     for (key, icon) in plan.icons.iter().enumerate() {
         if icon.is_some() {
             continue;
         }
-        if let Err(err) = set_key_image_data(device, key as u8, blank_image) {
+        if let Err(err) = deck.set_key_image(key, blank_image) {
             eprintln!("{err}");
         }
     }
@@ -573,63 +2024,67 @@ fn set_page(
     state
 }
 
+fn status_key_state(status: &PlannedStatusKey) -> StatusKeyState {
+    StatusKeyState {
+        command: status.command.clone(),
+        icons: status.icons.clone(),
+        faces: status.faces.clone(),
+        title: status.title.clone(),
+        check_interval: status.check_interval,
+        next_check_at: if status.poll_now {
+            Instant::now()
+        } else {
+            Instant::now() + status.check_interval
+        },
+        current: status.current.clone(),
+    }
+}
+
+fn draw_metric_sample(
+    deck: &ConnectedDeck,
+    key: usize,
+    metric: &mut MetricKeyState,
+    sampled: Result<MetricSample, String>,
+) {
+    match render_metric_icon(
+        &metric.sampler,
+        sampled,
+        &metric.face,
+        metric.title.as_ref(),
+        deck.model,
+    ) {
+        Ok((image, error)) => {
+            if let Some(err) = error
+                .as_ref()
+                .filter(|err| Some(*err) != metric.last_error.as_ref())
+            {
+                eprintln!("{err}");
+            }
+            metric.last_error = error;
+            if let Err(err) = deck.set_key_image(key, &image) {
+                eprintln!("{err}");
+            }
+        }
+        Err(err) => eprintln!("{err}"),
+    }
+}
+
 fn advance_dynamic_keys(
-    device: &HidDevice,
+    deck: &ConnectedDeck,
     image_dir: &Path,
-    image_cache: &mut ImageCache,
-    status_cache: &mut StatusCache,
+    status_pool: &mut StatusPool,
     state: &mut PageState,
 ) {
     let now = Instant::now();
-    for key in 0..KEY_COUNT {
-        let check = match state.status_states[key].as_ref() {
-            Some(status) if now >= status.next_check_at => Some((
-                status.command.clone(),
-                status.icon_on.clone(),
-                status.icon_off.clone(),
-                status.clock_background.clone(),
-                status.current_on,
-                status.check_interval,
-            )),
-            _ => None,
-        };
-
-        if let Some((command, icon_on, icon_off, clock_background, current_on, check_interval)) =
-            check
-        {
-            let new_state = match run_status_check(&command) {
-                Ok(is_on) => Some(is_on),
-                Err(err) => {
-                    eprintln!("{err}");
-                    None
-                }
-            };
-
-            if let Some(is_on) = new_state
-                && current_on != Some(is_on)
-            {
-                let icon = if is_on { &icon_on } else { &icon_off };
-                if let Err(err) = apply_icon_to_key(
-                    device,
-                    image_dir,
-                    image_cache,
-                    state,
-                    key,
-                    icon,
-                    clock_background.as_deref(),
-                ) {
-                    eprintln!("{err}");
-                }
-            }
-
-            if let Some(status) = state.status_states[key].as_mut() {
-                if let Some(is_on) = new_state {
-                    status_cache.insert(status.command.clone(), is_on);
-                    status.current_on = Some(is_on);
-                }
-                status.next_check_at = now + check_interval;
-            }
+    for status in state.status_states.iter_mut().flatten() {
+        if now < status.next_check_at {
+            continue;
         }
+        status_pool.submit(StatusCheck {
+            command: status.command.clone(),
+            mode: status.icons.mode(),
+        });
+        status.next_check_at = now + status.check_interval;
     }
 
     for (key, dynamic_state) in state.dynamic_states.iter_mut().enumerate() {
@@ -642,11 +2097,9 @@ fn advance_dynamic_keys(
 
                     animation.current_frame =
                         (animation.current_frame + 1) % animation.frames.len();
-                    if let Err(err) = set_key_image_data(
-                        device,
-                        key as u8,
-                        &animation.frames[animation.current_frame],
-                    ) {
+                    if let Err(err) =
+                        deck.set_key_image(key, &animation.frames[animation.current_frame])
+                    {
                         eprintln!("{err}");
                         continue;
                     }
@@ -657,15 +2110,18 @@ fn advance_dynamic_keys(
                         continue;
                     }
 
-                    let next_text = current_clock_text();
+                    let time = clock.face.now();
+                    let next_text = clock.face.text(time);
                     if next_text != clock.current_text {
                         match render_clock_svg(
                             image_dir,
-                            &next_text,
-                            clock.background_name.as_deref(),
+                            &clock.face,
+                            time,
+                            clock.title.as_ref(),
+                            deck.model,
                         ) {
                             Ok(image) => {
-                                if let Err(err) = set_key_image_data(device, key as u8, &image) {
+                                if let Err(err) = deck.set_key_image(key, &image) {
                                     eprintln!("{err}");
                                 } else {
                                     clock.current_text = next_text;
@@ -674,7 +2130,7 @@ fn advance_dynamic_keys(
                             Err(err) => eprintln!("{err}"),
                         }
                     }
-                    clock.next_update_at = now + Duration::from_secs(1);
+                    clock.next_update_at = now + clock.face.refresh_delay(time);
                 }
                 DynamicKeyState::Calendar(calendar) => {
                     if now < calendar.next_update_at {
@@ -683,9 +2139,13 @@ fn advance_dynamic_keys(
 
                     let next_key = current_calendar_key();
                     if next_key != calendar.current_key {
-                        match render_calendar_icon() {
+                        match render_calendar_icon(
+                            &calendar.face,
+                            calendar.title.as_ref(),
+                            deck.model,
+                        ) {
                             Ok(image) => {
-                                if let Err(err) = set_key_image_data(device, key as u8, &image) {
+                                if let Err(err) = deck.set_key_image(key, &image) {
                                     eprintln!("{err}");
                                 } else {
                                     calendar.current_key = next_key;
@@ -696,6 +2156,25 @@ fn advance_dynamic_keys(
                     }
                     calendar.next_update_at = next_midnight_instant();
                 }
+                DynamicKeyState::Metric(metric) => {
+                    if now < metric.next_update_at {
+                        continue;
+                    }
+
+                    metric.next_update_at = now + metric.face.interval;
+                    match metric.sampler.disk_path() {
+                        Some(path) => {
+                            status_pool.read_disk(path);
+                        }
+                        None => {
+                            let sampled = metric.sampler.sample();
+                            draw_metric_sample(deck, key, metric, sampled);
+                        }
+                    }
+                }
+                DynamicKeyState::Brightness
+                | DynamicKeyState::Timer(_)
+                | DynamicKeyState::NowPlaying { .. } => {}
             }
         }
     }
@@ -711,12 +2190,13 @@ pub(crate) fn run() {
             return;
         }
     };
-    install_reload_signal_handler();
+    let (loop_tx, loop_rx) = mpsc::channel();
+    install_reload_signal_handler(loop_tx.clone());
 
     let profile_locked = args.config_path.is_some() || args.profile_explicit;
-    let mut profile = args.profile.clone();
+    let profile = args.profile.clone();
 
-    let mut config_path = match args.config_path.clone() {
+    let config_path = match args.config_path.clone() {
         Some(path) => path,
         None => match default_config_path(&profile) {
             Ok(path) => path,
@@ -727,7 +2207,7 @@ pub(crate) fn run() {
         },
     };
 
-    let mut image_dir = match default_image_dir(&profile) {
+    let image_dir = match default_image_dir(&profile) {
         Ok(path) => path,
         Err(err) => {
             eprintln!("{err}");
@@ -781,172 +2261,485 @@ pub(crate) fn run() {
             eprintln!("Using blank button layout until a readable profile config is available");
         }
     }
-    let mut image_cache = build_image_cache(&config, &image_dir);
-    let mut status_cache = StatusCache::new();
 
-    let blank_image = match blank_image_data() {
-        Ok(data) => data,
-        Err(err) => {
-            eprintln!("{err}");
-            return;
-        }
+    let mut session = DaemonSession {
+        profile,
+        profile_locked,
+        config_path,
+        image_dir,
+        config,
+        config_raw,
+        debug: args.debug,
+        files_watched: false,
+        state_path: streamrs_state_path(),
     };
+    #[cfg(target_os = "linux")]
+    {
+        let mut watched_dirs = vec![session.image_dir.clone()];
+        watched_dirs.extend(session.config_path.parent().map(Path::to_path_buf));
+        if !session.profile_locked {
+            watched_dirs.extend(current_profile_path().parent().map(Path::to_path_buf));
+        }
+        watched_dirs.sort();
+        watched_dirs.dedup();
+        if let Err(err) = watch::watch_hidraw(loop_tx.clone()) {
+            eprintln!("{err}; looking for new Stream Decks only while a configured one is missing");
+        }
+        match watch::watch_files(&watched_dirs, loop_tx.clone()) {
+            Ok(()) => session.files_watched = true,
+            Err(err) => eprintln!(
+                "{err}; checking for config changes every {} seconds instead",
+                RELOAD_RETRY_INTERVAL.as_secs()
+            ),
+        }
+    }
+
+    let (event_tx, event_rx) = mpsc::channel();
+    match serve_dbus(None, None, loop_tx.clone(), event_rx) {
+        Ok(()) => eprintln!("Owning D-Bus name '{DBUS_NAME}' on the session bus"),
+        Err(err) => eprintln!("{err}"),
+    }
+    match control_socket_path() {
+        Some(path) => match bind_control_socket(&path) {
+            Ok(listener) => {
+                eprintln!("Listening for control requests on '{}'", path.display());
+                serve_control_socket(listener, loop_tx.clone());
+            }
+            Err(err) => eprintln!("{err}"),
+        },
+        None => eprintln!("XDG_RUNTIME_DIR is not set; control socket disabled"),
+    }
+    for provider in default_focus_providers() {
+        let name = provider.name();
+        match provider.start(loop_tx.clone()) {
+            Ok(()) => eprintln!("Following the focused window through {name}"),
+            Err(err) => eprintln!("{err}; {name} focus tracking disabled"),
+        }
+    }
 
-    let mut device: Option<HidDevice> = None;
-    let mut current_page = 0usize;
-    let mut total_pages = page_count(&config);
-    let mut page_state: Option<PageState> = None;
-    let mut last_reload_check = Instant::now();
-    let mut last_pressed_button = None;
-    let mut last_device_probe = Instant::now() - Duration::from_secs(1);
+    run_event_loop(session, connect_hid_decks, &loop_tx, &loop_rx, &event_tx);
+}
+
+fn connect_hid_decks(
+    config: &Config,
+    wanted: &mut dyn FnMut(&str, u16) -> bool,
+) -> Vec<ConnectedDeck> {
+    get_devices(config.vendor_id, config.usage, config.usage_page, wanted)
+        .into_iter()
+        .map(|(device, input, model, serial)| {
+            ConnectedDeck::new(Box::new(device), Box::new(input), model, serial)
+        })
+        .collect()
+}
+
+fn mapped_profile_config_path(config_path: &Path, profile: &str) -> PathBuf {
+    config_path.with_file_name(format!("{profile}.toml"))
+}
+
+fn mapped_profile_for_serial(
+    devices: &BTreeMap<String, String>,
+    profile: &str,
+    serial: &str,
+) -> Option<String> {
+    devices
+        .get(serial)
+        .filter(|mapped| mapped.as_str() != profile)
+        .cloned()
+}
+
+fn keep_device_settings(config: &mut Config, base: &Config) {
+    config.vendor_id = base.vendor_id;
+    config.product_id = base.product_id;
+    config.usage = base.usage;
+    config.usage_page = base.usage_page;
+}
+
+fn load_mapped_profile(config_path: &Path, profile: &str) -> Result<MappedProfile, String> {
+    let config_path = mapped_profile_config_path(config_path, profile);
+    let config_raw = read_config_file(&config_path)?;
+    let config = load_profile_config(profile, &config_path)?;
+    Ok(MappedProfile {
+        config_path,
+        config,
+        config_raw,
+    })
+}
+
+fn session_config<'a>(
+    profile: Option<&str>,
+    primary: &'a Config,
+    mapped_profiles: &'a HashMap<String, MappedProfile>,
+) -> &'a Config {
+    profile
+        .and_then(|profile| mapped_profiles.get(profile))
+        .map_or(primary, |mapped| &mapped.config)
+}
+
+fn run_event_loop(
+    session: DaemonSession,
+    mut connect: impl FnMut(&Config, &mut dyn FnMut(&str, u16) -> bool) -> Vec<ConnectedDeck>,
+    loop_tx: &mpsc::Sender<LoopEvent>,
+    loop_rx: &mpsc::Receiver<LoopEvent>,
+    events: &mpsc::Sender<DaemonEvent>,
+) {
+    let DaemonSession {
+        mut profile,
+        profile_locked,
+        mut config_path,
+        mut image_dir,
+        mut config,
+        mut config_raw,
+        debug,
+        files_watched,
+        state_path,
+    } = session;
+
+    let mut decks: Vec<DeckSession> = Vec::new();
+    let mut mapped_profiles: HashMap<String, MappedProfile> = HashMap::new();
+    let mut page_cursors: HashMap<String, usize> = HashMap::new();
+    let mut skipped_serials: HashSet<String> = HashSet::new();
     let mut waiting_for_device_logged = false;
-    let (status_refresh_tx, status_refresh_rx) = mpsc::channel::<usize>();
+    let mut reload_replies: Vec<(mpsc::Sender<ControlResponse>, Option<String>)> = Vec::new();
+    let mut changed_files: HashSet<PathBuf> = HashSet::new();
+    let mut session_locked = false;
+    let mut now_playing: Option<NowPlaying> = None;
+    // The profile picked with current_profile or on the command line, which
+    // `auto_profile` rules temporarily replace while a matching app has focus.
+    let mut base_profile = profile.clone();
+    let mut base_config_path = config_path.clone();
+    let mut auto_rules = config.auto_profile.clone();
+    let mut devices = config.devices.clone();
+    let mut auto_profile: Option<String> = None;
+    let mut status_pool = StatusPool::new(STATUS_WORKERS, STATUS_CHECK_TIMEOUT, loop_tx.clone());
+    let mut scheduler = Scheduler::default();
+    scheduler.schedule(Timer::DeviceProbe, Some(Instant::now()));
+    let mut probe_interval = DEVICE_PROBE_INTERVAL;
+    if !files_watched {
+        scheduler.schedule(
+            Timer::ReloadCheck,
+            Some(Instant::now() + RELOAD_RETRY_INTERVAL),
+        );
+    }
 
     loop {
-        if device.is_none() && last_device_probe.elapsed() >= Duration::from_millis(500) {
-            last_device_probe = Instant::now();
-            if !waiting_for_device_logged {
-                eprintln!("Waiting for Stream Deck connection...");
-                waiting_for_device_logged = true;
+        let first_event = match scheduler.next_deadline() {
+            Some(deadline) => {
+                match loop_rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                    Ok(event) => Some(event),
+                    Err(mpsc::RecvTimeoutError::Timeout) => None,
+                    Err(mpsc::RecvTimeoutError::Disconnected) => return,
+                }
             }
+            None => match loop_rx.recv() {
+                Ok(event) => Some(event),
+                Err(_) => return,
+            },
+        };
 
-            if let Some(connected_device) = get_device(
-                config.vendor_id,
-                config.product_id,
-                config.usage,
-                config.usage_page,
-            ) {
-                eprintln!("Stream Deck connected");
-                if let Err(err) = set_brightness(&connected_device, config.brightness.clamp(0, 100))
-                {
-                    eprintln!("{err}");
+        let mut signal_requested = false;
+        let mut focus_switch = false;
+        let mut reload_due_to_device_issue = false;
+        for event in first_event
+            .into_iter()
+            .chain(iter::from_fn(|| loop_rx.try_recv().ok()))
+        {
+            match event {
+                #[cfg(test)]
+                LoopEvent::Shutdown => return,
+                LoopEvent::Reload => signal_requested = true,
+                LoopEvent::SessionLocked(locked) => {
+                    session_locked = locked;
+                    for session in &mut decks {
+                        let deck_config =
+                            session_config(session.profile.as_deref(), &config, &mapped_profiles);
+                        session.set_session_locked(locked, deck_config, &image_dir);
+                    }
+                }
+                LoopEvent::MediaChanged(changed) => {
+                    now_playing = changed;
+                    for session in &mut decks {
+                        session.set_now_playing(now_playing.clone());
+                    }
+                }
+                LoopEvent::SwitchProfile(requested) => {
+                    match request_profile_switch(&requested, profile_locked) {
+                        Ok(()) => signal_requested = true,
+                        Err(err) => eprintln!("{err}"),
+                    }
+                }
+                LoopEvent::FocusChanged(focused) => {
+                    let target = auto_profile_for(&auto_rules, &focused)
+                        .filter(|target| *target != base_profile);
+                    if target != auto_profile {
+                        auto_profile = target;
+                        focus_switch = true;
+                    }
+                }
+                LoopEvent::StatusChecked(check, result) => {
+                    status_pool.finish(&check);
+                    match result {
+                        Ok(reading) => {
+                            for session in &mut decks {
+                                session.apply_status_reading(&image_dir, &check, &reading);
+                            }
+                        }
+                        Err(err) => eprintln!("{err}"),
+                    }
+                }
+                LoopEvent::DiskRead(path, usage) => {
+                    status_pool.finish_disk(&path);
+                    for session in &mut decks {
+                        session.apply_disk_usage(&path, &usage);
+                    }
+                }
+                LoopEvent::DevicesChanged => {
+                    probe_interval = DEVICE_PROBE_INTERVAL;
+                    scheduler.schedule(
+                        Timer::DeviceProbe,
+                        Some(Instant::now() + DEVICE_HOTPLUG_DEBOUNCE),
+                    );
+                }
+                LoopEvent::FileChanged(path) => {
+                    changed_files.insert(path);
+                    scheduler.schedule(
+                        Timer::FileChanges,
+                        Some(Instant::now() + FILE_CHANGE_DEBOUNCE),
+                    );
+                }
+                LoopEvent::Deck(serial, request) => {
+                    let Some(session) = decks
+                        .iter_mut()
+                        .find(|session| session.deck.serial == serial)
+                    else {
+                        continue;
+                    };
+                    match request {
+                        DeckRequest::RefreshStatus(key_index) => {
+                            request_immediate_status_check(&mut session.page_state, key_index);
+                        }
+                        DeckRequest::Page(target) => {
+                            let deck_config = session_config(
+                                session.profile.as_deref(),
+                                &config,
+                                &mapped_profiles,
+                            );
+                            session.go_to_page(deck_config, &image_dir, target);
+                        }
+                    }
+                }
+                LoopEvent::KeyStates(serial, result) => {
+                    let Some(index) = decks
+                        .iter()
+                        .position(|session| session.deck.serial == serial)
+                    else {
+                        continue;
+                    };
+                    match result {
+                        Ok(pressed) => {
+                            let session = &mut decks[index];
+                            let deck_config = session_config(
+                                session.profile.as_deref(),
+                                &config,
+                                &mapped_profiles,
+                            );
+                            session.handle_key_states(
+                                pressed,
+                                deck_config,
+                                &image_dir,
+                                debug,
+                                loop_tx,
+                            );
+                        }
+                        Err(err) => {
+                            if err.to_ascii_lowercase().contains("device disconnected") {
+                                eprintln!("Lost Stream Deck connection while reading button state");
+                            } else {
+                                eprintln!("{err}");
+                            }
+                            eprintln!("Stream Deck '{serial}' disconnected");
+                            let session = decks.remove(index);
+                            page_cursors.insert(serial, session.current_page);
+                            probe_interval = DEVICE_PROBE_INTERVAL;
+                            reload_due_to_device_issue = true;
+                        }
+                    }
+                }
+                LoopEvent::Control(ControlMessage { request, reply }) => {
+                    let response = match request {
+                        ControlRequest::Reload => {
+                            reload_replies.push((reply, None));
+                            continue;
+                        }
+                        ControlRequest::SwitchProfile { profile: requested } => {
+                            match request_profile_switch(&requested, profile_locked) {
+                                Ok(()) => {
+                                    reload_replies.push((reply, Some(requested)));
+                                    continue;
+                                }
+                                Err(err) => ControlResponse::failure(err),
+                            }
+                        }
+                        ControlRequest::FocusChanged { app_id } => {
+                            let focused = FocusedApp::from_app_id(app_id.as_deref());
+                            let _ = loop_tx.send(LoopEvent::FocusChanged(focused));
+                            ControlResponse::success()
+                        }
+                        ControlRequest::State => ControlResponse::with_state(daemon_state(
+                            &profile,
+                            &decks,
+                            &config,
+                            &mapped_profiles,
+                        )),
+                        request => match run_deck_control_request(
+                            &request,
+                            &mut decks,
+                            &config,
+                            &mapped_profiles,
+                            &image_dir,
+                            debug,
+                            loop_tx,
+                        ) {
+                            Ok(()) => ControlResponse::success(),
+                            Err(err) => ControlResponse::failure(err),
+                        },
+                    };
+                    let _ = reply.send(response);
                 }
-                total_pages = page_count(&config);
-                current_page = min(current_page, total_pages.saturating_sub(1));
-                page_state = Some(set_page(
-                    &connected_device,
-                    &config,
-                    &image_dir,
-                    &mut image_cache,
-                    &status_cache,
-                    current_page,
-                    &blank_image,
-                ));
-                last_pressed_button = None;
-                waiting_for_device_logged = false;
-                device = Some(connected_device);
             }
         }
 
-        let mut disconnected = false;
-        let mut reload_due_to_device_issue = false;
-        if let (Some(device_ref), Some(page_state_ref)) = (device.as_ref(), page_state.as_mut()) {
-            while let Ok(key_index) = status_refresh_rx.try_recv() {
-                request_immediate_status_check(page_state_ref, key_index);
+        let now = Instant::now();
+        let mut probe_due = false;
+        let mut periodic_reload = false;
+        let mut files_changed = false;
+        for timer in scheduler.take_due(now) {
+            match timer {
+                Timer::DeviceProbe => probe_due = true,
+                Timer::ReloadCheck => periodic_reload = true,
+                Timer::FileChanges => files_changed = true,
+                Timer::Deck(_) => {}
             }
+        }
 
-            advance_dynamic_keys(
-                device_ref,
-                &image_dir,
-                &mut image_cache,
-                &mut status_cache,
-                page_state_ref,
-            );
+        if probe_due {
+            if decks.is_empty() && !waiting_for_device_logged {
+                eprintln!("Waiting for Stream Deck connection...");
+                waiting_for_device_logged = true;
+            }
 
-            match read_states(device_ref, 10) {
-                Ok(pressed_button) => {
-                    if pressed_button != last_pressed_button {
-                        if let Some(index) = pressed_button
-                            && let Some(action) = page_state_ref.button_actions[index].clone()
-                        {
-                            match action {
-                                ButtonAction::Launch(action) => {
-                                    watch_action_completion(
-                                        &action,
-                                        args.debug,
-                                        index,
-                                        &status_refresh_tx,
-                                    );
-                                }
-                                ButtonAction::KeyboardShortcut(shortcut) => {
-                                    if let Err(err) = send_shortcut(&shortcut) {
-                                        eprintln!("{err}");
-                                    } else {
-                                        request_immediate_status_check(page_state_ref, index);
-                                    }
-                                }
-                                ButtonAction::PreviousPage => {
-                                    if current_page > 0 {
-                                        current_page -= 1;
-                                        *page_state_ref = set_page(
-                                            device_ref,
-                                            &config,
-                                            &image_dir,
-                                            &mut image_cache,
-                                            &status_cache,
-                                            current_page,
-                                            &blank_image,
-                                        );
-                                    }
-                                }
-                                ButtonAction::NextPage => {
-                                    if current_page + 1 < total_pages {
-                                        current_page += 1;
-                                        *page_state_ref = set_page(
-                                            device_ref,
-                                            &config,
-                                            &image_dir,
-                                            &mut image_cache,
-                                            &status_cache,
-                                            current_page,
-                                            &blank_image,
-                                        );
-                                    }
-                                }
-                            }
+            let connected_serials = decks
+                .iter()
+                .map(|session| session.deck.serial.clone())
+                .collect::<HashSet<_>>();
+            let mut wanted = |serial: &str, product_id: u16| {
+                if connected_serials.contains(serial) || skipped_serials.contains(serial) {
+                    return false;
+                }
+                let Some(mapped) = mapped_profile_for_serial(&devices, &base_profile, serial)
+                else {
+                    return product_id == config.product_id;
+                };
+                if !mapped_profiles.contains_key(&mapped) {
+                    match load_mapped_profile(&base_config_path, &mapped) {
+                        Ok(loaded) => {
+                            mapped_profiles.insert(mapped.clone(), loaded);
+                        }
+                        Err(err) => {
+                            eprintln!("{err}");
+                            skipped_serials.insert(serial.to_string());
+                            return false;
                         }
-
-                        last_pressed_button = pressed_button;
                     }
                 }
-                Err(err) => {
-                    if err.to_ascii_lowercase().contains("device disconnected") {
-                        eprintln!("Lost Stream Deck connection while reading button state");
-                    } else {
-                        eprintln!("{err}");
+                let expected = mapped_profiles[&mapped].config.product_id;
+                if product_id != expected {
+                    eprintln!(
+                        "Stream Deck '{}' has product_id 0x{:04x} but profile '{}' expects 0x{:04x}",
+                        serial, product_id, mapped, expected
+                    );
+                    skipped_serials.insert(serial.to_string());
+                    return false;
+                }
+                true
+            };
+
+            for connected in connect(&config, &mut wanted) {
+                let serial = connected.serial.clone();
+                let deck_profile = mapped_profile_for_serial(&devices, &base_profile, &serial);
+                let deck_config =
+                    session_config(deck_profile.as_deref(), &config, &mapped_profiles);
+                eprintln!(
+                    "{} '{}' connected (profile '{}')",
+                    connected.model.name,
+                    serial,
+                    deck_profile.as_deref().unwrap_or(&profile)
+                );
+                let page = page_cursors.remove(&serial).unwrap_or(0);
+                match DeckSession::open(
+                    connected,
+                    deck_profile,
+                    deck_config,
+                    &image_dir,
+                    page,
+                    &state_path,
+                    loop_tx,
+                    events.clone(),
+                ) {
+                    Ok(mut session) => {
+                        if session_locked {
+                            session.set_session_locked(true, deck_config, &image_dir);
+                        }
+                        if now_playing.is_some() {
+                            session.set_now_playing(now_playing.clone());
+                        }
+                        decks.push(session);
+                        waiting_for_device_logged = false;
                     }
-                    disconnected = true;
-                    reload_due_to_device_issue = true;
+                    Err(err) => eprintln!("{err}"),
                 }
             }
         }
 
-        if disconnected {
-            eprintln!("Stream Deck disconnected");
-            device = None;
-            page_state = None;
-            last_pressed_button = None;
-        }
-
-        let signal_requested = take_reload_request();
-        let periodic_reload = last_reload_check.elapsed() >= RELOAD_RETRY_INTERVAL;
-        if periodic_reload {
-            last_reload_check = Instant::now();
+        let mut config_changed = false;
+        if files_changed {
+            let changed = mem::take(&mut changed_files);
+            let profile_file = current_profile_path();
+            config_changed = changed.iter().any(|path| {
+                *path == profile_file
+                    || (path.parent() == config_path.parent()
+                        && path.extension().is_some_and(|ext| ext == "toml"))
+            });
+            let icons = changed
+                .iter()
+                .filter_map(|path| path.strip_prefix(&image_dir).ok())
+                .filter_map(|name| name.to_str())
+                .map(str::to_string)
+                .collect::<HashSet<_>>();
+            if !icons.is_empty() {
+                for session in &mut decks {
+                    let deck_config =
+                        session_config(session.profile.as_deref(), &config, &mapped_profiles);
+                    session.refresh_icons(deck_config, &image_dir, &icons);
+                }
+            }
         }
 
-        if signal_requested || periodic_reload || reload_due_to_device_issue {
-            if reload_due_to_device_issue {
-                last_reload_check = Instant::now();
+        signal_requested |= !reload_replies.is_empty();
+        if signal_requested || periodic_reload || reload_due_to_device_issue || config_changed {
+            if !files_watched && (periodic_reload || reload_due_to_device_issue) {
+                scheduler.schedule(Timer::ReloadCheck, Some(now + RELOAD_RETRY_INTERVAL));
             }
-            let mut reload_profile = profile.clone();
-            let mut reload_path = config_path.clone();
+            skipped_serials.clear();
+            let deck_count = decks.len();
+            let mut reload_profile = base_profile.clone();
+            let mut reload_path = base_config_path.clone();
             let mut reload_image_dir = image_dir.clone();
 
             if !profile_locked {
                 let discovered_profiles = discover_profiles();
                 match load_current_profile() {
-                    Ok(Some(selected_profile)) if selected_profile != profile => {
-                        if !(selected_profile == BLANK_PROFILE && !discovered_profiles.is_empty()) {
+                    Ok(Some(selected_profile)) if selected_profile != base_profile => {
+                        if selected_profile != BLANK_PROFILE || discovered_profiles.is_empty() {
                             match (
                                 default_config_path(&selected_profile),
                                 default_image_dir(&selected_profile),
@@ -965,13 +2758,37 @@ pub(crate) fn run() {
                         eprintln!("{err}");
                         eprintln!(
                             "Keeping current profile '{}' after current_profile read error",
-                            profile
+                            base_profile
                         );
                     }
                 }
             }
+            let base_switched = reload_profile != base_profile;
+            if base_switched {
+                base_profile = reload_profile.clone();
+                base_config_path = reload_path.clone();
+            }
+
+            let auto_switched = match auto_profile.as_ref() {
+                Some(target) if *target != base_profile => {
+                    reload_path = mapped_profile_config_path(&base_config_path, target);
+                    reload_profile = target.clone();
+                    true
+                }
+                _ => false,
+            };
+            if auto_switched && (base_switched || config_changed || signal_requested) {
+                match load_profile_config(&base_profile, &base_config_path) {
+                    Ok(base) => {
+                        auto_rules = base.auto_profile;
+                        devices = base.devices;
+                    }
+                    Err(err) => eprintln!("{err}"),
+                }
+            }
 
             if reload_path != config_path
+                && !auto_switched
                 && let Err(err) =
                     ensure_profile_initialized(&reload_profile, &reload_path, &reload_image_dir)
             {
@@ -987,52 +2804,51 @@ pub(crate) fn run() {
                     let should_parse = signal_requested || profile_switched || raw != config_raw;
                     if should_parse {
                         match load_profile_config(&reload_profile, &reload_path) {
-                            Ok(new_config) => {
-                                if (
-                                    new_config.vendor_id,
-                                    new_config.product_id,
-                                    new_config.usage,
-                                    new_config.usage_page,
-                                ) != (
-                                    config.vendor_id,
-                                    config.product_id,
-                                    config.usage,
-                                    config.usage_page,
-                                ) {
-                                    eprintln!(
-                                        "Warning: HID identifiers changed in config; existing connection will keep using the current device until it reconnects"
-                                    );
+                            Ok(mut new_config) => {
+                                if auto_switched {
+                                    keep_device_settings(&mut new_config, &config);
                                 }
-
-                                total_pages = page_count(&new_config);
-                                if profile_switched {
-                                    current_page = 0;
+                                let (mapping, mapping_profile) = if auto_switched {
+                                    (&devices, &base_profile)
                                 } else {
-                                    current_page = min(current_page, total_pages.saturating_sub(1));
-                                }
-                                image_cache = build_image_cache(&new_config, &reload_image_dir);
+                                    (&new_config.devices, &reload_profile)
+                                };
+                                let hid_changed =
+                                    (
+                                        new_config.vendor_id,
+                                        new_config.usage,
+                                        new_config.usage_page,
+                                    ) != (config.vendor_id, config.usage, config.usage_page);
+                                let product_changed = new_config.product_id != config.product_id;
+                                // The device model follows product_id and decks may move
+                                // between profiles, so affected sessions reconnect instead.
+                                decks.retain(|session| {
+                                    let serial = &session.deck.serial;
+                                    let reassigned =
+                                        mapped_profile_for_serial(mapping, mapping_profile, serial)
+                                            != session.profile;
+                                    let stale = hid_changed
+                                        || reassigned
+                                        || (session.profile.is_none() && product_changed);
+                                    if stale {
+                                        eprintln!(
+                                            "Device settings changed in config; reconnecting Stream Deck '{serial}'"
+                                        );
+                                    }
+                                    !stale
+                                });
+
                                 if profile_switched {
-                                    status_cache.clear();
+                                    page_cursors.values_mut().for_each(|page| *page = 0);
                                 }
-
-                                if let Some(device_ref) = device.as_ref() {
-                                    if let Err(err) = set_brightness(
-                                        device_ref,
-                                        new_config.brightness.clamp(0, 100),
-                                    ) {
-                                        eprintln!("{err}");
+                                for session in
+                                    decks.iter_mut().filter(|session| session.profile.is_none())
+                                {
+                                    if profile_switched {
+                                        session.current_page = 0;
+                                        session.status_cache.clear();
                                     }
-
-                                    page_state = Some(set_page(
-                                        device_ref,
-                                        &new_config,
-                                        &reload_image_dir,
-                                        &mut image_cache,
-                                        &status_cache,
-                                        current_page,
-                                        &blank_image,
-                                    ));
-                                    last_pressed_button = None;
+                                    session.apply_config(&new_config, &reload_image_dir);
                                 }
 
                                 profile = reload_profile;
@@ -1040,12 +2856,19 @@ pub(crate) fn run() {
                                 image_dir = reload_image_dir;
                                 config = new_config;
                                 config_raw = raw;
+                                if profile == base_profile {
+                                    auto_rules = config.auto_profile.clone();
+                                    devices = config.devices.clone();
+                                }
                                 if profile_switched {
                                     eprintln!(
                                         "Switched to profile '{}' (config '{}')",
                                         profile,
                                         config_path.display()
                                     );
+                                    let _ = events.send(DaemonEvent::ProfileSwitched {
+                                        profile: profile.clone(),
+                                    });
                                 }
                                 eprintln!("Config reloaded from '{}'", config_path.display());
                             }
@@ -1055,8 +2878,263 @@ pub(crate) fn run() {
                 }
                 Err(err) => eprintln!("{err}"),
             }
+
+            mapped_profiles.retain(|name, _| {
+                decks
+                    .iter()
+                    .any(|session| session.profile.as_deref() == Some(name.as_str()))
+            });
+            for (name, mapped) in mapped_profiles.iter_mut() {
+                let raw = match read_config_file(&mapped.config_path) {
+                    Ok(raw) => raw,
+                    Err(err) => {
+                        eprintln!("{err}");
+                        continue;
+                    }
+                };
+                if !signal_requested && raw == mapped.config_raw {
+                    continue;
+                }
+                match load_profile_config(name, &mapped.config_path) {
+                    Ok(new_config) => {
+                        decks.retain(|session| {
+                            let stale = session.profile.as_deref() == Some(name.as_str())
+                                && new_config.product_id != mapped.config.product_id;
+                            if stale {
+                                eprintln!(
+                                    "Device settings changed in config; reconnecting Stream Deck '{}'",
+                                    session.deck.serial
+                                );
+                            }
+                            !stale
+                        });
+                        for session in decks
+                            .iter_mut()
+                            .filter(|session| session.profile.as_deref() == Some(name.as_str()))
+                        {
+                            session.apply_config(&new_config, &image_dir);
+                        }
+                        mapped.config = new_config;
+                        mapped.config_raw = raw;
+                        eprintln!("Config reloaded from '{}'", mapped.config_path.display());
+                    }
+                    Err(err) => eprintln!("{err}"),
+                }
+            }
+            if decks.len() < deck_count {
+                scheduler.schedule(Timer::DeviceProbe, Some(now));
+            }
+        } else if focus_switch {
+            let (target, target_path) = match &auto_profile {
+                Some(target) => (
+                    target.clone(),
+                    mapped_profile_config_path(&base_config_path, target),
+                ),
+                None => (base_profile.clone(), base_config_path.clone()),
+            };
+            let loaded = read_config_file(&target_path).and_then(|raw| {
+                load_profile_config(&target, &target_path).map(|loaded| (loaded, raw))
+            });
+            match loaded {
+                Ok((mut new_config, raw)) => {
+                    if auto_profile.is_some() {
+                        keep_device_settings(&mut new_config, &config);
+                    }
+                    for session in decks.iter_mut().filter(|session| session.profile.is_none()) {
+                        session.switch_profile(&new_config, &image_dir);
+                    }
+                    page_cursors.values_mut().for_each(|page| *page = 0);
+                    profile = target;
+                    config_path = target_path;
+                    config = new_config;
+                    config_raw = raw;
+                    if profile == base_profile {
+                        auto_rules = config.auto_profile.clone();
+                        devices = config.devices.clone();
+                    }
+                    eprintln!(
+                        "Switched to profile '{}' (config '{}')",
+                        profile,
+                        config_path.display()
+                    );
+                    let _ = events.send(DaemonEvent::ProfileSwitched {
+                        profile: profile.clone(),
+                    });
+                }
+                Err(err) => eprintln!("{err}"),
+            }
+        }
+
+        for (reply, requested) in reload_replies.drain(..) {
+            let response = match requested {
+                Some(requested) if requested != profile => ControlResponse::failure(format!(
+                    "Failed to switch to profile '{requested}'; see the daemon log"
+                )),
+                _ => ControlResponse::success(),
+            };
+            let _ = reply.send(response);
+        }
+
+        if !deck_missing(&decks, &devices, &page_cursors, &skipped_serials) {
+            probe_interval = DEVICE_PROBE_INTERVAL;
+        } else if !scheduler.is_scheduled(&Timer::DeviceProbe) {
+            scheduler.schedule(Timer::DeviceProbe, Some(now + probe_interval));
+            probe_interval = (probe_interval * 2).min(MAX_DEVICE_PROBE_INTERVAL);
+        }
+
+        for session in &mut decks {
+            let deck_config = session_config(session.profile.as_deref(), &config, &mapped_profiles);
+            session.run_timers(deck_config, &image_dir, debug, &mut status_pool, loop_tx);
+            scheduler.schedule(
+                Timer::Deck(session.deck.serial.clone()),
+                session.next_deadline(),
+            );
+        }
+    }
+}
+
+fn deck_missing(
+    decks: &[DeckSession],
+    devices: &BTreeMap<String, String>,
+    lost: &HashMap<String, usize>,
+    skipped_serials: &HashSet<String>,
+) -> bool {
+    let connected = |serial: &String| decks.iter().any(|session| session.deck.serial == *serial);
+    decks.is_empty()
+        || lost.keys().any(|serial| !connected(serial))
+        || devices
+            .keys()
+            .any(|serial| !skipped_serials.contains(serial) && !connected(serial))
+}
+
+fn request_profile_switch(requested: &str, profile_locked: bool) -> Result<(), String> {
+    if profile_locked {
+        return Err("Profile is fixed by --profile or --config and cannot be switched".to_string());
+    }
+    if !discover_profiles()
+        .iter()
+        .any(|profile| profile == requested)
+    {
+        return Err(format!("Unknown profile '{requested}'"));
+    }
+    save_current_profile(requested)
+}
+
+fn control_target_decks<'a>(
+    decks: &'a mut [DeckSession],
+    serial: Option<&str>,
+) -> Result<Vec<&'a mut DeckSession>, String> {
+    if decks.is_empty() {
+        return Err("No Stream Deck is connected".to_string());
+    }
+    let targets = decks
+        .iter_mut()
+        .filter(|session| serial.is_none_or(|serial| session.deck.serial == serial))
+        .collect::<Vec<_>>();
+    if targets.is_empty() {
+        return Err(format!(
+            "No connected Stream Deck has serial '{}'",
+            serial.unwrap_or_default()
+        ));
+    }
+    Ok(targets)
+}
+
+fn control_key_index(session: &DeckSession, key: usize) -> Result<usize, String> {
+    if key == 0 || key > session.deck.model.key_count {
+        return Err(format!(
+            "Key {key} is out of range for {} '{}' (1..={})",
+            session.deck.model.name, session.deck.serial, session.deck.model.key_count
+        ));
+    }
+    Ok(key - 1)
+}
+
+fn run_deck_control_request(
+    request: &ControlRequest,
+    decks: &mut [DeckSession],
+    config: &Config,
+    mapped_profiles: &HashMap<String, MappedProfile>,
+    image_dir: &Path,
+    debug: bool,
+    loop_tx: &mpsc::Sender<LoopEvent>,
+) -> Result<(), String> {
+    match request {
+        ControlRequest::Page { page, serial } => {
+            let target = parse_page_target(page)?;
+            for session in control_target_decks(decks, serial.as_deref())? {
+                let deck_config =
+                    session_config(session.profile.as_deref(), config, mapped_profiles);
+                session.go_to_page(deck_config, image_dir, target);
+            }
         }
+        ControlRequest::PressKey { key, serial } => {
+            for session in control_target_decks(decks, serial.as_deref())? {
+                let key = control_key_index(session, *key)?;
+                let deck_config =
+                    session_config(session.profile.as_deref(), config, mapped_profiles);
+                let action = session.page_state.button_actions[key].clone();
+                session.run_action(action, key, deck_config, image_dir, debug, loop_tx);
+            }
+        }
+        ControlRequest::SetBrightness { brightness, serial } => {
+            if *brightness > 100 {
+                return Err(format!(
+                    "Brightness {brightness} is out of range; expected 0..=100"
+                ));
+            }
+            for session in control_target_decks(decks, serial.as_deref())? {
+                session.set_brightness(*brightness);
+            }
+        }
+        ControlRequest::SetKeyImage {
+            key,
+            icon,
+            seconds,
+            serial,
+        } => {
+            let seconds = seconds.unwrap_or(DEFAULT_KEY_IMAGE_OVERRIDE_SECONDS);
+            if !(1..=MAX_KEY_IMAGE_OVERRIDE_SECONDS).contains(&seconds) {
+                return Err(format!(
+                    "Key image duration {seconds} is out of range; expected 1..={MAX_KEY_IMAGE_OVERRIDE_SECONDS} seconds"
+                ));
+            }
+            for session in control_target_decks(decks, serial.as_deref())? {
+                let key = control_key_index(session, *key)?;
+                session.override_key_image(image_dir, key, icon, Duration::from_secs(seconds))?;
+            }
+        }
+        ControlRequest::Reload
+        | ControlRequest::SwitchProfile { .. }
+        | ControlRequest::State
+        | ControlRequest::FocusChanged { .. } => {}
+    }
+    Ok(())
+}
 
-        sleep(Duration::from_millis(10));
+fn daemon_state(
+    profile: &str,
+    decks: &[DeckSession],
+    config: &Config,
+    mapped_profiles: &HashMap<String, MappedProfile>,
+) -> DaemonState {
+    DaemonState {
+        profile: profile.to_string(),
+        decks: decks
+            .iter()
+            .map(|session| {
+                let deck_config =
+                    session_config(session.profile.as_deref(), config, mapped_profiles);
+                DeckState {
+                    serial: session.deck.serial.clone(),
+                    model: session.deck.model.name.to_string(),
+                    profile: session.profile.as_deref().unwrap_or(profile).to_string(),
+                    page: session.current_page + 1,
+                    pages: page_count(deck_config, &session.folder_path()),
+                    folder: session.folder_path().iter().map(|key| key + 1).collect(),
+                    brightness: session.brightness,
+                }
+            })
+            .collect(),
     }
 }
@@ -0,0 +1,275 @@
+use super::LoopEvent;
+use super::config::MediaCommand;
+use gtk::gio;
+use gtk::glib::{self, variant::ToVariant};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::mpsc;
+use streamrs::image::media::{PlaybackStatus, art_path_from_url};
+
+const MPRIS_NAME_PREFIX: &str = "org.mpris.MediaPlayer2.";
+const MPRIS_OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+const MPRIS_PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+const PROPERTIES_INTERFACE: &str = "org.freedesktop.DBus.Properties";
+const MEDIA_CALL_TIMEOUT_MS: i32 = 2000;
+
+// What the now-playing keys show, taken from the player's `PlaybackStatus`
+// and `Metadata` properties.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct NowPlaying {
+    // The player's unique bus name, which media commands are sent to.
+    pub(crate) player: String,
+    pub(crate) status: PlaybackStatus,
+    pub(crate) title: Option<String>,
+    // Only set for `file://` art URLs.
+    pub(crate) art: Option<PathBuf>,
+}
+
+impl NowPlaying {
+    // Takes the properties of a `GetAll` reply or a `PropertiesChanged`
+    // signal; properties that are missing keep their value.
+    fn update(&mut self, properties: &glib::VariantDict) {
+        if let Ok(Some(status)) = properties.lookup::<String>("PlaybackStatus") {
+            self.status = PlaybackStatus::from_mpris(&status);
+        }
+        if let Some(metadata) = properties.lookup_value("Metadata", Some(glib::VariantTy::VARDICT))
+        {
+            let metadata = glib::VariantDict::new(Some(&metadata));
+            self.title = metadata
+                .lookup::<String>("xesam:title")
+                .ok()
+                .flatten()
+                .filter(|title| !title.trim().is_empty());
+            self.art = metadata
+                .lookup::<String>("mpris:artUrl")
+                .ok()
+                .flatten()
+                .and_then(|url| art_path_from_url(&url));
+        }
+    }
+}
+
+impl MediaCommand {
+    fn mpris_method(self) -> &'static str {
+        match self {
+            Self::PlayPause => "PlayPause",
+            Self::Next => "Next",
+            Self::Previous => "Previous",
+        }
+    }
+}
+
+// The players on the bus, most recently started first. Keys follow the first
+// one that is playing, or the first one when none is.
+struct MediaPlayers {
+    players: Vec<NowPlaying>,
+    // MPRIS names and the unique name that owns each.
+    names: HashMap<String, String>,
+    shown: Option<NowPlaying>,
+    sender: mpsc::Sender<LoopEvent>,
+}
+
+impl MediaPlayers {
+    fn update(&mut self, player: &str, properties: &glib::VariantDict) {
+        let index = self.players.iter().position(|known| known.player == player);
+        let mut now = match index {
+            Some(index) => self.players.remove(index),
+            None => NowPlaying {
+                player: player.to_string(),
+                ..NowPlaying::default()
+            },
+        };
+        let was_playing = now.status == PlaybackStatus::Playing;
+        now.update(properties);
+        let index = if now.status == PlaybackStatus::Playing && !was_playing {
+            0
+        } else {
+            index.unwrap_or(self.players.len())
+        };
+        self.players.insert(index, now);
+        self.publish();
+    }
+
+    // A player is gone once its last MPRIS name is.
+    fn release_name(&mut self, name: &str, player: &str) {
+        self.names.remove(name);
+        if !self.names.values().any(|owner| owner == player) {
+            self.players.retain(|known| known.player != player);
+            self.publish();
+        }
+    }
+
+    fn publish(&mut self) {
+        let active = self
+            .players
+            .iter()
+            .find(|now| now.status == PlaybackStatus::Playing)
+            .or(self.players.first())
+            .cloned();
+        if active != self.shown {
+            self.shown = active.clone();
+            let _ = self.sender.send(LoopEvent::MediaChanged(active));
+        }
+    }
+}
+
+fn call_bus(
+    connection: &gio::DBusConnection,
+    method: &str,
+    parameters: glib::Variant,
+) -> Result<glib::Variant, String> {
+    connection
+        .call_sync(
+            Some("org.freedesktop.DBus"),
+            "/org/freedesktop/DBus",
+            "org.freedesktop.DBus",
+            method,
+            Some(&parameters),
+            None,
+            gio::DBusCallFlags::NONE,
+            MEDIA_CALL_TIMEOUT_MS,
+            gio::Cancellable::NONE,
+        )
+        .map_err(|err| format!("D-Bus call {method} failed: {err}"))
+}
+
+fn player_properties(
+    connection: &gio::DBusConnection,
+    player: &str,
+) -> Result<glib::VariantDict, String> {
+    let reply = connection
+        .call_sync(
+            Some(player),
+            MPRIS_OBJECT_PATH,
+            PROPERTIES_INTERFACE,
+            "GetAll",
+            Some(&(MPRIS_PLAYER_INTERFACE,).to_variant()),
+            Some(glib::VariantTy::new("(a{sv})").expect("reply type should be valid")),
+            gio::DBusCallFlags::NONE,
+            MEDIA_CALL_TIMEOUT_MS,
+            gio::Cancellable::NONE,
+        )
+        .map_err(|err| format!("Failed to read MPRIS player '{player}': {err}"))?;
+    Ok(glib::VariantDict::new(Some(&reply.child_value(0))))
+}
+
+fn add_player(players: &RefCell<MediaPlayers>, connection: &gio::DBusConnection, owner: &str) {
+    match player_properties(connection, owner) {
+        Ok(properties) => players.borrow_mut().update(owner, &properties),
+        Err(err) => eprintln!("{err}"),
+    }
+}
+
+// MPRIS names on the bus with their owners.
+fn running_players(connection: &gio::DBusConnection) -> Result<Vec<(String, String)>, String> {
+    let reply = call_bus(connection, "ListNames", ().to_variant())?;
+    let (names,) = reply
+        .get::<(Vec<String>,)>()
+        .ok_or_else(|| "Unexpected ListNames reply".to_string())?;
+    Ok(names
+        .into_iter()
+        .filter(|name| name.starts_with(MPRIS_NAME_PREFIX))
+        .filter_map(|name| {
+            call_bus(connection, "GetNameOwner", (name.as_str(),).to_variant())
+                .ok()?
+                .get::<(String,)>()
+                .map(|(owner,)| (name, owner))
+        })
+        .collect())
+}
+
+// Follows MPRIS players on `connection` and reports the one the now-playing
+// keys show as `LoopEvent::MediaChanged`. Players are told apart by their
+// unique names, so one with several well-known names counts once. Dropping the
+// subscriptions stops the reports.
+pub(crate) fn watch_media_players(
+    connection: &gio::DBusConnection,
+    sender: mpsc::Sender<LoopEvent>,
+) -> Vec<gio::SignalSubscription> {
+    let players = Rc::new(RefCell::new(MediaPlayers {
+        players: Vec::new(),
+        names: HashMap::new(),
+        shown: None,
+        sender,
+    }));
+    let changes = {
+        let players = Rc::clone(&players);
+        connection.subscribe_to_signal(
+            None,
+            Some(PROPERTIES_INTERFACE),
+            Some("PropertiesChanged"),
+            Some(MPRIS_OBJECT_PATH),
+            Some(MPRIS_PLAYER_INTERFACE),
+            gio::DBusSignalFlags::NONE,
+            move |signal| {
+                let Some(changed) = signal.parameters.try_child_value(1) else {
+                    return;
+                };
+                players
+                    .borrow_mut()
+                    .update(signal.sender_name, &glib::VariantDict::new(Some(&changed)));
+            },
+        )
+    };
+    let owners = {
+        let players = Rc::clone(&players);
+        connection.subscribe_to_signal(
+            Some("org.freedesktop.DBus"),
+            Some("org.freedesktop.DBus"),
+            Some("NameOwnerChanged"),
+            Some("/org/freedesktop/DBus"),
+            None,
+            gio::DBusSignalFlags::NONE,
+            move |signal| {
+                let Some((name, old_owner, new_owner)) =
+                    signal.parameters.get::<(String, String, String)>()
+                else {
+                    return;
+                };
+                if !name.starts_with(MPRIS_NAME_PREFIX) {
+                    return;
+                }
+                if !old_owner.is_empty() {
+                    players.borrow_mut().release_name(&name, &old_owner);
+                }
+                if !new_owner.is_empty() {
+                    players.borrow_mut().names.insert(name, new_owner.clone());
+                    add_player(&players, signal.connection, &new_owner);
+                }
+            },
+        )
+    };
+    match running_players(connection) {
+        Ok(names) => {
+            for (name, owner) in names {
+                players.borrow_mut().names.insert(name, owner.clone());
+                add_player(&players, connection, &owner);
+            }
+        }
+        Err(err) => eprintln!("{err}"),
+    }
+    vec![changes, owners]
+}
+
+pub(crate) fn send_media_command(
+    connection: &gio::DBusConnection,
+    player: &str,
+    command: MediaCommand,
+) {
+    let method = command.mpris_method();
+    if let Err(err) = connection.call_sync(
+        Some(player),
+        MPRIS_OBJECT_PATH,
+        MPRIS_PLAYER_INTERFACE,
+        method,
+        None,
+        None,
+        gio::DBusCallFlags::NONE,
+        MEDIA_CALL_TIMEOUT_MS,
+        gio::Cancellable::NONE,
+    ) {
+        eprintln!("Failed to send {method} to MPRIS player '{player}': {err}");
+    }
+}
@@ -0,0 +1,98 @@
+use super::config::StatusMode;
+use super::{LoopEvent, run_status_check};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
+use std::time::Duration;
+use streamrs::metrics::read_disk_usage;
+
+pub(crate) const STATUS_WORKERS: usize = 4;
+
+// A status command and how its result is read. Keys sharing both share one run.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct StatusCheck {
+    pub(crate) command: String,
+    pub(crate) mode: StatusMode,
+}
+
+// Runs status commands on a fixed set of worker threads and posts each result
+// to the event loop as `LoopEvent::StatusChecked`. Workers stop once the pool
+// is dropped. Disk usage of metric keys is read here too, as
+// `LoopEvent::DiskRead`.
+pub(crate) struct StatusPool {
+    jobs: mpsc::Sender<StatusCheck>,
+    in_flight: HashSet<StatusCheck>,
+    results: mpsc::Sender<LoopEvent>,
+    disk_reads: HashSet<PathBuf>,
+}
+
+impl StatusPool {
+    pub(crate) fn new(workers: usize, timeout: Duration, results: mpsc::Sender<LoopEvent>) -> Self {
+        let (jobs, queue) = mpsc::channel::<StatusCheck>();
+        let queue = Arc::new(Mutex::new(queue));
+        for _ in 0..workers.max(1) {
+            let queue = Arc::clone(&queue);
+            let results = results.clone();
+            thread::spawn(move || {
+                loop {
+                    let job = match queue.lock() {
+                        Ok(queue) => queue.recv(),
+                        Err(_) => return,
+                    };
+                    let Ok(check) = job else {
+                        return;
+                    };
+                    let reading = run_status_check(&check.command, check.mode, timeout);
+                    if results
+                        .send(LoopEvent::StatusChecked(check, reading))
+                        .is_err()
+                    {
+                        return;
+                    }
+                }
+            });
+        }
+        Self {
+            jobs,
+            in_flight: HashSet::new(),
+            results,
+            disk_reads: HashSet::new(),
+        }
+    }
+
+    // Queues `check` unless the same check has not reported back yet.
+    pub(crate) fn submit(&mut self, check: StatusCheck) -> bool {
+        if self.in_flight.contains(&check) {
+            return false;
+        }
+        if self.jobs.send(check.clone()).is_err() {
+            return false;
+        }
+        self.in_flight.insert(check)
+    }
+
+    pub(crate) fn finish(&mut self, check: &StatusCheck) {
+        self.in_flight.remove(check);
+    }
+
+    // Reads the disk usage of `path` unless a read of it has not reported back
+    // yet. Each read gets a thread of its own: statvfs can't be timed out, and
+    // a stale network mount would otherwise hold a worker for good.
+    pub(crate) fn read_disk(&mut self, path: PathBuf) -> bool {
+        if self.disk_reads.contains(&path) {
+            return false;
+        }
+        let results = self.results.clone();
+        let job = path.clone();
+        thread::spawn(move || {
+            let usage = read_disk_usage(&job);
+            let _ = results.send(LoopEvent::DiskRead(job, usage));
+        });
+        self.disk_reads.insert(path)
+    }
+
+    pub(crate) fn finish_disk(&mut self, path: &Path) {
+        self.disk_reads.remove(path);
+    }
+}
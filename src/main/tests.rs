@@ -6,6 +6,16 @@ use std::fs;
 use std::io::Cursor;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicUsize, Ordering};
+use streamrs::idle::{DEFAULT_IDLE_DIM_BRIGHTNESS, IdleThresholds};
+use streamrs::image::calendar::CalendarLayout;
+use streamrs::image::clock::ClockStyle;
+use streamrs::image::overlay::TitlePosition;
+use streamrs::image::timer::TimerState;
+use streamrs::metrics::Metric;
+use streamrs::streamdeck::{DEFAULT_DEVICE_MODEL, STREAMDECK_MINI};
+
+#[path = "event_loop_tests.rs"]
+mod event_loop_tests;
 
 static TEST_DIR_COUNTER: AtomicUsize = AtomicUsize::new(0);
 
@@ -25,15 +35,8 @@ fn write_test_png(path: &Path, rgba: [u8; 4]) {
 
 fn test_key(icon: &str) -> KeyBinding {
     KeyBinding {
-        action: None,
-        shortcut: None,
         icon: icon.to_string(),
-        clock_background: None,
-        icon_on: None,
-        icon_off: None,
-        status: None,
-        status_interval_seconds: None,
-        status_interval_ms: None,
+        ..KeyBinding::default()
     }
 }
 
@@ -46,14 +49,20 @@ fn test_config_with_keys(keys: Vec<KeyBinding>) -> Config {
         brightness: default_brightness(),
         keys_per_page: default_keys_per_page(),
         keys,
+        ..Config::default()
     }
 }
 
 #[test]
 fn svg_icon_is_supported() {
     let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" width="128" height="64"><rect width="128" height="64" fill="#00ff00"/></svg>"##;
-    let data = get_image_data(Path::new("icon.svg"), svg.as_bytes())
-        .expect("SVG should decode and encode for Stream Deck");
+    let data = get_image_data(
+        Path::new("icon.svg"),
+        svg.as_bytes(),
+        None,
+        &DEFAULT_DEVICE_MODEL,
+    )
+    .expect("SVG should decode and encode for Stream Deck");
     assert!(data.len() > 2);
     assert_eq!(data[0], 0xFF);
     assert_eq!(data[1], 0xD8);
@@ -66,7 +75,7 @@ fn gif_icon_is_supported() {
         0x00, 0xFF, 0xFF, 0xFF, 0x21, 0xF9, 0x04, 0x01, 0x00, 0x00, 0x00, 0x00, 0x2C, 0x00, 0x00,
         0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x02, 0x02, 0x44, 0x01, 0x00, 0x3B,
     ];
-    let data = get_image_data(Path::new("icon.gif"), gif)
+    let data = get_image_data(Path::new("icon.gif"), gif, None, &DEFAULT_DEVICE_MODEL)
         .expect("GIF should decode and encode for Stream Deck");
     assert!(data.len() > 2);
     assert_eq!(data[0], 0xFF);
@@ -104,8 +113,8 @@ fn animated_gif_fixture_is_supported() {
         .expect("fixture frames should decode");
     assert!(frames.len() > 1, "fixture should be an animated GIF");
 
-    let loaded =
-        load_animated_gif(path, &gif_data).expect("animated GIF should load for animation");
+    let loaded = load_animated_gif(path, &gif_data, None, &DEFAULT_DEVICE_MODEL)
+        .expect("animated GIF should load for animation");
     match loaded {
         LoadedKeyImage::Animated { frames, delays } => {
             assert!(frames.len() > 1);
@@ -114,6 +123,9 @@ fn animated_gif_fixture_is_supported() {
         LoadedKeyImage::Static(_) => panic!("animated GIF should not load as static"),
         LoadedKeyImage::Clock { .. } => panic!("animated GIF should not load as clock"),
         LoadedKeyImage::Calendar { .. } => panic!("animated GIF should not load as calendar"),
+        LoadedKeyImage::Brightness => panic!("animated GIF should not load as brightness gauge"),
+        LoadedKeyImage::Metric { .. } => panic!("animated GIF should not load as metric"),
+        LoadedKeyImage::NowPlaying => panic!("animated GIF should not load as now playing"),
     }
 }
 
@@ -141,8 +153,13 @@ fn encode_animated_frames_builds_animation_state() {
         image::Delay::from_numer_denom_ms(200, 1),
     );
 
-    let loaded = encode_animated_frames(vec![frame1, frame2], Path::new("anim.gif"))
-        .expect("multi-frame animation should load");
+    let loaded = encode_animated_frames(
+        vec![frame1, frame2],
+        Path::new("anim.gif"),
+        None,
+        &DEFAULT_DEVICE_MODEL,
+    )
+    .expect("multi-frame animation should load");
     match loaded {
         LoadedKeyImage::Animated { frames, delays } => {
             assert_eq!(frames.len(), 2);
@@ -153,23 +170,32 @@ fn encode_animated_frames_builds_animation_state() {
         LoadedKeyImage::Static(_) => panic!("expected animated state"),
         LoadedKeyImage::Clock { .. } => panic!("expected animated state"),
         LoadedKeyImage::Calendar { .. } => panic!("expected animated state"),
+        LoadedKeyImage::Brightness => panic!("expected animated state"),
+        LoadedKeyImage::Metric { .. } => panic!("expected animated state"),
+        LoadedKeyImage::NowPlaying => panic!("expected animated state"),
     }
 }
 
 #[test]
 fn clock_icon_renders_svg_without_background_file() {
     let missing_dir = Path::new("/tmp/streamrs-missing-clock-assets");
-    let loaded =
-        load_key_image(missing_dir, CLOCK_ICON_ALIAS, None).expect("clock icon should render");
+    let loaded = load_key_image(
+        missing_dir,
+        CLOCK_ICON_ALIAS,
+        &LiveFaces::default(),
+        None,
+        &DEFAULT_DEVICE_MODEL,
+    )
+    .expect("clock icon should render");
     match loaded {
         LoadedKeyImage::Clock {
             image,
             current_text,
-            background_name,
+            face,
         } => {
             assert_eq!(current_text.len(), 5);
             assert_eq!(&current_text[2..3], ":");
-            assert!(background_name.is_none());
+            assert_eq!(face, ClockFace::default());
             assert!(image.len() > 2);
             assert_eq!(image[0], 0xFF);
             assert_eq!(image[1], 0xD8);
@@ -181,10 +207,21 @@ fn clock_icon_renders_svg_without_background_file() {
 #[test]
 fn calendar_icon_renders_svg_without_background_file() {
     let missing_dir = Path::new("/tmp/streamrs-missing-calendar-assets");
-    let loaded = load_key_image(missing_dir, CALENDAR_ICON_ALIAS, None)
-        .expect("calendar icon should render");
+    let loaded = load_key_image(
+        missing_dir,
+        CALENDAR_ICON_ALIAS,
+        &LiveFaces::default(),
+        None,
+        &DEFAULT_DEVICE_MODEL,
+    )
+    .expect("calendar icon should render");
     match loaded {
-        LoadedKeyImage::Calendar { image, current_key } => {
+        LoadedKeyImage::Calendar {
+            image,
+            current_key,
+            face,
+        } => {
+            assert_eq!(face, CalendarFace::default());
             assert_eq!(current_key.len(), 10);
             assert_eq!(&current_key[4..5], "-");
             assert_eq!(&current_key[7..8], "-");
@@ -196,6 +233,61 @@ fn calendar_icon_renders_svg_without_background_file() {
     }
 }
 
+#[test]
+fn metric_icons_keep_their_sampler_and_draw_failed_readings() {
+    let faces = LiveFaces {
+        metric: MetricFace::from_fields(Some("sparkline"), Some(5)).expect("face should parse"),
+        ..LiveFaces::default()
+    };
+    let loaded = load_key_image(
+        Path::new("/tmp/streamrs-unused"),
+        "metric://mem",
+        &faces,
+        None,
+        &DEFAULT_DEVICE_MODEL,
+    )
+    .expect("memory metric should render");
+    let LoadedKeyImage::Metric {
+        image,
+        sampler,
+        face,
+        error,
+    } = loaded
+    else {
+        panic!("expected metric image variant");
+    };
+    assert_eq!(sampler.metric(), &Metric::Memory);
+    assert_eq!(face.interval, Duration::from_secs(5));
+    assert_eq!(error, None);
+    assert_eq!(sampler.history().len(), 1);
+    assert_eq!(&image[..2], &[0xFF, 0xD8]);
+
+    let loaded = load_key_image(
+        Path::new("/tmp/streamrs-unused"),
+        "metric://temp/streamrs-missing-sensor",
+        &LiveFaces::default(),
+        None,
+        &DEFAULT_DEVICE_MODEL,
+    )
+    .expect("a missing sensor should still draw the key");
+    let LoadedKeyImage::Metric { error, sampler, .. } = loaded else {
+        panic!("expected metric image variant");
+    };
+    assert!(error.is_some_and(|err| err.contains("streamrs-missing-sensor")));
+    assert!(sampler.history().is_empty());
+
+    assert!(
+        load_key_image(
+            Path::new("/tmp/streamrs-unused"),
+            "metric://gpu",
+            &LiveFaces::default(),
+            None,
+            &DEFAULT_DEVICE_MODEL,
+        )
+        .is_err()
+    );
+}
+
 #[test]
 fn calendar_refresh_delay_targets_next_midnight() {
     let offset = FixedOffset::east_opt(2 * 3600).expect("fixed offset should be valid");
@@ -268,10 +360,42 @@ fn shortcut_config_is_parsed_as_keyboard_action() {
     );
 }
 
+#[test]
+fn text_config_is_parsed_verbatim_and_yields_to_shortcuts() {
+    let raw = r#"
+            [[keys]]
+            text = "  Kind regards,\nStreamrs 🙂"
+            action = "true"
+            icon = "blank.png"
+
+            [[keys]]
+            text = "ignored"
+            shortcut = "Ctrl+V"
+            icon = "blank.png"
+        "#;
+    let config = parse_config(Path::new("test.toml"), raw).expect("config with text should parse");
+    assert_eq!(
+        key_configured_action(&config.keys[0]),
+        Some(ConfiguredAction::Text(
+            "  Kind regards,\nStreamrs 🙂".to_string()
+        ))
+    );
+    assert_eq!(
+        key_configured_action(&config.keys[1]),
+        Some(ConfiguredAction::KeyboardShortcut("Ctrl+V".to_string()))
+    );
+}
+
 #[test]
 fn blank_icon_family_renders_black_key_image() {
-    let loaded = load_key_image(Path::new("/tmp/streamrs-unused"), "blank_3.png", None)
-        .expect("blank icon alias should render as black image");
+    let loaded = load_key_image(
+        Path::new("/tmp/streamrs-unused"),
+        "blank_3.png",
+        &LiveFaces::default(),
+        None,
+        &DEFAULT_DEVICE_MODEL,
+    )
+    .expect("blank icon alias should render as black image");
     match loaded {
         LoadedKeyImage::Static(image) => {
             assert!(image.len() > 2);
@@ -281,6 +405,9 @@ fn blank_icon_family_renders_black_key_image() {
         LoadedKeyImage::Animated { .. } => panic!("blank icon should be static"),
         LoadedKeyImage::Clock { .. } => panic!("blank icon should not load as clock"),
         LoadedKeyImage::Calendar { .. } => panic!("blank icon should not load as calendar"),
+        LoadedKeyImage::Brightness => panic!("blank icon should not load as brightness gauge"),
+        LoadedKeyImage::Metric { .. } => panic!("blank icon should not load as metric"),
+        LoadedKeyImage::NowPlaying => panic!("blank icon should not load as now playing"),
     }
 }
 
@@ -331,6 +458,238 @@ fn legacy_status_interval_ms_is_supported() {
     assert_eq!(key_status_interval(key), Duration::from_secs(3));
 }
 
+#[test]
+fn status_output_mode_picks_faces_from_stdout() {
+    let raw = r#"
+            [[keys]]
+            icon = "default.png"
+            status = "test-command"
+            status_mode = "output"
+            status_icons = { muted = "muted.png" }
+        "#;
+    let config = parse_config(Path::new("test.toml"), raw).expect("output mode should parse");
+    let plan = plan_page_layout(&config, &StatusCache::new(), &[], 0);
+    let status = plan.status_slots[0]
+        .as_ref()
+        .expect("status slot should be planned");
+    assert_eq!(status.icons.mode(), StatusMode::Output);
+    assert_eq!(status.icons.face(None).icon.as_deref(), Some("default.png"));
+    let face = status
+        .icons
+        .face(Some(&StatusReading::Output("muted".to_string())));
+    assert_eq!(face.icon.as_deref(), Some("muted.png"));
+    let face = status
+        .icons
+        .face(Some(&StatusReading::Output("12 unread".to_string())));
+    assert_eq!(face.icon.as_deref(), Some("default.png"));
+    assert_eq!(face.title.as_deref(), Some("12 unread"));
+
+    // An exit-mode reading of the same command is not reused.
+    let mut status_cache = StatusCache::new();
+    status_cache.insert("test-command".to_string(), StatusReading::Exit(true));
+    let plan = plan_page_layout(&config, &status_cache, &[], 0);
+    assert!(
+        plan.status_slots[0]
+            .as_ref()
+            .is_some_and(|status| status.poll_now)
+    );
+
+    let err = parse_config(
+        Path::new("test.toml"),
+        &raw.replace(r#"status_mode = "output""#, r#"status_mode = "stdout""#),
+    )
+    .expect_err("unknown status mode should fail");
+    assert!(err.contains("invalid status_mode 'stdout'"));
+}
+
+#[test]
+fn key_titles_parse_with_their_style_and_reach_the_page_plan() {
+    let raw = r##"
+            [[keys]]
+            icon = "mic.png"
+            title = "Mic"
+            title_position = "top"
+            title_color = "#ffcc00"
+            title_size = 12
+
+            [[keys]]
+            icon = "plain.png"
+            title = "  "
+        "##;
+    let config = parse_config(Path::new("test.toml"), raw).expect("titles should parse");
+    let title = key_title(&config.keys[0])
+        .expect("title should be valid")
+        .expect("title should be set");
+    assert_eq!(
+        title,
+        KeyTitle {
+            position: TitlePosition::Top,
+            color: "#ffcc00".to_string(),
+            size: Some(12),
+            ..KeyTitle::new("Mic")
+        }
+    );
+    let plan = plan_page_layout(&config, &StatusCache::new(), &[], 0);
+    assert_eq!(plan.titles[0].as_ref(), Some(&title));
+    assert_eq!(plan.titles[1], None);
+
+    let err = parse_config(
+        Path::new("test.toml"),
+        &raw.replace("title_size = 12", "title_size = 100"),
+    )
+    .expect_err("oversized title should fail");
+    assert!(err.contains("key 1: invalid title_size 100"));
+    let err = parse_config(
+        Path::new("test.toml"),
+        &raw.replace(r#"title_position = "top""#, r#"title_position = "left""#),
+    )
+    .expect_err("unknown title position should fail");
+    assert!(err.contains("invalid title_position 'left'"));
+}
+
+#[test]
+fn clock_options_parse_into_the_page_plan() {
+    let raw = r#"
+            [[keys]]
+            icon = "clock.svg"
+            clock_format = "12h"
+            clock_seconds = true
+            clock_date = true
+            clock_time_zone = "UTC"
+            clock_label = "UTC"
+            clock_style = "analog"
+        "#;
+    let config = parse_config(Path::new("test.toml"), raw).expect("clock options should parse");
+    let plan = plan_page_layout(&config, &StatusCache::new(), &[], 0);
+    let (icon, faces) = plan.icons[0]
+        .clone()
+        .expect("clock key should have an icon");
+    assert_eq!(icon, "clock.svg");
+    assert_eq!(
+        faces.clock,
+        ClockFace {
+            hour12: true,
+            seconds: true,
+            date: true,
+            time_zone: Some("UTC".to_string()),
+            label: Some("UTC".to_string()),
+            style: ClockStyle::Analog,
+            ..ClockFace::default()
+        }
+    );
+
+    let err = parse_config(
+        Path::new("test.toml"),
+        &raw.replace(r#"clock_format = "12h""#, r#"clock_format = "36h""#),
+    )
+    .expect_err("unknown clock format should fail");
+    assert!(err.contains("key 1: invalid clock_format '36h'"));
+}
+
+#[test]
+fn calendar_options_parse_into_the_page_plan() {
+    let raw = r##"
+            [[keys]]
+            icon = "calendar.svg"
+            calendar_weekday = true
+            calendar_week_number = true
+            calendar_first_weekday = "sunday"
+            calendar_header_color = "#1e66f5"
+            calendar_layout = "month"
+        "##;
+    let config = parse_config(Path::new("test.toml"), raw).expect("calendar options should parse");
+    let plan = plan_page_layout(&config, &StatusCache::new(), &[], 0);
+    let (_, faces) = plan.icons[0]
+        .clone()
+        .expect("calendar key should have an icon");
+    assert_eq!(
+        faces.calendar,
+        CalendarFace {
+            weekday: true,
+            week_number: true,
+            first_weekday: chrono::Weekday::Sun,
+            header_color: "#1e66f5".to_string(),
+            layout: CalendarLayout::Month,
+            ..CalendarFace::default()
+        }
+    );
+
+    let err = parse_config(
+        Path::new("test.toml"),
+        &raw.replace(
+            r#"calendar_layout = "month""#,
+            r#"calendar_layout = "year""#,
+        ),
+    )
+    .expect_err("unknown calendar layout should fail");
+    assert!(err.contains("key 1: invalid calendar_layout 'year'"));
+}
+
+#[test]
+fn timer_keys_plan_press_and_reset_actions_inside_folders() {
+    let raw = r#"
+            [[keys]]
+            icon = "red.png"
+            [[keys.keys]]
+            icon = "red.png"
+            [[keys.keys]]
+            icon = "timer://25:00"
+            timer_command = "notify-send Done"
+            double_press_action = "echo double"
+            [[keys]]
+            icon = "stopwatch://"
+        "#;
+    let config = parse_config(Path::new("test.toml"), raw).expect("timer keys should parse");
+    let plan = plan_page_layout(&config, &StatusCache::new(), &[0], 0);
+    let countdown = TimerId {
+        folder: vec![0],
+        index: 1,
+        kind: TimerKind::Countdown(Duration::from_secs(1500)),
+    };
+    assert_eq!(plan.timer_slots[2], Some(countdown.clone()));
+    assert_eq!(
+        plan.button_actions[2],
+        Some(ButtonAction::ToggleTimer(countdown.clone()))
+    );
+    assert_eq!(
+        plan.long_press_actions[2],
+        Some(ButtonAction::ResetTimer(countdown))
+    );
+    assert_eq!(
+        plan.double_press_actions[2],
+        Some(ButtonAction::Launch("echo double".to_string()))
+    );
+    assert_eq!(
+        key_timer_command(&config.folder_keys(&[0]).unwrap()[1]).as_deref(),
+        Some("notify-send Done")
+    );
+
+    let root = plan_page_layout(&config, &StatusCache::new(), &[], 0);
+    assert_eq!(
+        root.timer_slots[1].as_ref().map(|id| id.kind),
+        Some(TimerKind::Stopwatch)
+    );
+    assert_eq!(root.timer_slots[0], None);
+
+    for (icon, extra, expected) in [
+        ("timer://soon", "", "key 1: invalid timer length 'soon'"),
+        (
+            "timer://5m",
+            "action = \"echo tap\"",
+            "key 1: timer icon 'timer://5m' can't",
+        ),
+        (
+            "stopwatch://",
+            "status = \"true\"",
+            "key 1: timer icon 'stopwatch://' can't",
+        ),
+    ] {
+        let raw = format!("[[keys]]\nicon = \"{icon}\"\n{extra}\n");
+        let err = parse_config(Path::new("test.toml"), &raw).expect_err("timer key should fail");
+        assert!(err.contains(expected), "{err}");
+    }
+}
+
 #[test]
 fn parse_config_rejects_empty_key_list() {
     let raw = r#"
@@ -352,6 +711,320 @@ fn parse_config_rejects_invalid_keys_per_page() {
     assert!(err.contains("keys_per_page"));
 }
 
+#[test]
+fn parse_config_rejects_unsupported_product_id() {
+    let raw = r#"
+            product_id = 0x1234
+            [[keys]]
+            icon = "blank.png"
+        "#;
+    let err =
+        parse_config(Path::new("test.toml"), raw).expect_err("unknown product_id should fail");
+    assert!(err.contains("product_id 0x1234"));
+}
+
+#[test]
+fn parse_config_validates_device_profile_mapping() {
+    let raw = r#"
+            [devices]
+            CL12345 = "desk"
+            [[keys]]
+            icon = "blank.png"
+        "#;
+    let config = parse_config(Path::new("test.toml"), raw).expect("mapping should parse");
+    assert_eq!(
+        mapped_profile_for_serial(&config.devices, "default", "CL12345").as_deref(),
+        Some("desk")
+    );
+    assert_eq!(
+        mapped_profile_for_serial(&config.devices, "desk", "CL12345"),
+        None
+    );
+    assert_eq!(
+        mapped_profile_for_serial(&config.devices, "default", "OTHER"),
+        None
+    );
+    assert_eq!(
+        mapped_profile_config_path(Path::new("/cfg/default.toml"), "desk"),
+        PathBuf::from("/cfg/desk.toml")
+    );
+
+    let invalid = raw.replace("\"desk\"", "\"../desk\"");
+    let err = parse_config(Path::new("test.toml"), &invalid)
+        .expect_err("invalid profile names should fail");
+    assert!(err.contains("invalid profile name '../desk'"));
+}
+
+#[test]
+fn page_plan_includes_long_and_double_press_actions() {
+    let config = test_config_with_keys(vec![
+        KeyBinding {
+            action: Some("echo tap".to_string()),
+            long_press_action: Some(" echo long ".to_string()),
+            double_press_action: Some("echo double".to_string()),
+            ..test_key("blank.png")
+        },
+        KeyBinding {
+            long_press_action: Some("   ".to_string()),
+            ..test_key("blank.png")
+        },
+    ]);
+    let plan = plan_page_layout(&config, &StatusCache::new(), &[], 0);
+    assert_eq!(
+        plan.button_actions[0],
+        Some(ButtonAction::Launch("echo tap".to_string()))
+    );
+    assert_eq!(
+        plan.long_press_actions[0],
+        Some(ButtonAction::Launch("echo long".to_string()))
+    );
+    assert_eq!(
+        plan.double_press_actions[0],
+        Some(ButtonAction::Launch("echo double".to_string()))
+    );
+    assert_eq!(plan.long_press_actions[1], None);
+}
+
+#[test]
+fn parse_config_rejects_out_of_range_press_thresholds() {
+    let raw = r#"
+            long_press_ms = 10
+            [[keys]]
+            icon = "blank.png"
+        "#;
+    let err = parse_config(Path::new("test.toml"), raw).expect_err("threshold should fail");
+    assert!(err.contains("invalid long_press_ms 10"));
+}
+
+#[test]
+fn parse_config_reads_builtin_brightness_actions() {
+    let raw = r#"
+            [[keys]]
+            icon = "blank.png"
+            builtin = "brightness_down"
+            action = "ignored"
+            [[keys]]
+            icon = "blank.png"
+            builtin = "brightness_cycle"
+            brightness_levels = [100, 20]
+            [[keys]]
+            icon = "blank.png"
+            builtin = "brightness_cycle"
+        "#;
+    let config = parse_config(Path::new("test.toml"), raw).expect("builtin config should parse");
+    assert_eq!(
+        key_configured_action(&config.keys[0]),
+        Some(ConfiguredAction::Builtin(BuiltinAction::Brightness(
+            BrightnessChange::Down(DEFAULT_BRIGHTNESS_STEP)
+        )))
+    );
+    assert_eq!(
+        key_configured_action(&config.keys[1]),
+        Some(ConfiguredAction::Builtin(BuiltinAction::Brightness(
+            BrightnessChange::Cycle(vec![100, 20])
+        )))
+    );
+    assert_eq!(
+        key_configured_action(&config.keys[2]),
+        Some(ConfiguredAction::Builtin(BuiltinAction::Brightness(
+            BrightnessChange::Cycle(DEFAULT_BRIGHTNESS_LEVELS.to_vec())
+        )))
+    );
+
+    for (raw, expected) in [
+        (
+            "[[keys]]\nbuiltin = \"brightness_sideways\"\n",
+            "key 1: unknown builtin 'brightness_sideways'",
+        ),
+        (
+            "[[keys]]\nbuiltin = \"brightness_up\"\nbrightness_step = 0\n",
+            "key 1: invalid brightness_step 0",
+        ),
+        (
+            "[[keys]]\nbuiltin = \"brightness_cycle\"\nbrightness_levels = [50, 120]\n",
+            "key 1: invalid brightness level 120",
+        ),
+    ] {
+        let err = parse_config(Path::new("test.toml"), raw).expect_err("builtin should fail");
+        assert!(err.contains(expected), "{err}");
+    }
+}
+
+#[test]
+fn parse_config_reads_page_and_profile_builtins() {
+    let raw = r#"
+            [[keys]]
+            builtin = "page:first"
+            [[keys]]
+            builtin = "page: Back"
+            [[keys]]
+            builtin = "page:3"
+            [[keys]]
+            builtin = "profile:work"
+        "#;
+    let config = parse_config(Path::new("test.toml"), raw).expect("builtin config should parse");
+    let actions = config
+        .keys
+        .iter()
+        .map(key_configured_action)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        actions,
+        [
+            Some(ConfiguredAction::Builtin(BuiltinAction::Page(
+                PageTarget::First
+            ))),
+            Some(ConfiguredAction::Builtin(BuiltinAction::Page(
+                PageTarget::Back
+            ))),
+            Some(ConfiguredAction::Builtin(BuiltinAction::Page(
+                PageTarget::Page(2)
+            ))),
+            Some(ConfiguredAction::Builtin(BuiltinAction::Profile(
+                "work".to_string()
+            ))),
+        ]
+    );
+
+    for (raw, expected) in [
+        (
+            "[[keys]]\nbuiltin = \"page:sideways\"\n",
+            "key 1: invalid page 'sideways'",
+        ),
+        (
+            "[[keys]]\nbuiltin = \"profile:../work\"\n",
+            "key 1: invalid profile name '../work'",
+        ),
+        (
+            "[[keys]]\nbuiltin = \"folder:up\"\n",
+            "key 1: unknown builtin 'folder:up'",
+        ),
+    ] {
+        let err = parse_config(Path::new("test.toml"), raw).expect_err("builtin should fail");
+        assert!(err.contains(expected), "{err}");
+    }
+}
+
+#[test]
+fn auto_profile_rules_match_any_focused_app_id() {
+    let raw = r#"
+            [[auto_profile]]
+            app_id = "gimp"
+            profile = "gimp"
+            [[auto_profile]]
+            app_id = "org.inkscape.Inkscape"
+            profile = "drawing"
+            [[keys]]
+            icon = "blank.png"
+        "#;
+    let config = parse_config(Path::new("test.toml"), raw).expect("rules should parse");
+    let x11_gimp = FocusedApp {
+        app_ids: vec!["gimp".to_string(), "Gimp".to_string()],
+    };
+    assert_eq!(
+        auto_profile_for(&config.auto_profile, &x11_gimp).as_deref(),
+        Some("gimp")
+    );
+    let inkscape = FocusedApp::from_app_id(Some(" org.inkscape.Inkscape "));
+    assert_eq!(
+        auto_profile_for(&config.auto_profile, &inkscape).as_deref(),
+        Some("drawing")
+    );
+    assert_eq!(
+        auto_profile_for(&config.auto_profile, &FocusedApp::from_app_id(None)),
+        None
+    );
+
+    let err = parse_config(
+        Path::new("test.toml"),
+        "[[auto_profile]]\napp_id = \"gimp\"\nprofile = \"../gimp\"\n[[keys]]\n",
+    )
+    .expect_err("invalid profile name should fail");
+    assert!(
+        err.contains("auto_profile 1 has invalid profile name '../gimp'"),
+        "{err}"
+    );
+}
+
+#[test]
+fn parse_config_reads_and_checks_idle_settings() {
+    let raw = r#"
+            idle_dim_minutes = 2
+            idle_off_minutes = 10
+            idle_blank_keys = true
+            [[keys]]
+            icon = "blank.png"
+        "#;
+    let config = parse_config(Path::new("test.toml"), raw).expect("idle config should parse");
+    assert_eq!(
+        idle_thresholds(&config),
+        IdleThresholds {
+            dim_after: Some(Duration::from_secs(120)),
+            off_after: Some(Duration::from_secs(600)),
+        }
+    );
+    assert_eq!(idle_dim_brightness(&config), DEFAULT_IDLE_DIM_BRIGHTNESS);
+    assert!(config.idle_blank_keys);
+
+    let err = parse_config(
+        Path::new("test.toml"),
+        "idle_dim_brightness = 150\n[[keys]]\nicon = \"blank.png\"\n",
+    )
+    .expect_err("brightness should fail");
+    assert!(err.contains("invalid idle_dim_brightness 150"));
+}
+
+#[test]
+fn parse_config_reads_macro_steps_in_order() {
+    let raw = r#"
+            [[keys]]
+            icon = "blank.png"
+            action = "ignored"
+            stop_on_error = true
+            [[keys.steps]]
+            action = "notify-send start"
+            [[keys.steps]]
+            delay_ms = 250
+            [[keys.steps]]
+            text = "hello"
+            [[keys.steps]]
+            shortcut = "Ctrl+V"
+            [[keys.steps]]
+            page = "3"
+        "#;
+    let config = parse_config(Path::new("test.toml"), raw).expect("macro config should parse");
+    assert_eq!(
+        key_configured_action(&config.keys[0]),
+        Some(ConfiguredAction::Macro(KeyMacro {
+            steps: vec![
+                MacroStep::Launch("notify-send start".to_string()),
+                MacroStep::Delay(Duration::from_millis(250)),
+                MacroStep::Text("hello".to_string()),
+                MacroStep::KeyboardShortcut("Ctrl+V".to_string()),
+                MacroStep::Page(PageTarget::Page(2)),
+            ],
+            stop_on_error: true,
+        }))
+    );
+}
+
+#[test]
+fn parse_config_rejects_ambiguous_or_invalid_macro_steps() {
+    for (step, expected) in [
+        ("action = \"a\"\ntext = \"b\"", "only one of"),
+        ("page = \"0\"", "invalid page '0'"),
+        ("delay_ms = 600000", "delay_ms 600000"),
+        ("text = \"\"", "expected one of"),
+    ] {
+        let raw = format!(
+            "[[keys]]\nicon = \"blank.png\"\n[[keys.keys]]\nicon = \"blank.png\"\n[[keys.keys.steps]]\n{step}\n"
+        );
+        let err = parse_config(Path::new("test.toml"), &raw).expect_err("step should fail");
+        assert!(err.contains("key 1 step 1"), "{err}");
+        assert!(err.contains(expected), "{err}");
+    }
+}
+
 #[test]
 fn launcher_like_status_commands_are_detected() {
     assert!(is_launcher_like_command("open https://example.com"));
@@ -379,28 +1052,13 @@ fn image_cache_warming_includes_status_and_navigation_icons() {
 
     let mut keys = Vec::new();
     for _ in 0..15 {
-        keys.push(KeyBinding {
-            action: None,
-            shortcut: None,
-            icon: "base.png".to_string(),
-            clock_background: None,
-            icon_on: None,
-            icon_off: None,
-            status: None,
-            status_interval_seconds: None,
-            status_interval_ms: None,
-        });
+        keys.push(test_key("base.png"));
     }
     keys.push(KeyBinding {
-        action: None,
-        shortcut: None,
-        icon: "status-default.png".to_string(),
-        clock_background: None,
         icon_on: Some("status-on.png".to_string()),
         icon_off: Some("status-off.png".to_string()),
         status: Some("test-status".to_string()),
-        status_interval_seconds: None,
-        status_interval_ms: None,
+        ..test_key("status-default.png")
     });
 
     let config = Config {
@@ -411,32 +1069,48 @@ fn image_cache_warming_includes_status_and_navigation_icons() {
         brightness: default_brightness(),
         keys_per_page: default_keys_per_page(),
         keys,
+        ..Config::default()
     };
 
     let cache = build_image_cache(&config, &dir);
     assert_eq!(cache.len(), 6, "duplicate icons should be cached once");
     assert!(cache.contains_key(&ImageCacheKey {
         icon: "base.png".to_string(),
-        clock_background: None,
+        faces: LiveFaces::default(),
+        title: None,
     }));
     assert!(cache.contains_key(&ImageCacheKey {
         icon: "status-on.png".to_string(),
-        clock_background: None,
+        faces: LiveFaces::default(),
+        title: None,
     }));
     assert!(cache.contains_key(&ImageCacheKey {
         icon: "status-off.png".to_string(),
-        clock_background: None,
+        faces: LiveFaces::default(),
+        title: None,
     }));
     assert!(cache.contains_key(&ImageCacheKey {
         icon: NEXT_PAGE_ICON.to_string(),
-        clock_background: None,
+        faces: LiveFaces::default(),
+        title: None,
     }));
     assert!(cache.contains_key(&ImageCacheKey {
         icon: PREVIOUS_PAGE_ICON.to_string(),
-        clock_background: None,
+        faces: LiveFaces::default(),
+        title: None,
     }));
 }
 
+fn clock_with_background(name: &str) -> LiveFaces {
+    LiveFaces {
+        clock: ClockFace {
+            background: Some(name.to_string()),
+            ..ClockFace::default()
+        },
+        ..LiveFaces::default()
+    }
+}
+
 #[test]
 fn clock_cache_key_includes_background_name() {
     let dir = test_temp_dir("clock-cache-key");
@@ -444,12 +1118,33 @@ fn clock_cache_key_includes_background_name() {
     write_test_png(&dir.join("bg-b.png"), [30, 30, 30, 255]);
 
     let mut cache = ImageCache::new();
-    let first = load_key_image_cached(&dir, &mut cache, CLOCK_ICON_ALIAS, Some("bg-a.png"))
-        .expect("first clock variant should render");
-    let second = load_key_image_cached(&dir, &mut cache, CLOCK_ICON_ALIAS, Some("bg-b.png"))
-        .expect("second clock variant should render");
-    let repeat = load_key_image_cached(&dir, &mut cache, CLOCK_ICON_ALIAS, Some("bg-a.png"))
-        .expect("repeat clock variant should come from cache");
+    let first = load_key_image_cached(
+        &dir,
+        &mut cache,
+        CLOCK_ICON_ALIAS,
+        &clock_with_background("bg-a.png"),
+        None,
+        &DEFAULT_DEVICE_MODEL,
+    )
+    .expect("first clock variant should render");
+    let second = load_key_image_cached(
+        &dir,
+        &mut cache,
+        CLOCK_ICON_ALIAS,
+        &clock_with_background("bg-b.png"),
+        None,
+        &DEFAULT_DEVICE_MODEL,
+    )
+    .expect("second clock variant should render");
+    let repeat = load_key_image_cached(
+        &dir,
+        &mut cache,
+        CLOCK_ICON_ALIAS,
+        &clock_with_background("bg-a.png"),
+        None,
+        &DEFAULT_DEVICE_MODEL,
+    )
+    .expect("repeat clock variant should come from cache");
 
     assert!(matches!(first, LoadedKeyImage::Clock { .. }));
     assert!(matches!(second, LoadedKeyImage::Clock { .. }));
@@ -457,6 +1152,83 @@ fn clock_cache_key_includes_background_name() {
     assert_eq!(cache.len(), 2, "clock background variants must not collide");
 }
 
+#[test]
+fn page_layout_plan_uses_connected_model_key_count() {
+    let keys = (0..8)
+        .map(|i| test_key(&format!("icon-{i}.png")))
+        .collect::<Vec<_>>();
+    let mut config = test_config_with_keys(keys);
+    config.product_id = STREAMDECK_MINI.product_ids[0];
+
+    let plan = plan_page_layout(&config, &StatusCache::new(), &[], 0);
+    assert_eq!(plan.icons.len(), STREAMDECK_MINI.key_count);
+    assert_eq!(plan.total_pages, 2);
+    assert_eq!(plan.button_actions[5], Some(ButtonAction::NextPage));
+    assert_eq!(
+        plan.icons[4].as_ref().map(|(icon, _)| icon.as_str()),
+        Some("icon-4.png")
+    );
+}
+
+#[test]
+fn page_layout_plan_pages_folder_levels_behind_a_back_key() {
+    let nested = (0..20)
+        .map(|i| test_key(&format!("nested-{i}.png")))
+        .collect::<Vec<_>>();
+    let config = test_config_with_keys(vec![
+        KeyBinding {
+            keys: Some(nested),
+            ..test_key("folder.png")
+        },
+        test_key("root.png"),
+    ]);
+    let status_cache = StatusCache::new();
+
+    let root = plan_page_layout(&config, &status_cache, &[], 0);
+    assert_eq!(root.button_actions[0], Some(ButtonAction::OpenFolder(0)));
+    assert_eq!(
+        root.icons[0].as_ref().map(|(icon, _)| icon.as_str()),
+        Some("folder.png")
+    );
+
+    let first = plan_page_layout(&config, &status_cache, &[0], 0);
+    assert_eq!(first.total_pages, 2);
+    assert_eq!(first.button_actions[0], Some(ButtonAction::FolderBack));
+    assert_eq!(
+        first.icons[0].as_ref().map(|(icon, _)| icon.as_str()),
+        Some(FOLDER_BACK_ICON)
+    );
+    assert_eq!(
+        first.icons[1].as_ref().map(|(icon, _)| icon.as_str()),
+        Some("nested-0.png")
+    );
+    assert_eq!(first.button_actions[14], Some(ButtonAction::NextPage));
+
+    let second = plan_page_layout(&config, &status_cache, &[0], 1);
+    assert_eq!(second.button_actions[0], Some(ButtonAction::FolderBack));
+    assert_eq!(second.button_actions[14], Some(ButtonAction::PreviousPage));
+    assert_eq!(
+        second.icons[1].as_ref().map(|(icon, _)| icon.as_str()),
+        Some("nested-13.png")
+    );
+}
+
+#[test]
+fn bmp_models_encode_images_at_native_size() {
+    let svg = r##"<svg xmlns="http://www.w3.org/2000/svg" width="64" height="64"><rect width="64" height="64" fill="#ff0000"/></svg>"##;
+    let data = get_image_data(
+        Path::new("icon.svg"),
+        svg.as_bytes(),
+        None,
+        &STREAMDECK_MINI,
+    )
+    .expect("SVG should encode for the Mini");
+    assert_eq!(&data[..2], b"BM");
+    let decoded = image::load_from_memory(&data).expect("BMP should decode");
+    assert_eq!(decoded.width(), STREAMDECK_MINI.image_size);
+    assert_eq!(decoded.height(), STREAMDECK_MINI.image_size);
+}
+
 #[test]
 fn page_layout_plan_places_navigation_keys_across_pages() {
     let keys = (0..30)
@@ -468,7 +1240,7 @@ fn page_layout_plan_places_navigation_keys_across_pages() {
     let prev_key = layout.previous_page_key();
     let next_key = layout.next_page_key();
 
-    let first = plan_page_layout(&config, &status_cache, 0);
+    let first = plan_page_layout(&config, &status_cache, &[], 0);
     assert_eq!(first.total_pages, 3);
     assert_eq!(first.page, 0);
     assert_eq!(first.button_actions[next_key], Some(ButtonAction::NextPage));
@@ -488,7 +1260,7 @@ fn page_layout_plan_places_navigation_keys_across_pages() {
         Some(NEXT_PAGE_ICON)
     );
 
-    let middle = plan_page_layout(&config, &status_cache, 1);
+    let middle = plan_page_layout(&config, &status_cache, &[], 1);
     assert_eq!(middle.page, 1);
     assert_eq!(
         middle.button_actions[prev_key],
@@ -519,7 +1291,7 @@ fn page_layout_plan_places_navigation_keys_across_pages() {
         Some(NEXT_PAGE_ICON)
     );
 
-    let last = plan_page_layout(&config, &status_cache, 2);
+    let last = plan_page_layout(&config, &status_cache, &[], 2);
     assert_eq!(last.page, 2);
     assert_eq!(last.button_actions[prev_key], None);
     assert_eq!(
@@ -540,6 +1312,80 @@ fn page_layout_plan_places_navigation_keys_across_pages() {
     );
 }
 
+#[test]
+fn now_playing_keys_default_to_play_pause_and_read_media_builtins() {
+    let raw = r#"
+            [[keys]]
+            icon = "media://now-playing"
+            [[keys]]
+            icon = "media://now-playing"
+            builtin = "media:next"
+            [[keys]]
+            builtin = "media: Previous"
+        "#;
+    let config = parse_config(Path::new("test.toml"), raw).expect("media config should parse");
+    let plan = plan_page_layout(&config, &StatusCache::new(), &[], 0);
+    assert_eq!(
+        &plan.button_actions[..3],
+        [
+            Some(ButtonAction::Builtin(BuiltinAction::Media(
+                MediaCommand::PlayPause
+            ))),
+            Some(ButtonAction::Builtin(BuiltinAction::Media(
+                MediaCommand::Next
+            ))),
+            Some(ButtonAction::Builtin(BuiltinAction::Media(
+                MediaCommand::Previous
+            ))),
+        ]
+    );
+
+    let err = parse_config(
+        Path::new("test.toml"),
+        "[[keys]]\nbuiltin = \"media:stop\"\n",
+    )
+    .expect_err("unknown media command should fail");
+    assert!(
+        err.contains("key 1: unknown media command in builtin 'media:stop'"),
+        "{err}"
+    );
+}
+
+#[test]
+fn page_layout_plan_skips_navigation_keys_with_manual_page_navigation() {
+    let mut keys = (0..30)
+        .map(|i| test_key(&format!("icon-{i}.png")))
+        .collect::<Vec<_>>();
+    keys[14].builtin = Some("page:next".to_string());
+    let config = Config {
+        manual_page_navigation: true,
+        ..test_config_with_keys(keys)
+    };
+    let status_cache = StatusCache::new();
+
+    let first = plan_page_layout(&config, &status_cache, &[], 0);
+    assert_eq!(first.total_pages, 2);
+    assert_eq!(
+        first.icons[14].as_ref().map(|(icon, _)| icon.as_str()),
+        Some("icon-14.png")
+    );
+    assert_eq!(
+        first.button_actions[14],
+        Some(ButtonAction::Builtin(BuiltinAction::Page(PageTarget::Next)))
+    );
+
+    let last = plan_page_layout(&config, &status_cache, &[], 1);
+    assert_eq!(
+        last.icons[14].as_ref().map(|(icon, _)| icon.as_str()),
+        Some("icon-29.png")
+    );
+    assert!(
+        !last
+            .button_actions
+            .contains(&Some(ButtonAction::PreviousPage))
+    );
+}
+
 #[test]
 fn page_layout_plan_uses_cached_status_for_initial_icon_and_poll_timing() {
     let mut key = test_key("default.png");
@@ -549,11 +1395,11 @@ fn page_layout_plan_uses_cached_status_for_initial_icon_and_poll_timing() {
     key.status_interval_seconds = Some(3);
     let config = test_config_with_keys(vec![key]);
 
-    let no_cache_plan = plan_page_layout(&config, &StatusCache::new(), 0);
+    let no_cache_plan = plan_page_layout(&config, &StatusCache::new(), &[], 0);
     let no_cache_status = no_cache_plan.status_slots[0]
         .as_ref()
         .expect("status slot should be planned");
-    assert_eq!(no_cache_status.current_on, None);
+    assert_eq!(no_cache_status.current, None);
     assert!(no_cache_status.poll_now);
     assert_eq!(no_cache_status.check_interval, Duration::from_secs(3));
     assert_eq!(
@@ -564,12 +1410,12 @@ fn page_layout_plan_uses_cached_status_for_initial_icon_and_poll_timing() {
     );
 
     let mut status_cache = StatusCache::new();
-    status_cache.insert("test-status".to_string(), true);
-    let cached_plan = plan_page_layout(&config, &status_cache, 0);
+    status_cache.insert("test-status".to_string(), StatusReading::Exit(true));
+    let cached_plan = plan_page_layout(&config, &status_cache, &[], 0);
     let cached_status = cached_plan.status_slots[0]
         .as_ref()
         .expect("status slot should be planned");
-    assert_eq!(cached_status.current_on, Some(true));
+    assert_eq!(cached_status.current, Some(StatusReading::Exit(true)));
     assert!(!cached_status.poll_now);
     assert_eq!(cached_status.check_interval, Duration::from_secs(3));
     assert_eq!(
@@ -579,20 +1425,43 @@ fn page_layout_plan_uses_cached_status_for_initial_icon_and_poll_timing() {
 }
 
 #[test]
-fn immediate_status_refresh_schedules_status_key_one_second_later() {
-    let mut state = PageState {
-        button_actions: std::array::from_fn(|_| None),
-        dynamic_states: std::array::from_fn(|_| None),
-        status_states: std::array::from_fn(|_| None),
+fn status_pool_runs_identical_checks_once_until_they_report() {
+    let (results, finished) = mpsc::channel();
+    let mut pool = StatusPool::new(1, Duration::from_secs(5), results);
+    let check = StatusCheck {
+        command: "echo ready".to_string(),
+        mode: StatusMode::Output,
     };
+    assert!(pool.submit(check.clone()));
+    assert!(!pool.submit(check.clone()));
+
+    match finished.recv_timeout(Duration::from_secs(5)) {
+        Ok(LoopEvent::StatusChecked(done, Ok(reading))) => {
+            assert_eq!(done, check);
+            assert_eq!(reading, StatusReading::Output("ready".to_string()));
+        }
+        _ => panic!("status check should report its output"),
+    }
+    assert!(finished.recv_timeout(Duration::from_millis(200)).is_err());
+
+    pool.finish(&check);
+    assert!(pool.submit(check));
+}
+
+#[test]
+fn immediate_status_refresh_schedules_status_key_one_second_later() {
+    let mut state = PageState::empty(DEFAULT_DEVICE_MODEL.key_count);
     state.status_states[0] = Some(StatusKeyState {
         command: "echo ok".to_string(),
-        icon_on: "on.png".to_string(),
-        icon_off: "off.png".to_string(),
-        clock_background: None,
+        icons: StatusIcons::Exit {
+            icon_on: "on.png".to_string(),
+            icon_off: "off.png".to_string(),
+        },
+        faces: LiveFaces::default(),
+        title: None,
         check_interval: Duration::from_secs(5),
         next_check_at: Instant::now() + Duration::from_secs(5),
-        current_on: Some(false),
+        current: Some(StatusReading::Exit(false)),
     });
 
     let before = Instant::now();
@@ -613,7 +1482,7 @@ fn page_layout_plan_treats_launcher_like_status_as_action_when_missing_action()
     key.status = Some("xdg-open https://example.com".to_string());
     let config = test_config_with_keys(vec![key]);
 
-    let plan = plan_page_layout(&config, &StatusCache::new(), 0);
+    let plan = plan_page_layout(&config, &StatusCache::new(), &[], 0);
     assert!(
         plan.status_slots[0].is_none(),
         "launcher-like status should not poll"
@@ -640,7 +1509,7 @@ fn page_layout_plan_uses_keyboard_shortcut_action() {
     key.shortcut = Some("Ctrl+Shift+T".to_string());
     let config = test_config_with_keys(vec![key]);
 
-    let plan = plan_page_layout(&config, &StatusCache::new(), 0);
+    let plan = plan_page_layout(&config, &StatusCache::new(), &[], 0);
     assert_eq!(
         plan.button_actions[0],
         Some(ButtonAction::KeyboardShortcut("Ctrl+Shift+T".to_string()))
@@ -660,3 +1529,35 @@ fn configured_shortcut_takes_precedence_over_launch_command() {
         ))
     );
 }
+
+#[test]
+fn scheduler_returns_the_latest_deadline_of_each_timer_in_order() {
+    let start = Instant::now();
+    let mut scheduler = Scheduler::default();
+    scheduler.schedule(Timer::ReloadCheck, Some(start + Duration::from_secs(10)));
+    scheduler.schedule(
+        Timer::Deck("A".to_string()),
+        Some(start + Duration::from_secs(1)),
+    );
+    scheduler.schedule(
+        Timer::Deck("A".to_string()),
+        Some(start + Duration::from_secs(5)),
+    );
+    scheduler.schedule(Timer::DeviceProbe, Some(start + Duration::from_secs(2)));
+    scheduler.schedule(Timer::DeviceProbe, None);
+
+    assert_eq!(
+        scheduler.next_deadline(),
+        Some(start + Duration::from_secs(5))
+    );
+    assert!(
+        scheduler
+            .take_due(start + Duration::from_secs(4))
+            .is_empty()
+    );
+    assert_eq!(
+        scheduler.take_due(start + Duration::from_secs(10)),
+        vec![Timer::Deck("A".to_string()), Timer::ReloadCheck]
+    );
+    assert_eq!(scheduler.next_deadline(), None);
+}
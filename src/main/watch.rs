@@ -0,0 +1,133 @@
+use super::LoopEvent;
+use std::ffi::{CString, OsStr};
+use std::fs::File;
+use std::io::{self, ErrorKind, Read};
+use std::os::fd::FromRawFd;
+use std::os::unix::ffi::OsStrExt;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread;
+
+const IN_CLOEXEC: i32 = 0o2_000_000;
+const IN_ATTRIB: u32 = 0x0000_0004;
+const IN_CLOSE_WRITE: u32 = 0x0000_0008;
+const IN_MOVED_FROM: u32 = 0x0000_0040;
+const IN_MOVED_TO: u32 = 0x0000_0080;
+const IN_CREATE: u32 = 0x0000_0100;
+const IN_DELETE: u32 = 0x0000_0200;
+// Atomic saves show up as IN_MOVED_TO of the final name; the temporary file
+// they write first is ignored by the event loop's name checks.
+const WATCH_MASK: u32 = IN_CLOSE_WRITE | IN_MOVED_FROM | IN_MOVED_TO | IN_DELETE;
+// udev creates a hidraw node first and grants access to it right after.
+const HIDRAW_WATCH_MASK: u32 = IN_CREATE | IN_ATTRIB;
+const DEVICE_DIR: &str = "/dev";
+// wd, mask, cookie and len, followed by `len` bytes of NUL padded name.
+const EVENT_HEADER_LENGTH: usize = 16;
+
+unsafe extern "C" {
+    fn inotify_init1(flags: i32) -> i32;
+    fn inotify_add_watch(fd: i32, pathname: *const std::ffi::c_char, mask: u32) -> i32;
+}
+
+// Reports every file written, renamed or deleted directly inside `dirs` as
+// `LoopEvent::FileChanged`; the event loop debounces and filters them.
+pub(crate) fn watch_files(dirs: &[PathBuf], sender: mpsc::Sender<LoopEvent>) -> Result<(), String> {
+    watch_dirs(dirs, WATCH_MASK, sender, |path| {
+        Some(LoopEvent::FileChanged(path))
+    })
+}
+
+// Reports hidraw nodes appearing or becoming accessible as
+// `LoopEvent::DevicesChanged`, so decks are probed when plugged in.
+pub(crate) fn watch_hidraw(sender: mpsc::Sender<LoopEvent>) -> Result<(), String> {
+    watch_dirs(
+        &[PathBuf::from(DEVICE_DIR)],
+        HIDRAW_WATCH_MASK,
+        sender,
+        |path| {
+            path.file_name()
+                .is_some_and(|name| name.as_bytes().starts_with(b"hidraw"))
+                .then_some(LoopEvent::DevicesChanged)
+        },
+    )
+}
+
+fn watch_dirs(
+    dirs: &[PathBuf],
+    mask: u32,
+    sender: mpsc::Sender<LoopEvent>,
+    event: impl Fn(PathBuf) -> Option<LoopEvent> + Send + 'static,
+) -> Result<(), String> {
+    // SAFETY: inotify_init1 has no memory preconditions.
+    let fd = unsafe { inotify_init1(IN_CLOEXEC) };
+    if fd < 0 {
+        return Err(format!(
+            "Failed to initialize inotify: {}",
+            io::Error::last_os_error()
+        ));
+    }
+    // SAFETY: `fd` was just created and is owned by nothing else.
+    let mut inotify = unsafe { File::from_raw_fd(fd) };
+
+    let mut watches = Vec::new();
+    for dir in dirs {
+        let path = CString::new(dir.as_os_str().as_bytes())
+            .map_err(|_| format!("Cannot watch '{}': path contains NUL", dir.display()))?;
+        // SAFETY: `path` is a valid NUL terminated string for the duration of the call.
+        let wd = unsafe { inotify_add_watch(fd, path.as_ptr(), mask) };
+        if wd < 0 {
+            return Err(format!(
+                "Failed to watch '{}': {}",
+                dir.display(),
+                io::Error::last_os_error()
+            ));
+        }
+        watches.push((wd, dir.clone()));
+    }
+
+    thread::spawn(move || {
+        let mut buf = [0u8; 4096];
+        loop {
+            let length = match inotify.read(&mut buf) {
+                Ok(0) => return,
+                Ok(length) => length,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => {
+                    eprintln!("File watching stopped: {err}");
+                    return;
+                }
+            };
+            for (wd, name) in parse_events(&buf[..length]) {
+                let Some((_, dir)) = watches.iter().find(|(watch, _)| *watch == wd) else {
+                    continue;
+                };
+                let Some(event) = event(dir.join(name)) else {
+                    continue;
+                };
+                if sender.send(event).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+fn parse_events(buf: &[u8]) -> Vec<(i32, &OsStr)> {
+    let mut events = Vec::new();
+    let mut offset = 0;
+    while let Some(header) = buf.get(offset..offset + EVENT_HEADER_LENGTH) {
+        let wd = i32::from_ne_bytes([header[0], header[1], header[2], header[3]]);
+        let name_length = u32::from_ne_bytes([header[12], header[13], header[14], header[15]]);
+        let name_start = offset + EVENT_HEADER_LENGTH;
+        let Some(name) = buf.get(name_start..name_start + name_length as usize) else {
+            break;
+        };
+        let name = name.split(|byte| *byte == 0).next().unwrap_or_default();
+        if !name.is_empty() {
+            events.push((wd, OsStr::from_bytes(name)));
+        }
+        offset = name_start + name_length as usize;
+    }
+    events
+}
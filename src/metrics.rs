@@ -0,0 +1,490 @@
+use std::collections::VecDeque;
+use std::ffi::{CString, c_char, c_int, c_ulong};
+use std::fs;
+use std::mem::MaybeUninit;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+pub const METRIC_ICON_PREFIX: &str = "metric://";
+// Readings kept for sparklines.
+pub const METRIC_HISTORY_LEN: usize = 30;
+
+// `fsblkcnt_t`: 64 bits on musl and on 64-bit glibc, `unsigned long` on
+// 32-bit glibc without large file support.
+#[cfg(any(target_pointer_width = "64", target_env = "musl"))]
+type FsBlkCnt = u64;
+#[cfg(not(any(target_pointer_width = "64", target_env = "musl")))]
+type FsBlkCnt = c_ulong;
+
+// The leading fields of `struct statvfs`, which come first in the same order
+// on glibc and musl; `_rest` leaves room for the fields after them.
+#[repr(C)]
+struct StatVfs {
+    f_bsize: c_ulong,
+    f_frsize: c_ulong,
+    f_blocks: FsBlkCnt,
+    f_bfree: FsBlkCnt,
+    f_bavail: FsBlkCnt,
+    _rest: [u64; 16],
+}
+
+// Only widens anything on 32-bit glibc.
+#[allow(clippy::useless_conversion)]
+fn blocks(count: FsBlkCnt) -> u64 {
+    u64::from(count)
+}
+
+unsafe extern "C" {
+    fn statvfs(path: *const c_char, buf: *mut StatVfs) -> c_int;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Metric {
+    Cpu,
+    Memory,
+    Load,
+    // A mount point.
+    Disk(PathBuf),
+    // A hwmon directory such as `hwmon2`, or the chip name in its `name` file.
+    Temperature(String),
+}
+
+impl Metric {
+    // `None` for icons that are not metrics.
+    pub fn from_icon(icon: &str) -> Option<Result<Self, String>> {
+        let rest = icon
+            .get(..METRIC_ICON_PREFIX.len())
+            .filter(|head| head.eq_ignore_ascii_case(METRIC_ICON_PREFIX))
+            .map(|_| &icon[METRIC_ICON_PREFIX.len()..])?;
+        let (name, argument) = match rest.split_once('/') {
+            Some((name, argument)) => (name, Some(argument)),
+            None => (rest, None),
+        };
+        Some(match (name.to_ascii_lowercase().as_str(), argument) {
+            ("cpu", None) => Ok(Self::Cpu),
+            ("mem", None) => Ok(Self::Memory),
+            ("load", None) => Ok(Self::Load),
+            ("disk", Some(mount)) => Ok(Self::Disk(Path::new("/").join(mount.trim_matches('/')))),
+            ("temp", Some(hwmon))
+                if !hwmon.is_empty() && !hwmon.contains('/') && !hwmon.starts_with('.') =>
+            {
+                Ok(Self::Temperature(hwmon.to_string()))
+            }
+            _ => Err(format!(
+                "invalid metric icon '{icon}'; expected metric://cpu, metric://mem, metric://load, metric://disk/<mount> or metric://temp/<hwmon>"
+            )),
+        })
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Self::Cpu => "CPU".to_string(),
+            Self::Memory => "MEM".to_string(),
+            Self::Load => "LOAD".to_string(),
+            Self::Disk(mount) => mount.display().to_string(),
+            Self::Temperature(_) => "TEMP".to_string(),
+        }
+    }
+}
+
+// One reading: the value as shown and how full its gauge is, from 0 to 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricSample {
+    pub label: String,
+    pub text: String,
+    pub fraction: f32,
+}
+
+impl MetricSample {
+    pub fn unavailable(metric: &Metric) -> Self {
+        Self {
+            label: metric.label(),
+            text: "--".to_string(),
+            fraction: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CpuTimes {
+    busy: u64,
+    total: u64,
+}
+
+// The aggregate `cpu` line of /proc/stat. Guest time is already part of user time.
+fn parse_cpu_times(stat: &str) -> Option<CpuTimes> {
+    let line = stat.lines().find(|line| line.starts_with("cpu "))?;
+    let fields = line
+        .split_whitespace()
+        .skip(1)
+        .take(8)
+        .map(|field| field.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    if fields.len() < 4 {
+        return None;
+    }
+    let total = fields.iter().sum::<u64>();
+    let idle = fields[3] + fields.get(4).copied().unwrap_or(0);
+    Some(CpuTimes {
+        busy: total - idle,
+        total,
+    })
+}
+
+fn parse_cpu_count(stat: &str) -> usize {
+    stat.lines()
+        .filter(|line| {
+            line.strip_prefix("cpu")
+                .is_some_and(|rest| rest.starts_with(|ch: char| ch.is_ascii_digit()))
+        })
+        .count()
+}
+
+fn meminfo_kib(meminfo: &str, field: &str) -> Option<u64> {
+    meminfo.lines().find_map(|line| {
+        let value = line.strip_prefix(field)?.strip_prefix(':')?;
+        value.split_whitespace().next()?.parse().ok()
+    })
+}
+
+fn percent_text(fraction: f32) -> String {
+    format!("{:.0}%", fraction * 100.0)
+}
+
+// The used fraction of the filesystem holding `path`, as `df` counts it:
+// blocks reserved for root are left out. This can block for as long as a
+// stale network mount does.
+pub fn read_disk_usage(path: &Path) -> Result<f32, String> {
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| format!("Mount path '{}' contains a NUL byte", path.display()))?;
+    let mut stat = MaybeUninit::<StatVfs>::zeroed();
+    // SAFETY: `c_path` is NUL-terminated and `stat` is larger than the
+    // struct statvfs that the call fills in.
+    if unsafe { statvfs(c_path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+        return Err(format!(
+            "Failed to read disk usage of '{}': {}",
+            path.display(),
+            std::io::Error::last_os_error()
+        ));
+    }
+    // SAFETY: the struct started zeroed and the call succeeded.
+    let stat = unsafe { stat.assume_init() };
+    let used = blocks(stat.f_blocks).saturating_sub(blocks(stat.f_bfree));
+    let usable = used + blocks(stat.f_bavail);
+    Ok(if usable == 0 {
+        0.0
+    } else {
+        used as f32 / usable as f32
+    })
+}
+
+// Reads one metric for a key and keeps its recent readings.
+#[derive(Debug, Clone)]
+pub struct MetricSampler {
+    metric: Metric,
+    // `/` outside tests, which point it at fixture files.
+    root: PathBuf,
+    last_cpu: Option<CpuTimes>,
+    history: VecDeque<f32>,
+}
+
+impl MetricSampler {
+    pub fn new(metric: Metric) -> Self {
+        Self::with_root(metric, Path::new("/"))
+    }
+
+    pub fn with_root(metric: Metric, root: &Path) -> Self {
+        Self {
+            metric,
+            root: root.to_path_buf(),
+            last_cpu: None,
+            history: VecDeque::with_capacity(METRIC_HISTORY_LEN),
+        }
+    }
+
+    pub fn metric(&self) -> &Metric {
+        &self.metric
+    }
+
+    // Gauge fractions of the recent readings, oldest first.
+    pub fn history(&self) -> Vec<f32> {
+        self.history.iter().copied().collect()
+    }
+
+    // The path a disk metric reads, for callers that read it elsewhere with
+    // `read_disk_usage` and hand the result to `record_disk`.
+    pub fn disk_path(&self) -> Option<PathBuf> {
+        match &self.metric {
+            Metric::Disk(mount) => Some(self.root.join(mount.strip_prefix("/").unwrap_or(mount))),
+            _ => None,
+        }
+    }
+
+    // Reads the metric and adds it to the history. The first CPU reading
+    // covers the time since boot.
+    pub fn sample(&mut self) -> Result<MetricSample, String> {
+        let reading = match &self.metric {
+            Metric::Cpu => self.read_cpu(),
+            Metric::Memory => self.read_memory(),
+            Metric::Load => self.read_load(),
+            Metric::Disk(_) => self.read_disk(),
+            Metric::Temperature(hwmon) => self.read_temperature(hwmon),
+        };
+        self.record(reading)
+    }
+
+    // Adds a `read_disk_usage` result taken elsewhere to the history.
+    pub fn record_disk(&mut self, usage: Result<f32, String>) -> Result<MetricSample, String> {
+        self.record(usage.map(|fraction| (percent_text(fraction), fraction)))
+    }
+
+    fn record(&mut self, reading: Result<(String, f32), String>) -> Result<MetricSample, String> {
+        let (text, fraction) = reading?;
+        let fraction = fraction.clamp(0.0, 1.0);
+        if self.history.len() == METRIC_HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back(fraction);
+        Ok(MetricSample {
+            label: self.metric.label(),
+            text,
+            fraction,
+        })
+    }
+
+    fn read(&self, path: &str) -> Result<String, String> {
+        let path = self.root.join(path);
+        fs::read_to_string(&path)
+            .map_err(|err| format!("Failed to read '{}': {err}", path.display()))
+    }
+
+    fn read_cpu(&mut self) -> Result<(String, f32), String> {
+        let times = parse_cpu_times(&self.read("proc/stat")?)
+            .ok_or_else(|| "Failed to parse CPU times from /proc/stat".to_string())?;
+        let (busy, total) = match self.last_cpu.replace(times) {
+            Some(last) if times.total > last.total => (
+                times.busy.saturating_sub(last.busy),
+                times.total - last.total,
+            ),
+            _ => (times.busy, times.total.max(1)),
+        };
+        let fraction = busy as f32 / total as f32;
+        Ok((percent_text(fraction), fraction))
+    }
+
+    fn read_memory(&self) -> Result<(String, f32), String> {
+        let meminfo = self.read("proc/meminfo")?;
+        let (Some(total), Some(available)) = (
+            meminfo_kib(&meminfo, "MemTotal").filter(|&total| total > 0),
+            meminfo_kib(&meminfo, "MemAvailable"),
+        ) else {
+            return Err("Failed to parse memory usage from /proc/meminfo".to_string());
+        };
+        let fraction = 1.0 - available.min(total) as f32 / total as f32;
+        Ok((percent_text(fraction), fraction))
+    }
+
+    // The gauge is full when every CPU has a runnable task.
+    fn read_load(&self) -> Result<(String, f32), String> {
+        let load = self
+            .read("proc/loadavg")?
+            .split_whitespace()
+            .next()
+            .and_then(|field| field.parse::<f32>().ok())
+            .ok_or_else(|| "Failed to parse the load average from /proc/loadavg".to_string())?;
+        let cpus = parse_cpu_count(&self.read("proc/stat")?).max(1);
+        Ok((format!("{load:.2}"), load / cpus as f32))
+    }
+
+    fn read_disk(&self) -> Result<(String, f32), String> {
+        let path = self
+            .disk_path()
+            .ok_or_else(|| format!("{:?} is not a disk metric", self.metric))?;
+        let fraction = read_disk_usage(&path)?;
+        Ok((percent_text(fraction), fraction))
+    }
+
+    // Reads `temp1_input`; the gauge is full at 100 °C.
+    fn read_temperature(&self, hwmon: &str) -> Result<(String, f32), String> {
+        let hwmon_dir = self.find_hwmon(hwmon)?;
+        let input = hwmon_dir.join("temp1_input");
+        let millidegrees = fs::read_to_string(&input)
+            .map_err(|err| format!("Failed to read '{}': {err}", input.display()))?
+            .trim()
+            .parse::<f32>()
+            .map_err(|err| format!("Failed to parse '{}': {err}", input.display()))?;
+        let degrees = millidegrees / 1000.0;
+        Ok((format!("{degrees:.0}°C"), degrees / 100.0))
+    }
+
+    fn find_hwmon(&self, hwmon: &str) -> Result<PathBuf, String> {
+        let class_dir = self.root.join("sys/class/hwmon");
+        let direct = class_dir.join(hwmon);
+        if direct.is_dir() {
+            return Ok(direct);
+        }
+        let mut entries = fs::read_dir(&class_dir)
+            .map_err(|err| {
+                format!(
+                    "Failed to look for hwmon device '{hwmon}' in '{}': {err}",
+                    class_dir.display()
+                )
+            })?
+            .flatten()
+            .map(|entry| entry.path())
+            .collect::<Vec<_>>();
+        entries.sort();
+        entries
+            .into_iter()
+            .find(|dir| fs::read_to_string(dir.join("name")).is_ok_and(|name| name.trim() == hwmon))
+            .ok_or_else(|| format!("No hwmon device named '{hwmon}'"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static TEST_DIR_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    fn fixture_root(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let id = TEST_DIR_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let root = std::env::temp_dir().join(format!("streamrs-metrics-tests-{name}-{id}"));
+        for (path, contents) in files {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().expect("fixture file should have a parent"))
+                .expect("fixture dir should be creatable");
+            fs::write(&path, contents).expect("fixture file should be written");
+        }
+        root
+    }
+
+    #[test]
+    fn metric_icons_parse_their_kind_and_argument() {
+        assert_eq!(Metric::from_icon("metric://cpu"), Some(Ok(Metric::Cpu)));
+        assert_eq!(Metric::from_icon("METRIC://Mem"), Some(Ok(Metric::Memory)));
+        assert_eq!(Metric::from_icon("metric://load"), Some(Ok(Metric::Load)));
+        assert_eq!(
+            Metric::from_icon("metric://disk/"),
+            Some(Ok(Metric::Disk(PathBuf::from("/"))))
+        );
+        assert_eq!(
+            Metric::from_icon("metric://disk/home/user/"),
+            Some(Ok(Metric::Disk(PathBuf::from("/home/user"))))
+        );
+        assert_eq!(
+            Metric::from_icon("metric://temp/coretemp"),
+            Some(Ok(Metric::Temperature("coretemp".to_string())))
+        );
+        assert_eq!(Metric::from_icon("cpu.png"), None);
+        for bad in [
+            "metric://gpu",
+            "metric://cpu/0",
+            "metric://disk",
+            "metric://temp/",
+            "metric://temp/../hwmon0",
+        ] {
+            assert!(
+                matches!(Metric::from_icon(bad), Some(Err(_))),
+                "{bad} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn cpu_usage_compares_consecutive_readings() {
+        let root = fixture_root(
+            "cpu",
+            &[(
+                "proc/stat",
+                "cpu  100 0 100 700 100 0 0 0 0 0\ncpu0 50 0 50 350 50 0 0 0 0 0\n",
+            )],
+        );
+        let mut sampler = MetricSampler::with_root(Metric::Cpu, &root);
+        let since_boot = sampler.sample().expect("CPU should be read");
+        assert_eq!(since_boot.text, "20%");
+
+        fs::write(
+            root.join("proc/stat"),
+            "cpu  160 0 120 720 100 0 0 0 0 0\ncpu0 80 0 60 360 50 0 0 0 0 0\n",
+        )
+        .expect("fixture should be updated");
+        let sample = sampler.sample().expect("CPU should be read again");
+        assert_eq!(
+            (sample.label.as_str(), sample.text.as_str()),
+            ("CPU", "80%")
+        );
+        assert_eq!(sampler.history(), vec![0.2, 0.8]);
+    }
+
+    #[test]
+    fn memory_load_and_temperature_read_their_files() {
+        let root = fixture_root(
+            "files",
+            &[
+                (
+                    "proc/meminfo",
+                    "MemTotal:       16000000 kB\nMemFree:         1000000 kB\nMemAvailable:    4000000 kB\n",
+                ),
+                ("proc/loadavg", "3.00 2.00 1.00 2/300 4242\n"),
+                ("proc/stat", "cpu  1 1 1 1\ncpu0 1 1 1 1\ncpu1 1 1 1 1\n"),
+                ("sys/class/hwmon/hwmon0/name", "acpitz\n"),
+                ("sys/class/hwmon/hwmon3/name", "coretemp\n"),
+                ("sys/class/hwmon/hwmon3/temp1_input", "57400\n"),
+            ],
+        );
+        let sample = |metric| {
+            MetricSampler::with_root(metric, &root)
+                .sample()
+                .expect("metric should be read")
+        };
+        let memory = sample(Metric::Memory);
+        assert_eq!((memory.text.as_str(), memory.fraction), ("75%", 0.75));
+        let load = sample(Metric::Load);
+        assert_eq!((load.text.as_str(), load.fraction), ("3.00", 1.0));
+        let by_name = sample(Metric::Temperature("coretemp".to_string()));
+        assert_eq!(by_name.text, "57°C");
+        assert_eq!(
+            sample(Metric::Temperature("hwmon3".to_string())).text,
+            "57°C"
+        );
+
+        let mut missing =
+            MetricSampler::with_root(Metric::Temperature("k10temp".to_string()), &root);
+        assert!(missing.sample().is_err());
+        assert!(missing.history().is_empty());
+    }
+
+    #[test]
+    fn disk_usage_and_history_stay_bounded() {
+        let root = fixture_root("disk", &[("mounted/file", "")]);
+        let mut sampler = MetricSampler::with_root(Metric::Disk(PathBuf::from("/mounted")), &root);
+        for _ in 0..METRIC_HISTORY_LEN + 5 {
+            let sample = sampler.sample().expect("disk usage should be read");
+            assert!((0.0..=1.0).contains(&sample.fraction));
+            assert!(sample.text.ends_with('%'));
+        }
+        assert_eq!(sampler.history().len(), METRIC_HISTORY_LEN);
+        assert_eq!(
+            sampler.sample().map(|sample| sample.label),
+            Ok("/mounted".to_string())
+        );
+
+        let mut missing = MetricSampler::with_root(Metric::Disk(PathBuf::from("/gone")), &root);
+        assert!(missing.sample().is_err());
+
+        let path = sampler.disk_path().expect("disk metrics have a path");
+        assert_eq!(path, root.join("mounted"));
+        let usage = read_disk_usage(&path);
+        assert_eq!(
+            sampler
+                .record_disk(usage.clone())
+                .map(|sample| sample.fraction),
+            usage
+        );
+        assert!(sampler.record_disk(Err("stale".to_string())).is_err());
+        assert_eq!(sampler.history().len(), METRIC_HISTORY_LEN);
+        assert_eq!(MetricSampler::new(Metric::Cpu).disk_path(), None);
+    }
+}
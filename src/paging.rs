@@ -1,16 +1,22 @@
-pub const STREAMDECK_KEY_COUNT: usize = 15;
+use crate::streamdeck::{DeviceModel, MAX_KEY_COUNT};
+
 pub const MIN_KEYS_PER_PAGE: usize = 3;
+pub const MAX_KEYS_PER_PAGE: usize = MAX_KEY_COUNT;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum NavigationSlot {
     PreviousPage,
     NextPage,
+    FolderBack,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct PagingLayout {
     total_slots: usize,
     keys_per_page: usize,
+    first_slot: usize,
+    // Whether the last slots of a page are kept for previous/next page keys.
+    navigation_keys: bool,
 }
 
 impl PagingLayout {
@@ -18,9 +24,49 @@ impl PagingLayout {
         Self {
             total_slots,
             keys_per_page,
+            first_slot: 0,
+            navigation_keys: true,
+        }
+    }
+
+    pub fn for_model(model: &DeviceModel, keys_per_page: usize) -> Self {
+        Self::new(model.key_count, keys_per_page.min(model.key_count))
+    }
+
+    // Folder pages keep slot 0 for the back key and page the remaining slots.
+    pub fn for_folder(self) -> Self {
+        let keys_per_page = self
+            .keys_per_page
+            .saturating_sub(1)
+            .max(MIN_KEYS_PER_PAGE)
+            .min(self.total_slots.saturating_sub(1));
+        Self {
+            keys_per_page,
+            first_slot: 1,
+            ..self
+        }
+    }
+
+    // For configs that place their own page keys; every slot holds an action.
+    pub fn without_navigation_keys(self) -> Self {
+        Self {
+            navigation_keys: false,
+            ..self
         }
     }
 
+    pub fn has_navigation_keys(self) -> bool {
+        self.navigation_keys
+    }
+
+    pub fn first_slot(self) -> usize {
+        self.first_slot
+    }
+
+    pub fn folder_back_key(self) -> Option<usize> {
+        (self.first_slot > 0).then_some(0)
+    }
+
     pub fn total_slots(self) -> usize {
         self.total_slots
     }
@@ -38,15 +84,17 @@ impl PagingLayout {
     }
 
     pub fn previous_page_key(self) -> usize {
-        self.keys_per_page.saturating_sub(2)
+        self.first_slot + self.keys_per_page.saturating_sub(2)
     }
 
     pub fn next_page_key(self) -> usize {
-        self.keys_per_page.saturating_sub(1)
+        self.first_slot + self.keys_per_page.saturating_sub(1)
     }
 
     pub fn page_count(self, action_count: usize) -> usize {
-        if action_count <= self.keys_per_page {
+        if !self.navigation_keys {
+            action_count.div_ceil(self.keys_per_page.max(1)).max(1)
+        } else if action_count <= self.keys_per_page {
             1
         } else if action_count <= self.edge_page_action_key_count() * 2 {
             2
@@ -57,7 +105,7 @@ impl PagingLayout {
     }
 
     pub fn page_capacity(self, page: usize, total_pages: usize) -> usize {
-        if total_pages == 1 {
+        if total_pages == 1 || !self.navigation_keys {
             self.keys_per_page
         } else if page == 0 || page + 1 == total_pages {
             self.edge_page_action_key_count()
@@ -78,7 +126,13 @@ impl PagingLayout {
         total_pages: usize,
         slot: usize,
     ) -> Option<NavigationSlot> {
-        if total_pages <= 1 || slot >= self.total_slots {
+        if slot >= self.total_slots {
+            return None;
+        }
+        if self.folder_back_key() == Some(slot) {
+            return Some(NavigationSlot::FolderBack);
+        }
+        if total_pages <= 1 || !self.navigation_keys {
             return None;
         }
 
@@ -123,6 +177,7 @@ impl PagingLayout {
             return None;
         }
 
+        let slot = slot.checked_sub(self.first_slot)?;
         let capacity = self.page_capacity(page, total_pages);
         if slot >= capacity {
             return None;
@@ -49,6 +49,13 @@ pub fn streamrs_state_path() -> PathBuf {
         .join("state.toml")
 }
 
+// The daemon's control socket lives in the per-user runtime directory only.
+pub fn control_socket_path() -> Option<PathBuf> {
+    env::var_os("XDG_RUNTIME_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map(|dir| PathBuf::from(dir).join("streamrs.sock"))
+}
+
 pub fn profile_from_config_path(path: &Path) -> String {
     path.file_stem()
         .and_then(|stem| stem.to_str())
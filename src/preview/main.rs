@@ -4,12 +4,24 @@ use std::collections::HashSet;
 use std::env;
 use std::fs;
 use std::path::Path;
-use streamrs::image::calendar::{CALENDAR_ICON_ALIAS, is_calendar_icon, render_calendar_svg};
-use streamrs::image::clock::{
-    CLOCK_ICON_ALIAS, current_clock_text, is_clock_icon, render_clock_segments_svg,
+use std::time::Instant;
+use streamrs::config::streamrs_schema::DEFAULT_BRIGHTNESS;
+use streamrs::image::brightness::{
+    BRIGHTNESS_ICON_ALIAS, is_brightness_icon, render_brightness_svg,
 };
+use streamrs::image::calendar::{
+    CALENDAR_ICON_ALIAS, CalendarFace, is_calendar_icon, render_calendar_svg,
+};
+use streamrs::image::clock::{CLOCK_ICON_ALIAS, ClockFace, is_clock_icon, render_clock_face_svg};
+use streamrs::image::media::{
+    NOW_PLAYING_ICON, PlaybackStatus, is_now_playing_icon, render_media_svg,
+};
+use streamrs::image::metric::{MetricFace, render_metric_snapshot_svg};
+use streamrs::image::overlay::{KeyTitle, draw_title};
 use streamrs::image::svg::load_svg_data;
-use streamrs::process::run_shell_status;
+use streamrs::image::timer::{KeyTimer, TIMER_ICON_PREFIX, TimerKind, render_timer_svg};
+use streamrs::metrics::{METRIC_ICON_PREFIX, Metric};
+use streamrs::process::{STATUS_CHECK_TIMEOUT, run_shell_status};
 
 #[path = "../cli/preview.rs"]
 mod cli;
@@ -67,9 +79,15 @@ fn render_blank_base(
     Ok(resize(&rendered, width, height, Lanczos3))
 }
 
-fn load_icon_image(icon_name: &str, image_dir: &Path) -> Result<RgbaImage, String> {
+fn load_icon_image(
+    icon_name: &str,
+    clock: &ClockFace,
+    calendar: &CalendarFace,
+    metric: &MetricFace,
+    image_dir: &Path,
+) -> Result<RgbaImage, String> {
     if is_clock_icon(icon_name) {
-        let clock_svg = render_clock_segments_svg(image_dir, &current_clock_text(), None);
+        let clock_svg = render_clock_face_svg(image_dir, clock, clock.now());
         let img = load_svg_data(
             CLOCK_ICON_ALIAS,
             clock_svg.as_bytes(),
@@ -80,7 +98,7 @@ fn load_icon_image(icon_name: &str, image_dir: &Path) -> Result<RgbaImage, Strin
         return Ok(img);
     }
     if is_calendar_icon(icon_name) {
-        let calendar_svg = render_calendar_svg();
+        let calendar_svg = render_calendar_svg(calendar);
         let img = load_svg_data(
             CALENDAR_ICON_ALIAS,
             calendar_svg.as_bytes(),
@@ -90,6 +108,50 @@ fn load_icon_image(icon_name: &str, image_dir: &Path) -> Result<RgbaImage, Strin
         )?;
         return Ok(img);
     }
+    if is_brightness_icon(icon_name) {
+        let brightness_svg = render_brightness_svg(DEFAULT_BRIGHTNESS);
+        let img = load_svg_data(
+            BRIGHTNESS_ICON_ALIAS,
+            brightness_svg.as_bytes(),
+            None,
+            CLOCK_RENDER_SIZE,
+            CLOCK_RENDER_SIZE,
+        )?;
+        return Ok(img);
+    }
+    if let Some(kind) = TimerKind::from_icon(icon_name) {
+        let timer_svg = render_timer_svg(&KeyTimer::new(kind?).face(Instant::now()));
+        let img = load_svg_data(
+            TIMER_ICON_PREFIX,
+            timer_svg.as_bytes(),
+            None,
+            CLOCK_RENDER_SIZE,
+            CLOCK_RENDER_SIZE,
+        )?;
+        return Ok(img);
+    }
+    if is_now_playing_icon(icon_name) {
+        let media_svg = render_media_svg(PlaybackStatus::Stopped);
+        let img = load_svg_data(
+            NOW_PLAYING_ICON,
+            media_svg.as_bytes(),
+            None,
+            CLOCK_RENDER_SIZE,
+            CLOCK_RENDER_SIZE,
+        )?;
+        return Ok(img);
+    }
+    if let Some(kind) = Metric::from_icon(icon_name) {
+        let metric_svg = render_metric_snapshot_svg(kind?, metric);
+        let img = load_svg_data(
+            METRIC_ICON_PREFIX,
+            metric_svg.as_bytes(),
+            None,
+            CLOCK_RENDER_SIZE,
+            CLOCK_RENDER_SIZE,
+        )?;
+        return Ok(img);
+    }
 
     let path = image_dir.join(icon_name);
     let data =
@@ -124,7 +186,7 @@ fn choose_icon_name(key: &KeyBinding, evaluate_status: bool) -> String {
     if !evaluate_status || status.is_none() {
         return icon;
     }
-    let is_on = run_shell_status(status.unwrap().as_str()).unwrap_or(false);
+    let is_on = run_shell_status(status.unwrap().as_str(), STATUS_CHECK_TIMEOUT).unwrap_or(false);
     let on = key
         .icon_on
         .as_ref()
@@ -386,7 +448,40 @@ fn compose_preview(args: &CliArgs) -> Result<(), String> {
             continue;
         }
         let icon_name = choose_icon_name(&config.keys[idx], EVALUATE_STATUS);
-        let icon = match load_icon_image(&icon_name, &args.image_dir) {
+        let clock = if is_clock_icon(&icon_name) {
+            config.keys[idx].clock_face().unwrap_or_else(|err| {
+                eprintln!(
+                    "Warning: key {}: {err}. Using the default clock face",
+                    idx + 1
+                );
+                ClockFace::default()
+            })
+        } else {
+            ClockFace::default()
+        };
+        let calendar = if is_calendar_icon(&icon_name) {
+            config.keys[idx].calendar_face().unwrap_or_else(|err| {
+                eprintln!(
+                    "Warning: key {}: {err}. Using the default calendar face",
+                    idx + 1
+                );
+                CalendarFace::default()
+            })
+        } else {
+            CalendarFace::default()
+        };
+        let metric = if Metric::from_icon(&icon_name).is_some() {
+            config.keys[idx].metric_face().unwrap_or_else(|err| {
+                eprintln!(
+                    "Warning: key {}: {err}. Using the default metric face",
+                    idx + 1
+                );
+                MetricFace::default()
+            })
+        } else {
+            MetricFace::default()
+        };
+        let icon = match load_icon_image(&icon_name, &clock, &calendar, &metric, &args.image_dir) {
             Ok(img) => img,
             Err(err) => {
                 if warned_icon_errors.insert(icon_name.clone()) {
@@ -424,6 +519,20 @@ fn compose_preview(args: &CliArgs) -> Result<(), String> {
         let content_h = (box_h - ICON_CONTENT_SHRINK_Y.max(0)).max(1);
 
         let mut fitted_inner = resize(&icon, content_w as u32, content_h as u32, Lanczos3);
+        let key = &config.keys[idx];
+        let title = KeyTitle::from_fields(
+            key.title.as_deref(),
+            key.title_position.as_deref(),
+            key.title_color.as_deref(),
+            key.title_size,
+        )
+        .and_then(|title| match title {
+            Some(title) => draw_title(&mut fitted_inner, &title),
+            None => Ok(()),
+        });
+        if let Err(err) = title {
+            eprintln!("Warning: key {}: {err}", idx + 1);
+        }
         let content_radius = ((content_w.min(content_h) as f32) * 0.16).round().max(2.0) as u32;
         let content_round_mask =
             rounded_rect_mask(content_w as u32, content_h as u32, content_radius);
@@ -10,20 +10,23 @@ use gtk::{
     },
 };
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::env;
 use std::fs;
 #[cfg(unix)]
 use std::fs::Permissions;
-use std::io::ErrorKind;
+use std::io::{ErrorKind, Read};
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
+#[cfg(unix)]
+use std::os::unix::process::CommandExt;
 use std::panic::{AssertUnwindSafe, catch_unwind};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{Child, Command, Stdio};
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::{Arc, Mutex, OnceLock};
-use std::time::Duration;
+use std::sync::{Arc, Mutex, OnceLock, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
 use xkeysym::key as keysym;
 
 const PORTAL_BUS_NAME: &str = "org.freedesktop.portal.Desktop";
@@ -77,21 +80,107 @@ pub fn wait_argv_command_success(command_line: &str, debug: bool) -> Result<bool
     Ok(status.success())
 }
 
-pub fn run_shell_status(command: &str) -> Result<bool, String> {
+pub const STATUS_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+const STATUS_CHECK_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+pub fn run_shell_status(command: &str, timeout: Duration) -> Result<bool, String> {
+    run_status_command(command, false, timeout).map(|(success, _)| success)
+}
+
+// Output-mode status checks read stdout whatever the exit status.
+pub fn run_shell_status_output(command: &str, timeout: Duration) -> Result<String, String> {
+    run_status_command(command, true, timeout)
+        .map(|(_, stdout)| String::from_utf8_lossy(&stdout).into_owned())
+}
+
+// The command runs in its own process group, which is killed as a whole once
+// `timeout` passes, along with anything it started in the background.
+fn run_status_command(
+    command: &str,
+    capture_stdout: bool,
+    timeout: Duration,
+) -> Result<(bool, Vec<u8>), String> {
     if command.trim().is_empty() {
         return Err("Status check command is empty".to_string());
     }
 
-    let status = Command::new("sh")
-        .arg("-c")
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c")
         .arg(command)
         .stdin(Stdio::null())
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .status()
+        .stdout(if capture_stdout {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stderr(Stdio::null());
+    #[cfg(unix)]
+    cmd.process_group(0);
+    let mut child = cmd
+        .spawn()
         .map_err(|err| format!("Failed to run status check '{command}': {err}"))?;
 
-    Ok(status.success())
+    let deadline = Instant::now() + timeout;
+    let timed_out = |child: &mut Child| {
+        kill_process_group(child);
+        let _ = child.wait();
+        format!(
+            "Status check '{command}' timed out after {} ms",
+            timeout.as_millis()
+        )
+    };
+    // Read on another thread so a full pipe cannot stall the command.
+    let stdout = child.stdout.take().map(|mut stdout| {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut data = Vec::new();
+            let _ = stdout.read_to_end(&mut data);
+            let _ = sender.send(data);
+        });
+        receiver
+    });
+
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() >= deadline => return Err(timed_out(&mut child)),
+            Ok(None) => thread::sleep(STATUS_CHECK_POLL_INTERVAL),
+            Err(err) => {
+                kill_process_group(&mut child);
+                return Err(format!(
+                    "Failed waiting for status check '{command}': {err}"
+                ));
+            }
+        }
+    };
+    let stdout = match stdout {
+        // Background commands may keep the pipe open after the shell exits.
+        Some(receiver) => {
+            match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(data) => data,
+                Err(_) => return Err(timed_out(&mut child)),
+            }
+        }
+        None => Vec::new(),
+    };
+    Ok((status.success(), stdout))
+}
+
+#[cfg(unix)]
+fn kill_process_group(child: &mut Child) {
+    unsafe extern "C" {
+        fn kill(pid: i32, signal: i32) -> i32;
+    }
+    const SIGKILL: i32 = 9;
+
+    // SAFETY: kill(2) takes plain integers; the negative pid names the group
+    // the child leads.
+    let _ = unsafe { kill(-(child.id() as i32), SIGKILL) };
+}
+
+#[cfg(not(unix))]
+fn kill_process_group(child: &mut Child) {
+    let _ = child.kill();
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -140,6 +229,9 @@ struct PortalKeyboardBackend {
 struct StreamrsState {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     portal_remote_desktop_restore_token: Option<String>,
+    // Brightness chosen with brightness keys, by deck serial.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    deck_brightness: BTreeMap<String, usize>,
 }
 
 impl PortalKeyboardBackend {
@@ -292,10 +384,22 @@ impl PortalKeyboardBackend {
         trigger_result
     }
 
+    fn type_keysyms(&self, keysyms: &[i32]) -> Result<(), String> {
+        for &keysym in keysyms {
+            self.notify_keysym(keysym, PORTAL_KEY_PRESSED)?;
+            self.notify_keysym(keysym, PORTAL_KEY_RELEASED)?;
+        }
+        Ok(())
+    }
+
     fn notify_key(&self, key: ShortcutKey, state: u32) -> Result<(), String> {
         let keysym = shortcut_key_to_keysym(key).ok_or_else(|| {
             format!("Keyboard shortcut key '{key:?}' is not supported by the portal backend")
         })?;
+        self.notify_keysym(keysym, state)
+    }
+
+    fn notify_keysym(&self, keysym: i32, state: u32) -> Result<(), String> {
         let options = portal_options(&[]);
         self.proxy
             .call_sync(
@@ -356,6 +460,44 @@ pub fn send_keyboard_shortcut(shortcut: &str) -> Result<(), String> {
     Ok(())
 }
 
+// On Wayland the text is sent as keysyms over the portal session. Control
+// characters other than newline and tab have no keysym, so text containing them
+// goes through enigo's text entry instead, as does text the portal rejects.
+pub fn type_text(text: &str) -> Result<(), String> {
+    if text.is_empty() {
+        return Ok(());
+    }
+    let length = text.chars().count();
+    eprintln!("Typing {length} characters");
+    let mut portal_error = None;
+
+    if should_try_portal_keyboard() {
+        match text_to_keysyms(text) {
+            Ok(keysyms) => {
+                match with_portal_keyboard_backend(|backend| backend.type_keysyms(&keysyms)) {
+                    Ok(()) => return Ok(()),
+                    Err(err) => {
+                        eprintln!("Portal keyboard backend failed: {err}");
+                        portal_error = Some(err);
+                    }
+                }
+            }
+            Err(ch) => eprintln!(
+                "Text contains U+{:04X}, which has no keysym; using the fallback backend",
+                u32::from(ch)
+            ),
+        }
+    }
+
+    type_text_with_enigo(text).map_err(|err| {
+        if let Some(portal_err) = portal_error {
+            format!("Portal backend failed: {portal_err}; fallback backend failed: {err}")
+        } else {
+            err
+        }
+    })
+}
+
 fn parse_keyboard_shortcut(shortcut: &str) -> Result<ParsedShortcut, String> {
     let tokens: Vec<&str> = shortcut
         .split('+')
@@ -468,6 +610,13 @@ fn should_try_portal_keyboard() -> bool {
 }
 
 fn send_keyboard_shortcut_via_portal(parsed: &ParsedShortcut) -> Result<(), String> {
+    with_portal_keyboard_backend(|backend| backend.send_shortcut(parsed))
+}
+
+// A failed call drops the cached session so the next one starts a fresh portal session.
+fn with_portal_keyboard_backend(
+    send: impl FnOnce(&PortalKeyboardBackend) -> Result<(), String>,
+) -> Result<(), String> {
     let cache = PORTAL_KEYBOARD_BACKEND.get_or_init(|| Mutex::new(None));
     let mut guard = cache
         .lock()
@@ -481,7 +630,7 @@ fn send_keyboard_shortcut_via_portal(parsed: &ParsedShortcut) -> Result<(), Stri
         .as_ref()
         .ok_or_else(|| "Portal keyboard backend cache was unexpectedly empty".to_string())?;
 
-    if let Err(err) = backend.send_shortcut(parsed) {
+    if let Err(err) = send(backend) {
         *guard = None;
         return Err(err);
     }
@@ -531,6 +680,17 @@ fn send_keyboard_shortcut_with_enigo(
     .map_err(|_| format!("Keyboard input backend panicked while sending shortcut '{shortcut}'"))?
 }
 
+fn type_text_with_enigo(text: &str) -> Result<(), String> {
+    catch_unwind(AssertUnwindSafe(|| {
+        let mut enigo = Enigo::new(&Settings::default())
+            .map_err(|err| format!("Failed to initialize keyboard input backend: {err}"))?;
+        enigo
+            .text(text)
+            .map_err(|err| format!("Failed to type text: {err}"))
+    }))
+    .map_err(|_| "Keyboard input backend panicked while typing text".to_string())?
+}
+
 fn shortcut_key_to_enigo(key: ShortcutKey) -> Option<Key> {
     match key {
         ShortcutKey::Control => Some(Key::Control),
@@ -672,6 +832,26 @@ fn shortcut_key_to_keysym(key: ShortcutKey) -> Option<i32> {
     i32::try_from(raw).ok()
 }
 
+// Returns the first character that has no keysym.
+fn text_to_keysyms(text: &str) -> Result<Vec<i32>, char> {
+    text.chars()
+        .map(|ch| text_char_to_keysym(ch).ok_or(ch))
+        .collect()
+}
+
+fn text_char_to_keysym(ch: char) -> Option<i32> {
+    let raw = match ch {
+        '\n' | '\r' => keysym::Return,
+        '\t' => keysym::Tab,
+        ch if ch.is_control() => return None,
+        ch => xkeysym::Keysym::from_char(ch).raw(),
+    };
+    if raw == keysym::NoSymbol {
+        return None;
+    }
+    i32::try_from(raw).ok()
+}
+
 fn next_portal_token(prefix: &str) -> String {
     let suffix = PORTAL_TOKEN_COUNTER.fetch_add(1, Ordering::Relaxed);
     format!("{prefix}_{suffix}")
@@ -681,23 +861,32 @@ fn streamrs_state_path() -> PathBuf {
     crate::paths::streamrs_state_path()
 }
 
-fn load_streamrs_state() -> Result<StreamrsState, String> {
-    let path = streamrs_state_path();
-    match crate::config::toml::load_from_file(&path) {
+fn load_streamrs_state(path: &Path) -> Result<StreamrsState, String> {
+    match crate::config::toml::load_from_file(path) {
         Ok(state) => Ok(state),
-        Err(_) if matches!(fs::metadata(&path), Err(metadata_err) if metadata_err.kind() == ErrorKind::NotFound) => {
+        Err(_) if matches!(fs::metadata(path), Err(metadata_err) if metadata_err.kind() == ErrorKind::NotFound) => {
             Ok(StreamrsState::default())
         }
         Err(err) => Err(err),
     }
 }
 
-fn save_streamrs_state(state: &StreamrsState) -> Result<(), String> {
-    let path = streamrs_state_path();
-    crate::config::toml::save_to_file_pretty(&path, state)?;
+// An empty state removes the file instead of leaving an empty one behind.
+fn save_streamrs_state(path: &Path, state: &StreamrsState) -> Result<(), String> {
+    if *state == StreamrsState::default() {
+        return match fs::remove_file(path) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(format!(
+                "Failed to remove streamrs state file '{}': {err}",
+                path.display()
+            )),
+        };
+    }
+    crate::config::toml::save_to_file_pretty(path, state)?;
 
     #[cfg(unix)]
-    fs::set_permissions(&path, Permissions::from_mode(0o600)).map_err(|err| {
+    fs::set_permissions(path, Permissions::from_mode(0o600)).map_err(|err| {
         format!(
             "Failed to secure streamrs state file '{}': {err}",
             path.display()
@@ -707,8 +896,33 @@ fn save_streamrs_state(state: &StreamrsState) -> Result<(), String> {
     Ok(())
 }
 
+pub fn load_deck_brightness(state_path: &Path, serial: &str) -> Result<Option<usize>, String> {
+    Ok(load_streamrs_state(state_path)?
+        .deck_brightness
+        .get(serial)
+        .copied())
+}
+
+// `None` forgets the deck's brightness again.
+pub fn save_deck_brightness(
+    state_path: &Path,
+    serial: &str,
+    brightness: Option<usize>,
+) -> Result<(), String> {
+    let brightness = brightness.map(|brightness| brightness.min(100));
+    let mut state = load_streamrs_state(state_path)?;
+    let previous = match brightness {
+        Some(brightness) => state.deck_brightness.insert(serial.to_string(), brightness),
+        None => state.deck_brightness.remove(serial),
+    };
+    if previous == brightness {
+        return Ok(());
+    }
+    save_streamrs_state(state_path, &state)
+}
+
 fn load_portal_restore_token() -> Result<Option<String>, String> {
-    Ok(load_streamrs_state()?
+    Ok(load_streamrs_state(&streamrs_state_path())?
         .portal_remote_desktop_restore_token
         .and_then(|token| {
             let trimmed = token.trim();
@@ -717,28 +931,17 @@ fn load_portal_restore_token() -> Result<Option<String>, String> {
 }
 
 fn save_portal_restore_token(token: &str) -> Result<(), String> {
-    let mut state = load_streamrs_state()?;
+    let path = streamrs_state_path();
+    let mut state = load_streamrs_state(&path)?;
     state.portal_remote_desktop_restore_token = Some(token.trim().to_string());
-    save_streamrs_state(&state)
+    save_streamrs_state(&path, &state)
 }
 
 fn clear_portal_restore_token() -> Result<(), String> {
     let path = streamrs_state_path();
-    let mut state = load_streamrs_state()?;
+    let mut state = load_streamrs_state(&path)?;
     state.portal_remote_desktop_restore_token = None;
-
-    if state == StreamrsState::default() {
-        match fs::remove_file(&path) {
-            Ok(()) => Ok(()),
-            Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
-            Err(err) => Err(format!(
-                "Failed to remove streamrs state file '{}': {err}",
-                path.display()
-            )),
-        }
-    } else {
-        save_streamrs_state(&state)
-    }
+    save_streamrs_state(&path, &state)
 }
 
 fn portal_request_path(connection: &gio::DBusConnection, token: &str) -> Result<String, String> {
@@ -898,14 +1101,47 @@ mod tests {
 
     #[test]
     fn empty_status_command_is_rejected() {
-        let err = run_shell_status("   ").expect_err("blank status command should fail");
+        let err = run_shell_status("   ", STATUS_CHECK_TIMEOUT)
+            .expect_err("blank status command should fail");
         assert!(err.contains("empty"));
     }
 
     #[test]
     fn shell_status_reports_success_and_failure() {
-        assert!(run_shell_status("true").expect("true should run"));
-        assert!(!run_shell_status("false").expect("false should run"));
+        assert!(run_shell_status("true", STATUS_CHECK_TIMEOUT).expect("true should run"));
+        assert!(!run_shell_status("false", STATUS_CHECK_TIMEOUT).expect("false should run"));
+    }
+
+    #[test]
+    fn status_checks_time_out_and_kill_their_process_group() {
+        let dir = std::env::temp_dir().join(format!(
+            "streamrs-process-tests-status-timeout-{}",
+            TEST_COUNTER.fetch_add(1, AtomicOrdering::Relaxed)
+        ));
+        fs::create_dir_all(&dir).expect("test dir should be creatable");
+        let late = dir.join("late");
+        let started = Instant::now();
+        let err = run_shell_status(
+            &format!("(sleep 1; touch '{}') & wait", late.display()),
+            Duration::from_millis(200),
+        )
+        .expect_err("slow status check should time out");
+        assert!(err.contains("timed out after 200 ms"));
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        // A command left running in the background would still create the file.
+        thread::sleep(Duration::from_millis(1500));
+        assert!(!late.exists());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn shell_status_output_keeps_stdout_of_failing_commands() {
+        assert_eq!(
+            run_shell_status_output("echo 42%; exit 3", STATUS_CHECK_TIMEOUT)
+                .expect("command should run"),
+            "42%\n"
+        );
     }
 
     #[test]
@@ -969,6 +1205,23 @@ mod tests {
         assert!(err.contains("empty"));
     }
 
+    #[test]
+    fn text_maps_to_portal_keysyms() {
+        assert_eq!(
+            text_to_keysyms("aZ é\n"),
+            Ok(vec![
+                keysym::a as i32,
+                keysym::Z as i32,
+                keysym::space as i32,
+                keysym::eacute as i32,
+                keysym::Return as i32,
+            ])
+        );
+        assert_eq!(text_to_keysyms("€"), Ok(vec![keysym::EuroSign as i32]));
+        assert_eq!(text_to_keysyms("🙂"), Ok(vec![0x0100_0000 + 0x1f642]));
+        assert_eq!(text_to_keysyms("a\u{7}b"), Err('\u{7}'));
+    }
+
     #[test]
     fn dbus_sender_name_is_sanitized_for_portal_request_paths() {
         assert_eq!(sanitize_dbus_path_component(":1.42"), "1_42");
@@ -1001,4 +1254,44 @@ mod tests {
             );
         });
     }
+
+    #[test]
+    fn deck_brightness_is_kept_next_to_the_portal_token() {
+        with_temp_xdg_state_home("deck-brightness", || {
+            let path = streamrs_state_path();
+            save_portal_restore_token("token-123").expect("token should save");
+            save_deck_brightness(&path, "CL1", Some(30)).expect("brightness should save");
+            assert_eq!(
+                load_deck_brightness(&path, "CL1").expect("brightness should load"),
+                Some(30)
+            );
+            assert_eq!(
+                load_deck_brightness(&path, "CL2").expect("missing deck should load"),
+                None
+            );
+            assert_eq!(
+                load_portal_restore_token().expect("token should load"),
+                Some("token-123".to_string())
+            );
+
+            save_deck_brightness(&path, "CL1", Some(150)).expect("brightness should save");
+            assert_eq!(
+                load_deck_brightness(&path, "CL1").expect("brightness should load"),
+                Some(100)
+            );
+            let saved = fs::read_to_string(&path).expect("state should be readable");
+            fs::write(&path, format!("# unchanged\n{saved}")).expect("state should be writable");
+            save_deck_brightness(&path, "CL1", Some(150)).expect("brightness should save");
+            assert!(
+                fs::read_to_string(&path)
+                    .expect("state should be readable")
+                    .starts_with("# unchanged"),
+                "an unchanged clamped level should not rewrite the state file"
+            );
+
+            clear_portal_restore_token().expect("token should clear");
+            save_deck_brightness(&path, "CL1", None).expect("brightness should clear");
+            assert!(!path.exists());
+        });
+    }
 }
@@ -0,0 +1,114 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+// What a status command in output mode asks its key to show. Fields left
+// unset keep the key's own icon, no title and no background.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StatusOutput {
+    pub icon: Option<String>,
+    pub title: Option<String>,
+    pub background: Option<String>,
+}
+
+impl StatusOutput {
+    // Stdout is either a JSON object with `icon`, `title` and `background`, or
+    // a line naming one of `state_icons`, or a line shown as the title. Icons
+    // may name a state as well as an icon file.
+    pub fn parse(stdout: &str, state_icons: &BTreeMap<String, String>) -> Self {
+        let trimmed = stdout.trim();
+        if trimmed.starts_with('{')
+            && let Ok(Value::Object(fields)) = serde_json::from_str::<Value>(trimmed)
+        {
+            let text = |name: &str| match fields.get(name) {
+                Some(Value::String(value)) => non_empty(value),
+                Some(Value::Number(value)) => Some(value.to_string()),
+                _ => None,
+            };
+            return Self {
+                icon: text("icon").map(|icon| state_icons.get(&icon).cloned().unwrap_or(icon)),
+                title: text("title"),
+                background: text("background"),
+            };
+        }
+
+        let Some(line) = trimmed.lines().next().and_then(non_empty) else {
+            return Self::default();
+        };
+        match state_icons.get(&line) {
+            Some(icon) => Self {
+                icon: Some(icon.clone()),
+                ..Self::default()
+            },
+            None => Self {
+                title: Some(line),
+                ..Self::default()
+            },
+        }
+    }
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let trimmed = value.trim();
+    (!trimmed.is_empty()).then(|| trimmed.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn states() -> BTreeMap<String, String> {
+        BTreeMap::from([
+            ("muted".to_string(), "volume-muted.png".to_string()),
+            ("loud".to_string(), "volume-high.png".to_string()),
+        ])
+    }
+
+    #[test]
+    fn output_lines_pick_a_state_or_become_the_title() {
+        assert_eq!(
+            StatusOutput::parse("muted\n", &states()),
+            StatusOutput {
+                icon: Some("volume-muted.png".to_string()),
+                ..StatusOutput::default()
+            }
+        );
+        assert_eq!(
+            StatusOutput::parse("  42%\nignored\n", &states()),
+            StatusOutput {
+                title: Some("42%".to_string()),
+                ..StatusOutput::default()
+            }
+        );
+        assert_eq!(
+            StatusOutput::parse(" \n", &states()),
+            StatusOutput::default()
+        );
+    }
+
+    #[test]
+    fn json_output_sets_icon_title_and_background() {
+        assert_eq!(
+            StatusOutput::parse(
+                r##"{"icon": "loud", "title": 7, "background": "#ff0000", "extra": true}"##,
+                &states()
+            ),
+            StatusOutput {
+                icon: Some("volume-high.png".to_string()),
+                title: Some("7".to_string()),
+                background: Some("#ff0000".to_string()),
+            }
+        );
+        assert_eq!(
+            StatusOutput::parse(r#"{"icon": "mail.png", "title": ""}"#, &states()),
+            StatusOutput {
+                icon: Some("mail.png".to_string()),
+                ..StatusOutput::default()
+            }
+        );
+        // Not an object, so the text is the title.
+        assert_eq!(
+            StatusOutput::parse("{broken", &states()).title.as_deref(),
+            Some("{broken")
+        );
+    }
+}
@@ -1,59 +1,329 @@
 use hidapi::{HidApi, HidDevice};
 use std::cmp::min;
 
-pub fn get_device(
+pub mod mock;
+
+pub trait DeckTransport {
+    fn send_feature_report(&self, data: &[u8]) -> Result<(), String>;
+    fn write(&self, data: &[u8]) -> Result<usize, String>;
+    fn read_timeout(&self, buf: &mut [u8], timeout_ms: i32) -> Result<usize, String>;
+}
+
+impl DeckTransport for HidDevice {
+    fn send_feature_report(&self, data: &[u8]) -> Result<(), String> {
+        HidDevice::send_feature_report(self, data).map_err(|err| err.to_string())
+    }
+
+    fn write(&self, data: &[u8]) -> Result<usize, String> {
+        HidDevice::write(self, data).map_err(|err| err.to_string())
+    }
+
+    fn read_timeout(&self, buf: &mut [u8], timeout_ms: i32) -> Result<usize, String> {
+        HidDevice::read_timeout(self, buf, timeout_ms).map_err(|err| err.to_string())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+    Jpeg,
+    Bmp,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageRotation {
+    None,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageReportHeader {
+    // 16-byte header used by the Original and Mini: page, last flag and 1-based key.
+    V1 { first_page: u8 },
+    // 8-byte header used by MK.2-era devices: key, last flag, payload length and page.
+    V2,
+}
+
+impl ImageReportHeader {
+    pub fn header_length(self) -> usize {
+        match self {
+            ImageReportHeader::V1 { .. } => 16,
+            ImageReportHeader::V2 => 8,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReportLayout {
+    pub image_report_length: usize,
+    pub image_header: ImageReportHeader,
+    pub key_state_offset: usize,
+    pub key_event_kind_offset: Option<usize>,
+    pub key_columns_reversed: bool,
+    pub brightness_prefix: &'static [u8],
+    pub brightness_report_length: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeviceModel {
+    pub name: &'static str,
+    pub product_ids: &'static [u16],
+    pub key_count: usize,
+    pub rows: usize,
+    pub columns: usize,
+    pub image_size: u32,
+    pub image_rotation: ImageRotation,
+    pub image_flip_horizontal: bool,
+    pub image_flip_vertical: bool,
+    pub image_format: ImageFormat,
+    pub reports: ReportLayout,
+}
+
+const V1_BRIGHTNESS_PREFIX: &[u8] = &[0x05, 0x55, 0xaa, 0xd1, 0x01];
+const V2_BRIGHTNESS_PREFIX: &[u8] = &[0x03, 0x08];
+
+const V2_REPORTS: ReportLayout = ReportLayout {
+    image_report_length: 1024,
+    image_header: ImageReportHeader::V2,
+    key_state_offset: 4,
+    key_event_kind_offset: None,
+    key_columns_reversed: false,
+    brightness_prefix: V2_BRIGHTNESS_PREFIX,
+    brightness_report_length: 32,
+};
+
+pub const STREAMDECK_ORIGINAL: DeviceModel = DeviceModel {
+    name: "Stream Deck Original",
+    product_ids: &[0x0060],
+    key_count: 15,
+    rows: 3,
+    columns: 5,
+    image_size: 72,
+    image_rotation: ImageRotation::Rotate180,
+    image_flip_horizontal: false,
+    image_flip_vertical: false,
+    image_format: ImageFormat::Bmp,
+    reports: ReportLayout {
+        image_report_length: 8191,
+        image_header: ImageReportHeader::V1 { first_page: 1 },
+        key_state_offset: 1,
+        key_event_kind_offset: None,
+        key_columns_reversed: true,
+        brightness_prefix: V1_BRIGHTNESS_PREFIX,
+        brightness_report_length: 17,
+    },
+};
+
+pub const STREAMDECK_ORIGINAL_V2: DeviceModel = DeviceModel {
+    name: "Stream Deck Original (v2)",
+    product_ids: &[0x006d],
+    key_count: 15,
+    rows: 3,
+    columns: 5,
+    image_size: 72,
+    image_rotation: ImageRotation::Rotate180,
+    image_flip_horizontal: false,
+    image_flip_vertical: false,
+    image_format: ImageFormat::Jpeg,
+    reports: V2_REPORTS,
+};
+
+pub const STREAMDECK_MK2: DeviceModel = DeviceModel {
+    name: "Stream Deck MK.2",
+    product_ids: &[0x0080],
+    key_count: 15,
+    rows: 3,
+    columns: 5,
+    image_size: 72,
+    image_rotation: ImageRotation::Rotate180,
+    image_flip_horizontal: false,
+    image_flip_vertical: false,
+    image_format: ImageFormat::Jpeg,
+    reports: V2_REPORTS,
+};
+
+pub const STREAMDECK_MINI: DeviceModel = DeviceModel {
+    name: "Stream Deck Mini",
+    product_ids: &[0x0063, 0x0090],
+    key_count: 6,
+    rows: 2,
+    columns: 3,
+    image_size: 80,
+    image_rotation: ImageRotation::Rotate270,
+    image_flip_horizontal: false,
+    image_flip_vertical: true,
+    image_format: ImageFormat::Bmp,
+    reports: ReportLayout {
+        image_report_length: 1024,
+        image_header: ImageReportHeader::V1 { first_page: 0 },
+        key_state_offset: 1,
+        key_event_kind_offset: None,
+        key_columns_reversed: false,
+        brightness_prefix: V1_BRIGHTNESS_PREFIX,
+        brightness_report_length: 17,
+    },
+};
+
+pub const STREAMDECK_XL: DeviceModel = DeviceModel {
+    name: "Stream Deck XL",
+    product_ids: &[0x006c, 0x008f],
+    key_count: 32,
+    rows: 4,
+    columns: 8,
+    image_size: 96,
+    image_rotation: ImageRotation::Rotate180,
+    image_flip_horizontal: false,
+    image_flip_vertical: false,
+    image_format: ImageFormat::Jpeg,
+    reports: V2_REPORTS,
+};
+
+pub const STREAMDECK_PLUS: DeviceModel = DeviceModel {
+    name: "Stream Deck +",
+    product_ids: &[0x0084],
+    key_count: 8,
+    rows: 2,
+    columns: 4,
+    image_size: 120,
+    image_rotation: ImageRotation::None,
+    image_flip_horizontal: false,
+    image_flip_vertical: false,
+    image_format: ImageFormat::Jpeg,
+    reports: ReportLayout {
+        // Input reports also carry dial and touch events; byte 1 is zero for key reports.
+        key_event_kind_offset: Some(1),
+        ..V2_REPORTS
+    },
+};
+
+pub const DEVICE_MODELS: &[DeviceModel] = &[
+    STREAMDECK_ORIGINAL,
+    STREAMDECK_ORIGINAL_V2,
+    STREAMDECK_MK2,
+    STREAMDECK_MINI,
+    STREAMDECK_XL,
+    STREAMDECK_PLUS,
+];
+
+pub const DEFAULT_DEVICE_MODEL: DeviceModel = STREAMDECK_MK2;
+pub const MAX_KEY_COUNT: usize = 32;
+
+impl DeviceModel {
+    pub fn from_product_id(product_id: u16) -> Option<&'static DeviceModel> {
+        DEVICE_MODELS
+            .iter()
+            .find(|model| model.product_ids.contains(&product_id))
+    }
+
+    // The Original reports and addresses keys right-to-left within each row.
+    pub fn native_key_index(&self, key: usize) -> usize {
+        if !self.reports.key_columns_reversed || self.columns == 0 {
+            return key;
+        }
+        let column = key % self.columns;
+        key - column + (self.columns - 1 - column)
+    }
+}
+
+pub fn supported_product_ids() -> Vec<u16> {
+    DEVICE_MODELS
+        .iter()
+        .flat_map(|model| model.product_ids.iter().copied())
+        .collect()
+}
+
+// Opens every attached deck that `wanted` accepts for its serial number and
+// product id. Devices without a serial number are identified by their HID path.
+pub fn get_devices(
     vendor_id: u16,
-    product_id: u16,
     usage: u16,
     usage_page: u16,
-) -> Option<HidDevice> {
+    wanted: &mut dyn FnMut(&str, u16) -> bool,
+) -> Vec<(HidDevice, HidDevice, &'static DeviceModel, String)> {
     let api = HidApi::new().expect("Failed to create HID API");
+    let mut devices = Vec::new();
     for dev in api.device_list() {
-        if (
-            dev.vendor_id(),
-            dev.product_id(),
-            dev.usage(),
-            dev.usage_page(),
-        ) == (vendor_id, product_id, usage, usage_page)
-        {
-            match dev.open_device(&api) {
-                Ok(device) => {
-                    return Some(device);
-                }
-                Err(e) => eprintln!("Error: {:?}", e),
-            }
+        if (dev.vendor_id(), dev.usage(), dev.usage_page()) != (vendor_id, usage, usage_page) {
+            continue;
+        }
+        let Some(model) = DeviceModel::from_product_id(dev.product_id()) else {
+            continue;
+        };
+        let serial = dev
+            .serial_number()
+            .map(str::trim)
+            .filter(|serial| !serial.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| dev.path().to_string_lossy().into_owned());
+        if !wanted(&serial, dev.product_id()) {
+            continue;
+        }
+        // HidDevice is not Sync, so key reports are read through a second handle
+        // on another thread; hidraw delivers every report to each open handle.
+        match (dev.open_device(&api), dev.open_device(&api)) {
+            (Ok(device), Ok(input)) => devices.push((device, input, model, serial)),
+            (Err(e), _) | (_, Err(e)) => eprintln!("Error: {:?}", e),
         }
     }
-    None
+    devices
+}
+
+pub fn brightness_report(model: &DeviceModel, percentage: usize) -> Vec<u8> {
+    let layout = &model.reports;
+    let mut buf = vec![0u8; layout.brightness_report_length];
+    let prefix_len = layout.brightness_prefix.len();
+    buf[..prefix_len].copy_from_slice(layout.brightness_prefix);
+    buf[prefix_len] = percentage.min(100) as u8;
+    buf
 }
 
-pub fn set_brightness(device: &HidDevice, percentage: usize) -> Result<(), String> {
-    let mut buf = [0u8; 32];
-    buf[0..3].copy_from_slice(&[0x03, 0x08, percentage as u8]);
+pub fn set_brightness(
+    device: &dyn DeckTransport,
+    model: &DeviceModel,
+    percentage: usize,
+) -> Result<(), String> {
     device
-        .send_feature_report(&buf)
+        .send_feature_report(&brightness_report(model, percentage))
         .map_err(|err| format!("Failed to set brightness: {err}"))?;
     Ok(())
 }
 
-fn get_pressed_button(buf: &[u8]) -> Option<usize> {
-    buf.iter().position(|&x| x == 1)
+// Pressed keys as a bitmap in logical key order; `None` for reports that do
+// not carry key state, such as dial and touch events on the Stream Deck +.
+pub fn key_states_from_report(model: &DeviceModel, report: &[u8]) -> Option<u32> {
+    let layout = &model.reports;
+    if let Some(kind_offset) = layout.key_event_kind_offset
+        && report.get(kind_offset).copied().unwrap_or_default() != 0
+    {
+        return None;
+    }
+    let end = min(report.len(), layout.key_state_offset + model.key_count);
+    let states = report.get(layout.key_state_offset..end)?;
+    Some(
+        states
+            .iter()
+            .enumerate()
+            .filter(|(_, state)| **state != 0)
+            .fold(0, |bitmap, (native, _)| {
+                bitmap | (1 << model.native_key_index(native))
+            }),
+    )
 }
 
-pub fn read_states(device: &HidDevice, timeout_ms: i32) -> Result<Option<usize>, String> {
-    let mut buf = [0u8; 32];
-    buf[0] = 19;
+pub fn read_key_states(
+    device: &dyn DeckTransport,
+    model: &DeviceModel,
+    timeout_ms: i32,
+) -> Result<Option<u32>, String> {
+    let mut buf = vec![0u8; (model.reports.key_state_offset + model.key_count).max(32)];
     match device.read_timeout(&mut buf, timeout_ms) {
-        Ok(size) if size > 0 => Ok(get_pressed_button(&buf[4..19])),
+        Ok(size) if size > 0 => Ok(key_states_from_report(model, &buf[..size])),
         Ok(_) => Ok(None),
         Err(err) => {
             // SIGHUP used for config reload can interrupt a blocking HID read.
             // Treat EINTR as a transient event and keep the existing device session.
-            let err_text = err.to_string();
-            if err_text
-                .to_ascii_lowercase()
-                .contains("interrupted system call")
-            {
+            if err.to_ascii_lowercase().contains("interrupted system call") {
                 Ok(None)
             } else {
                 Err(format!("Failed to read button state: {err}"))
@@ -62,34 +332,170 @@ pub fn read_states(device: &HidDevice, timeout_ms: i32) -> Result<Option<usize>,
     }
 }
 
-pub fn set_key_image_data(device: &HidDevice, key: u8, data: &[u8]) -> Result<(), String> {
+fn image_report_header(
+    model: &DeviceModel,
+    key: u8,
+    page_number: usize,
+    this_length: usize,
+    is_last: bool,
+) -> Vec<u8> {
+    let mut header = vec![0u8; model.reports.image_header.header_length()];
+    match model.reports.image_header {
+        ImageReportHeader::V1 { first_page } => {
+            header[..6].copy_from_slice(&[
+                0x02,
+                0x01,
+                (page_number as u8).wrapping_add(first_page),
+                0,
+                u8::from(is_last),
+                key + 1,
+            ]);
+        }
+        ImageReportHeader::V2 => {
+            header.copy_from_slice(&[
+                0x02,
+                0x07,
+                key,
+                u8::from(is_last),
+                (this_length & 0xFF) as u8,
+                (this_length >> 8) as u8,
+                (page_number & 0xFF) as u8,
+                (page_number >> 8) as u8,
+            ]);
+        }
+    }
+    header
+}
+
+pub fn image_reports(model: &DeviceModel, key: u8, data: &[u8]) -> Vec<Vec<u8>> {
+    let layout = &model.reports;
+    let header_length = layout.image_header.header_length();
+    let payload_length = layout.image_report_length - header_length;
+    let native_key = model.native_key_index(key as usize) as u8;
+
+    let mut reports = Vec::new();
     let mut page_number = 0;
     let mut bytes_remaining = data.len();
     while bytes_remaining > 0 {
-        let this_length = min(bytes_remaining, 1024 - 8);
-        let bytes_sent = page_number * (1024 - 8);
-        let header = [
-            0x02,
-            0x07,
-            key,
-            if this_length == bytes_remaining { 1 } else { 0 },
-            (this_length & 0xFF) as u8,
-            (this_length >> 8) as u8,
-            (page_number & 0xFF) as u8,
-            (page_number >> 8) as u8,
-        ];
-
-        let mut payload = Vec::with_capacity(1024);
-        payload.extend_from_slice(&header);
+        let this_length = min(bytes_remaining, payload_length);
+        let bytes_sent = page_number * payload_length;
+        let is_last = this_length == bytes_remaining;
+
+        let mut payload = Vec::with_capacity(layout.image_report_length);
+        payload.extend_from_slice(&image_report_header(
+            model,
+            native_key,
+            page_number,
+            this_length,
+            is_last,
+        ));
         payload.extend_from_slice(&data[bytes_sent..bytes_sent + this_length]);
-        payload.resize(1024, 0);
-        device
-            .write(&payload)
-            .map_err(|err| format!("Failed to write image to button {key}: {err}"))?;
+        payload.resize(layout.image_report_length, 0);
+        reports.push(payload);
 
         bytes_remaining -= this_length;
         page_number += 1;
     }
+    reports
+}
+
+pub fn set_key_image_data(
+    device: &dyn DeckTransport,
+    model: &DeviceModel,
+    key: u8,
+    data: &[u8],
+) -> Result<(), String> {
+    for payload in image_reports(model, key, data) {
+        device
+            .write(&payload)
+            .map_err(|err| format!("Failed to write image to button {key}: {err}"))?;
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn product_ids_select_expected_models() {
+        assert_eq!(DeviceModel::from_product_id(0x0080), Some(&STREAMDECK_MK2));
+        assert_eq!(DeviceModel::from_product_id(0x0090), Some(&STREAMDECK_MINI));
+        assert_eq!(DeviceModel::from_product_id(0x008f), Some(&STREAMDECK_XL));
+        assert_eq!(DeviceModel::from_product_id(0x0084), Some(&STREAMDECK_PLUS));
+        assert_eq!(DeviceModel::from_product_id(0x0001), None);
+        assert!(
+            DEVICE_MODELS
+                .iter()
+                .all(|model| model.key_count == model.rows * model.columns
+                    && model.key_count <= MAX_KEY_COUNT)
+        );
+    }
+
+    #[test]
+    fn mk2_image_reports_match_legacy_layout() {
+        let data = vec![0xAB; 1500];
+        let reports = image_reports(&STREAMDECK_MK2, 4, &data);
+        assert_eq!(reports.len(), 2);
+        assert_eq!(&reports[0][..8], &[0x02, 0x07, 4, 0, 0xF8, 0x03, 0, 0]);
+        assert_eq!(&reports[1][..8], &[0x02, 0x07, 4, 1, 0xE4, 0x01, 1, 0]);
+        assert!(reports.iter().all(|report| report.len() == 1024));
+    }
+
+    #[test]
+    fn original_image_reports_use_v1_header_and_mirrored_keys() {
+        let data = vec![0xAB; 10_000];
+        let reports = image_reports(&STREAMDECK_ORIGINAL, 0, &data);
+        assert_eq!(reports.len(), 2);
+        assert_eq!(&reports[0][..6], &[0x02, 0x01, 1, 0, 0, 5]);
+        assert_eq!(&reports[1][..6], &[0x02, 0x01, 2, 0, 1, 5]);
+        assert!(reports.iter().all(|report| report.len() == 8191));
+    }
+
+    #[test]
+    fn key_state_reports_respect_model_offsets() {
+        let mut mk2 = [0u8; 32];
+        mk2[4 + 7] = 1;
+        mk2[4 + 14] = 1;
+        assert_eq!(
+            key_states_from_report(&STREAMDECK_MK2, &mk2),
+            Some((1 << 7) | (1 << 14))
+        );
+        assert_eq!(key_states_from_report(&STREAMDECK_MK2, &[0u8; 32]), Some(0));
+
+        let mut mini = [0u8; 17];
+        mini[1 + 5] = 1;
+        assert_eq!(
+            key_states_from_report(&STREAMDECK_MINI, &mini),
+            Some(1 << 5)
+        );
+
+        let mut original = [0u8; 16];
+        original[1] = 1;
+        assert_eq!(
+            key_states_from_report(&STREAMDECK_ORIGINAL, &original),
+            Some(1 << 4)
+        );
+
+        let mut xl = [0u8; 36];
+        xl[4 + 31] = 1;
+        assert_eq!(key_states_from_report(&STREAMDECK_XL, &xl), Some(1 << 31));
+
+        let mut plus_dial = [0u8; 14];
+        plus_dial[1] = 0x03;
+        plus_dial[4] = 1;
+        assert_eq!(key_states_from_report(&STREAMDECK_PLUS, &plus_dial), None);
+    }
+
+    #[test]
+    fn brightness_reports_follow_protocol_generation() {
+        let v2 = brightness_report(&STREAMDECK_XL, 60);
+        assert_eq!(v2.len(), 32);
+        assert_eq!(&v2[..3], &[0x03, 0x08, 60]);
+
+        let v1 = brightness_report(&STREAMDECK_MINI, 150);
+        assert_eq!(v1.len(), 17);
+        assert_eq!(&v1[..6], &[0x05, 0x55, 0xaa, 0xd1, 0x01, 100]);
+    }
+}
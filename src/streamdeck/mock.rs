@@ -0,0 +1,268 @@
+use super::{DeckTransport, DeviceModel, ImageFormat, ImageReportHeader};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+const READ_POLL_INTERVAL: Duration = Duration::from_millis(2);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MockImagePage {
+    pub key: usize,
+    pub page: usize,
+    pub is_last: bool,
+    pub payload: Vec<u8>,
+}
+
+#[derive(Default)]
+struct MockDeckState {
+    feature_reports: Vec<Vec<u8>>,
+    image_pages: Vec<MockImagePage>,
+    pending_reads: VecDeque<Vec<u8>>,
+    disconnected: bool,
+}
+
+// Cloned handles share one recording, so a test can keep a handle while the
+// daemon owns the transport.
+#[derive(Clone)]
+pub struct MockDeck {
+    model: &'static DeviceModel,
+    serial: String,
+    state: Arc<Mutex<MockDeckState>>,
+}
+
+impl MockDeck {
+    pub fn new(model: &'static DeviceModel) -> Self {
+        Self::with_serial(model, "MOCK0001")
+    }
+
+    pub fn with_serial(model: &'static DeviceModel, serial: &str) -> Self {
+        Self {
+            model,
+            serial: serial.to_string(),
+            state: Arc::new(Mutex::new(MockDeckState::default())),
+        }
+    }
+
+    pub fn model(&self) -> &'static DeviceModel {
+        self.model
+    }
+
+    pub fn serial(&self) -> &str {
+        &self.serial
+    }
+
+    pub fn product_id(&self) -> u16 {
+        self.model.product_ids[0]
+    }
+
+    fn state(&self) -> MutexGuard<'_, MockDeckState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn queue_key_state(&self, pressed: &[usize]) {
+        let offset = self.model.reports.key_state_offset;
+        let mut report = vec![0u8; offset + self.model.key_count];
+        for &key in pressed {
+            if key < self.model.key_count {
+                report[offset + self.model.native_key_index(key)] = 1;
+            }
+        }
+        self.state().pending_reads.push_back(report);
+    }
+
+    pub fn press_key(&self, key: usize) {
+        self.queue_key_state(&[key]);
+        self.queue_key_state(&[]);
+    }
+
+    pub fn pending_reads(&self) -> usize {
+        self.state().pending_reads.len()
+    }
+
+    pub fn disconnect(&self) {
+        self.state().disconnected = true;
+    }
+
+    pub fn reconnect(&self) {
+        self.state().disconnected = false;
+    }
+
+    pub fn is_connected(&self) -> bool {
+        !self.state().disconnected
+    }
+
+    pub fn feature_reports(&self) -> Vec<Vec<u8>> {
+        self.state().feature_reports.clone()
+    }
+
+    pub fn brightness_levels(&self) -> Vec<u8> {
+        let prefix = self.model.reports.brightness_prefix;
+        self.state()
+            .feature_reports
+            .iter()
+            .filter(|report| report.starts_with(prefix))
+            .filter_map(|report| report.get(prefix.len()).copied())
+            .collect()
+    }
+
+    pub fn image_pages(&self) -> Vec<MockImagePage> {
+        self.state().image_pages.clone()
+    }
+
+    // Completed images per key in the order they were sent. V1 reports carry no
+    // payload length, so BMP images are cut back to the size in their file header.
+    pub fn key_images(&self, key: usize) -> Vec<Vec<u8>> {
+        let mut images = Vec::new();
+        let mut current = Vec::new();
+        for page in self
+            .state()
+            .image_pages
+            .iter()
+            .filter(|page| page.key == key)
+        {
+            if page.page == 0 {
+                current.clear();
+            }
+            current.extend_from_slice(&page.payload);
+            if page.is_last {
+                let mut image = std::mem::take(&mut current);
+                if self.model.image_format == ImageFormat::Bmp && image.len() >= 6 {
+                    let size = u32::from_le_bytes([image[2], image[3], image[4], image[5]]);
+                    image.truncate(size as usize);
+                }
+                images.push(image);
+            }
+        }
+        images
+    }
+
+    pub fn last_key_image(&self, key: usize) -> Option<Vec<u8>> {
+        self.key_images(key).pop()
+    }
+
+    pub fn clear_recorded(&self) {
+        let mut state = self.state();
+        state.feature_reports.clear();
+        state.image_pages.clear();
+    }
+
+    fn parse_image_page(&self, data: &[u8]) -> Result<MockImagePage, String> {
+        let layout = &self.model.reports;
+        let header_length = layout.image_header.header_length();
+        if data.len() != layout.image_report_length {
+            return Err(format!(
+                "Unexpected image report length {} (expected {})",
+                data.len(),
+                layout.image_report_length
+            ));
+        }
+        let (native_key, page, is_last, payload_length) = match layout.image_header {
+            ImageReportHeader::V1 { first_page } => (
+                data[5].wrapping_sub(1) as usize,
+                data[2].wrapping_sub(first_page) as usize,
+                data[4] == 1,
+                layout.image_report_length - header_length,
+            ),
+            ImageReportHeader::V2 => (
+                data[2] as usize,
+                data[6] as usize | ((data[7] as usize) << 8),
+                data[3] == 1,
+                data[4] as usize | ((data[5] as usize) << 8),
+            ),
+        };
+        Ok(MockImagePage {
+            key: self.model.native_key_index(native_key),
+            page,
+            is_last,
+            payload: data[header_length..header_length + payload_length].to_vec(),
+        })
+    }
+}
+
+impl DeckTransport for MockDeck {
+    fn send_feature_report(&self, data: &[u8]) -> Result<(), String> {
+        let mut state = self.state();
+        if state.disconnected {
+            return Err("device disconnected".to_string());
+        }
+        state.feature_reports.push(data.to_vec());
+        Ok(())
+    }
+
+    fn write(&self, data: &[u8]) -> Result<usize, String> {
+        if self.state().disconnected {
+            return Err("device disconnected".to_string());
+        }
+        let page = self.parse_image_page(data)?;
+        self.state().image_pages.push(page);
+        Ok(data.len())
+    }
+
+    // Returns as soon as a report is queued, like a blocking HID read; a
+    // negative timeout waits indefinitely.
+    fn read_timeout(&self, buf: &mut [u8], timeout_ms: i32) -> Result<usize, String> {
+        let deadline = u64::try_from(timeout_ms)
+            .ok()
+            .map(|timeout_ms| Instant::now() + Duration::from_millis(timeout_ms));
+        loop {
+            let next = {
+                let mut state = self.state();
+                if state.disconnected {
+                    return Err("device disconnected".to_string());
+                }
+                state.pending_reads.pop_front()
+            };
+            if let Some(report) = next {
+                let size = report.len().min(buf.len());
+                buf[..size].copy_from_slice(&report[..size]);
+                return Ok(size);
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Ok(0);
+            }
+            sleep(READ_POLL_INTERVAL);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streamdeck::{
+        STREAMDECK_MK2, STREAMDECK_ORIGINAL, read_key_states, set_brightness, set_key_image_data,
+    };
+
+    #[test]
+    fn mock_records_images_and_brightness() {
+        let deck = MockDeck::new(&STREAMDECK_MK2);
+        let image = (0..2500).map(|value| value as u8).collect::<Vec<_>>();
+        set_key_image_data(&deck, deck.model(), 3, &image).expect("image should be written");
+        set_brightness(&deck, deck.model(), 55).expect("brightness should be set");
+
+        assert_eq!(deck.image_pages().len(), 3);
+        assert_eq!(deck.last_key_image(3), Some(image));
+        assert_eq!(deck.brightness_levels(), vec![55]);
+    }
+
+    #[test]
+    fn mock_replays_presses_in_logical_key_order() {
+        let deck = MockDeck::new(&STREAMDECK_ORIGINAL);
+        deck.press_key(1);
+
+        deck.queue_key_state(&[0, 14]);
+
+        assert_eq!(read_key_states(&deck, deck.model(), 0), Ok(Some(1 << 1)));
+        assert_eq!(read_key_states(&deck, deck.model(), 0), Ok(Some(0)));
+        assert_eq!(
+            read_key_states(&deck, deck.model(), 0),
+            Ok(Some((1 << 0) | (1 << 14)))
+        );
+        assert_eq!(read_key_states(&deck, deck.model(), 0), Ok(None));
+
+        deck.disconnect();
+        assert!(read_key_states(&deck, deck.model(), 0).is_err());
+    }
+}